@@ -9,6 +9,7 @@ fn main() {
     let mut build = cc::Build::new();
     build
         .file(md4c_src.join("md4c.c"))
+        .file(md4c_src.join("entity.c"))
         .include(&md4c_src)
         .define("MD4C_USE_UTF8", None)
         .warnings(false)
@@ -18,7 +19,6 @@ fn main() {
     #[cfg(feature = "html")]
     {
         build.file(md4c_src.join("md4c-html.c"));
-        build.file(md4c_src.join("entity.c"));
     }
 
     build.compile("md4c");
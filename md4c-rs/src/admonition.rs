@@ -0,0 +1,190 @@
+//! GitHub-style alerts.
+//!
+//! MD4C has no notion of alerts, so with [`ParserFlags::admonitions`] the
+//! parser wraps the handler in [`Admonitions`], which looks at the start of
+//! each block quote and reports quotes opening with a `[!KIND]` line as
+//! [`Block::Admonition`] instead.
+//!
+//! [`ParserFlags::admonitions`]: crate::ParserFlags::admonitions
+
+use crate::parser::{source_offset, Event, ParserHandler};
+use crate::types::{AdmonitionKind, Block, BlockType, Span, SpanType, TextType};
+use std::borrow::Cow;
+
+/// Longest marker, `[!IMPORTANT]`; text beyond that can't be one.
+const MAX_MARKER_LEN: usize = 12;
+
+/// Handler adapter turning alert block quotes into admonitions.
+pub(crate) struct Admonitions<'i, H> {
+    inner: H,
+    input: &'i str,
+    // Events held back while the start of a block quote is examined
+    pending: Option<Vec<Event<'i>>>,
+    // Text of the pending quote's first line so far
+    marker: String,
+    // For each open block quote, whether it is reported as an admonition
+    quotes: Vec<bool>,
+}
+
+impl<'i, H: ParserHandler> Admonitions<'i, H> {
+    pub fn new(inner: H, input: &'i str) -> Self {
+        Admonitions {
+            inner,
+            input,
+            pending: None,
+            marker: String::new(),
+            quotes: Vec::new(),
+        }
+    }
+
+    /// Whether the pending quote's first paragraph has started.
+    fn in_first_paragraph(&self) -> bool {
+        self.pending.as_ref().is_some_and(|events| events.len() > 1)
+    }
+
+    /// The pending quote turned out to be an ordinary one: replay its events.
+    fn flush(&mut self) -> bool {
+        let Some(events) = self.pending.take() else {
+            return true;
+        };
+        self.marker.clear();
+        events.into_iter().all(|event| match event {
+            Event::EnterBlock(block) => self.inner.enter_block(block),
+            Event::LeaveBlock(block_type) => self.inner.leave_block(block_type),
+            Event::EnterSpan(span) => self.inner.enter_span(span),
+            Event::LeaveSpan(span_type) => self.inner.leave_span(span_type),
+            Event::Text(text_type, text) => self.inner.text(text_type, &text),
+        })
+    }
+
+    /// The first line of the pending quote is complete: if it is a marker,
+    /// open an admonition in place of the quote, dropping the marker.
+    ///
+    /// Returns `None` if the quote is an ordinary one.
+    fn open(&mut self) -> Option<bool> {
+        let kind = AdmonitionKind::from_marker(self.marker.trim_end())?;
+        self.pending = None;
+        self.marker.clear();
+        if let Some(quote) = self.quotes.last_mut() {
+            *quote = true;
+        }
+        Some(self.inner.enter_block(Block::Admonition(kind)))
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for Admonitions<'_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        if self.pending.as_ref().is_some_and(|events| events.len() == 1)
+            && matches!(block, Block::Paragraph)
+        {
+            self.pending.as_mut().unwrap().push(Event::EnterBlock(Block::Paragraph));
+            return true;
+        }
+        if !self.flush() {
+            return false;
+        }
+        if matches!(block, Block::Quote) {
+            self.quotes.push(false);
+            self.pending = Some(vec![Event::EnterBlock(Block::Quote)]);
+            return true;
+        }
+        self.inner.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        if self.in_first_paragraph() && block_type == BlockType::Paragraph {
+            // A marker alone in its paragraph takes the paragraph with it
+            if let Some(ok) = self.open() {
+                return ok;
+            }
+        }
+        if !self.flush() {
+            return false;
+        }
+        match block_type {
+            BlockType::Quote if self.quotes.pop() == Some(true) => {
+                self.inner.leave_block(BlockType::Admonition)
+            }
+            _ => self.inner.leave_block(block_type),
+        }
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.flush() && self.inner.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.flush() && self.inner.leave_span(span_type)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        if self.in_first_paragraph() {
+            match text_type {
+                TextType::Normal if self.marker.len() + text.len() <= MAX_MARKER_LEN => {
+                    self.marker.push_str(text);
+                    let text = match source_offset(self.input, text) {
+                        Some(offset) => Cow::Borrowed(&self.input[offset..offset + text.len()]),
+                        None => Cow::Owned(text.to_string()),
+                    };
+                    self.pending.as_mut().unwrap().push(Event::Text(text_type, text));
+                    return true;
+                }
+                // The rest of the paragraph follows the marker line
+                TextType::SoftBreak | TextType::HardBreak => {
+                    if let Some(ok) = self.open() {
+                        return ok && self.inner.enter_block(Block::Paragraph);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.flush() && self.inner.text(text_type, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_to_events, Event, ParserFlags};
+    use crate::types::{AdmonitionKind, Block, BlockType};
+
+    fn blocks(input: &str) -> Vec<String> {
+        let flags = ParserFlags::commonmark().admonitions();
+        parse_to_events(input, flags)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::EnterBlock(Block::Admonition(kind)) => Some(format!("<{}>", kind.name())),
+                Event::EnterBlock(block) => Some(format!("<{:?}>", block.block_type())),
+                Event::LeaveBlock(block_type) => Some(format!("</{:?}>", block_type)),
+                Event::Text(_, text) => Some(text.into_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_admonitions() {
+        assert_eq!(
+            blocks("> [!Warning]  \n> Mind *the* gap\n"),
+            [
+                "<Document>", "<warning>", "<Paragraph>", "Mind ", "the", " gap", "</Paragraph>",
+                "</Admonition>", "</Document>"
+            ]
+        );
+        assert_eq!(
+            blocks("> [!TIP]\n>\n> > [!NOTE]\n"),
+            ["<Document>", "<tip>", "<note>", "</Admonition>", "</Admonition>", "</Document>"]
+        );
+        assert_eq!(AdmonitionKind::from_marker("[!caution]"), Some(AdmonitionKind::Caution));
+        assert_eq!(BlockType::Admonition, Block::Admonition(AdmonitionKind::Tip).block_type());
+    }
+
+    #[test]
+    fn test_plain_quotes() {
+        for input in ["> [!NOTE] text\n", "> [!SIDEBAR]\n> x\n", "> **[!NOTE]**\n", "> x\n"] {
+            let events = blocks(input);
+            assert_eq!(events[1], "<Quote>", "{}", input);
+            assert_eq!(events[events.len() - 2], "</Quote>", "{}", input);
+        }
+    }
+}
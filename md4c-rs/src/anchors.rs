@@ -0,0 +1,162 @@
+//! Checking links to anchors within a document.
+//!
+//! [`validate_anchors`] treats every heading as an anchor named by its
+//! GitHub-style slug, like [`toc::extract`](crate::toc::extract) and
+//! `HtmlRenderer::with_heading_ids`, and reports the `#fragment` links that
+//! point at no anchor. `id` and `name` attributes in raw HTML count as
+//! anchors too.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::{validate_anchors, ParserFlags};
+//!
+//! let doc = "# Setup\n\nSee [setup](#setup) and\n[usage](#usage).\n";
+//! let broken = validate_anchors(doc, ParserFlags::commonmark());
+//! assert_eq!(broken.len(), 1);
+//! assert_eq!(broken[0].fragment, "usage");
+//! assert_eq!(broken[0].line, 4);
+//! ```
+
+use crate::parser::{parse, source_offset, ParserFlags, ParserHandler};
+use crate::slug::{self, Slugger};
+use crate::types::{Block, BlockType, Span, TextType};
+use std::collections::HashSet;
+
+/// A `#fragment` link that matches no anchor in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenAnchor {
+    /// The fragment, without the `#`
+    pub fragment: String,
+    /// Byte offset of the link's text in the input
+    pub byte_offset: usize,
+    /// Line of the link, starting at 1
+    pub line: usize,
+}
+
+/// Find the links to anchors that don't exist in the document, in order.
+///
+/// A bare `#` links to the top of the page and is always valid. If parsing
+/// fails, no links are reported.
+pub fn validate_anchors(input: &str, flags: ParserFlags) -> Vec<BrokenAnchor> {
+    let mut collector = AnchorCollector {
+        input,
+        anchors: HashSet::new(),
+        slugger: Slugger::default(),
+        heading: None,
+        links: Vec::new(),
+        unplaced: Vec::new(),
+        cursor: 0,
+    };
+    if parse(input, flags, &mut collector).is_err() {
+        return Vec::new();
+    }
+    collector.place_links(collector.cursor);
+
+    let anchors = collector.anchors;
+    collector
+        .links
+        .into_iter()
+        .filter(|(fragment, _)| !fragment.is_empty() && !anchors.contains(fragment))
+        .map(|(fragment, byte_offset)| BrokenAnchor {
+            fragment,
+            byte_offset,
+            line: input[..byte_offset].matches('\n').count() + 1,
+        })
+        .collect()
+}
+
+struct AnchorCollector<'a> {
+    input: &'a str,
+    anchors: HashSet<String>,
+    slugger: Slugger,
+    // Text of the heading being read
+    heading: Option<String>,
+    // Fragment links, with the offset of their text
+    links: Vec<(String, usize)>,
+    // Fragments of links whose text has not been seen yet
+    unplaced: Vec<String>,
+    // End of the last text from the input
+    cursor: usize,
+}
+
+impl AnchorCollector<'_> {
+    fn place_links(&mut self, offset: usize) {
+        self.links.extend(self.unplaced.drain(..).map(|fragment| (fragment, offset)));
+    }
+}
+
+impl ParserHandler for AnchorCollector<'_> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        if let Block::Heading(_) = block {
+            self.heading = Some(String::new());
+        }
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        if block_type == BlockType::Heading {
+            if let Some(text) = self.heading.take() {
+                let slug = self.slugger.unique(slug::github(text.trim()));
+                self.anchors.insert(slug);
+            }
+        }
+        true
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        if let Span::Link(detail) = span {
+            if let Some(fragment) = detail.href.strip_prefix('#') {
+                // Placed at its text, or at the next text if it has none
+                self.unplaced.push(fragment.to_string());
+            }
+        }
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        if let Some(offset) = source_offset(self.input, text) {
+            self.place_links(offset);
+            self.cursor = offset + text.len();
+        }
+        if let Some(ref mut heading) = self.heading {
+            slug::push_heading_text(heading, text_type, text);
+        }
+        if text_type == TextType::Html {
+            self.anchors.extend(html_anchors(text));
+        }
+        true
+    }
+}
+
+/// Values of the `id` and `name` attributes in a piece of raw HTML.
+fn html_anchors(html: &str) -> impl Iterator<Item = String> + '_ {
+    ["id=", "name="].into_iter().flat_map(move |attribute| {
+        html.match_indices(attribute).filter_map(move |(i, _)| {
+            if !html[..i].ends_with(|c: char| c.is_ascii_whitespace()) {
+                return None;
+            }
+            let value = &html[i + attribute.len()..];
+            let quote = value.chars().next().filter(|&c| c == '"' || c == '\'')?;
+            let value = &value[1..];
+            Some(value[..value.find(quote)?].to_string())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_anchors() {
+        let doc = "# Tom &amp; Jerry\n\n## Same\n\n## Same\n\n<a name=\"top\"></a>\n\n\
+                   [a](#tom--jerry) [b](#same-1) [c](#top) [d](#) [e](https://x.org#nope)\n\n\
+                   > [f](#same-2)\n> [](#gone) g\n\n# Foo <b>bar</b>\n\n[h](#foo-bar)\n";
+        let broken = validate_anchors(doc, ParserFlags::commonmark());
+        let summary: Vec<(&str, usize)> =
+            broken.iter().map(|b| (b.fragment.as_str(), b.line)).collect();
+        assert_eq!(summary, [("same-2", 11), ("gone", 12)]);
+        assert_eq!(&doc[broken[0].byte_offset..broken[0].byte_offset + 1], "f");
+    }
+}
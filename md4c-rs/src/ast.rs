@@ -0,0 +1,615 @@
+//! Owned syntax tree built from parser events.
+//!
+//! [`parse_to_ast`] collects the event stream into a tree of [`Node`]s, for
+//! code that needs to look around the document rather than react to events
+//! in order.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::ast::parse_to_ast;
+//! use md4c::{BlockType, ParserFlags};
+//!
+//! let doc = parse_to_ast("# Title\n\nSome *text*.", ParserFlags::commonmark()).unwrap();
+//! let heading = doc.find_block(BlockType::Heading).unwrap();
+//! assert_eq!(heading.text(), "Title");
+//! assert_eq!(doc.children().len(), 2);
+//! ```
+//!
+//! A [`VisitMut`] rewrites a tree in place, and [`Node::replay`] feeds it
+//! back to any [`ParserHandler`], such as a renderer:
+//!
+//! ```
+//! use md4c::ast::{parse_to_ast, Node, VisitMut};
+//! use md4c::{Block, ParserFlags};
+//!
+//! struct Demote;
+//!
+//! impl VisitMut for Demote {
+//!     fn visit_block_mut(&mut self, block: &mut Block<'static>, children: &mut Vec<Node>) {
+//!         if let Block::Heading(heading) = block {
+//!             heading.level = (heading.level + 1).min(6);
+//!         }
+//!         md4c::ast::visit_children_mut(self, children);
+//!     }
+//! }
+//!
+//! let mut doc = parse_to_ast("# Title\n\n## Part", ParserFlags::commonmark()).unwrap();
+//! doc.visit_mut(&mut Demote);
+//! # #[cfg(feature = "html")]
+//! assert_eq!(
+//!     md4c::html::render_ast(&doc, md4c::html::HtmlFlags::new()),
+//!     "<h2>Title</h2>\n<h3>Part</h3>\n"
+//! );
+//! ```
+
+use crate::fmt::{FormatOptions, MarkdownWriter};
+use crate::html_text::HtmlToText;
+use crate::parser::{parse, ParseResult, ParserFlags, ParserHandler};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+use std::borrow::Cow;
+
+/// What a [`Node`] represents.
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    /// A block element
+    Block(Block<'static>),
+    /// An inline span
+    Span(Span<'static>),
+    /// Text content
+    Text(TextType, String),
+}
+
+/// How [`Node::text_with`] treats raw HTML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HtmlText {
+    /// Include raw HTML as written
+    #[default]
+    Keep,
+    /// Strip tags and comments from raw HTML and decode its entities,
+    /// keeping only the text content
+    Strip,
+}
+
+/// A node in the syntax tree.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The element or text this node holds
+    pub kind: NodeKind,
+    /// Child nodes, in document order (always empty for text)
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        Node {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// Child nodes, in document order.
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Iterate over this node and all its descendants, depth first in
+    /// document order.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk { stack: vec![self] }
+    }
+
+    /// Block type, if this is a block node.
+    pub fn block_type(&self) -> Option<BlockType> {
+        match self.kind {
+            NodeKind::Block(ref block) => Some(block.block_type()),
+            _ => None,
+        }
+    }
+
+    /// Span type, if this is a span node.
+    pub fn span_type(&self) -> Option<SpanType> {
+        match self.kind {
+            NodeKind::Span(ref span) => Some(span.span_type()),
+            _ => None,
+        }
+    }
+
+    /// First block of the given type among this node and its descendants.
+    pub fn find_block(&self, block_type: BlockType) -> Option<&Node> {
+        self.walk().find(|n| n.block_type() == Some(block_type))
+    }
+
+    /// All blocks of the given type among this node and its descendants.
+    pub fn find_blocks(&self, block_type: BlockType) -> impl Iterator<Item = &Node> {
+        self.walk().filter(move |n| n.block_type() == Some(block_type))
+    }
+
+    /// All spans of the given type among this node and its descendants.
+    pub fn find_spans(&self, span_type: SpanType) -> impl Iterator<Item = &Node> {
+        self.walk().filter(move |n| n.span_type() == Some(span_type))
+    }
+
+    /// Nodes matching `selector` among this node and its descendants, in
+    /// document order.
+    ///
+    /// ```
+    /// use md4c::ast::{parse_to_ast, Node};
+    /// use md4c::{BlockType, ParserFlags};
+    ///
+    /// let md = "```rust\nfn a() {}\n```\n\n```sh\nls\n```\n\n![logo](l.png)";
+    /// let doc = parse_to_ast(md, ParserFlags::commonmark()).unwrap();
+    /// let code = doc.select(BlockType::Code).with_lang("rust");
+    /// assert_eq!(code.map(Node::text).collect::<Vec<_>>(), ["fn a() {}\n"]);
+    /// let images: Vec<_> = doc.images().filter_map(Node::url).collect();
+    /// assert_eq!(images, ["l.png"]);
+    /// ```
+    pub fn select(&self, selector: impl Into<Selector>) -> Select<'_> {
+        Select {
+            walk: self.walk(),
+            selector: selector.into(),
+            lang: None,
+            level: None,
+        }
+    }
+
+    /// Headings of the given level.
+    pub fn headings(&self, level: u8) -> Select<'_> {
+        self.select(BlockType::Heading).with_level(level)
+    }
+
+    /// Code blocks.
+    pub fn code_blocks(&self) -> Select<'_> {
+        self.select(BlockType::Code)
+    }
+
+    /// Links, including autolinks but not wiki links.
+    pub fn links(&self) -> Select<'_> {
+        self.select(SpanType::Link)
+    }
+
+    /// Images.
+    pub fn images(&self) -> Select<'_> {
+        self.select(SpanType::Image)
+    }
+
+    /// Destination of a link or image, or target of a wiki link, with
+    /// entities decoded.
+    pub fn url(&self) -> Option<Cow<'_, str>> {
+        match self.kind {
+            NodeKind::Span(Span::Link(ref link)) => Some(link.href.decoded()),
+            NodeKind::Span(Span::Image(ref image)) => Some(image.src.decoded()),
+            NodeKind::Span(Span::WikiLink(ref wiki)) => Some(wiki.target.decoded()),
+            _ => None,
+        }
+    }
+
+    /// Language of a code block, if it has one.
+    pub fn lang(&self) -> Option<Cow<'_, str>> {
+        match self.kind {
+            NodeKind::Block(Block::Code(ref code)) if !code.lang.is_empty() => {
+                Some(code.lang.decoded())
+            }
+            _ => None,
+        }
+    }
+
+    /// Concatenated text content of this node, with line breaks as `\n`.
+    pub fn text(&self) -> String {
+        self.text_with(HtmlText::Keep)
+    }
+
+    /// Concatenated text content of this node, with raw HTML handled as
+    /// given.
+    ///
+    /// Stripping HTML gives clean text for search indexes or snippets from
+    /// documents that mix in HTML, like many READMEs:
+    ///
+    /// ```
+    /// use md4c::ast::{parse_to_ast, HtmlText};
+    /// use md4c::ParserFlags;
+    ///
+    /// let md = "<p align=\"center\">Fast &amp; <b>small</b></p>\n";
+    /// let doc = parse_to_ast(md, ParserFlags::commonmark()).unwrap();
+    /// assert_eq!(doc.text_with(HtmlText::Strip), "Fast & small\n");
+    /// ```
+    pub fn text_with(&self, html: HtmlText) -> String {
+        let mut text = String::new();
+        let mut stripper = HtmlToText::new();
+        for node in self.walk() {
+            match node.kind {
+                NodeKind::Text(TextType::SoftBreak | TextType::HardBreak, _) => text.push('\n'),
+                NodeKind::Text(TextType::Html, ref t) if html == HtmlText::Strip => {
+                    stripper.push(&mut text, t)
+                }
+                NodeKind::Text(_, ref t) => text.push_str(t),
+                _ => {}
+            }
+        }
+        text
+    }
+}
+
+impl Node {
+    /// Rewrite this node and its descendants with `visitor`.
+    pub fn visit_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_node_mut(self);
+    }
+
+    /// Send this node and its descendants to `handler` as parser events.
+    ///
+    /// Returns `false` if the handler stopped early.
+    pub fn replay<H: ParserHandler + ?Sized>(&self, handler: &mut H) -> bool {
+        match &self.kind {
+            NodeKind::Block(block) => {
+                handler.enter_block(block.clone())
+                    && self.children.iter().all(|child| child.replay(handler))
+                    && handler.leave_block(block.block_type())
+            }
+            NodeKind::Span(span) => {
+                handler.enter_span(span.clone())
+                    && self.children.iter().all(|child| child.replay(handler))
+                    && handler.leave_span(span.span_type())
+            }
+            NodeKind::Text(text_type, text) => handler.text(*text_type, text),
+        }
+    }
+}
+
+/// Rewrites a syntax tree in place, see [`Node::visit_mut`].
+///
+/// Each hook by default visits the node's children; an overriding hook
+/// calls [`visit_children_mut`] itself to keep going down. Block and span
+/// hooks get the children as a `Vec`, to remove, insert or replace nodes.
+pub trait VisitMut {
+    /// Visit any node, dispatching on its kind.
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        match &mut node.kind {
+            NodeKind::Block(block) => self.visit_block_mut(block, &mut node.children),
+            NodeKind::Span(span) => self.visit_span_mut(span, &mut node.children),
+            NodeKind::Text(text_type, text) => self.visit_text_mut(text_type, text),
+        }
+    }
+
+    /// Visit a block and its children.
+    fn visit_block_mut(&mut self, block: &mut Block<'static>, children: &mut Vec<Node>) {
+        let _ = block;
+        visit_children_mut(self, children);
+    }
+
+    /// Visit a span and its children.
+    fn visit_span_mut(&mut self, span: &mut Span<'static>, children: &mut Vec<Node>) {
+        let _ = span;
+        visit_children_mut(self, children);
+    }
+
+    /// Visit a text node.
+    fn visit_text_mut(&mut self, text_type: &mut TextType, text: &mut String) {
+        let _ = (text_type, text);
+    }
+}
+
+/// Demotes headings by a number of levels, or promotes them if it is
+/// negative, staying within levels 1 to 6.
+///
+/// ```
+/// use md4c::ast::{parse_to_ast, HeadingOffset, Node};
+/// use md4c::{BlockType, ParserFlags};
+///
+/// let mut doc = parse_to_ast("# Post\n\n## Part", ParserFlags::commonmark()).unwrap();
+/// doc.visit_mut(&mut HeadingOffset(-1));
+/// let titles: Vec<String> = doc.headings(1).map(Node::text).collect();
+/// assert_eq!(titles, ["Post", "Part"]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadingOffset(pub i8);
+
+impl VisitMut for HeadingOffset {
+    fn visit_block_mut(&mut self, block: &mut Block<'static>, children: &mut Vec<Node>) {
+        if let Block::Heading(heading) = block {
+            heading.level = shift_heading(heading.level, self.0);
+        }
+        visit_children_mut(self, children);
+    }
+}
+
+/// Move a heading level by `offset`, staying within 1 to 6.
+pub(crate) fn shift_heading(level: u8, offset: i8) -> u8 {
+    (i16::from(level) + i16::from(offset)).clamp(1, 6) as u8
+}
+
+/// Visit each of `children` with `visitor`, in order.
+pub fn visit_children_mut<V: VisitMut + ?Sized>(visitor: &mut V, children: &mut [Node]) {
+    for child in children {
+        visitor.visit_node_mut(child);
+    }
+}
+
+/// The kind of node to look for with [`Node::select`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selector {
+    /// Blocks of a type
+    Block(BlockType),
+    /// Spans of a type
+    Span(SpanType),
+    /// Text of a type
+    Text(TextType),
+}
+
+impl From<BlockType> for Selector {
+    fn from(block_type: BlockType) -> Self {
+        Selector::Block(block_type)
+    }
+}
+
+impl From<SpanType> for Selector {
+    fn from(span_type: SpanType) -> Self {
+        Selector::Span(span_type)
+    }
+}
+
+impl From<TextType> for Selector {
+    fn from(text_type: TextType) -> Self {
+        Selector::Text(text_type)
+    }
+}
+
+impl Selector {
+    fn matches(self, node: &Node) -> bool {
+        match (self, &node.kind) {
+            (Selector::Block(block_type), NodeKind::Block(block)) => {
+                block.block_type() == block_type
+            }
+            (Selector::Span(span_type), NodeKind::Span(span)) => span.span_type() == span_type,
+            (Selector::Text(text_type), NodeKind::Text(t, _)) => *t == text_type,
+            _ => false,
+        }
+    }
+}
+
+/// Iterator over the nodes matching a [`Selector`], see [`Node::select`].
+pub struct Select<'a> {
+    walk: Walk<'a>,
+    selector: Selector,
+    lang: Option<String>,
+    level: Option<u8>,
+}
+
+impl Select<'_> {
+    /// Only code blocks in the given language, ignoring ASCII case.
+    pub fn with_lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    /// Only headings of the given level.
+    pub fn with_level(mut self, level: u8) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        if !self.selector.matches(node) {
+            return false;
+        }
+        if let Some(lang) = &self.lang {
+            if !node.lang().is_some_and(|l| l.eq_ignore_ascii_case(lang)) {
+                return false;
+            }
+        }
+        match (self.level, &node.kind) {
+            (Some(level), NodeKind::Block(Block::Heading(heading))) => heading.level == level,
+            (Some(_), _) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+impl<'a> Iterator for Select<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        loop {
+            let node = self.walk.next()?;
+            if self.matches(node) {
+                return Some(node);
+            }
+        }
+    }
+}
+
+/// Depth-first iterator over a node and its descendants, see [`Node::walk`].
+pub struct Walk<'a> {
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children.iter().rev());
+        Some(node)
+    }
+}
+
+/// Builds the tree from parser events.
+struct TreeBuilder {
+    // Open nodes, the document first
+    stack: Vec<Node>,
+}
+
+impl TreeBuilder {
+    fn open(&mut self, kind: NodeKind) -> bool {
+        self.stack.push(Node::new(kind));
+        true
+    }
+
+    fn close(&mut self) -> bool {
+        // The document itself stays on the stack to be returned
+        if self.stack.len() > 1 {
+            let node = self.stack.pop().unwrap();
+            self.stack.last_mut().unwrap().children.push(node);
+        }
+        true
+    }
+}
+
+impl ParserHandler for TreeBuilder {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.open(NodeKind::Block(block.into_owned()))
+    }
+
+    fn leave_block(&mut self, _block_type: BlockType) -> bool {
+        self.close()
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.open(NodeKind::Span(span.into_owned()))
+    }
+
+    fn leave_span(&mut self, _span_type: SpanType) -> bool {
+        self.close()
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        if let Some(parent) = self.stack.last_mut() {
+            parent
+                .children
+                .push(Node::new(NodeKind::Text(text_type, text.to_string())));
+        }
+        true
+    }
+}
+
+/// Parse markdown into a syntax tree.
+///
+/// The returned node is the [`Block::Document`] root.
+pub fn parse_to_ast(input: &str, flags: ParserFlags) -> ParseResult<Node> {
+    let mut builder = TreeBuilder { stack: Vec::new() };
+    parse(input, flags, &mut builder)?;
+    Ok(builder
+        .stack
+        .pop()
+        .unwrap_or_else(|| Node::new(NodeKind::Block(Block::Document))))
+}
+
+/// Write a syntax tree back out as Markdown, e.g. after rewriting it with
+/// [`VisitMut`].
+///
+/// The output is canonical, as from [`fmt::format`](crate::fmt::format):
+/// the options choose the bullet, fence, heading and emphasis styles, and
+/// their parser flags which extensions may be written.
+///
+/// ```
+/// use md4c::ast::{parse_to_ast, to_markdown};
+/// use md4c::fmt::{EmphasisStyle, FormatOptions};
+/// use md4c::ParserFlags;
+///
+/// let doc = parse_to_ast("Title\n=====\n\n* *one*\n", ParserFlags::commonmark()).unwrap();
+/// let options = FormatOptions::new().with_emphasis(EmphasisStyle::Underscore);
+/// assert_eq!(to_markdown(&doc, &options), "# Title\n\n- _one_\n");
+/// ```
+pub fn to_markdown(doc: &Node, options: &FormatOptions) -> String {
+    MarkdownWriter::new(options).document(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Attribute;
+
+    #[test]
+    fn test_tree_structure() {
+        let doc = parse_to_ast("> - a **b**\n> - c\n", ParserFlags::commonmark()).unwrap();
+        assert_eq!(doc.block_type(), Some(BlockType::Document));
+
+        let quote = &doc.children()[0];
+        assert_eq!(quote.block_type(), Some(BlockType::Quote));
+        let list = &quote.children()[0];
+        assert_eq!(list.block_type(), Some(BlockType::UnorderedList));
+        assert_eq!(list.children().len(), 2);
+        assert_eq!(list.children()[0].text(), "a b");
+
+        let strong = doc.find_spans(SpanType::Strong).next().unwrap();
+        assert_eq!(strong.text(), "b");
+    }
+
+    #[test]
+    fn test_walk_order() {
+        let doc = parse_to_ast("# One\n\n## Two\n\ntext\n\n# Three", ParserFlags::commonmark())
+            .unwrap();
+        let headings: Vec<String> = doc.find_blocks(BlockType::Heading).map(Node::text).collect();
+        assert_eq!(headings, ["One", "Two", "Three"]);
+        assert_eq!(doc.walk().count(), 9);
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        // Rewrite image paths and drop raw HTML
+        struct Rewrite;
+        impl VisitMut for Rewrite {
+            fn visit_block_mut(&mut self, _block: &mut Block<'static>, children: &mut Vec<Node>) {
+                children.retain(|n| {
+                    n.block_type() != Some(BlockType::Html)
+                        && !matches!(n.kind, NodeKind::Text(TextType::Html, _))
+                });
+                visit_children_mut(self, children);
+            }
+            fn visit_span_mut(&mut self, span: &mut Span<'static>, children: &mut Vec<Node>) {
+                if let Span::Image(image) = span {
+                    image.src = Attribute::new(format!("/static/{}", image.src.as_str()));
+                }
+                visit_children_mut(self, children);
+            }
+        }
+
+        let md = "<div>x</div>\n\nSee <b>![a](a.png)</b>\n";
+        let mut doc = parse_to_ast(md, ParserFlags::commonmark()).unwrap();
+        doc.visit_mut(&mut Rewrite);
+        let mut replayed = TreeBuilder { stack: Vec::new() };
+        assert!(doc.replay(&mut replayed));
+        let copy = replayed.stack.pop().unwrap();
+        assert_eq!(copy.children().len(), 1);
+        assert_eq!(copy.text(), "See a");
+        let image = copy.find_spans(SpanType::Image).next().unwrap();
+        let NodeKind::Span(Span::Image(ref image)) = image.kind else { panic!() };
+        assert_eq!(image.src, "/static/a.png");
+    }
+
+    #[test]
+    fn test_select() {
+        let md = "# A\n\n## B [x](/x) <https://y.org>\n\n- ## C ![i](i.png)\n\n\
+                  ```Rust ignore\nlet a;\n```\n\n    let b;\n\n~~~py\nc\n~~~\n";
+        let doc = parse_to_ast(md, ParserFlags::commonmark()).unwrap();
+        let h2: Vec<String> = doc.headings(2).map(Node::text).collect();
+        assert_eq!(h2, ["B x https://y.org", "C i"]);
+        let urls: Vec<_> = doc.links().filter_map(Node::url).collect();
+        assert_eq!(urls, ["/x", "https://y.org"]);
+        assert_eq!(doc.images().count(), 1);
+        assert_eq!(doc.code_blocks().count(), 3);
+        let rust: Vec<String> = doc.code_blocks().with_lang("rust").map(Node::text).collect();
+        assert_eq!(rust, ["let a;\n"]);
+        // Filters that don't apply to a kind match nothing
+        assert_eq!(doc.links().with_level(1).count(), 0);
+        assert_eq!(doc.select(TextType::SoftBreak).count(), 0);
+    }
+
+    #[test]
+    fn test_text_strips_html() {
+        let md = "<div>\n<!-- note -->\n<p>A &lt;tag&gt;</p>\n</div>\n\nInline <kbd>Ctrl</kbd>\n";
+        let doc = parse_to_ast(md, ParserFlags::commonmark()).unwrap();
+        assert_eq!(doc.text_with(HtmlText::Strip), "\n\nA <tag>\n\nInline Ctrl");
+        assert!(doc.text().contains("<kbd>Ctrl</kbd>"));
+    }
+
+    #[test]
+    fn test_to_markdown_non_ascii() {
+        let md = "# Grüße\n\ncafé. naïve) *日本語*\n\n- É\n- ß)\n";
+        let doc = parse_to_ast(md, ParserFlags::commonmark()).unwrap();
+        let out = to_markdown(&doc, &FormatOptions::new());
+        assert_eq!(out, md);
+        let again = parse_to_ast(&out, ParserFlags::commonmark()).unwrap();
+        assert_eq!(to_markdown(&again, &FormatOptions::new()), out);
+    }
+}
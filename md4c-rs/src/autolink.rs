@@ -0,0 +1,357 @@
+//! Autolinks for additional URL schemes.
+//!
+//! MD4C's permissive autolinks only recognize `http(s)://`, `www.` and email
+//! addresses. [`ExtraAutolinks`] wraps a handler and turns URLs with other
+//! registered prefixes (e.g. `gemini://`, `ssh://`) found in normal text into
+//! link spans, exactly as if MD4C had recognized them.
+//!
+//! MD4C splits text at entities and backslash escapes, so a URL can run
+//! across several text events, as in `gemini://x.org/?a=1&amp;b=2`. A URL
+//! reaching the end of a text event is held back until the next event shows
+//! where it ends.
+//!
+//! Only URLs starting with one of the prefixes are found. Identifiers
+//! without a fixed prefix, such as a bare `PROJ-123` issue key, aren't
+//! supported; a handler of its own can look for them in the text.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::autolink::ExtraAutolinks;
+//! use md4c::{parse, ParserFlags, ParserHandler, Span};
+//!
+//! struct Links(Vec<String>);
+//!
+//! impl ParserHandler for Links {
+//!     fn enter_span(&mut self, span: Span) -> bool {
+//!         if let Span::Link(detail) = span {
+//!             self.0.push(detail.href.to_string());
+//!         }
+//!         true
+//!     }
+//! }
+//!
+//! let mut links = Links(Vec::new());
+//! let mut handler = ExtraAutolinks::new(&mut links, ["gemini://"]);
+//! parse("See gemini://example.org/page.", ParserFlags::commonmark(), &mut handler).unwrap();
+//! assert_eq!(links.0, vec!["gemini://example.org/page"]);
+//! ```
+
+use crate::parser::ParserHandler;
+use crate::types::{Attribute, Block, BlockType, LinkDetail, Span, SpanType, TextType};
+
+/// Handler adapter recognizing autolinks with additional URL prefixes.
+pub struct ExtraAutolinks<H> {
+    inner: H,
+    prefixes: Vec<String>,
+    // Depth of spans whose text must not be linked (links, images, wiki links)
+    link_depth: usize,
+    // Text events from the start of a URL that reached the end of one,
+    // until it is known where the URL ends
+    held: Vec<(TextType, String)>,
+}
+
+impl<H: ParserHandler> ExtraAutolinks<H> {
+    /// Wrap a handler, linking URLs starting with any of the given prefixes.
+    ///
+    /// Prefixes include the scheme separator, e.g. `"ssh://"` or `"magnet:"`,
+    /// and are matched case-insensitively.
+    pub fn new<I, S>(inner: H, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ExtraAutolinks {
+            inner,
+            prefixes: prefixes
+                .into_iter()
+                .map(|p| p.into().to_ascii_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect(),
+            link_depth: 0,
+            held: Vec::new(),
+        }
+    }
+
+    /// Unwrap the inner handler.
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    /// Find the next URL in `text`, returning its byte range.
+    fn find_url(&self, text: &str) -> Option<(usize, usize)> {
+        let lower = text.to_ascii_lowercase();
+        let mut best: Option<(usize, usize)> = None;
+
+        for prefix in &self.prefixes {
+            let mut from = 0;
+            while let Some(pos) = lower[from..].find(prefix.as_str()) {
+                let start = from + pos;
+                from = start + prefix.len();
+                if best.is_some_and(|(s, _)| s <= start) {
+                    break;
+                }
+                // Must start at a word boundary
+                if text[..start]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_alphanumeric())
+                {
+                    continue;
+                }
+                let end = url_end(text, start + prefix.len());
+                if end > start + prefix.len() {
+                    best = Some((start, end));
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Pass on normal text, linking the URLs in it.
+    fn link_text(&mut self, text: &str) -> bool {
+        let mut rest = text;
+        while let Some((start, end)) = self.find_url(rest) {
+            if start > 0 && !self.inner.text(TextType::Normal, &rest[..start]) {
+                return false;
+            }
+            if end == rest.len() {
+                // The next text event may continue the URL
+                self.held.push((TextType::Normal, rest[start..].to_string()));
+                return true;
+            }
+            let url = &rest[start..end];
+            if !self.emit_link(&[(TextType::Normal, url.to_string())]) {
+                return false;
+            }
+            rest = &rest[end..];
+        }
+        rest.is_empty() || self.inner.text(TextType::Normal, rest)
+    }
+
+    fn emit_link(&mut self, pieces: &[(TextType, String)]) -> bool {
+        let href = Attribute::from_pieces(pieces.iter().map(|(t, text)| (*t, text.as_str())));
+        let link = Span::Link(LinkDetail {
+            href,
+            title: "".into(),
+            is_autolink: true,
+        });
+        self.inner.enter_span(link)
+            && pieces.iter().all(|(text_type, text)| self.inner.text(*text_type, text))
+            && self.inner.leave_span(SpanType::Link)
+    }
+
+    /// Link the held URL once it is known where it ends, which is at the
+    /// latest when `done`. The text after it is passed on as usual.
+    fn link_held(&mut self, done: bool) -> bool {
+        let joined: String = self.held.iter().map(|(_, text)| text.as_str()).collect();
+        let end = self.find_url(&joined).map_or(0, |(_, end)| end);
+        // Until a space ends it, more text may continue the URL, even after
+        // punctuation that would be trimmed off now
+        if !done && end > 0 && !joined[end..].contains(|c: char| c.is_whitespace() || c == '<') {
+            return true;
+        }
+
+        // Split the text at the URL's end, keeping entities whole
+        let (mut url, mut rest) = (Vec::new(), Vec::new());
+        let mut offset = 0;
+        for (text_type, mut text) in std::mem::take(&mut self.held) {
+            let len = text.len();
+            if offset + len <= end {
+                url.push((text_type, text));
+            } else if offset < end && text_type == TextType::Normal {
+                let tail = text.split_off(end - offset);
+                url.push((text_type, text));
+                rest.push((text_type, tail));
+            } else {
+                rest.push((text_type, text));
+            }
+            offset += len;
+        }
+
+        let url_text: String = url.iter().map(|(_, text)| text.as_str()).collect();
+        let linked = if url.is_empty() {
+            true
+        } else if self.find_url(&url_text) == Some((0, url_text.len())) {
+            self.emit_link(&url)
+        } else {
+            url.iter().all(|(text_type, text)| self.inner.text(*text_type, text))
+        };
+        linked && rest.iter().all(|(text_type, text)| self.text(*text_type, text))
+    }
+
+    /// Pass on any held text before another event.
+    fn release(&mut self) -> bool {
+        while !self.held.is_empty() {
+            if !self.link_held(true) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Find the end of a URL whose body starts at `from`, trimming trailing
+/// punctuation the same way GFM extended autolinks do.
+fn url_end(text: &str, from: usize) -> usize {
+    let mut end = text[from..]
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .map(|i| from + i)
+        .unwrap_or(text.len());
+
+    loop {
+        let url = &text[from..end];
+        match url.chars().next_back() {
+            Some('?' | '!' | '.' | ',' | ':' | ';' | '*' | '_' | '~' | '\'' | '"') => end -= 1,
+            Some(')') if url.matches(')').count() > url.matches('(').count() => end -= 1,
+            _ => return end,
+        }
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for ExtraAutolinks<H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.release() && self.inner.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.release() && self.inner.leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        if !self.release() {
+            return false;
+        }
+        if matches!(span, Span::Link(_) | Span::Image(_) | Span::WikiLink(_)) {
+            self.link_depth += 1;
+        }
+        self.inner.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        if !self.release() {
+            return false;
+        }
+        if matches!(span_type, SpanType::Link | SpanType::Image | SpanType::WikiLink) {
+            self.link_depth = self.link_depth.saturating_sub(1);
+        }
+        self.inner.leave_span(span_type)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let linkable = matches!(text_type, TextType::Normal | TextType::Entity)
+            && self.link_depth == 0
+            && !self.prefixes.is_empty();
+        if !linkable {
+            return self.release() && self.inner.text(text_type, text);
+        }
+        if !self.held.is_empty() {
+            self.held.push((text_type, text.to_string()));
+            return self.link_held(false);
+        }
+        match text_type {
+            TextType::Normal => self.link_text(text),
+            _ => self.inner.text(text_type, text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, parse_to_events, Event, ParserFlags};
+
+    struct Collector(Vec<Event<'static>>);
+
+    impl ParserHandler for Collector {
+        fn enter_span(&mut self, span: Span) -> bool {
+            self.0.push(Event::EnterSpan(span.into_owned()));
+            true
+        }
+
+        fn leave_span(&mut self, span_type: SpanType) -> bool {
+            self.0.push(Event::LeaveSpan(span_type));
+            true
+        }
+
+        fn text(&mut self, text_type: TextType, text: &str) -> bool {
+            self.0.push(Event::Text(text_type, text.to_string().into()));
+            true
+        }
+    }
+
+    fn hrefs(markdown: &str, prefixes: &[&str]) -> Vec<String> {
+        let mut collector = Collector(Vec::new());
+        let mut handler = ExtraAutolinks::new(&mut collector, prefixes.iter().copied());
+        parse(markdown, ParserFlags::commonmark(), &mut handler).unwrap();
+        collector
+            .0
+            .into_iter()
+            .filter_map(|e| match e {
+                Event::EnterSpan(Span::Link(d)) => Some(d.href.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_extra_schemes() {
+        assert_eq!(
+            hrefs("Try ssh://host/x or SSH://h2, (jira://PROJ-123).", &["ssh://", "jira://"]),
+            vec!["ssh://host/x", "SSH://h2", "jira://PROJ-123"]
+        );
+        assert!(hrefs("xssh://host and ssh:// alone", &["ssh://"]).is_empty());
+    }
+
+    #[test]
+    fn test_urls_across_text_events() {
+        let prefixes = ["gemini://", "ssh://"];
+        assert_eq!(
+            hrefs("gemini://x.org/?a=1&amp;b=2 and ssh://host/a\\_b.", &prefixes),
+            vec!["gemini://x.org/?a=1&amp;b=2", "ssh://host/a_b"]
+        );
+        // As in GFM, an entity at the end is left out of the URL
+        assert_eq!(hrefs("ssh://h/a&quot;", &prefixes), vec!["ssh://h/a"]);
+        assert_eq!(hrefs("ssh://h/a&amp; *b* ssh://", &prefixes), vec!["ssh://h/a"]);
+
+        let mut collector = Collector(Vec::new());
+        let mut handler = ExtraAutolinks::new(&mut collector, prefixes);
+        parse("(gemini://x.org/&lt;a)", ParserFlags::commonmark(), &mut handler).unwrap();
+        let events = collector.0;
+        let href = events.iter().find_map(|e| match e {
+            Event::EnterSpan(Span::Link(d)) => Some(d.href.decoded().into_owned()),
+            _ => None,
+        });
+        assert_eq!(href.as_deref(), Some("gemini://x.org/<a"));
+        let texts: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Text(text_type, text) => Some((*text_type, text.as_ref())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (TextType::Normal, "("),
+                (TextType::Normal, "gemini://x.org/"),
+                (TextType::Entity, "&lt;"),
+                (TextType::Normal, "a"),
+                (TextType::Normal, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_existing_links_untouched() {
+        assert_eq!(
+            hrefs("[ssh://a](http://b) `ssh://c`", &["ssh://"]),
+            vec!["http://b"]
+        );
+        // Plain parsing is unaffected
+        let events = parse_to_events("ssh://a", ParserFlags::commonmark()).unwrap();
+        assert!(!events.iter().any(|e| matches!(e, Event::EnterSpan(_))));
+    }
+}
@@ -0,0 +1,220 @@
+//! Handler combinators.
+//!
+//! Run several handlers over a single parse instead of merging them by hand:
+//! [`Tee`] feeds every event to two handlers in lockstep, while [`Chain`]
+//! runs any number of handlers and lets each one drop out independently.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::combinator::Tee;
+//! use md4c::{parse, Block, ParserFlags, ParserHandler, TextType};
+//!
+//! #[derive(Default)]
+//! struct Blocks(usize);
+//!
+//! impl ParserHandler for Blocks {
+//!     fn enter_block(&mut self, _: Block) -> bool {
+//!         self.0 += 1;
+//!         true
+//!     }
+//! }
+//!
+//! #[derive(Default)]
+//! struct Words(usize);
+//!
+//! impl ParserHandler for Words {
+//!     fn text(&mut self, _: TextType, text: &str) -> bool {
+//!         self.0 += text.split_whitespace().count();
+//!         true
+//!     }
+//! }
+//!
+//! let mut both = Tee(Blocks::default(), Words::default());
+//! parse("# Title\n\nTwo words", ParserFlags::commonmark(), &mut both).unwrap();
+//! assert_eq!((both.0 .0, both.1 .0), (3, 3));
+//! ```
+
+use crate::parser::ParserHandler;
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+
+/// Feeds every event to two handlers.
+///
+/// Both handlers see each event; parsing stops as soon as either returns
+/// `false`. Nest `Tee`s to combine more handlers.
+#[derive(Debug, Clone, Default)]
+pub struct Tee<A, B>(pub A, pub B);
+
+impl<A: ParserHandler, B: ParserHandler> ParserHandler for Tee<A, B> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        let a = self.0.enter_block(block.clone());
+        self.1.enter_block(block) && a
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        let a = self.0.leave_block(block_type);
+        self.1.leave_block(block_type) && a
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        let a = self.0.enter_span(span.clone());
+        self.1.enter_span(span) && a
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        let a = self.0.leave_span(span_type);
+        self.1.leave_span(span_type) && a
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let a = self.0.text(text_type, text);
+        self.1.text(text_type, text) && a
+    }
+
+    fn leave_block_with_detail(&mut self, block: Block) -> bool {
+        let a = self.0.leave_block_with_detail(block.clone());
+        self.1.leave_block_with_detail(block) && a
+    }
+
+    fn leave_span_with_detail(&mut self, span: Span) -> bool {
+        let a = self.0.leave_span_with_detail(span.clone());
+        self.1.leave_span_with_detail(span) && a
+    }
+
+    fn wants_leave_details(&self) -> bool {
+        self.0.wants_leave_details() || self.1.wants_leave_details()
+    }
+}
+
+/// Runs handlers in sequence, each able to stop on its own.
+///
+/// Every event goes to each active handler in the order they were added. A
+/// handler returning `false` is done: it gets no further events while the
+/// others carry on. Parsing stops once no handler is active.
+///
+/// Handlers are usually added by `&mut` reference so their results can be
+/// read after parsing.
+#[derive(Default)]
+pub struct Chain<'a> {
+    // Handlers, with whether each is still active
+    handlers: Vec<(Box<dyn ParserHandler + 'a>, bool)>,
+}
+
+impl<'a> Chain<'a> {
+    /// Create an empty chain.
+    pub fn new() -> Self {
+        Chain {
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Add a handler to the end of the chain.
+    pub fn with(mut self, handler: impl ParserHandler + 'a) -> Self {
+        self.handlers.push((Box::new(handler), true));
+        self
+    }
+
+    /// Number of handlers that have not stopped.
+    pub fn active(&self) -> usize {
+        self.handlers.iter().filter(|(_, active)| *active).count()
+    }
+
+    fn each(&mut self, mut f: impl FnMut(&mut dyn ParserHandler) -> bool) -> bool {
+        let mut any = false;
+        for (handler, active) in &mut self.handlers {
+            if *active {
+                *active = f(handler.as_mut());
+                any |= *active;
+            }
+        }
+        any
+    }
+}
+
+impl ParserHandler for Chain<'_> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.each(|h| h.enter_block(block.clone()))
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.each(|h| h.leave_block(block_type))
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.each(|h| h.enter_span(span.clone()))
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.each(|h| h.leave_span(span_type))
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        self.each(|h| h.text(text_type, text))
+    }
+
+    fn leave_block_with_detail(&mut self, block: Block) -> bool {
+        self.each(|h| h.leave_block_with_detail(block.clone()))
+    }
+
+    fn leave_span_with_detail(&mut self, span: Span) -> bool {
+        self.each(|h| h.leave_span_with_detail(span.clone()))
+    }
+
+    fn wants_leave_details(&self) -> bool {
+        self.handlers.iter().any(|(handler, _)| handler.wants_leave_details())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, ParserFlags};
+
+    /// Records text until it has seen `limit` runs.
+    struct Texts {
+        seen: Vec<String>,
+        limit: usize,
+    }
+
+    impl Texts {
+        fn new(limit: usize) -> Self {
+            Texts {
+                seen: Vec::new(),
+                limit,
+            }
+        }
+    }
+
+    impl ParserHandler for Texts {
+        fn text(&mut self, _: TextType, text: &str) -> bool {
+            self.seen.push(text.to_string());
+            self.seen.len() < self.limit
+        }
+    }
+
+    const DOC: &str = "one\n\ntwo\n\nthree\n";
+
+    #[test]
+    fn test_tee_stops_with_either() {
+        let mut tee = Tee(Texts::new(usize::MAX), Texts::new(2));
+        assert!(parse(DOC, ParserFlags::commonmark(), &mut tee).is_err());
+        assert_eq!(tee.0.seen, ["one", "two"]);
+        assert_eq!(tee.1.seen, ["one", "two"]);
+    }
+
+    #[test]
+    fn test_chain_flow_control() {
+        let (mut all, mut first) = (Texts::new(usize::MAX), Texts::new(1));
+        let mut chain = Chain::new().with(&mut first).with(&mut all);
+        parse(DOC, ParserFlags::commonmark(), &mut chain).unwrap();
+        assert_eq!(chain.active(), 1);
+        drop(chain);
+        assert_eq!(first.seen, ["one"]);
+        assert_eq!(all.seen, ["one", "two", "three"]);
+
+        // Parsing stops once every handler is done
+        let (mut a, mut b) = (Texts::new(1), Texts::new(2));
+        let mut chain = Chain::new().with(&mut a).with(&mut b);
+        assert!(parse(DOC, ParserFlags::commonmark(), &mut chain).is_err());
+    }
+}
@@ -0,0 +1,414 @@
+//! Highlighted text, superscripts and subscripts.
+//!
+//! MD4C knows none of `==highlighted==`, `^super^` or `~sub~` text, so with
+//! [`ParserFlags::mark`] or [`ParserFlags::super_sub`] the parser wraps the
+//! handler in [`Delimiters`], which finds pairs of delimiters in normal text
+//! and reports what lies between them as a span.
+//!
+//! Like `~~`, a delimiter is a run of exactly as many characters as it has
+//! (`==`, `^`, `~`). An opening one must be followed, and a closing one
+//! preceded, by something other than whitespace; both must be in the same
+//! span. Superscripts and subscripts can't contain whitespace at all.
+//!
+//! MD4C's strikethrough also takes single tildes, so with `super_sub` a
+//! strikethrough opened by one `~` is reported as a subscript.
+//!
+//! [`ParserFlags::mark`]: crate::ParserFlags::mark
+//! [`ParserFlags::super_sub`]: crate::ParserFlags::super_sub
+
+use crate::parser::{reborrow, source_offset, Event, ParserHandler};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The spans this adapter finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Mark,
+    Superscript,
+    Subscript,
+}
+
+impl Kind {
+    fn from_delimiter(c: u8) -> Option<Self> {
+        match c {
+            b'=' => Some(Kind::Mark),
+            b'^' => Some(Kind::Superscript),
+            b'~' => Some(Kind::Subscript),
+            _ => None,
+        }
+    }
+
+    fn delimiter_len(self) -> usize {
+        match self {
+            Kind::Mark => 2,
+            Kind::Superscript | Kind::Subscript => 1,
+        }
+    }
+
+    fn span(self) -> Span<'static> {
+        match self {
+            Kind::Mark => Span::Mark,
+            Kind::Superscript => Span::Superscript,
+            Kind::Subscript => Span::Subscript,
+        }
+    }
+}
+
+/// An opening delimiter waiting for its closing one.
+struct Pending<'i> {
+    kind: Kind,
+    // Span nesting depth the opening delimiter was found at
+    depth: usize,
+    // The delimiter, emitted as text if no closing one turns up
+    opener: Cow<'i, str>,
+    // Events held back since the delimiter
+    events: Vec<Event<'i>>,
+}
+
+/// Handler adapter turning delimited text into spans.
+pub(crate) struct Delimiters<'i, H> {
+    inner: H,
+    input: &'i str,
+    mark: bool,
+    super_sub: bool,
+    depth: usize,
+    // Opening delimiters, innermost last
+    pending: Vec<Pending<'i>>,
+    // Last character of the previous text, to tell the length of runs
+    // split by an escape
+    last: Option<char>,
+    // A strikethrough was entered, and is held back until its first text
+    // tells whether it opened with a single tilde
+    held_strike: bool,
+    // For each open strikethrough, whether it is reported as a subscript
+    strikes: Vec<bool>,
+}
+
+impl<'i, H: ParserHandler> Delimiters<'i, H> {
+    pub fn new(inner: H, input: &'i str, mark: bool, super_sub: bool) -> Self {
+        Delimiters {
+            inner,
+            input,
+            mark,
+            super_sub,
+            depth: 0,
+            pending: Vec::new(),
+            last: None,
+            held_strike: false,
+            strikes: Vec::new(),
+        }
+    }
+
+    fn kind_of(&self, c: u8) -> Option<Kind> {
+        Kind::from_delimiter(c).filter(|kind| match kind {
+            Kind::Mark => self.mark,
+            Kind::Superscript | Kind::Subscript => self.super_sub,
+        })
+    }
+
+    /// Text borrowed from the input where possible, so the handler can
+    /// still find its source offset.
+    fn borrow(&self, text: &str) -> Cow<'i, str> {
+        match source_offset(self.input, text) {
+            Some(offset) => Cow::Borrowed(&self.input[offset..offset + text.len()]),
+            None => Cow::Owned(text.to_string()),
+        }
+    }
+
+    fn replay(&mut self, events: Vec<Event<'i>>) -> bool {
+        events.into_iter().all(|event| match event {
+            Event::EnterBlock(block) => self.inner.enter_block(block),
+            Event::LeaveBlock(block_type) => self.inner.leave_block(block_type),
+            Event::EnterSpan(span) => self.inner.enter_span(span),
+            Event::LeaveSpan(span_type) => self.inner.leave_span(span_type),
+            Event::Text(text_type, text) => self.inner.text(text_type, &text),
+        })
+    }
+
+    /// Pass events on, or hold them back while a delimiter is pending.
+    fn forward(&mut self, events: Vec<Event<'i>>) -> bool {
+        match self.pending.last_mut() {
+            Some(pending) => {
+                pending.events.extend(events);
+                true
+            }
+            None => self.replay(events),
+        }
+    }
+
+    fn emit(&mut self, text_type: TextType, text: Cow<'i, str>) -> bool {
+        text.is_empty() || self.forward(vec![Event::Text(text_type, text)])
+    }
+
+    /// The delimiter at `index` has no closing one: it was plain text.
+    fn dissolve(&mut self, index: usize) -> bool {
+        let pending = self.pending.remove(index);
+        let mut events = vec![Event::Text(TextType::Normal, pending.opener)];
+        events.extend(pending.events);
+        // In front of the delimiters above it, which stay pending
+        match index.checked_sub(1) {
+            Some(below) => {
+                self.pending[below].events.extend(events);
+                true
+            }
+            None => self.replay(events),
+        }
+    }
+
+    /// The closing delimiter for the one at `index` was found: wrap what
+    /// it held back in a span.
+    fn close(&mut self, index: usize) -> bool {
+        while self.pending.len() > index + 1 {
+            if !self.dissolve(self.pending.len() - 1) {
+                return false;
+            }
+        }
+        let pending = self.pending.pop().unwrap();
+        let span = pending.kind.span();
+        let span_type = span.span_type();
+        let mut events = vec![Event::EnterSpan(span)];
+        events.extend(pending.events);
+        events.push(Event::LeaveSpan(span_type));
+        self.forward(events)
+    }
+
+    /// Give up on delimiters that may not span whitespace.
+    fn break_scripts(&mut self) -> bool {
+        while let Some(index) = self.pending.iter().rposition(|p| p.kind != Kind::Mark) {
+            if !self.dissolve(index) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Give up on delimiters at the current depth.
+    fn flush(&mut self) -> bool {
+        while self.pending.last().is_some_and(|p| p.depth >= self.depth) {
+            if !self.dissolve(self.pending.len() - 1) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Emit a held back strikethrough, as a subscript if the text at
+    /// `offset` follows a single tilde.
+    fn release_strike(&mut self, offset: Option<usize>) -> bool {
+        if !std::mem::take(&mut self.held_strike) {
+            return true;
+        }
+        let before = offset.map(|offset| &self.input[..offset]);
+        let single = before.is_some_and(|b| b.ends_with('~') && !b.ends_with("~~"));
+        self.strikes.push(single);
+        let span = if single { Span::Subscript } else { Span::Strikethrough };
+        self.forward(vec![Event::EnterSpan(span)])
+    }
+
+    /// Whether the byte at `offset` in the input is escaped by a backslash.
+    fn escaped(&self, offset: usize) -> bool {
+        let before = &self.input.as_bytes()[..offset];
+        before.iter().rev().take_while(|&&b| b == b'\\').count() % 2 == 1
+    }
+
+    fn normal_text(&mut self, text: Cow<'i, str>) -> bool {
+        let offset = match text {
+            Cow::Borrowed(text) => source_offset(self.input, text),
+            Cow::Owned(_) => None,
+        };
+        let bytes = text.as_bytes();
+        let (mut start, mut i) = (0, 0);
+        while i < bytes.len() {
+            if bytes[i].is_ascii_whitespace() && self.pending.iter().any(|p| p.kind != Kind::Mark) {
+                if !self.emit(TextType::Normal, slice(&text, start..i)) || !self.break_scripts() {
+                    return false;
+                }
+                start = i;
+            }
+            let Some(kind) = self.kind_of(bytes[i]) else {
+                i += 1;
+                continue;
+            };
+            if offset.is_some_and(|offset| self.escaped(offset + i)) {
+                i += 1;
+                continue;
+            }
+            let run = bytes[i..].iter().take_while(|&&b| b == bytes[i]).count();
+            let before = if i > 0 { text[..i].chars().next_back() } else { self.last };
+            let after = text[i + run..].chars().next();
+            if run != kind.delimiter_len() || before == Some(bytes[i] as char) {
+                i += run;
+                continue;
+            }
+
+            let closes = self.pending.iter().rposition(|p| p.kind == kind).filter(|&index| {
+                let pending = &self.pending[index];
+                let empty =
+                    index + 1 == self.pending.len() && pending.events.is_empty() && i == start;
+                pending.depth == self.depth && !empty && before.is_none_or(|c| !c.is_whitespace())
+            });
+            let opens = !self.pending.iter().any(|p| p.kind == kind)
+                && after.is_none_or(|c| !c.is_whitespace());
+            if closes.is_some() || opens {
+                if !self.emit(TextType::Normal, slice(&text, start..i)) {
+                    return false;
+                }
+                match closes {
+                    Some(index) => {
+                        if !self.close(index) {
+                            return false;
+                        }
+                    }
+                    None => self.pending.push(Pending {
+                        kind,
+                        depth: self.depth,
+                        opener: slice(&text, i..i + run),
+                        events: Vec::new(),
+                    }),
+                }
+                start = i + run;
+            }
+            i += run;
+        }
+        self.emit(TextType::Normal, slice(&text, start..text.len()))
+    }
+}
+
+/// Part of a text, still borrowed if the text is.
+fn slice<'i>(text: &Cow<'i, str>, range: Range<usize>) -> Cow<'i, str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(&text[range]),
+        Cow::Owned(text) => Cow::Owned(text[range].to_string()),
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for Delimiters<'_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.last = None;
+        self.release_strike(None) && self.flush() && self.inner.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.last = None;
+        self.release_strike(None) && self.flush() && self.inner.leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.last = None;
+        if !self.release_strike(None) {
+            return false;
+        }
+        self.depth += 1;
+        if self.super_sub && matches!(span, Span::Strikethrough) {
+            self.held_strike = true;
+            return true;
+        }
+        let input = self.input;
+        let span = span.map_strs(&mut |s| reborrow(input, s));
+        self.forward(vec![Event::EnterSpan(span)])
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.last = None;
+        // The span the opening delimiters are in ends before the closing ones
+        if !self.release_strike(None) || !self.flush() {
+            return false;
+        }
+        self.depth = self.depth.saturating_sub(1);
+        let span_type = match span_type {
+            SpanType::Strikethrough if self.super_sub && self.strikes.pop() == Some(true) => {
+                SpanType::Subscript
+            }
+            _ => span_type,
+        };
+        self.forward(vec![Event::LeaveSpan(span_type)])
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let text = self.borrow(text);
+        let offset = match text {
+            Cow::Borrowed(text) => source_offset(self.input, text),
+            Cow::Owned(_) => None,
+        };
+        if !self.release_strike(offset) {
+            return false;
+        }
+        let ok = match text_type {
+            TextType::Normal => self.normal_text(text.clone()),
+            TextType::SoftBreak | TextType::HardBreak => {
+                self.break_scripts() && self.emit(text_type, text.clone())
+            }
+            _ => self.emit(text_type, text.clone()),
+        };
+        self.last = text.chars().next_back();
+        ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fmt::{format, FormatOptions};
+    use crate::parser::{parse_to_events, Event, ParserFlags};
+
+    fn spans(input: &str, flags: ParserFlags) -> String {
+        parse_to_events(input, flags)
+            .unwrap()
+            .into_iter()
+            .map(|event| match event {
+                Event::EnterSpan(span) => format!("<{:?}>", span.span_type()),
+                Event::LeaveSpan(span_type) => format!("</{:?}>", span_type),
+                Event::Text(_, text) => text.into_owned(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_marks() {
+        let spans = |input| spans(input, ParserFlags::commonmark().mark());
+        assert_eq!(spans("a ==b== c"), "a <Mark>b</Mark> c");
+        assert_eq!(
+            spans("==a *b*== ==c=="),
+            "<Mark>a <Emphasis>b</Emphasis></Mark> <Mark>c</Mark>"
+        );
+        assert_eq!(spans("*==a==*"), "<Emphasis><Mark>a</Mark></Emphasis>");
+        assert_eq!(spans("`==a==`"), "<Code>==a==</Code>");
+        for input in ["a == b == c", "a ==b", "===a===", "==a *b==*", "a ====", "\\==a=="] {
+            assert!(!spans(input).contains("<Mark>"), "{}", input);
+        }
+        assert_eq!(spans("*==a* b=="), "<Emphasis>==a</Emphasis> b==");
+        assert_eq!(spans("x^2^"), "x^2^");
+    }
+
+    #[test]
+    fn test_super_sub() {
+        let spans = |input| spans(input, ParserFlags::github().mark().super_sub());
+        assert_eq!(
+            spans("x^2^ H~2~O"),
+            "x<Superscript>2</Superscript> H<Subscript>2</Subscript>O"
+        );
+        assert_eq!(
+            spans("~i~ ~~del~~"),
+            "<Subscript>i</Subscript> <Strikethrough>del</Strikethrough>"
+        );
+        assert_eq!(spans("==e^x^=="), "<Mark>e<Superscript>x</Superscript></Mark>");
+        assert_eq!(spans("^a ==b== c^"), "^a <Mark>b</Mark> c^");
+        assert_eq!(spans("2 ^ 3 ^ 4"), "2 ^ 3 ^ 4");
+    }
+
+    #[test]
+    fn test_escaped_super_sub() {
+        let spans = |input| spans(input, ParserFlags::commonmark().super_sub());
+        for input in ["\\~a\\~", "\\^a\\^", "\\~a~", "a^b\\^", "\\~text\\~\\~"] {
+            let spans = spans(input);
+            assert!(!spans.contains("<Sub") && !spans.contains("<Sup"), "{}: {}", input, spans);
+        }
+        // An escaped backslash leaves the delimiter alone
+        assert_eq!(spans("\\\\^a^"), "\\<Superscript>a</Superscript>");
+
+        let options = FormatOptions::new().with_parser_flags(ParserFlags::commonmark().super_sub());
+        let md = format("a ~text~~ b^", &options);
+        assert_eq!(spans(&md), "a ~text~~ b^");
+    }
+}
@@ -0,0 +1,173 @@
+//! Wiki link embeds.
+//!
+//! MD4C reads the `![` of `![[note]]` as the start of an image, which leaves
+//! an embed as plain text. With [`ParserFlags::wiki_links`] the parser wraps
+//! the handler in [`Embeds`], which finds `![[target]]` and
+//! `![[target|label]]` in normal text and reports them as wiki links with
+//! [`WikiLinkDetail::is_embed`] set.
+//!
+//! As in MD4C, a target is at most 100 bytes long and has no brackets, and
+//! a label after a pipe can't be empty. An embed has to be in a single run
+//! of text, so one with an entity or an escape in it, or emphasis in its
+//! label, stays text. An escaped `!` is a literal one, followed by a plain
+//! wiki link.
+//!
+//! [`ParserFlags::wiki_links`]: crate::ParserFlags::wiki_links
+//! [`WikiLinkDetail::is_embed`]: crate::WikiLinkDetail::is_embed
+
+use crate::parser::ParserHandler;
+use crate::types::{Attribute, Block, BlockType, Span, SpanType, TextType, WikiLinkDetail};
+use std::borrow::Cow;
+
+/// Longest target MD4C takes for a wiki link.
+const MAX_TARGET_LEN: usize = 100;
+
+/// Handler adapter turning `![[target]]` text into wiki link embeds.
+pub(crate) struct Embeds<H> {
+    inner: H,
+}
+
+impl<H: ParserHandler> Embeds<H> {
+    pub fn new(inner: H) -> Self {
+        Embeds { inner }
+    }
+
+    fn normal_text(&mut self, mut text: &str) -> bool {
+        while let Some(embed) = find_embed(text) {
+            let span = Span::WikiLink(WikiLinkDetail {
+                target: Attribute::new(embed.target),
+                label: embed.label.map(Cow::Borrowed),
+                is_embed: true,
+            });
+            let content = embed.label.unwrap_or(embed.target);
+            let before = &text[..embed.range.start];
+            let ok = (before.is_empty() || self.inner.text(TextType::Normal, before))
+                && self.inner.enter_span(span)
+                && self.inner.text(TextType::Normal, content)
+                && self.inner.leave_span(SpanType::WikiLink);
+            if !ok {
+                return false;
+            }
+            text = &text[embed.range.end..];
+        }
+        text.is_empty() || self.inner.text(TextType::Normal, text)
+    }
+}
+
+/// An embed found in a text.
+struct Embed<'t> {
+    // From the `!` to after the closing `]]`
+    range: std::ops::Range<usize>,
+    target: &'t str,
+    label: Option<&'t str>,
+}
+
+/// The first embed in `text`.
+fn find_embed(text: &str) -> Option<Embed<'_>> {
+    let mut from = 0;
+    while let Some(found) = text[from..].find("![[") {
+        let start = from + found;
+        let rest = &text[start + 3..];
+        if let Some(close) = rest.find("]]") {
+            let (target, label) = match rest[..close].split_once('|') {
+                Some((target, label)) => (target, Some(label)),
+                None => (&rest[..close], None),
+            };
+            let valid = !target.is_empty()
+                && target.len() <= MAX_TARGET_LEN
+                && !target.contains(['[', ']'])
+                && label.is_none_or(|label| !label.is_empty() && !label.contains(['[', ']']));
+            if valid {
+                return Some(Embed {
+                    range: start..start + 3 + close + 2,
+                    target,
+                    label,
+                });
+            }
+        }
+        from = start + 1;
+    }
+    None
+}
+
+impl<H: ParserHandler> ParserHandler for Embeds<H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.inner.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.inner.leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.inner.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.inner.leave_span(span_type)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        match text_type {
+            TextType::Normal => self.normal_text(text),
+            _ => self.inner.text(text_type, text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{parse_to_events, Event, ParserFlags};
+    use crate::types::Span;
+    use crate::{render_html, HtmlFlags};
+
+    fn links(input: &str) -> String {
+        parse_to_events(input, ParserFlags::commonmark().wiki_links())
+            .unwrap()
+            .into_iter()
+            .map(|event| match event {
+                Event::EnterSpan(Span::WikiLink(link)) if link.is_embed => {
+                    format!("<embed {}>", link.target)
+                }
+                Event::EnterSpan(span) => format!("<{:?}>", span.span_type()),
+                Event::LeaveSpan(span_type) => format!("</{:?}>", span_type),
+                Event::Text(_, text) => text.into_owned(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_embeds() {
+        assert_eq!(
+            links("See ![[Note]] and [[Page]]"),
+            "See <embed Note>Note</WikiLink> and <WikiLink>Page</WikiLink>"
+        );
+        assert_eq!(
+            links("a![[n|the note]]b ![[x]]"),
+            "a<embed n>the note</WikiLink>b <embed x>x</WikiLink>"
+        );
+        // An escaped `!`, an image and text that can't be a target
+        assert_eq!(links("\\![[n]]"), "!<WikiLink>n</WikiLink>");
+        assert_eq!(links("![[n]](i.png)"), "<Image>[n]</Image>");
+        assert_eq!(
+            links("![[]] ![[a[b]] ![[c|]] `![[d]]`"),
+            "![[]] ![[a[b]] ![[c|]] <Code>![[d]]</Code>"
+        );
+        let long = format!("![[{}]]", "x".repeat(101));
+        assert_eq!(links(&long), long);
+    }
+
+    #[test]
+    fn test_embeds_in_html() {
+        // MD4C's renderer doesn't know embeds and keeps them as text, and
+        // so does the Rust one
+        let md = "![[foo]] ![[p|q <]] ![[a &amp; b|the *b*]] [[c]]\n";
+        let expected = "<p>![[foo]] ![[p|q &lt;]] ![[a &amp; b|the <em>b</em>]] \
+                        <x-wikilink data-target=\"c\">c</x-wikilink></p>\n";
+        for flags in [ParserFlags::commonmark(), ParserFlags::commonmark().mark()] {
+            let html = render_html(md, flags.wiki_links(), HtmlFlags::new()).unwrap();
+            assert_eq!(html, expected);
+        }
+    }
+}
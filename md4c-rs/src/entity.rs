@@ -0,0 +1,66 @@
+//! HTML entity references, decoded with MD4C's entity table.
+
+use crate::sys;
+
+/// Length of the entity reference at the start of `s`, or 0 if there is
+/// none.
+pub(crate) fn entity_len(s: &str) -> usize {
+    let b = s.as_bytes();
+    let (digits, min, max, valid): (usize, usize, usize, fn(&u8) -> bool) = match b.get(1) {
+        Some(b'#') if matches!(b.get(2), Some(b'x' | b'X')) => (3, 1, 6, u8::is_ascii_hexdigit),
+        Some(b'#') => (2, 1, 7, u8::is_ascii_digit),
+        Some(c) if c.is_ascii_alphabetic() => (1, 2, 48, u8::is_ascii_alphanumeric),
+        _ => return 0,
+    };
+    let count = b[digits..].iter().take_while(|c| valid(c)).count();
+    if (min..=max).contains(&count) && b.get(digits + count) == Some(&b';') {
+        digits + count + 1
+    } else {
+        0
+    }
+}
+
+/// Decode an entity reference such as `&amp;` or `&#x41;`.
+///
+/// Unknown named entities are returned verbatim.
+pub(crate) fn decode_entity(entity: &str) -> String {
+    let codepoint = |cp: u32| {
+        char::from_u32(cp)
+            .filter(|&c| c != '\0')
+            .unwrap_or('\u{FFFD}')
+    };
+
+    if let Some(num) = entity.strip_prefix("&#").and_then(|e| e.strip_suffix(';')) {
+        let cp = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => num.parse(),
+        };
+        return codepoint(cp.unwrap_or(0)).to_string();
+    }
+
+    let found = unsafe { sys::entity_lookup(entity.as_ptr() as *const _, entity.len()) };
+    if found.is_null() {
+        return entity.to_string();
+    }
+    let codepoints = unsafe { (*found).codepoints };
+    let mut decoded = codepoint(codepoints[0]).to_string();
+    if codepoints[1] != 0 {
+        decoded.push(codepoint(codepoints[1]));
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entity() {
+        assert_eq!(decode_entity("&amp;"), "&");
+        assert_eq!(decode_entity("&#x1F600;"), "😀");
+        assert_eq!(decode_entity("&#0;"), "\u{FFFD}");
+        assert_eq!(decode_entity("&nosuch;"), "&nosuch;");
+        assert_eq!(entity_len("&amp; x"), 5);
+        assert_eq!(entity_len("& amp;"), 0);
+    }
+}
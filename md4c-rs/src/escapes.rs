@@ -0,0 +1,195 @@
+//! Backslash escapes and entity references with their source positions.
+//!
+//! Parser events carry the expanded text: `\*` arrives as `*`, and although
+//! entities keep their original spelling, nothing says where they were.
+//! [`TrackEscapes`] wraps a handler and records each escape and entity in
+//! the document text together with its exact source text and byte range,
+//! so tools such as formatters can reproduce the author's escaping.
+//!
+//! Only text content is covered; escapes inside link destinations, titles
+//! and code info strings are already resolved in the event details.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::escapes::{find_escapes, EscapeKind};
+//! use md4c::ParserFlags;
+//!
+//! let markdown = r"1\. Not a list &amp; \*not emphasis*";
+//! let escapes = find_escapes(markdown, ParserFlags::commonmark()).unwrap();
+//!
+//! let found: Vec<_> = escapes.iter().map(|e| (e.kind, e.source, e.offset)).collect();
+//! assert_eq!(
+//!     found,
+//!     [
+//!         (EscapeKind::Backslash, r"\.", 1),
+//!         (EscapeKind::Entity, "&amp;", 15),
+//!         (EscapeKind::Backslash, r"\*", 21),
+//!     ]
+//! );
+//! ```
+
+use crate::parser::{parse, source_offset, ParseResult, ParserFlags, ParserHandler};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+use std::ops::Range;
+
+/// Kind of an [`Escape`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EscapeKind {
+    /// A backslash escape such as `\*`
+    Backslash,
+    /// An entity or numeric character reference such as `&amp;` or `&#42;`
+    Entity,
+}
+
+/// An escape or entity reference in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Escape<'a> {
+    /// What kind of escape this is
+    pub kind: EscapeKind,
+    /// The original source text, including the backslash or `&` and `;`
+    pub source: &'a str,
+    /// Byte offset of `source` in the input
+    pub offset: usize,
+}
+
+impl Escape<'_> {
+    /// Byte range of the escape in the input.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.source.len()
+    }
+
+    /// The character a backslash escape stands for.
+    pub fn escaped_char(&self) -> Option<char> {
+        match self.kind {
+            EscapeKind::Backslash => self.source[1..].chars().next(),
+            EscapeKind::Entity => None,
+        }
+    }
+}
+
+/// Handler adapter recording escapes and entities in the text it passes on.
+pub struct TrackEscapes<'a, H> {
+    inner: H,
+    input: &'a str,
+    escapes: Vec<Escape<'a>>,
+}
+
+impl<'a, H: ParserHandler> TrackEscapes<'a, H> {
+    /// Wrap a handler for parsing `input`.
+    ///
+    /// `input` must be the same string passed to [`parse`], since offsets
+    /// are recovered from where the text events point.
+    pub fn new(inner: H, input: &'a str) -> Self {
+        TrackEscapes {
+            inner,
+            input,
+            escapes: Vec::new(),
+        }
+    }
+
+    /// Escapes found so far, in document order.
+    pub fn escapes(&self) -> &[Escape<'a>] {
+        &self.escapes
+    }
+
+    /// Unwrap the inner handler and the escapes found.
+    pub fn into_parts(self) -> (H, Vec<Escape<'a>>) {
+        (self.inner, self.escapes)
+    }
+
+    fn record(&mut self, text_type: TextType, text: &str) {
+        let Some(offset) = source_offset(self.input, text) else {
+            return;
+        };
+        let (kind, start) = match text_type {
+            TextType::Entity => (EscapeKind::Entity, offset),
+            // MD4C emits each escaped character as its own text run, which
+            // starts right after the backslash
+            TextType::Normal if text.len() == 1 && text.as_bytes()[0].is_ascii_punctuation() => {
+                let backslashes = self.input.as_bytes()[..offset]
+                    .iter()
+                    .rev()
+                    .take_while(|&&b| b == b'\\')
+                    .count();
+                if backslashes % 2 == 0 {
+                    return;
+                }
+                (EscapeKind::Backslash, offset - 1)
+            }
+            _ => return,
+        };
+        self.escapes.push(Escape {
+            kind,
+            source: &self.input[start..offset + text.len()],
+            offset: start,
+        });
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for TrackEscapes<'_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.inner.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.inner.leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.inner.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.inner.leave_span(span_type)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        self.record(text_type, text);
+        self.inner.text(text_type, text)
+    }
+}
+
+/// Find all escapes and entity references in the text of a document.
+pub fn find_escapes(input: &str, flags: ParserFlags) -> ParseResult<Vec<Escape<'_>>> {
+    struct Ignore;
+    impl ParserHandler for Ignore {}
+
+    let mut tracker = TrackEscapes::new(Ignore, input);
+    parse(input, flags, &mut tracker)?;
+    Ok(tracker.escapes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(input: &str) -> Vec<&str> {
+        let escapes = find_escapes(input, ParserFlags::github()).unwrap();
+        for escape in &escapes {
+            assert_eq!(&input[escape.range()], escape.source);
+        }
+        escapes.iter().map(|e| e.source).collect()
+    }
+
+    #[test]
+    fn test_backslash_escapes() {
+        assert_eq!(sources(r"a\*b\\c \#"), [r"\*", r"\\", r"\#"]);
+        // An escaped backslash doesn't escape what follows
+        assert_eq!(sources(r"\\*x\\\*"), [r"\\", r"\\", r"\*"]);
+        // Backslashes before other characters are literal
+        assert!(sources(r"C:\dir \ä").is_empty());
+        // Code keeps its backslashes
+        assert!(sources("`\\*` and\n\n    \\*\n").is_empty());
+    }
+
+    #[test]
+    fn test_entities_and_nesting() {
+        let md = "# A &copy; B\n\n> [x\\]](u) &#42; *&lt;\\_*\n";
+        assert_eq!(sources(md), ["&copy;", r"\]", "&#42;", "&lt;", r"\_"]);
+
+        let escapes = find_escapes(md, ParserFlags::commonmark()).unwrap();
+        assert_eq!(escapes[1].escaped_char(), Some(']'));
+        assert_eq!(escapes[0].escaped_char(), None);
+    }
+}
@@ -0,0 +1,937 @@
+//! Markdown formatting.
+//!
+//! [`format`] parses a document and writes it back out as canonical
+//! Markdown: one bullet character, one code fence style, one heading style,
+//! and paragraphs optionally re-wrapped to a column limit. The output
+//! renders the same as the input.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::fmt::{format, FormatOptions};
+//!
+//! let messy = "Title\n=====\n\n* one\n+ two\n\n~~~rust\nfn main() {}\n~~~\n";
+//! assert_eq!(
+//!     format(messy, &FormatOptions::new()),
+//!     "# Title\n\n- one\n\n* two\n\n```rust\nfn main() {}\n```\n"
+//! );
+//! ```
+
+use crate::ast::{parse_to_ast, Node, NodeKind};
+use crate::entity::entity_len;
+use crate::parser::{ParserFlags, FLAG_MARK, FLAG_SUPER_SUB};
+use crate::sys;
+use crate::types::*;
+
+/// How headings are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeadingStyle {
+    /// `# Heading` for every level
+    #[default]
+    Atx,
+    /// Underlined with `===` or `---` for levels 1 and 2, ATX otherwise
+    Setext,
+}
+
+/// How emphasis and strong emphasis are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmphasisStyle {
+    /// `*emphasis*` and `**strong**`
+    #[default]
+    Asterisk,
+    /// `_emphasis_` and `__strong__`, except inside words, where
+    /// underscores don't delimit emphasis, and with the underline
+    /// extension, where they mean underline
+    Underscore,
+}
+
+/// Options for [`format`] and [`to_markdown`](crate::ast::to_markdown).
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Flags for parsing the input; extensions such as tables are only
+    /// written back if enabled
+    pub parser_flags: ParserFlags,
+    /// Bullet for unordered lists
+    pub bullet: ListMark,
+    /// Code block fence; [`FenceChar::None`] writes indented code blocks
+    /// where possible
+    pub fence: FenceChar,
+    /// How headings are written
+    pub heading_style: HeadingStyle,
+    /// How emphasis is written
+    pub emphasis: EmphasisStyle,
+    /// Column to wrap paragraphs at, or `None` to keep line breaks as written
+    pub width: Option<usize>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            parser_flags: ParserFlags::github(),
+            bullet: ListMark::Dash,
+            fence: FenceChar::Backtick,
+            heading_style: HeadingStyle::Atx,
+            emphasis: EmphasisStyle::Asterisk,
+            width: None,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// GitHub-flavored input, `-` bullets, backtick fences and ATX headings,
+    /// without re-wrapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the parser flags for the input.
+    pub fn with_parser_flags(mut self, flags: ParserFlags) -> Self {
+        self.parser_flags = flags;
+        self
+    }
+
+    /// Set the bullet for unordered lists.
+    pub fn with_bullet(mut self, bullet: ListMark) -> Self {
+        self.bullet = bullet;
+        self
+    }
+
+    /// Set the code block fence.
+    pub fn with_fence(mut self, fence: FenceChar) -> Self {
+        self.fence = fence;
+        self
+    }
+
+    /// Set the heading style.
+    pub fn with_heading_style(mut self, style: HeadingStyle) -> Self {
+        self.heading_style = style;
+        self
+    }
+
+    /// Set the emphasis style.
+    pub fn with_emphasis(mut self, style: EmphasisStyle) -> Self {
+        self.emphasis = style;
+        self
+    }
+
+    /// Wrap paragraphs at the given column.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+}
+
+/// Reformat markdown into canonical form.
+///
+/// Adjacent lists alternate between two markers, since reusing the same
+/// marker would merge them into one list. Link reference definitions are
+/// resolved into inline links. If the input fails to parse, it is returned
+/// unchanged.
+pub fn format(markdown: &str, options: &FormatOptions) -> String {
+    match parse_to_ast(markdown, options.parser_flags) {
+        Ok(doc) => MarkdownWriter::new(options).document(&doc),
+        Err(_) => markdown.to_string(),
+    }
+}
+
+// Inline output uses these to mark spaces that must not become line breaks,
+// hard line breaks, and line breaks inside raw HTML, until lines are laid out
+const KEEP: char = '\0';
+const HARD_BREAK: char = '\u{1}';
+const RAW_BREAK: char = '\u{2}';
+
+/// Writes a syntax tree as Markdown.
+pub(crate) struct MarkdownWriter<'a> {
+    options: &'a FormatOptions,
+}
+
+/// Where a block sits relative to its siblings.
+#[derive(Default, Clone, Copy)]
+struct Context {
+    // Directly after inline text or a block quote in a tight list item
+    after_text: bool,
+    // Directly after a list
+    after_list: bool,
+    // First thing in a list item
+    item_start: bool,
+}
+
+impl<'a> MarkdownWriter<'a> {
+    pub fn new(options: &'a FormatOptions) -> Self {
+        MarkdownWriter { options }
+    }
+
+    fn flag(&self, flag: u32) -> bool {
+        self.options.parser_flags.raw() & flag != 0
+    }
+
+    pub fn document(&self, doc: &Node) -> String {
+        let mut out = String::new();
+        for line in self.blocks(doc.children(), self.options.width, None) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Lines for a sequence of sibling nodes, which may mix inline content
+    /// (in tight list items) with blocks. `item` is `Some(tight)` for the
+    /// content of a list item.
+    fn blocks(&self, nodes: &[Node], width: Option<usize>, item: Option<bool>) -> Vec<String> {
+        let tight = item == Some(true);
+        let mut lines: Vec<String> = Vec::new();
+        let mut prev_list: Option<&Node> = None;
+        let mut prev_quote = false;
+        let mut i = 0;
+        while i < nodes.len() {
+            if !lines.is_empty() && !tight {
+                lines.push(String::new());
+            }
+            let node = &nodes[i];
+            if !matches!(node.kind, NodeKind::Block(_)) {
+                let end = nodes[i..]
+                    .iter()
+                    .position(|n| matches!(n.kind, NodeKind::Block(_)))
+                    .map_or(nodes.len(), |p| i + p);
+                lines.extend(self.paragraph(&nodes[i..end], width));
+                i = end;
+                if i < nodes.len() {
+                    let ctx = Context {
+                        after_text: true,
+                        ..Context::default()
+                    };
+                    lines.extend(self.block(&nodes[i], width, ctx, false));
+                    prev_list = is_list(&nodes[i]).then_some(&nodes[i]);
+                    prev_quote = is_quote(&nodes[i]);
+                    i += 1;
+                }
+                continue;
+            }
+
+            let alternate = prev_list.is_some_and(|p| same_list_kind(p, node));
+            let ctx = Context {
+                // Without a blank line in between, text would continue the
+                // quote's last paragraph lazily
+                after_text: tight && prev_quote,
+                after_list: prev_list.is_some(),
+                item_start: item.is_some() && i == 0,
+            };
+            lines.extend(self.block(node, width, ctx, alternate));
+            prev_list = is_list(node).then_some(node);
+            prev_quote = is_quote(node);
+            i += 1;
+        }
+        lines
+    }
+
+    fn block(
+        &self,
+        node: &Node,
+        width: Option<usize>,
+        ctx: Context,
+        alternate: bool,
+    ) -> Vec<String> {
+        let NodeKind::Block(ref block) = node.kind else {
+            return self.paragraph(std::slice::from_ref(node), width);
+        };
+        match block {
+            Block::Document => self.blocks(node.children(), width, None),
+            Block::Paragraph => self.paragraph(node.children(), width),
+            Block::Heading(detail) => self.heading(node, detail.level, ctx),
+            Block::Code(detail) => self.code_block(node, detail, ctx),
+            Block::Html => raw_lines(node),
+            Block::HorizontalRule => {
+                // `---` right under text would underline it as a heading, and
+                // `- ---` is itself a rule
+                let rule = if ctx.after_text {
+                    "***"
+                } else if ctx.item_start {
+                    "___"
+                } else {
+                    "---"
+                };
+                vec![rule.to_string()]
+            }
+            Block::Quote | Block::Admonition(_) => {
+                let mut inner =
+                    self.blocks(node.children(), width.map(|w| w.saturating_sub(2)), None);
+                if let Block::Admonition(kind) = block {
+                    inner.insert(0, format!("[!{}]", kind.name().to_ascii_uppercase()));
+                }
+                let mut lines: Vec<String> = inner
+                    .into_iter()
+                    .map(|l| {
+                        if l.is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {}", l)
+                        }
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    lines.push(">".to_string());
+                }
+                lines
+            }
+            Block::UnorderedList(detail) => {
+                let mut bullet = mark_char(self.options.bullet);
+                if alternate {
+                    bullet = if bullet == '-' { '*' } else { '-' };
+                }
+                let markers = node.children().iter().map(|_| format!("{} ", bullet));
+                self.list(node, markers.collect(), detail.is_tight, width)
+            }
+            Block::OrderedList(detail) => {
+                let delimiter = if alternate { ')' } else { '.' };
+                let markers = (0..node.children().len())
+                    .map(|n| format!("{}{} ", detail.start as usize + n, delimiter));
+                self.list(node, markers.collect(), detail.is_tight, width)
+            }
+            Block::Table(detail) => self.table(node, detail.column_count as usize),
+            // Handled by their containers
+            _ => self.blocks(node.children(), width, None),
+        }
+    }
+
+    fn list(
+        &self,
+        list: &Node,
+        markers: Vec<String>,
+        tight: bool,
+        width: Option<usize>,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (item, marker) in list.children().iter().zip(markers) {
+            if !tight && !lines.is_empty() {
+                lines.push(String::new());
+            }
+            let indent = marker.len();
+            let mut content = Vec::new();
+            if let NodeKind::Block(Block::ListItem(ref detail)) = item.kind {
+                match detail.task_state {
+                    TaskState::Checked => content.push("[x] "),
+                    TaskState::Unchecked => content.push("[ ] "),
+                    TaskState::NotTask => {}
+                }
+            }
+            let inner = self.blocks(
+                item.children(),
+                width.map(|w| w.saturating_sub(indent)),
+                Some(tight),
+            );
+            let mut inner = inner.into_iter();
+            match inner.next() {
+                Some(first) => lines.push(format!("{}{}{}", marker, content.concat(), first)),
+                None => lines.push(
+                    format!("{}{}", marker, content.concat())
+                        .trim_end()
+                        .to_string(),
+                ),
+            }
+            for line in inner {
+                if line.is_empty() {
+                    lines.push(line);
+                } else {
+                    lines.push(format!("{}{}", " ".repeat(indent), line));
+                }
+            }
+        }
+        lines
+    }
+
+    fn heading(&self, node: &Node, level: u8, ctx: Context) -> Vec<String> {
+        let mut text = String::new();
+        self.inlines(node.children(), &mut text, false);
+        let mut text = text
+            .replace([KEEP, '\n', HARD_BREAK, RAW_BREAK], " ")
+            .trim()
+            .to_string();
+
+        let setext = self.options.heading_style == HeadingStyle::Setext
+            && level <= 2
+            && !text.is_empty()
+            && !ctx.after_text;
+        if setext {
+            let text = escape_line_start(&text);
+            let underline = if level == 1 { "=" } else { "-" };
+            let width = text.chars().count().max(3);
+            return vec![text, underline.repeat(width)];
+        }
+
+        // A trailing run of `#` would be read as a closing sequence
+        if text.ends_with('#') {
+            let run = text.len() - text.trim_end_matches('#').len();
+            if text[..text.len() - run].ends_with(' ') || run == text.len() {
+                text.insert(text.len() - run, '\\');
+            }
+        }
+        let marker = "#".repeat(level as usize);
+        if text.is_empty() {
+            vec![marker]
+        } else {
+            vec![format!("{} {}", marker, text)]
+        }
+    }
+
+    fn code_block(&self, node: &Node, detail: &CodeBlockDetail, ctx: Context) -> Vec<String> {
+        let mut code = String::new();
+        for child in node.children() {
+            if let NodeKind::Text(_, ref text) = child.kind {
+                code.push_str(text);
+            }
+        }
+        let has_lines = !code.is_empty();
+        let code = code.strip_suffix('\n').unwrap_or(&code);
+        let mut fence = self.options.fence;
+
+        // Indented code can't carry an info string, start with a blank line,
+        // or follow text or a list without being absorbed by it
+        let indentable = detail.info.is_empty()
+            && !code.trim().is_empty()
+            && !code.starts_with('\n')
+            && !ctx.after_text
+            && !ctx.after_list
+            && !ctx.item_start;
+        if fence == FenceChar::None && indentable {
+            return code
+                .split('\n')
+                .map(|l| {
+                    if l.trim().is_empty() {
+                        String::new()
+                    } else {
+                        format!("    {}", l)
+                    }
+                })
+                .collect();
+        }
+        if fence == FenceChar::None || (fence == FenceChar::Backtick && detail.info.contains('`')) {
+            fence = if detail.info.contains('`') {
+                FenceChar::Tilde
+            } else {
+                FenceChar::Backtick
+            };
+        }
+
+        let c = if fence == FenceChar::Tilde { '~' } else { '`' };
+        let longest = code
+            .split('\n')
+            .map(|l| l.trim_start().chars().take_while(|&x| x == c).count())
+            .max()
+            .unwrap_or(0);
+        let fence = c.to_string().repeat(longest.max(2) + 1);
+
+        let mut lines = vec![format!("{}{}", fence, detail.info)];
+        if has_lines {
+            lines.extend(code.split('\n').map(str::to_string));
+        }
+        lines.push(fence);
+        lines
+    }
+
+    fn table(&self, table: &Node, columns: usize) -> Vec<String> {
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut alignments = vec![Alignment::Default; columns];
+        for row in table
+            .walk()
+            .filter(|n| n.block_type() == Some(BlockType::TableRow))
+        {
+            let mut cells = Vec::new();
+            for (i, cell) in row.children().iter().enumerate() {
+                if let NodeKind::Block(Block::TableHeaderCell(ref detail)) = cell.kind {
+                    if let Some(a) = alignments.get_mut(i) {
+                        *a = detail.alignment;
+                    }
+                }
+                let mut text = String::new();
+                self.inlines(cell.children(), &mut text, true);
+                cells.push(
+                    text.replace([KEEP, '\n', HARD_BREAK, RAW_BREAK], " ")
+                        .trim()
+                        .to_string(),
+                );
+            }
+            cells.resize(columns, String::new());
+            rows.push(cells);
+        }
+
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| {
+                rows.iter()
+                    .map(|r| r[c].chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(3)
+            })
+            .collect();
+        let format_row = |cells: &[String]| {
+            let padded: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &w)| format!("{}{}", cell, " ".repeat(w - cell.chars().count())))
+                .collect();
+            format!("| {} |", padded.join(" | "))
+        };
+
+        let mut lines = Vec::with_capacity(rows.len() + 1);
+        let mut rows = rows.iter();
+        if let Some(header) = rows.next() {
+            lines.push(format_row(header));
+        }
+        let separator: Vec<String> = alignments
+            .iter()
+            .zip(&widths)
+            .map(|(alignment, &w)| match alignment {
+                Alignment::Left => format!(":{}", "-".repeat(w - 1)),
+                Alignment::Right => format!("{}:", "-".repeat(w - 1)),
+                Alignment::Center => format!(":{}:", "-".repeat(w - 2)),
+                Alignment::Default => "-".repeat(w),
+            })
+            .collect();
+        lines.push(format!("| {} |", separator.join(" | ")));
+        lines.extend(rows.map(|r| format_row(r)));
+        lines
+    }
+
+    /// Lay out inline content as paragraph lines.
+    fn paragraph(&self, nodes: &[Node], width: Option<usize>) -> Vec<String> {
+        let mut text = String::new();
+        self.inlines(nodes, &mut text, false);
+
+        let mut lines = Vec::new();
+        let segments: Vec<&str> = text.split(HARD_BREAK).collect();
+        for (n, segment) in segments.iter().enumerate() {
+            let mut segment_lines: Vec<String> = match width {
+                Some(width) => wrap(segment, width),
+                None => segment
+                    .split('\n')
+                    .map(|l| l.trim_matches(' ').to_string())
+                    .filter(|l| !l.is_empty())
+                    .collect(),
+            };
+            for line in &mut segment_lines {
+                *line = escape_line_start(line)
+                    .replace(KEEP, " ")
+                    .replace(RAW_BREAK, "\n");
+            }
+            if n + 1 < segments.len() {
+                if let Some(last) = segment_lines.last_mut() {
+                    last.push('\\');
+                }
+            }
+            lines.extend(segment_lines);
+        }
+        lines
+    }
+
+    fn inlines(&self, nodes: &[Node], out: &mut String, in_table: bool) {
+        for (i, node) in nodes.iter().enumerate() {
+            self.inline(node, nodes.get(i + 1), out, in_table);
+        }
+    }
+
+    /// Whether `_` can delimit emphasis written at the end of `out`, before
+    /// the node `next`.
+    fn underscore_fits(&self, out: &str, next: Option<&Node>) -> bool {
+        let word_follows = next.is_some_and(|next| match next.kind {
+            NodeKind::Text(TextType::Normal, ref text) => text.starts_with(char::is_alphanumeric),
+            _ => false,
+        });
+        self.options.emphasis == EmphasisStyle::Underscore
+            && !self.flag(sys::MD_FLAG_UNDERLINE)
+            && !out.ends_with(char::is_alphanumeric)
+            && !word_follows
+    }
+
+    fn inline(&self, node: &Node, next: Option<&Node>, out: &mut String, in_table: bool) {
+        let span = match node.kind {
+            NodeKind::Text(text_type, ref text) => {
+                self.text(text_type, text, out, in_table);
+                return;
+            }
+            NodeKind::Block(_) => return,
+            NodeKind::Span(ref span) => span,
+        };
+
+        let wrap_with = |delimiter: &str, out: &mut String| {
+            out.push_str(delimiter);
+            self.inlines(node.children(), out, in_table);
+            out.push_str(delimiter);
+        };
+        let underscore = self.underscore_fits(out, next);
+        match span {
+            // Right after another delimiter of the same kind, the two would
+            // merge
+            Span::Emphasis if underscore && !out.ends_with('_') => wrap_with("_", out),
+            Span::Emphasis if out.ends_with('*') && !self.flag(sys::MD_FLAG_UNDERLINE) => {
+                wrap_with("_", out)
+            }
+            Span::Emphasis => wrap_with("*", out),
+            Span::Strong if underscore && !out.ends_with('_') => wrap_with("__", out),
+            Span::Strong => wrap_with("**", out),
+            Span::Underline => wrap_with("_", out),
+            Span::Strikethrough => wrap_with("~~", out),
+            Span::Mark => wrap_with("==", out),
+            Span::Superscript => wrap_with("^", out),
+            Span::Subscript => wrap_with("~", out),
+            Span::LatexMath => wrap_with("$", out),
+            Span::LatexMathDisplay => wrap_with("$$", out),
+            Span::Unknown(_) => self.inlines(node.children(), out, in_table),
+            Span::Code => {
+                let mut code = String::new();
+                for child in node.children() {
+                    match child.kind {
+                        NodeKind::Text(TextType::SoftBreak | TextType::HardBreak, _) => {
+                            code.push(' ')
+                        }
+                        NodeKind::Text(_, ref t) => code.push_str(t),
+                        _ => {}
+                    }
+                }
+                out.push_str(&code_span(&code).replace(' ', &KEEP.to_string()));
+            }
+            Span::Link(detail) => {
+                let text = node.text();
+                if detail.is_autolink {
+                    let href = detail.href.as_str();
+                    if text == href || href.strip_prefix("mailto:") == Some(&text) {
+                        out.push('<');
+                        out.push_str(&text);
+                        out.push('>');
+                    } else {
+                        out.push_str(&text);
+                    }
+                    return;
+                }
+                // `!` right before the link would make it an image
+                if out.ends_with('!') && !out.ends_with("\\!") {
+                    out.insert(out.len() - 1, '\\');
+                }
+                out.push('[');
+                self.inlines(node.children(), out, in_table);
+                out.push_str("](");
+                push_destination(out, &detail.href, &detail.title);
+                out.push(')');
+            }
+            Span::Image(detail) => {
+                out.push_str("![");
+                self.inlines(node.children(), out, in_table);
+                out.push_str("](");
+                push_destination(out, &detail.src, &detail.title);
+                out.push(')');
+            }
+            Span::WikiLink(detail) => {
+                if detail.is_embed {
+                    out.push('!');
+                }
+                out.push_str("[[");
+                out.push_str(&detail.target.replace(' ', &KEEP.to_string()));
+                let label = node.text();
+                if label != detail.target.as_str() {
+                    out.push('|');
+                    self.inlines(node.children(), out, in_table);
+                }
+                out.push_str("]]");
+            }
+        }
+    }
+
+    fn text(&self, text_type: TextType, text: &str, out: &mut String, in_table: bool) {
+        match text_type {
+            TextType::SoftBreak => out.push('\n'),
+            TextType::HardBreak => out.push(HARD_BREAK),
+            TextType::NullChar => out.push('\u{FFFD}'),
+            TextType::Entity => out.push_str(text),
+            TextType::Html => out.extend(text.chars().map(|c| match c {
+                ' ' => KEEP,
+                '\n' => RAW_BREAK,
+                _ => c,
+            })),
+            TextType::Code | TextType::LatexMath => out.push_str(text),
+            _ => {
+                for (i, c) in text.char_indices() {
+                    let escape = match c {
+                        '\\' | '`' | '*' | '_' | '[' | ']' | '<' => true,
+                        // An escaped `&` arrives as its own text run, so
+                        // one at the end may start an entity with what follows
+                        '&' => i + 1 == text.len() || entity_len(&text[i..]) > 0,
+                        '~' => self.flag(sys::MD_FLAG_STRIKETHROUGH | FLAG_SUPER_SUB),
+                        '^' => self.flag(FLAG_SUPER_SUB),
+                        '$' => self.flag(sys::MD_FLAG_LATEXMATHSPANS),
+                        '=' => self.flag(FLAG_MARK),
+                        '|' => in_table,
+                        _ => false,
+                    };
+                    if escape {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+            }
+        }
+    }
+}
+
+fn is_list(node: &Node) -> bool {
+    matches!(
+        node.block_type(),
+        Some(BlockType::UnorderedList | BlockType::OrderedList)
+    )
+}
+
+fn is_quote(node: &Node) -> bool {
+    matches!(node.block_type(), Some(BlockType::Quote | BlockType::Admonition))
+}
+
+fn same_list_kind(a: &Node, b: &Node) -> bool {
+    is_list(b) && a.block_type() == b.block_type()
+}
+
+fn mark_char(mark: ListMark) -> char {
+    match mark {
+        ListMark::Dash => '-',
+        ListMark::Plus => '+',
+        ListMark::Asterisk => '*',
+    }
+}
+
+/// Lines of a raw HTML block.
+fn raw_lines(node: &Node) -> Vec<String> {
+    let mut html = String::new();
+    for child in node.children() {
+        if let NodeKind::Text(_, ref text) = child.kind {
+            html.push_str(text);
+        }
+    }
+    html.trim_end_matches('\n')
+        .split('\n')
+        .map(str::to_string)
+        .collect()
+}
+
+/// A code span holding `code`, with enough backticks to contain it.
+fn code_span(code: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let ticks = "`".repeat(longest + 1);
+    let pad = code.starts_with('`')
+        || code.ends_with('`')
+        || (code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty());
+    if pad {
+        format!("{} {} {}", ticks, code, ticks)
+    } else {
+        format!("{}{}{}", ticks, code, ticks)
+    }
+}
+
+/// Write a link destination and optional title.
+fn push_destination(out: &mut String, href: &str, title: &str) {
+    let keep = KEEP.to_string();
+    let mut depth: i32 = 0;
+    let balanced = href.chars().all(|c| {
+        depth += match c {
+            '(' => 1,
+            ')' => -1,
+            _ => 0,
+        };
+        depth >= 0
+    }) && depth == 0;
+    let bare =
+        balanced && !href.is_empty() && !href.contains(|c: char| c.is_whitespace() || c == '<');
+
+    if !bare {
+        out.push('<');
+    }
+    let mut chars = href.chars().peekable();
+    while let Some(c) = chars.next() {
+        let escape = match c {
+            '\\' => chars.peek().is_none_or(|n| n.is_ascii_punctuation()),
+            '<' | '>' => !bare,
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        if c == ' ' {
+            out.push_str(&keep);
+        } else {
+            out.push(c);
+        }
+    }
+    if !bare {
+        out.push('>');
+    }
+
+    if !title.is_empty() {
+        out.push_str(&keep);
+        out.push('"');
+        for c in title.chars() {
+            if c == '"' || c == '\\' {
+                out.push('\\');
+            }
+            if c == ' ' || c == '\n' {
+                out.push(KEEP);
+            } else {
+                out.push(c);
+            }
+        }
+        out.push('"');
+    }
+}
+
+/// Greedily fill lines up to `width` columns.
+///
+/// Never breaks before inline HTML, which could then start an HTML block.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+    for word in text.split([' ', '\n']).filter(|w| !w.is_empty()) {
+        let word_width = word.chars().count();
+        let fits = line_width + 1 + word_width <= width;
+        if !line.is_empty() && (fits || word.starts_with('<')) {
+            line.push(' ');
+            line.push_str(word);
+            line_width += 1 + word_width;
+        } else {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push_str(word);
+            line_width = word_width;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Escape a line's first word if it would otherwise start a block, such as
+/// a list item or heading.
+fn escape_line_start(line: &str) -> String {
+    let word = line.split([' ', KEEP]).next().unwrap_or("");
+    let all = |c: char| !word.is_empty() && word.chars().all(|x| x == c);
+    // Bytes, since the word before the delimiter needn't be ASCII
+    let ordered = match word.as_bytes() {
+        [digits @ .., b'.' | b')'] => {
+            (1..=9).contains(&digits.len()) && digits.iter().all(u8::is_ascii_digit)
+        }
+        _ => false,
+    };
+
+    if ordered {
+        let mut escaped = line.to_string();
+        escaped.insert(word.len() - 1, '\\');
+        escaped
+    } else if all('-')
+        || all('+')
+        || all('=')
+        || (all('#') && word.len() <= 6)
+        || word.starts_with('>')
+        || word.starts_with("~~~")
+    {
+        format!("\\{}", line)
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::{render_html, HtmlFlags};
+
+    const SAMPLES: &[&str] = &[
+        "Setext\n======\n\nSub\n---\n\n### ATX ###\n\n# trailing \\#\n",
+        "* a\n* b\n\n+ c\n\n1) one\n2) two\n\n3. three\n",
+        "- loose\n\n  para\n- [x] done\n- [ ] todo\n  - nested\n    > quote\n\n    code\n",
+        "> quote *em* **strong** `code` ``a`b``\n> lazy\n\n---\n\n***\n",
+        "[link](</a b> \"t\\\"q\") ![img](i.png) <http://x.y> <me@x.y> www.auto.com a\\*b\\_c\n",
+        "| a | b |\n|:-:|--:|\n| `x\\|y` | ~~del~~ |\n\nline  \nbreak\\\nagain\n",
+        "~~~ rust\n```\ninner\n```\n~~~\n\n<div>\n*raw*\n</div>\n\n&copy; &#65; \\&amp;\n",
+        "1986\\. year\n\n\\- not a list\n\\# not heading\n\n\\+ plus\n\n!\\[not image](x)\n",
+        "in*tra*word **b**old _a_ ***both*** *a **b*** __c__, (*d*) *e*.\n",
+        "café. naïve) 2024. 日本語 *強調* ß\n\n1. É\n2. ü\n",
+    ];
+
+    /// Rendered HTML, with whitespace collapsed since wrapping turns
+    /// spaces into line breaks.
+    fn html(md: &str) -> String {
+        let html = render_html(md, ParserFlags::github(), HtmlFlags::new()).unwrap();
+        html.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn test_format_preserves_rendering() {
+        let styles = [
+            FormatOptions::new(),
+            FormatOptions::new()
+                .with_bullet(ListMark::Asterisk)
+                .with_fence(FenceChar::Tilde)
+                .with_heading_style(HeadingStyle::Setext)
+                .with_emphasis(EmphasisStyle::Underscore)
+                .with_width(12),
+            FormatOptions::new()
+                .with_fence(FenceChar::None)
+                .with_width(30),
+        ];
+        for options in &styles {
+            for md in SAMPLES {
+                let formatted = format(md, options);
+                assert_eq!(
+                    html(&formatted),
+                    html(md),
+                    "{:?} formatted as:\n{}",
+                    md,
+                    formatted
+                );
+                assert_eq!(
+                    format(&formatted, options),
+                    formatted,
+                    "not idempotent for {:?}",
+                    md
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_output() {
+        let md = "Title\n===\n\n    code\n\n+ a  \n  b\n+ c\n\n|x|y|\n|-|:-|\n|1|22222|\n";
+        assert_eq!(
+            format(md, &FormatOptions::new()),
+            "# Title\n\n```\ncode\n```\n\n- a\\\n  b\n- c\n\n\
+             | x   | y     |\n| --- | :---- |\n| 1   | 22222 |\n"
+        );
+
+        let long = "one two three four five six seven eight nine ten\n";
+        let options = FormatOptions::new().with_width(20);
+        assert_eq!(
+            format(long, &options),
+            "one two three four\nfive six seven eight\nnine ten\n"
+        );
+        assert_eq!(
+            format(&format!("> {}", long), &options),
+            "> one two three four\n> five six seven\n> eight nine ten\n"
+        );
+
+        let options = FormatOptions::new().with_emphasis(EmphasisStyle::Underscore);
+        assert_eq!(
+            format("*a* in*tra*word **b**old **c**\n", &options),
+            "_a_ in*tra*word **b**old __c__\n"
+        );
+
+        let options = FormatOptions::new().with_parser_flags(ParserFlags::github().admonitions());
+        assert_eq!(format("> [!tip]\n> Use `fmt`.\n", &options), "> [!TIP]\n> Use `fmt`.\n");
+
+        let options = FormatOptions::new().with_parser_flags(ParserFlags::github().mark());
+        assert_eq!(format("==a== 1 == 1\n", &options), "==a== 1 \\=\\= 1\n");
+
+        let options = FormatOptions::new().with_parser_flags(ParserFlags::github().super_sub());
+        assert_eq!(format("x^2^ H~2~O 2^n\n", &options), "x^2^ H~2~O 2\\^n\n");
+    }
+}
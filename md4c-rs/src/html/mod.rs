@@ -0,0 +1,612 @@
+//! HTML rendering functionality.
+
+use crate::ast::Node;
+use crate::parser::{ParserFlags, FLAG_KEEP_UTF8_BOM};
+use crate::sys;
+use std::any::Any;
+use std::io::{self, Write};
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+
+mod renderer;
+mod sourcemap;
+mod writer;
+
+pub use renderer::{Attributes, HtmlRenderer, ImageOutput, MathMode};
+pub use crate::slug::SlugStyle;
+pub use sourcemap::{render_html_with_sourcemap, SourceMap, SourceMappedHtml, SourceMapping};
+
+/// Flag for URL sanitizing, done on the Rust side; kept clear of MD4C's
+/// own flags.
+pub(crate) const FLAG_SAFE_URLS: u32 = 0x0100_0000;
+
+/// HTML renderer configuration flags
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlFlags {
+    flags: u32,
+}
+
+impl HtmlFlags {
+    /// Create empty flags
+    pub const fn new() -> Self {
+        HtmlFlags { flags: 0 }
+    }
+
+    /// Enable debug output to stderr
+    ///
+    /// To receive the messages in Rust instead, use
+    /// [`HtmlRenderer::on_debug_log`].
+    pub const fn debug(mut self) -> Self {
+        self.flags |= sys::MD_HTML_FLAG_DEBUG;
+        self
+    }
+
+    /// Don't translate HTML entities
+    pub const fn verbatim_entities(mut self) -> Self {
+        self.flags |= sys::MD_HTML_FLAG_VERBATIM_ENTITIES;
+        self
+    }
+
+    /// Skip UTF-8 BOM if present
+    ///
+    /// The BOM is skipped by default, as the parser does. This only matters
+    /// with [`ParserFlags::keep_utf8_bom`], leaving the kept BOM out of the
+    /// HTML.
+    pub const fn skip_utf8_bom(mut self) -> Self {
+        self.flags |= sys::MD_HTML_FLAG_SKIP_UTF8_BOM;
+        self
+    }
+
+    /// Output XHTML-compliant HTML
+    pub const fn xhtml(mut self) -> Self {
+        self.flags |= sys::MD_HTML_FLAG_XHTML;
+        self
+    }
+
+    /// Replace link and image URLs that could run script, like
+    /// `javascript:` or `data:text/html`, with an empty URL (extension)
+    ///
+    /// Relative URLs and the `http`, `https`, `mailto`, `ftp`, `ftps`,
+    /// `tel`, `irc`, `ircs` and `xmpp` schemes are kept, and images may
+    /// also use `data:` URLs of PNG, GIF, JPEG and WebP images. Raw HTML is
+    /// passed through as always, so output for untrusted input should also
+    /// be parsed with [`ParserFlags::no_html`].
+    pub const fn safe_urls(mut self) -> Self {
+        self.flags |= FLAG_SAFE_URLS;
+        self
+    }
+
+    /// Get the raw flags value
+    pub const fn raw(self) -> u32 {
+        self.flags
+    }
+
+    /// The flags understood by MD4C itself.
+    pub(crate) const fn md4c(self) -> u32 {
+        self.flags & !FLAG_SAFE_URLS
+    }
+}
+
+/// Error type for HTML rendering
+#[derive(Debug, Clone)]
+pub enum HtmlError {
+    /// Renderer encountered an error
+    RenderError,
+}
+
+impl std::fmt::Display for HtmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HtmlError::RenderError => write!(f, "HTML rendering error"),
+        }
+    }
+}
+
+impl std::error::Error for HtmlError {}
+
+/// Result type for HTML operations
+pub type HtmlResult<T> = Result<T, HtmlError>;
+
+/// Escape text for HTML the way the renderers do, replacing `&`, `<`, `>`
+/// and `"` with entities.
+///
+/// For custom renderers built on [`parse`](crate::parse), so their output
+/// matches [`render_html`]. The result is safe in element content and in
+/// quoted attribute values.
+///
+/// # Example
+/// ```
+/// use md4c::html::escape_html;
+///
+/// assert_eq!(escape_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+/// ```
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    writer::escape_html(&mut out, text);
+    out
+}
+
+/// Escape a URL for an `href` or `src` attribute the way the renderers do.
+///
+/// Bytes outside the characters URLs commonly use unescaped are
+/// percent-encoded, existing `%` escapes are kept and `&` becomes `&amp;`.
+/// The renderers decode entities in link destinations first; this takes
+/// the URL as it is.
+///
+/// # Example
+/// ```
+/// use md4c::html::escape_url;
+///
+/// assert_eq!(escape_url("/a b?x=1&y=\"é\""), "/a%20b?x=1&amp;y=%22%C3%A9%22");
+/// ```
+pub fn escape_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    writer::escape_url(&mut out, url);
+    out
+}
+
+/// Render markdown to HTML string
+///
+/// # Arguments
+/// * `input` - Markdown source text
+/// * `parser_flags` - Parser configuration flags
+/// * `html_flags` - HTML renderer configuration flags
+///
+/// # Returns
+/// * `Ok(String)` - The rendered HTML
+/// * `Err(HtmlError)` - If rendering failed
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_html, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let markdown = "# Hello\n\nThis is **bold** text.";
+/// let html = render_html(markdown, ParserFlags::commonmark(), HtmlFlags::new()).unwrap();
+/// assert!(html.contains("<h1>Hello</h1>"));
+/// assert!(html.contains("<strong>bold</strong>"));
+/// ```
+pub fn render_html(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+) -> HtmlResult<String> {
+    let mut out = String::with_capacity(input.len() * 2);
+    render_html_into(input, parser_flags, html_flags, &mut out)?;
+    Ok(out)
+}
+
+/// Render markdown to HTML, appending it to `out`
+///
+/// Like [`render_html`], but reuses the caller's buffer, so rendering many
+/// snippets into one cleared `String` allocates only when it must grow. If
+/// rendering fails, `out` is left as it was.
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_html_into, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let mut html = String::new();
+/// for snippet in ["*one*", "**two**"] {
+///     html.clear();
+///     render_html_into(snippet, ParserFlags::commonmark(), HtmlFlags::new(), &mut html).unwrap();
+///     assert!(html.starts_with("<p><"));
+/// }
+/// ```
+pub fn render_html_into(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+    out: &mut String,
+) -> HtmlResult<()> {
+    // Extensions handled in Rust need the Rust writer
+    if parser_flags.md4c() != parser_flags.raw() || html_flags.md4c() != html_flags.raw() {
+        return HtmlRenderer::new()
+            .with_parser_flags(parser_flags)
+            .with_html_flags(html_flags)
+            .render_into(input, out);
+    }
+
+    let start = out.len();
+    let result = stream_html(input, parser_flags, html_flags, |bytes| {
+        if let Ok(s) = std::str::from_utf8(bytes) {
+            out.push_str(s);
+        }
+    });
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+/// Render a syntax tree to HTML
+///
+/// For documents rewritten with [`VisitMut`](crate::ast::VisitMut) after
+/// [`parse_to_ast`](crate::ast::parse_to_ast). See
+/// [`HtmlRenderer::render_ast`] for rendering with hooks.
+///
+/// # Example
+/// ```
+/// use md4c::ast::parse_to_ast;
+/// use md4c::html::{render_ast, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let doc = parse_to_ast("Some *text*", ParserFlags::commonmark()).unwrap();
+/// assert_eq!(render_ast(&doc, HtmlFlags::new()), "<p>Some <em>text</em></p>\n");
+/// ```
+pub fn render_ast(doc: &Node, html_flags: HtmlFlags) -> String {
+    HtmlRenderer::new().with_html_flags(html_flags).render_ast(doc)
+}
+
+/// Render a markdown snippet to HTML without the `<p>` tags of its
+/// paragraphs
+///
+/// For titles, table cells and labels, where a block element would break
+/// the layout. See [`HtmlRenderer::render_inline`].
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_html_inline, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let html = render_html_inline("Release *notes*", ParserFlags::commonmark(), HtmlFlags::new());
+/// assert_eq!(html.unwrap(), "Release <em>notes</em>");
+/// ```
+pub fn render_html_inline(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+) -> HtmlResult<String> {
+    HtmlRenderer::new()
+        .with_parser_flags(parser_flags)
+        .with_html_flags(html_flags)
+        .render_inline(input)
+}
+
+/// Render many documents to HTML in parallel
+///
+/// Each document is rendered with [`render_html`] on rayon's global
+/// thread pool, e.g. for a static site generator converting a whole site.
+/// Results are in the order of `inputs`. Requires the `rayon` feature.
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_many, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let pages = ["# Home", "# About"];
+/// let html = render_many(&pages, ParserFlags::commonmark(), HtmlFlags::new());
+/// assert_eq!(html[1].as_deref().unwrap(), "<h1>About</h1>\n");
+/// ```
+#[cfg(feature = "rayon")]
+pub fn render_many(
+    inputs: &[&str],
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+) -> Vec<HtmlResult<String>> {
+    use rayon::prelude::*;
+
+    inputs
+        .par_iter()
+        .map(|input| render_html(input, parser_flags, html_flags))
+        .collect()
+}
+
+/// Render markdown to HTML with streaming output
+///
+/// This function is useful for large documents where you want to process
+/// output incrementally rather than accumulating it in memory.
+///
+/// # Arguments
+/// * `input` - Markdown source text
+/// * `parser_flags` - Parser configuration flags
+/// * `html_flags` - HTML renderer configuration flags
+/// * `callback` - Function called with each chunk of HTML output
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_html_streaming, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+/// use std::io::Write;
+///
+/// let markdown = "# Hello World";
+/// let mut output = Vec::new();
+/// render_html_streaming(
+///     markdown,
+///     ParserFlags::commonmark(),
+///     HtmlFlags::new(),
+///     |chunk| {
+///         output.extend_from_slice(chunk.as_bytes());
+///     }
+/// ).unwrap();
+/// ```
+pub fn render_html_streaming<F>(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+    mut callback: F,
+) -> HtmlResult<()>
+where
+    F: FnMut(&str),
+{
+    if parser_flags.md4c() != parser_flags.raw() || html_flags.md4c() != html_flags.raw() {
+        callback(&render_html(input, parser_flags, html_flags)?);
+        return Ok(());
+    }
+
+    stream_html(input, parser_flags, html_flags, |bytes| {
+        if let Ok(s) = std::str::from_utf8(bytes) {
+            callback(s);
+        }
+    })
+}
+
+/// Render markdown to HTML, writing it to `writer` as it is produced
+///
+/// Each chunk is written with `write_all`. After an I/O error nothing more
+/// is written, and the error is returned once rendering has finished; a
+/// rendering failure is returned as an error of kind `Other`.
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_html_to_writer, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let mut out = Vec::new();
+/// render_html_to_writer("# Hello", ParserFlags::commonmark(), HtmlFlags::new(), &mut out)
+///     .unwrap();
+/// assert_eq!(out, b"<h1>Hello</h1>\n");
+/// ```
+pub fn render_html_to_writer<W: Write + ?Sized>(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+    writer: &mut W,
+) -> io::Result<()> {
+    let render_error = io::Error::other;
+    if parser_flags.md4c() != parser_flags.raw() || html_flags.md4c() != html_flags.raw() {
+        let html = render_html(input, parser_flags, html_flags).map_err(render_error)?;
+        return writer.write_all(html.as_bytes());
+    }
+
+    let mut error = None;
+    let result = stream_html(input, parser_flags, html_flags, |bytes| {
+        if error.is_none() {
+            error = writer.write_all(bytes).err();
+        }
+    });
+    match error {
+        Some(e) => Err(e),
+        None => result.map_err(render_error),
+    }
+}
+
+/// Run MD4C's HTML renderer, passing each chunk of output to `sink`.
+fn stream_html<F>(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+    mut sink: F,
+) -> HtmlResult<()>
+where
+    F: FnMut(&[u8]),
+{
+    struct CallbackContext<'a, F: FnMut(&[u8])> {
+        callback: &'a mut F,
+        // Panic payload from the callback, resumed once MD4C has returned
+        panic: Option<Box<dyn Any + Send>>,
+    }
+
+    unsafe extern "C" fn output_callback<F: FnMut(&[u8])>(
+        text: *const sys::MD_CHAR,
+        size: sys::MD_SIZE,
+        userdata: *mut c_void,
+    ) {
+        let ctx = &mut *(userdata as *mut CallbackContext<F>);
+        // The renderer can't be aborted from here, so skip the rest of the
+        // output after a panic rather than unwind through it
+        if ctx.panic.is_some() {
+            return;
+        }
+        let slice = std::slice::from_raw_parts(text as *const u8, size as usize);
+        let callback = &mut *ctx.callback;
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| callback(slice))) {
+            ctx.panic = Some(payload);
+        }
+    }
+
+    let mut ctx = CallbackContext {
+        callback: &mut sink,
+        panic: None,
+    };
+    // Skip a BOM as the Rust parser does, unless the parser flags keep it
+    let mut html_flags = html_flags.raw();
+    if parser_flags.raw() & FLAG_KEEP_UTF8_BOM == 0 {
+        html_flags |= sys::MD_HTML_FLAG_SKIP_UTF8_BOM;
+    }
+
+    let result = unsafe {
+        sys::md_html(
+            input.as_ptr() as *const sys::MD_CHAR,
+            input.len() as sys::MD_SIZE,
+            Some(output_callback::<F>),
+            &mut ctx as *mut CallbackContext<F> as *mut c_void,
+            parser_flags.md4c(),
+            html_flags,
+        )
+    };
+
+    if let Some(payload) = ctx.panic {
+        panic::resume_unwind(payload);
+    }
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(HtmlError::RenderError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_html_rendering() {
+        let html = render_html("Hello **world**", ParserFlags::commonmark(), HtmlFlags::new())
+            .unwrap();
+        assert!(html.contains("<strong>world</strong>"));
+    }
+
+    #[test]
+    fn test_heading() {
+        let html = render_html("# Title", ParserFlags::commonmark(), HtmlFlags::new()).unwrap();
+        assert!(html.contains("<h1>Title</h1>"));
+    }
+
+    #[test]
+    fn test_utf8_bom() {
+        let input = "\u{FEFF}# Title";
+        let html = |flags| render_html(input, flags, HtmlFlags::new()).unwrap();
+        // MD4C's renderer, and the Rust one for extensions
+        assert_eq!(html(ParserFlags::commonmark()), "<h1>Title</h1>\n");
+        assert_eq!(html(ParserFlags::commonmark().mark()), "<h1>Title</h1>\n");
+        assert_eq!(html(ParserFlags::commonmark().keep_utf8_bom()), "<p>\u{FEFF}# Title</p>\n");
+        for flags in [ParserFlags::commonmark(), ParserFlags::commonmark().mark()] {
+            let html = render_html(input, flags.keep_utf8_bom(), HtmlFlags::new().skip_utf8_bom());
+            assert_eq!(html.unwrap(), "<h1>Title</h1>\n");
+        }
+
+        let mut out = Vec::new();
+        let flags = ParserFlags::commonmark();
+        render_html_to_writer(input, flags, HtmlFlags::new(), &mut out).unwrap();
+        assert_eq!(out, b"<h1>Title</h1>\n");
+    }
+
+    #[test]
+    fn test_github_flavor() {
+        let html = render_html(
+            "~~strikethrough~~",
+            ParserFlags::github(),
+            HtmlFlags::new(),
+        )
+        .unwrap();
+        assert!(html.contains("<del>strikethrough</del>"));
+    }
+
+    #[test]
+    fn test_admonitions() {
+        let md = "> [!NOTE]\n> Read *this*.\n\n> [!NOTE] not an alert\n";
+        let html = render_html(md, ParserFlags::github().admonitions(), HtmlFlags::new()).unwrap();
+        assert_eq!(
+            html,
+            "<div class=\"markdown-alert markdown-alert-note\">\n\
+             <p class=\"markdown-alert-title\">Note</p>\n\
+             <p>Read <em>this</em>.</p>\n</div>\n\
+             <blockquote>\n<p>[!NOTE] not an alert</p>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn test_mark() {
+        let flags = ParserFlags::github().mark();
+        let html = render_html("A ==*big*== deal", flags, HtmlFlags::new()).unwrap();
+        assert_eq!(html, "<p>A <mark><em>big</em></mark> deal</p>\n");
+    }
+
+    #[test]
+    fn test_super_sub() {
+        let flags = ParserFlags::github().super_sub();
+        let html = render_html("E = mc^2^, H~2~O", flags, HtmlFlags::new()).unwrap();
+        assert_eq!(html, "<p>E = mc<sup>2</sup>, H<sub>2</sub>O</p>\n");
+    }
+
+    #[test]
+    fn test_safe_urls() {
+        let md = "[a](javascript:alert(1)) [b](JavaScript&#58;x) <vbscript:x> [c](/x:y) \
+                  [d](https://x.org) ![e](data:image/png;base64,AA) [f](data:image/png,AA) \
+                  ![g](data:text/html,x)";
+        let flags = HtmlFlags::new().safe_urls();
+        let html = render_html(md, ParserFlags::commonmark(), flags).unwrap();
+        assert_eq!(
+            html,
+            "<p><a href=\"\">a</a> <a href=\"\">b</a> <a href=\"\">vbscript:x</a> \
+             <a href=\"/x:y\">c</a> <a href=\"https://x.org\">d</a> \
+             <img src=\"data:image/png;base64,AA\" alt=\"e\"> <a href=\"\">f</a> \
+             <img src=\"\" alt=\"g\"></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_html_to_writer() {
+        struct Failing;
+        impl Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let md = "# Title\n\n*a* &copy; ==b==\n";
+        for flags in [ParserFlags::commonmark(), ParserFlags::commonmark().mark()] {
+            let mut out = Vec::new();
+            render_html_to_writer(md, flags, HtmlFlags::new(), &mut out).unwrap();
+            let expected = render_html(md, flags, HtmlFlags::new()).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
+            let error = render_html_to_writer(md, flags, HtmlFlags::new(), &mut Failing);
+            let error = error.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        }
+    }
+
+    #[test]
+    fn test_render_html_into() {
+        let mut out = String::from("<body>\n");
+        for flags in [ParserFlags::commonmark(), ParserFlags::commonmark().mark()] {
+            render_html_into("*a*", flags, HtmlFlags::new(), &mut out).unwrap();
+        }
+        assert_eq!(out, "<body>\n<p><em>a</em></p>\n<p><em>a</em></p>\n");
+    }
+
+    #[test]
+    fn test_escaping_matches_renderer() {
+        let url = "/p a?q=\"1\"&r=%20|ü";
+        let text = "a < b & \"c\" > 'd'";
+        let markdown = format!("[{text}](<{url}>)");
+        let html = render_html(&markdown, ParserFlags::commonmark(), HtmlFlags::new()).unwrap();
+        let link = format!("<a href=\"{}\">{}</a>", escape_url(url), escape_html(text));
+        assert_eq!(html, format!("<p>{link}</p>\n"));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_render_many() {
+        let pages: Vec<String> = (0..64).map(|i| format!("# Page {i}\n\n*{i}*")).collect();
+        let inputs: Vec<&str> = pages.iter().map(String::as_str).collect();
+        let flags = HtmlFlags::new().safe_urls();
+        let rendered = render_many(&inputs, ParserFlags::github(), flags);
+        assert_eq!(rendered.len(), pages.len());
+        for (page, html) in pages.iter().zip(rendered) {
+            assert_eq!(html.unwrap(), render_html(page, ParserFlags::github(), flags).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_render_html_inline() {
+        let inline = |md| render_html_inline(md, ParserFlags::github(), HtmlFlags::new()).unwrap();
+        assert_eq!(inline("A `b` & [c](/d)\n"), "A <code>b</code> &amp; <a href=\"/d\">c</a>");
+        assert_eq!(inline("one\n\ntwo"), "one\ntwo");
+        assert_eq!(inline("- item"), "<ul>\n<li>item</li>\n</ul>");
+        assert_eq!(inline(""), "");
+    }
+
+    #[test]
+    fn test_xhtml_output() {
+        let html = render_html("line1  \nline2", ParserFlags::commonmark(), HtmlFlags::new().xhtml())
+            .unwrap();
+        assert!(html.contains("<br />"));
+    }
+}
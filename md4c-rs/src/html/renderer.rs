@@ -0,0 +1,679 @@
+//! Configurable HTML renderer running on the Rust side.
+
+use super::writer::{escape_html, HtmlWriter};
+use super::{HtmlError, HtmlFlags, HtmlResult};
+use crate::ast::Node;
+use crate::parser::{parse, parse_with_debug, ParserFlags};
+use crate::slug::Slugify;
+use crate::sys;
+use crate::types::{BlockType, CodeBlockDetail, HeadingDetail, ImageDetail, LinkDetail, SpanType};
+use std::collections::HashMap;
+
+/// Extra attributes added to an element's opening tag by a hook.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes {
+    entries: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Set an attribute, replacing any previous value.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    /// Append a class to the `class` attribute.
+    pub fn add_class(&mut self, class: &str) {
+        match self.entries.iter_mut().find(|(n, _)| n == "class") {
+            Some((_, value)) => {
+                value.push(' ');
+                value.push_str(class);
+            }
+            None => self.entries.push(("class".to_string(), class.to_string())),
+        }
+    }
+
+    /// Value of an attribute, if set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether no attributes are set.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the attributes as ` name="value"` pairs.
+    pub(crate) fn write(&self, out: &mut String) {
+        for (name, value) in &self.entries {
+            out.push(' ');
+            out.push_str(name);
+            out.push_str("=\"");
+            escape_html(out, value);
+            out.push('"');
+        }
+    }
+}
+
+/// What the image hook writes for an image, see [`HtmlRenderer::on_image`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageOutput {
+    /// URL written as the `src` attribute
+    pub src: String,
+    /// Extra attributes for the `<img>` tag
+    pub attributes: Attributes,
+}
+
+impl ImageOutput {
+    /// Write the image with a different `src`, e.g. a rewritten path.
+    pub fn new(src: impl Into<String>) -> Self {
+        ImageOutput { src: src.into(), attributes: Attributes::default() }
+    }
+
+    /// Write the image inline as a base64 `data:` URI of `media_type`,
+    /// such as `image/png`.
+    pub fn data_uri(media_type: &str, data: &[u8]) -> Self {
+        let mut src = format!("data:{media_type};base64,");
+        base64(&mut src, data);
+        Self::new(src)
+    }
+}
+
+/// Append `data` to `out` in standard base64 with padding.
+fn base64(out: &mut String, data: &[u8]) {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+/// How LaTeX math spans are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MathMode {
+    /// `<x-equation>` and `<x-equation type="display">` elements, as MD4C
+    /// writes them
+    #[default]
+    Equation,
+    /// `\(...\)` and `\[...\]` delimiters, which KaTeX's and MathJax's
+    /// auto-render scripts find in the page text
+    Delimiters,
+    /// Delimiters inside `<span class="math inline">` and
+    /// `<span class="math display">`, as Pandoc writes them
+    Spans,
+}
+
+type HeadingHook = Box<dyn Fn(&HeadingDetail, &str, &mut Attributes) + Send + Sync>;
+type LinkHook = Box<dyn Fn(&mut LinkDetail, &mut Attributes) + Send + Sync>;
+type LinkResolver = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+type CodeBlockHook = Box<dyn Fn(&CodeBlockDetail, &str) -> Option<String> + Send + Sync>;
+type ImageHook = Box<dyn Fn(&ImageDetail) -> Option<ImageOutput> + Send + Sync>;
+type DebugLogHook = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Hooks consulted by [`HtmlWriter`] while rendering.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub heading_ids: Option<Box<dyn Slugify>>,
+    pub math_mode: MathMode,
+    pub heading_offset: i8,
+    pub heading: Option<HeadingHook>,
+    pub link: Option<LinkHook>,
+    pub link_resolver: Option<LinkResolver>,
+    pub code_block: Option<CodeBlockHook>,
+    pub image: Option<ImageHook>,
+    pub debug_log: Option<DebugLogHook>,
+    pub block_classes: HashMap<BlockType, String>,
+    pub span_classes: HashMap<SpanType, String>,
+    // Opening and closing tags for the code blocks of each diagram language
+    pub diagrams: HashMap<String, (String, String)>,
+}
+
+/// HTML renderer with hooks for customizing the output.
+///
+/// Without hooks the output is identical to [`render_html`](super::render_html),
+/// but the document is rendered from parser events in Rust rather than by
+/// MD4C's C renderer, so individual elements can be adjusted.
+///
+/// # Example
+/// ```
+/// use md4c::html::HtmlRenderer;
+///
+/// let renderer = HtmlRenderer::new()
+///     .on_link(|link, attrs| {
+///         if link.href.starts_with("http") {
+///             attrs.set("rel", "nofollow");
+///         }
+///     })
+///     .on_code_block(|code, text| {
+///         let plain = format!("<pre class=\"plain\">{}</pre>\n", text.trim_end());
+///         (code.lang == "text").then_some(plain)
+///     });
+///
+/// let html = renderer.render("[site](https://example.com)").unwrap();
+/// assert_eq!(html, "<p><a href=\"https://example.com\" rel=\"nofollow\">site</a></p>\n");
+/// ```
+#[derive(Default)]
+pub struct HtmlRenderer {
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+    hooks: Hooks,
+}
+
+impl HtmlRenderer {
+    /// Create a renderer for CommonMark with default HTML flags.
+    pub fn new() -> Self {
+        HtmlRenderer {
+            parser_flags: ParserFlags::commonmark(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the parser flags.
+    pub fn with_parser_flags(mut self, flags: ParserFlags) -> Self {
+        self.parser_flags = flags;
+        self
+    }
+
+    /// Set the HTML renderer flags.
+    pub fn with_html_flags(mut self, flags: HtmlFlags) -> Self {
+        self.html_flags = flags;
+        self
+    }
+
+    /// Give each heading an `id` attribute derived from its text by
+    /// `slugifier`, such as [`SlugStyle::GitHub`](crate::SlugStyle::GitHub).
+    ///
+    /// Repeated slugs get a numeric suffix, so every id in the document is
+    /// unique. A heading hook still runs afterwards and may override the id.
+    pub fn with_heading_ids(mut self, slugifier: impl Slugify + 'static) -> Self {
+        self.hooks.heading_ids = Some(Box::new(slugifier));
+        self
+    }
+
+    /// Choose how math spans are written when the parser flags include
+    /// [`latex_math_spans`](ParserFlags::latex_math_spans).
+    pub fn with_math_mode(mut self, mode: MathMode) -> Self {
+        self.hooks.math_mode = mode;
+        self
+    }
+
+    /// Demote headings by `offset` levels, or promote them if it is
+    /// negative, staying within `<h1>` to `<h6>`.
+    ///
+    /// For fragments placed under a page's own `<h1>`. The heading hook
+    /// sees the shifted level.
+    ///
+    /// ```
+    /// use md4c::html::HtmlRenderer;
+    ///
+    /// let renderer = HtmlRenderer::new().with_heading_offset(1);
+    /// assert_eq!(renderer.render("# Post").unwrap(), "<h2>Post</h2>\n");
+    /// ```
+    pub fn with_heading_offset(mut self, offset: i8) -> Self {
+        self.hooks.heading_offset = offset;
+        self
+    }
+
+    /// Call `hook` for each heading with its plain text, to add attributes
+    /// such as an `id` to the `<hN>` tag.
+    pub fn on_heading<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&HeadingDetail, &str, &mut Attributes) + Send + Sync + 'static,
+    {
+        self.hooks.heading = Some(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` for each link before it is written. The hook can rewrite
+    /// the URL or title and add attributes to the `<a>` tag.
+    pub fn on_link<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut LinkDetail, &mut Attributes) + Send + Sync + 'static,
+    {
+        self.hooks.link = Some(Box::new(hook));
+        self
+    }
+
+    /// Rewrite link URLs with `resolver`, e.g. to turn the relative links
+    /// and wiki links of a static site's sources into real URLs.
+    ///
+    /// The resolver is given each link's URL and each wiki link's target as
+    /// written in the source. Returning `Some(url)` replaces the link's URL,
+    /// and a wiki link with a resolved target is written as an `<a>` link
+    /// rather than `<x-wikilink>`; `None` leaves the link as it is. The link
+    /// hook runs afterwards and sees the resolved URL.
+    pub fn with_link_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.hooks.link_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Add `class` to the element written for each block of `block_type`.
+    ///
+    /// The class goes on the outermost tag, e.g. `<pre>` for code blocks,
+    /// and joins any class already there. In a code block's class, `{lang}`
+    /// is replaced by the block's language, so
+    /// `with_block_class(BlockType::Code, "highlight language-{lang}")`
+    /// matches common syntax highlighting stylesheets.
+    pub fn with_block_class(mut self, block_type: BlockType, class: impl Into<String>) -> Self {
+        self.hooks.block_classes.insert(block_type, class.into());
+        self
+    }
+
+    /// Write code blocks in `lang` as `<pre class="lang">` without the
+    /// `<code>` element, the markup diagram libraries like Mermaid look
+    /// for.
+    ///
+    /// The content is escaped as in any code block. Languages are matched
+    /// ignoring ASCII case, and the code block hook still runs afterwards.
+    ///
+    /// ```
+    /// use md4c::html::HtmlRenderer;
+    ///
+    /// let renderer = HtmlRenderer::new().with_diagram("mermaid");
+    /// let html = renderer.render("```mermaid\ngraph TD; A-->B\n```\n").unwrap();
+    /// assert_eq!(html, "<pre class=\"mermaid\">graph TD; A--&gt;B\n</pre>\n");
+    /// ```
+    pub fn with_diagram(self, lang: impl Into<String>) -> Self {
+        let lang = lang.into();
+        let mut open = String::from("<pre class=\"");
+        escape_html(&mut open, &lang);
+        open.push_str("\">");
+        self.with_diagram_wrapper(lang, open, "</pre>")
+    }
+
+    /// Write code blocks in `lang` between `open` and `close` instead of
+    /// `<pre><code>`, e.g. `<div class="graphviz">` and `</div>`.
+    ///
+    /// See [`with_diagram`](Self::with_diagram).
+    pub fn with_diagram_wrapper(
+        mut self,
+        lang: impl Into<String>,
+        open: impl Into<String>,
+        close: impl Into<String>,
+    ) -> Self {
+        let lang = lang.into().to_ascii_lowercase();
+        self.hooks.diagrams.insert(lang, (open.into(), close.into()));
+        self
+    }
+
+    /// Add `class` to the element written for each span of `span_type`.
+    pub fn with_span_class(mut self, span_type: SpanType, class: impl Into<String>) -> Self {
+        self.hooks.span_classes.insert(span_type, class.into());
+        self
+    }
+
+    /// Call `hook` for each code block with its raw content. Returning
+    /// `Some(html)` replaces the whole `<pre><code>` element, e.g. with
+    /// syntax-highlighted markup; `None` keeps the default output.
+    ///
+    /// The returned HTML is written as is, so the hook must escape it.
+    pub fn on_code_block<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CodeBlockDetail, &str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.hooks.code_block = Some(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` for each image before it is written. Returning
+    /// `Some(output)` replaces the image's `src` and adds attributes to the
+    /// `<img>` tag; `None` keeps the image as it is.
+    ///
+    /// A preview server can inline small local images, which would
+    /// otherwise be broken links:
+    ///
+    /// ```no_run
+    /// use md4c::html::{HtmlRenderer, ImageOutput};
+    ///
+    /// let renderer = HtmlRenderer::new().on_image(|image| {
+    ///     let data = std::fs::read(image.src.as_ref()).ok().filter(|d| d.len() < 16 * 1024)?;
+    ///     let mut output = ImageOutput::data_uri("image/png", &data);
+    ///     output.attributes.set("loading", "lazy");
+    ///     Some(output)
+    /// });
+    /// ```
+    ///
+    /// With [`HtmlFlags::safe_urls`], the new `src` is checked like any
+    /// other, and `data:` URIs are kept only for common image types.
+    pub fn on_image<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ImageDetail) -> Option<ImageOutput> + Send + Sync + 'static,
+    {
+        self.hooks.image = Some(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with MD4C's debug messages, see
+    /// [`parse_with_debug`](crate::parser::parse_with_debug).
+    ///
+    /// Without a hook, [`HtmlFlags::debug`] writes them to stderr, as MD4C's
+    /// own renderer does.
+    pub fn on_debug_log<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.hooks.debug_log = Some(Box::new(hook));
+        self
+    }
+
+    /// Render markdown to HTML.
+    pub fn render(&self, input: &str) -> HtmlResult<String> {
+        let mut out = String::with_capacity(input.len() * 2);
+        self.render_into(input, &mut out)?;
+        Ok(out)
+    }
+
+    /// Render markdown to HTML, appending it to `out`. If rendering fails,
+    /// `out` is left as it was.
+    pub fn render_into(&self, input: &str, out: &mut String) -> HtmlResult<()> {
+        self.render_with(input, out, false)
+    }
+
+    /// Render a snippet to HTML for use inside another element, such as a
+    /// title or table cell.
+    ///
+    /// Paragraphs are written without `<p>` tags, separated by newlines,
+    /// and the final newline is left off, so a one-paragraph snippet gives
+    /// only its inline content. Other blocks are written as usual.
+    pub fn render_inline(&self, input: &str) -> HtmlResult<String> {
+        let mut out = String::with_capacity(input.len() * 2);
+        self.render_with(input, &mut out, true)?;
+        if out.ends_with('\n') {
+            out.pop();
+        }
+        Ok(out)
+    }
+
+    /// Render a syntax tree to HTML, e.g. one rewritten with
+    /// [`VisitMut`](crate::ast::VisitMut). The parser flags don't apply, as
+    /// the tree is already parsed.
+    pub fn render_ast(&self, doc: &Node) -> String {
+        let mut writer = HtmlWriter::new(self.html_flags, 1024).with_hooks(&self.hooks);
+        doc.replay(&mut writer);
+        writer.out
+    }
+
+    fn render_with(&self, input: &str, out: &mut String, inline: bool) -> HtmlResult<()> {
+        let mut document = input;
+        if self.html_flags.raw() & sys::MD_HTML_FLAG_SKIP_UTF8_BOM != 0 {
+            document = document.strip_prefix('\u{FEFF}').unwrap_or(document);
+        }
+
+        let start = out.len();
+        let mut writer = HtmlWriter::new(self.html_flags, 0).with_hooks(&self.hooks);
+        if inline {
+            writer = writer.inline();
+        }
+        writer.out = std::mem::take(out);
+        let flags = self.parser_flags;
+        let result = match &self.hooks.debug_log {
+            Some(hook) => parse_with_debug(document, flags, &mut writer, |msg| hook(msg)),
+            None if self.html_flags.raw() & sys::MD_HTML_FLAG_DEBUG != 0 => {
+                parse_with_debug(document, flags, &mut writer, |msg| eprintln!("MD4C: {}", msg))
+            }
+            None => parse(document, flags, &mut writer),
+        };
+        *out = writer.out;
+        if result.is_err() {
+            out.truncate(start);
+            return Err(HtmlError::RenderError);
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for HtmlRenderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HtmlRenderer")
+            .field("parser_flags", &self.parser_flags)
+            .field("html_flags", &self.html_flags)
+            .field("heading_ids", &self.hooks.heading_ids.is_some())
+            .field("math_mode", &self.hooks.math_mode)
+            .field("heading_offset", &self.hooks.heading_offset)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::render_html;
+    use crate::slug::SlugStyle;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_no_hooks_matches_c_renderer() {
+        let md = "# Title\n\n[a](/x) and ```b```\n\n```rust\nlet x = 1 < 2;\n```\n";
+        let renderer = HtmlRenderer::new().with_parser_flags(ParserFlags::github());
+        assert_eq!(
+            renderer.render(md).unwrap(),
+            render_html(md, ParserFlags::github(), HtmlFlags::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hooks() {
+        let renderer = HtmlRenderer::new()
+            .on_heading(|heading, text, attrs| {
+                attrs.set("id", text.to_lowercase().replace(' ', "-"));
+                attrs.add_class(&format!("level-{}", heading.level));
+            })
+            .on_link(|link, attrs| {
+                link.href = link.href.replace("http:", "https:").into();
+                attrs.add_class("external");
+            })
+            .on_code_block(|code, text| {
+                (code.lang == "shout").then(|| format!("<pre>{}</pre>\n", text.to_uppercase()))
+            });
+
+        let html = renderer
+            .render("## Big *News*\n\n[x](http://a.com)\n\n```shout\nhi\n```\n\n```\nlow\n```\n")
+            .unwrap();
+        assert_eq!(
+            html,
+            "<h2 id=\"big-news\" class=\"level-2\">Big <em>News</em></h2>\n\
+             <p><a href=\"https://a.com\" class=\"external\">x</a></p>\n\
+             <pre>HI\n</pre>\n\
+             <pre><code>low\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_image_hook() {
+        let renderer = HtmlRenderer::new().on_image(|image| {
+            let mut output = match image.src.strip_prefix("local/") {
+                Some(_) => ImageOutput::data_uri("image/gif", b"GIF89"),
+                None => ImageOutput::new(format!("/static/{}", image.src)),
+            };
+            output.attributes.set("width", "10");
+            (!image.src.starts_with("http")).then_some(output)
+        });
+        let html = renderer.render("![a *b*](local/x.gif \"T\") ![c](y.png) ![d](http://e/f)");
+        assert_eq!(
+            html.unwrap(),
+            "<p><img src=\"data:image/gif;base64,R0lGODk=\" alt=\"a b\" title=\"T\" width=\"10\"> \
+             <img src=\"/static/y.png\" alt=\"c\" width=\"10\"> \
+             <img src=\"http://e/f\" alt=\"d\"></p>\n"
+        );
+
+        let mut encoded = String::new();
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob"] {
+            base64(&mut encoded, data);
+            encoded.push(',');
+        }
+        assert_eq!(encoded, ",Zg==,Zm8=,Zm9v,Zm9vYg==,");
+    }
+
+    #[test]
+    fn test_link_resolver() {
+        let renderer = HtmlRenderer::new()
+            .with_parser_flags(ParserFlags::commonmark().wiki_links())
+            .with_link_resolver(|url| {
+                let page = url.strip_suffix(".md").unwrap_or(url);
+                let relative = !page.contains(':') && !page.starts_with(['/', '#']);
+                relative.then(|| format!("/docs/{}.html", page.to_lowercase().replace(' ', "-")))
+            })
+            .on_link(|_, attrs| attrs.add_class("doc"));
+
+        let html = renderer
+            .render("[a](setup.md) [b](https://x.org) [[Getting Started|start]] [[https://y]]")
+            .unwrap();
+        assert_eq!(
+            html,
+            "<p><a href=\"/docs/setup.html\" class=\"doc\">a</a> \
+             <a href=\"https://x.org\" class=\"doc\">b</a> \
+             <a href=\"/docs/getting-started.html\" class=\"doc\">start</a> \
+             <x-wikilink data-target=\"https://y\">https://y</x-wikilink></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_classes() {
+        let renderer = HtmlRenderer::new()
+            .with_parser_flags(ParserFlags::github())
+            .with_html_flags(HtmlFlags::new().xhtml())
+            .with_block_class(BlockType::Code, "highlight language-{lang}")
+            .with_block_class(BlockType::Table, "table table-striped")
+            .with_block_class(BlockType::TableCell, "cell")
+            .with_block_class(BlockType::ListItem, "item")
+            .with_block_class(BlockType::Heading, "title")
+            .with_block_class(BlockType::HorizontalRule, "rule")
+            .with_span_class(SpanType::Link, "link")
+            .with_span_class(SpanType::Image, "image")
+            .on_link(|_, attrs| attrs.add_class("external"));
+
+        let md = "# Hi\n\n- [x] done\n\n```rust\nx\n```\n\n| a |\n|--:|\n| 1 |\n\n---\n\n\
+                  [l](/) ![i](/i.png)\n";
+        assert_eq!(
+            renderer.render(md).unwrap(),
+            "<h1 class=\"title\">Hi</h1>\n\
+             <ul>\n<li class=\"task-list-item item\">\
+             <input type=\"checkbox\" class=\"task-list-item-checkbox\" disabled checked>\
+             done</li>\n</ul>\n\
+             <pre class=\"highlight language-rust\">\
+             <code class=\"language-rust\">x\n</code></pre>\n\
+             <table class=\"table table-striped\">\n<thead>\n<tr>\n<th align=\"right\">a</th>\n\
+             </tr>\n</thead>\n<tbody>\n<tr>\n<td align=\"right\" class=\"cell\">1</td>\n\
+             </tr>\n</tbody>\n</table>\n\
+             <hr class=\"rule\" />\n\
+             <p><a href=\"/\" class=\"external link\">l</a> \
+             <img src=\"/i.png\" alt=\"i\" class=\"image\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_heading_offset() {
+        let md = "# A\n\n###### B\n";
+        let render = |offset| {
+            let renderer = HtmlRenderer::new()
+                .with_heading_offset(offset)
+                .on_heading(|heading, _, attrs| attrs.set("data-level", heading.level.to_string()));
+            renderer.render(md).unwrap()
+        };
+        assert_eq!(
+            render(2),
+            "<h3 data-level=\"3\">A</h3>\n<h6 data-level=\"6\">B</h6>\n"
+        );
+        assert_eq!(
+            render(-3),
+            "<h1 data-level=\"1\">A</h1>\n<h3 data-level=\"3\">B</h3>\n"
+        );
+    }
+
+    #[test]
+    fn test_diagrams() {
+        let renderer = HtmlRenderer::new()
+            .with_diagram("mermaid")
+            .with_diagram_wrapper("dot", "<div class=\"graphviz\">", "</div>")
+            .with_block_class(BlockType::Code, "diagram");
+        let md = "```Mermaid\na<b\n```\n\n~~~dot {engine=neato}\ndigraph {}\n~~~\n\n\
+                  ```mermaidx\n```\n";
+        assert_eq!(
+            renderer.render(md).unwrap(),
+            "<pre class=\"mermaid diagram\">a&lt;b\n</pre>\n\
+             <div class=\"graphviz diagram\">digraph {}\n</div>\n\
+             <pre class=\"diagram\"><code class=\"language-mermaidx\"></code></pre>\n"
+        );
+
+        // The code block hook gets the last word
+        let renderer = renderer.on_code_block(|code, text| {
+            (code.lang == "dot").then(|| format!("<svg>{}</svg>\n", text.len()))
+        });
+        assert!(renderer.render(md).unwrap().contains("<svg>11</svg>\n<pre"));
+    }
+
+    #[test]
+    fn test_math_mode() {
+        let md = "$a<b$ and $$x^2$$";
+        let render = |mode| {
+            let flags = ParserFlags::commonmark().latex_math_spans();
+            let renderer = HtmlRenderer::new().with_parser_flags(flags).with_math_mode(mode);
+            renderer.render(md).unwrap()
+        };
+        assert_eq!(
+            render(MathMode::Equation),
+            render_html(md, ParserFlags::commonmark().latex_math_spans(), HtmlFlags::new()).unwrap()
+        );
+        assert_eq!(render(MathMode::Delimiters), "<p>\\(a&lt;b\\) and \\[x^2\\]</p>\n");
+        assert_eq!(
+            render(MathMode::Spans),
+            "<p><span class=\"math inline\">\\(a&lt;b\\)</span> and \
+             <span class=\"math display\">\\[x^2\\]</span></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let renderer = HtmlRenderer::new().with_heading_ids(SlugStyle::GitHub);
+        let html = renderer
+            .render("# Hello, *World*!
+
+## Hello World
+
+## Hello World-1
+
+## Hello World
+")
+            .unwrap();
+        assert_eq!(
+            html,
+            "<h1 id=\"hello-world\">Hello, <em>World</em>!</h1>\n\
+             <h2 id=\"hello-world-1\">Hello World</h2>\n\
+             <h2 id=\"hello-world-1-1\">Hello World-1</h2>\n\
+             <h2 id=\"hello-world-2\">Hello World</h2>\n"
+        );
+        let slug = SlugStyle::GitHub.slugify("Ünïcode & émoji 🎉 v1.2_3");
+        assert_eq!(slug, "ünïcode--émoji--v12_3");
+    }
+
+    #[test]
+    fn test_shared_slugifier() {
+        let slugifier: Arc<dyn Slugify> = Arc::new(|text: &str| format!("sec-{}", text.len()));
+        let doc = "# One\n\n## Two\n\n## Six\n";
+        let html = HtmlRenderer::new().with_heading_ids(slugifier.clone()).render(doc).unwrap();
+        assert_eq!(
+            html,
+            "<h1 id=\"sec-3\">One</h1>\n<h2 id=\"sec-3-1\">Two</h2>\n<h2 id=\"sec-3-2\">Six</h2>\n"
+        );
+        let toc = crate::toc::extract_with(doc, ParserFlags::commonmark(), &slugifier);
+        let slugs: Vec<&str> = toc.iter().map(|e| e.slug.as_str()).collect();
+        assert_eq!(slugs, ["sec-3", "sec-3-1", "sec-3-2"]);
+    }
+}
@@ -0,0 +1,163 @@
+//! HTML rendering with a source map.
+
+use super::writer::HtmlWriter;
+use super::{HtmlError, HtmlFlags, HtmlResult};
+use crate::parser::{parse, ParserFlags};
+use crate::sys;
+use std::ops::Range;
+
+/// A run of HTML output produced from a run of Markdown input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMapping {
+    /// Byte range in the rendered HTML
+    pub output: Range<usize>,
+    /// Byte range in the Markdown input
+    pub input: Range<usize>,
+}
+
+/// Mapping between byte offsets in rendered HTML and the Markdown input.
+///
+/// Entries cover the text content of the document (including raw HTML and
+/// code) in output order, which is also input order. Markup generated by the
+/// renderer, such as tags, is not covered.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    entries: Vec<SourceMapping>,
+}
+
+impl SourceMap {
+    /// All mappings, sorted by output (and input) offset.
+    pub fn entries(&self) -> &[SourceMapping] {
+        &self.entries
+    }
+
+    /// Find the input offset corresponding to an output offset.
+    ///
+    /// Offsets inside a mapped range map to the start of the matching input
+    /// range (or the same relative position when both ranges have equal
+    /// length). Offsets in generated markup map to the end of the preceding
+    /// mapping. Returns `None` before the first mapping.
+    pub fn input_offset(&self, output_offset: usize) -> Option<usize> {
+        let index = self.entries.partition_point(|m| m.output.start <= output_offset);
+        let mapping = &self.entries[index.checked_sub(1)?];
+        Some(translate(output_offset, &mapping.output, &mapping.input))
+    }
+
+    /// Find the output offset corresponding to an input offset.
+    ///
+    /// The inverse of [`input_offset`](Self::input_offset).
+    pub fn output_offset(&self, input_offset: usize) -> Option<usize> {
+        let index = self.entries.partition_point(|m| m.input.start <= input_offset);
+        let mapping = &self.entries[index.checked_sub(1)?];
+        Some(translate(input_offset, &mapping.input, &mapping.output))
+    }
+}
+
+fn translate(offset: usize, from: &Range<usize>, to: &Range<usize>) -> usize {
+    if offset >= from.end {
+        to.end
+    } else if from.len() == to.len() {
+        to.start + (offset - from.start)
+    } else {
+        to.start
+    }
+}
+
+/// HTML output together with its [`SourceMap`].
+#[derive(Debug, Clone)]
+pub struct SourceMappedHtml {
+    /// The rendered HTML
+    pub html: String,
+    /// Mapping between `html` and the Markdown input
+    pub source_map: SourceMap,
+}
+
+/// Render markdown to HTML, recording where each piece of text came from
+///
+/// The HTML is identical to [`render_html`](super::render_html)'s output.
+/// The source map allows scroll-syncing between an editor buffer and a
+/// rendered preview.
+///
+/// # Example
+/// ```
+/// use md4c::html::{render_html_with_sourcemap, HtmlFlags};
+/// use md4c::parser::ParserFlags;
+///
+/// let markdown = "# Hello\n\nSome *text*.";
+/// let out = render_html_with_sourcemap(markdown, ParserFlags::commonmark(), HtmlFlags::new())
+///     .unwrap();
+///
+/// let text = out.html.find("text").unwrap();
+/// assert_eq!(out.source_map.input_offset(text), markdown.find("text"));
+/// ```
+pub fn render_html_with_sourcemap(
+    input: &str,
+    parser_flags: ParserFlags,
+    html_flags: HtmlFlags,
+) -> HtmlResult<SourceMappedHtml> {
+    let mut document = input;
+    if html_flags.raw() & sys::MD_HTML_FLAG_SKIP_UTF8_BOM != 0 {
+        document = document.strip_prefix('\u{FEFF}').unwrap_or(document);
+    }
+
+    let mut writer = HtmlWriter::new(html_flags, input.len() * 2).with_source_map(input);
+    parse(document, parser_flags, &mut writer).map_err(|_| HtmlError::RenderError)?;
+
+    Ok(SourceMappedHtml {
+        html: writer.out,
+        source_map: SourceMap {
+            entries: writer.mappings,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::render_html;
+
+    #[test]
+    fn test_sourcemap_ranges() {
+        let md = "# A &amp; B\n\n> quote `code`\n\n```\nx < y\n```\n";
+        let out = render_html_with_sourcemap(md, ParserFlags::commonmark(), HtmlFlags::new())
+            .unwrap();
+        assert_eq!(
+            out.html,
+            render_html(md, ParserFlags::commonmark(), HtmlFlags::new()).unwrap()
+        );
+
+        let pairs: Vec<(&str, &str)> = out
+            .source_map
+            .entries()
+            .iter()
+            .map(|m| (&out.html[m.output.clone()], &md[m.input.clone()]))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("A ", "A "),
+                ("&amp;", "&amp;"),
+                (" B", " B"),
+                ("quote ", "quote "),
+                ("code", "code"),
+                ("x &lt; y", "x < y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offset_lookup() {
+        let md = "para one\n\npara two";
+        let out = render_html_with_sourcemap(md, ParserFlags::commonmark(), HtmlFlags::new())
+            .unwrap();
+        let map = &out.source_map;
+
+        assert_eq!(map.input_offset(0), None);
+        let two = out.html.find("two").unwrap();
+        assert_eq!(map.input_offset(two), md.find("two"));
+        assert_eq!(map.output_offset(md.find("two").unwrap()), Some(two));
+        // Generated markup maps to the end of the preceding text
+        let close = out.html.find("</p>").unwrap();
+        assert_eq!(map.input_offset(close), Some(8));
+    }
+}
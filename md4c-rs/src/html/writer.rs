@@ -0,0 +1,679 @@
+//! HTML output written from parser events in Rust.
+//!
+//! Produces the same markup as MD4C's C renderer (`md4c-html.c`), but since
+//! it runs on the Rust side it can observe where each piece of output came
+//! from.
+
+use super::renderer::{Attributes, Hooks, MathMode};
+use super::{HtmlFlags, SourceMapping, FLAG_SAFE_URLS};
+use crate::ast::shift_heading;
+use crate::entity::decode_entity;
+use crate::parser::{source_offset, ParserHandler};
+use crate::slug::{push_heading_text, Slugger};
+use crate::sys;
+use crate::types::*;
+
+/// Event handler writing HTML into a string.
+pub(crate) struct HtmlWriter<'a> {
+    pub out: String,
+    flags: HtmlFlags,
+    image_nesting: usize,
+    heading_levels: Vec<u8>,
+    // Title and hook attributes of each open image
+    image_titles: Vec<(Attribute<'static>, Attributes)>,
+    // Input to record mappings against, if building a source map
+    source: Option<&'a str>,
+    pub mappings: Vec<SourceMapping>,
+    hooks: Option<&'a Hooks>,
+    // Open heading: where its attributes go and its text so far
+    heading: Option<(usize, String)>,
+    // Heading ids handed out so far
+    heading_ids: Slugger,
+    // Open code block for the code block hook: output start and content
+    code_block: Option<(usize, CodeBlockDetail<'static>, String)>,
+    // Closing tag of the open code block, if it is written as a diagram
+    diagram: Option<&'a str>,
+    // Closing markup of each open wiki link
+    wiki_links: Vec<&'static str>,
+    // Leave out the `<p>` tags of paragraphs
+    inline: bool,
+}
+
+impl<'a> HtmlWriter<'a> {
+    pub fn new(flags: HtmlFlags, capacity: usize) -> Self {
+        HtmlWriter {
+            out: String::with_capacity(capacity),
+            flags,
+            image_nesting: 0,
+            heading_levels: Vec::new(),
+            image_titles: Vec::new(),
+            source: None,
+            mappings: Vec::new(),
+            hooks: None,
+            heading: None,
+            heading_ids: Slugger::default(),
+            code_block: None,
+            diagram: None,
+            wiki_links: Vec::new(),
+            inline: false,
+        }
+    }
+
+    /// Consult `hooks` for headings, links, link URLs and code blocks.
+    pub fn with_hooks(mut self, hooks: &'a Hooks) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Write paragraphs without their `<p>` tags, each ending in a newline.
+    pub fn inline(mut self) -> Self {
+        self.inline = true;
+        self
+    }
+
+    /// Record a mapping for each text event that borrows from `source`.
+    pub fn with_source_map(mut self, source: &'a str) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    fn xhtml(&self) -> bool {
+        self.flags.raw() & sys::MD_HTML_FLAG_XHTML != 0
+    }
+
+    /// The class configured for `block`, with a code block's language
+    /// filled in.
+    fn block_class(&self, block: &Block) -> Option<String> {
+        let class = self.hooks?.block_classes.get(&block.block_type())?;
+        Some(match block {
+            Block::Code(detail) => class.replace("{lang}", &detail.lang),
+            _ => class.clone(),
+        })
+    }
+
+    /// The tags configured for code blocks in `lang`, if it is a diagram
+    /// language.
+    fn diagram_tags(&self, lang: &str) -> Option<(&'a str, &'a str)> {
+        let hooks = self.hooks?;
+        if hooks.diagrams.is_empty() || lang.is_empty() {
+            return None;
+        }
+        let (open, close) = hooks.diagrams.get(&lang.to_ascii_lowercase())?;
+        Some((open, close))
+    }
+
+    fn math_mode(&self) -> MathMode {
+        self.hooks.map_or(MathMode::Equation, |h| h.math_mode)
+    }
+
+    fn span_class(&self, span_type: SpanType) -> Option<&'a str> {
+        self.hooks?.span_classes.get(&span_type).map(String::as_str)
+    }
+
+    /// Add `class` to the opening tag written from output position `start`,
+    /// joining its `class` attribute if it has one.
+    fn add_class(&mut self, start: usize, class: &str) {
+        let Some(end) = self.out[start..].find('>').map(|i| start + i) else {
+            return;
+        };
+        let mut escaped = String::new();
+        escape_html(&mut escaped, class);
+        let tag = &self.out[start..end];
+        if let Some(value) = tag.find(" class=\"").map(|i| i + " class=\"".len()) {
+            let Some(quote) = tag[value..].find('"') else {
+                return;
+            };
+            self.insert(start + value + quote, &format!(" {}", escaped));
+        } else {
+            let at = if tag.ends_with(" /") { end - 2 } else { end };
+            self.insert(at, &format!(" class=\"{}\"", escaped));
+        }
+    }
+
+    /// Write a link or image URL, emptied if it isn't safe.
+    fn push_url(&mut self, url: &Attribute, image: bool) {
+        if self.flags.raw() & FLAG_SAFE_URLS != 0 && !is_safe_url(&url.decoded(), image) {
+            return;
+        }
+        self.push_attribute(url, escape_url);
+    }
+
+    fn verbatim_entities(&self) -> bool {
+        self.flags.raw() & sys::MD_HTML_FLAG_VERBATIM_ENTITIES != 0
+    }
+
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    /// Write an attribute value, decoding entities and escaping the result.
+    fn push_attribute(&mut self, value: &Attribute, escape: fn(&mut String, &str)) {
+        for (text_type, text) in value.substrings() {
+            match text_type {
+                TextType::Entity if self.verbatim_entities() => self.out.push_str(text),
+                TextType::Entity => escape(&mut self.out, &decode_entity(text)),
+                TextType::NullChar => escape(&mut self.out, "\u{FFFD}"),
+                _ => escape(&mut self.out, text),
+            }
+        }
+    }
+
+    /// Insert `text` at output position `at`, shifting later mappings.
+    fn insert(&mut self, at: usize, text: &str) {
+        self.out.insert_str(at, text);
+        for mapping in self.mappings.iter_mut().filter(|m| m.output.start >= at) {
+            mapping.output.start += text.len();
+            mapping.output.end += text.len();
+        }
+    }
+
+    /// Replace the output from `start` on, dropping its mappings.
+    fn replace_from(&mut self, start: usize, html: &str) {
+        self.out.truncate(start);
+        self.out.push_str(html);
+        self.mappings.retain(|m| m.output.start < start);
+    }
+
+    /// Rewrite a link URL with the link resolver, if there is one.
+    fn resolve_link(&self, url: &str) -> Option<String> {
+        self.hooks?.link_resolver.as_ref()?(url)
+    }
+
+    /// Write the opening `<a>` tag of a link.
+    fn write_link(&mut self, mut detail: LinkDetail) {
+        let mut attrs = Attributes::default();
+        if let Some(hook) = self.hooks.and_then(|h| h.link.as_ref()) {
+            hook(&mut detail, &mut attrs);
+        }
+        self.push("<a href=\"");
+        self.push_url(&detail.href, false);
+        if !detail.title.is_empty() {
+            self.push("\" title=\"");
+            self.push_attribute(&detail.title, escape_html);
+        }
+        self.push("\"");
+        attrs.write(&mut self.out);
+        self.push(">");
+    }
+
+    fn write_text(&mut self, text_type: TextType, text: &str) {
+        match text_type {
+            TextType::NullChar => self.push("\u{FFFD}"),
+            TextType::HardBreak => {
+                let br = if self.image_nesting > 0 {
+                    " "
+                } else if self.xhtml() {
+                    "<br />\n"
+                } else {
+                    "<br>\n"
+                };
+                self.push(br);
+            }
+            TextType::SoftBreak => {
+                let br = if self.image_nesting > 0 { " " } else { "\n" };
+                self.push(br);
+            }
+            TextType::Html => self.push(text),
+            TextType::Entity if self.verbatim_entities() => self.push(text),
+            TextType::Entity => escape_html(&mut self.out, &decode_entity(text)),
+            _ => escape_html(&mut self.out, text),
+        }
+    }
+}
+
+impl ParserHandler for HtmlWriter<'_> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        let start = self.out.len();
+        let class = self.block_class(&block);
+        match block {
+            Block::Document | Block::Html | Block::Unknown(_) => {}
+            Block::Quote => self.push("<blockquote>\n"),
+            Block::UnorderedList(_) => self.push("<ul>\n"),
+            Block::OrderedList(detail) => {
+                if detail.start == 1 {
+                    self.push("<ol>\n");
+                } else {
+                    self.push(&format!("<ol start=\"{}\">\n", detail.start));
+                }
+            }
+            Block::ListItem(ListItemDetail { task_state, .. }) => match task_state {
+                TaskState::NotTask => self.push("<li>"),
+                _ => {
+                    self.push(
+                        "<li class=\"task-list-item\">\
+                         <input type=\"checkbox\" class=\"task-list-item-checkbox\" disabled",
+                    );
+                    if task_state == TaskState::Checked {
+                        self.push(" checked");
+                    }
+                    self.push(">");
+                }
+            },
+            Block::HorizontalRule => {
+                let hr = if self.xhtml() { "<hr />\n" } else { "<hr>\n" };
+                self.push(hr);
+            }
+            Block::Heading(HeadingDetail { level }) => {
+                let offset = self.hooks.map_or(0, |h| h.heading_offset);
+                let level = shift_heading(level, offset);
+                self.heading_levels.push(level);
+                self.push(&format!("<h{}", level));
+                // Its class is added with the hooks' attributes
+                if class.is_some()
+                    || self.hooks.is_some_and(|h| h.heading.is_some() || h.heading_ids.is_some())
+                {
+                    self.heading = Some((self.out.len(), String::new()));
+                }
+                self.push(">");
+            }
+            Block::Code(detail) => {
+                if self.hooks.is_some_and(|h| h.code_block.is_some()) {
+                    let detail = detail.clone().into_owned();
+                    self.code_block = Some((self.out.len(), detail, String::new()));
+                }
+                if let Some((open, close)) = self.diagram_tags(&detail.lang) {
+                    self.push(open);
+                    self.diagram = Some(close);
+                } else {
+                    self.push("<pre><code");
+                    if !detail.lang.is_empty() {
+                        self.push(" class=\"language-");
+                        self.push_attribute(&detail.lang, escape_html);
+                        self.push("\"");
+                    }
+                    self.push(">");
+                }
+            }
+            Block::Paragraph if self.inline => {}
+            Block::Paragraph => self.push("<p>"),
+            Block::Table(_) => self.push("<table>\n"),
+            Block::TableHead => self.push("<thead>\n"),
+            Block::TableBody => self.push("<tbody>\n"),
+            Block::TableRow => self.push("<tr>\n"),
+            Block::TableHeaderCell(detail) => self.push(open_cell("th", detail.alignment)),
+            Block::TableCell(detail) => self.push(open_cell("td", detail.alignment)),
+            Block::Admonition(kind) => self.push(&format!(
+                "<div class=\"markdown-alert markdown-alert-{}\">\n\
+                 <p class=\"markdown-alert-title\">{}</p>\n",
+                kind.name(),
+                kind.title()
+            )),
+        }
+        if let Some(class) = class.filter(|_| self.heading.is_none()) {
+            self.add_class(start, &class);
+        }
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        match block_type {
+            BlockType::Quote => self.push("</blockquote>\n"),
+            BlockType::UnorderedList => self.push("</ul>\n"),
+            BlockType::OrderedList => self.push("</ol>\n"),
+            BlockType::ListItem => self.push("</li>\n"),
+            BlockType::Heading => {
+                let level = self.heading_levels.pop().unwrap_or(1);
+                self.push(&format!("</h{}>\n", level));
+                if let Some((at, text)) = self.heading.take() {
+                    let mut attrs = Attributes::default();
+                    if let Some(class) = self.hooks.and_then(|h| h.block_classes.get(&block_type)) {
+                        attrs.add_class(class);
+                    }
+                    if let Some(slugifier) = self.hooks.and_then(|h| h.heading_ids.as_ref()) {
+                        attrs.set("id", self.heading_ids.unique(slugifier.slugify(&text)));
+                    }
+                    if let Some(hook) = self.hooks.and_then(|h| h.heading.as_ref()) {
+                        hook(&HeadingDetail { level }, &text, &mut attrs);
+                    }
+                    let mut written = String::new();
+                    attrs.write(&mut written);
+                    self.insert(at, &written);
+                }
+            }
+            BlockType::Code => {
+                match self.diagram.take() {
+                    Some(close) => {
+                        self.push(close);
+                        self.push("\n");
+                    }
+                    None => self.push("</code></pre>\n"),
+                }
+                if let Some((start, detail, code)) = self.code_block.take() {
+                    let hook = self.hooks.and_then(|h| h.code_block.as_ref());
+                    if let Some(html) = hook.and_then(|hook| hook(&detail, &code)) {
+                        self.replace_from(start, &html);
+                    }
+                }
+            }
+            BlockType::Paragraph if self.inline => self.push("\n"),
+            BlockType::Paragraph => self.push("</p>\n"),
+            BlockType::Table => self.push("</table>\n"),
+            BlockType::TableHead => self.push("</thead>\n"),
+            BlockType::TableBody => self.push("</tbody>\n"),
+            BlockType::TableRow => self.push("</tr>\n"),
+            BlockType::TableHeaderCell => self.push("</th>\n"),
+            BlockType::TableCell => self.push("</td>\n"),
+            BlockType::Admonition => self.push("</div>\n"),
+            _ => {}
+        }
+        true
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        // Inside an image only the plain text goes into the alt attribute
+        let inside_image = self.image_nesting > 0;
+        if let Span::Image(ref detail) = span {
+            self.image_nesting += 1;
+            self.image_titles.push((detail.title.clone().into_owned(), Attributes::default()));
+        }
+        if inside_image {
+            return true;
+        }
+
+        let start = self.out.len();
+        let span_type = span.span_type();
+        match span {
+            Span::Emphasis => self.push("<em>"),
+            Span::Strong => self.push("<strong>"),
+            Span::Underline => self.push("<u>"),
+            Span::Link(mut detail) => {
+                if let Some(href) = self.resolve_link(&detail.href) {
+                    detail.href = href.into();
+                }
+                self.write_link(detail);
+            }
+            Span::Image(detail) => {
+                let hook = self.hooks.and_then(|h| h.image.as_ref());
+                let src = match hook.and_then(|hook| hook(&detail)) {
+                    Some(output) => {
+                        if let Some(image) = self.image_titles.last_mut() {
+                            image.1 = output.attributes;
+                        }
+                        output.src.into()
+                    }
+                    None => detail.src,
+                };
+                self.push("<img src=\"");
+                self.push_url(&src, true);
+                self.push("\" alt=\"");
+            }
+            Span::Code => self.push("<code>"),
+            Span::Strikethrough => self.push("<del>"),
+            Span::Mark => self.push("<mark>"),
+            Span::Superscript => self.push("<sup>"),
+            Span::Subscript => self.push("<sub>"),
+            Span::Unknown(_) => {}
+            Span::LatexMath | Span::LatexMathDisplay => {
+                let display = span_type == SpanType::LatexMathDisplay;
+                self.push(match (self.math_mode(), display) {
+                    (MathMode::Equation, false) => "<x-equation>",
+                    (MathMode::Equation, true) => "<x-equation type=\"display\">",
+                    (MathMode::Delimiters, false) => "\\(",
+                    (MathMode::Delimiters, true) => "\\[",
+                    (MathMode::Spans, false) => "<span class=\"math inline\">\\(",
+                    (MathMode::Spans, true) => "<span class=\"math display\">\\[",
+                });
+            }
+            Span::WikiLink(detail) if detail.is_embed => {
+                // MD4C's renderer knows no embeds, and keeps them as text
+                self.wiki_links.push("]]");
+                self.push("![[");
+                if detail.label.is_some() {
+                    escape_html(&mut self.out, &detail.target);
+                    self.push("|");
+                }
+            }
+            Span::WikiLink(detail) => {
+                if let Some(href) = self.resolve_link(&detail.target) {
+                    self.wiki_links.push("</a>");
+                    // Written as a plain link, which the link hook sees too
+                    let link =
+                        LinkDetail { href: href.into(), title: "".into(), is_autolink: false };
+                    self.write_link(link);
+                } else {
+                    self.wiki_links.push("</x-wikilink>");
+                    self.push("<x-wikilink data-target=\"");
+                    self.push_attribute(&detail.target, escape_html);
+                    self.push("\">");
+                }
+            }
+        }
+        // Images get theirs when the tag is closed
+        if let Some(class) = self.span_class(span_type).filter(|_| span_type != SpanType::Image) {
+            self.add_class(start, class);
+        }
+        true
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        let mut image = None;
+        if span_type == SpanType::Image {
+            self.image_nesting = self.image_nesting.saturating_sub(1);
+            image = self.image_titles.pop();
+        }
+        if self.image_nesting > 0 {
+            return true;
+        }
+
+        match span_type {
+            SpanType::Emphasis => self.push("</em>"),
+            SpanType::Strong => self.push("</strong>"),
+            SpanType::Underline => self.push("</u>"),
+            SpanType::Link => self.push("</a>"),
+            SpanType::Image => {
+                let (title, attrs) = image.unwrap_or_default();
+                if !title.is_empty() {
+                    self.push("\" title=\"");
+                    self.push_attribute(&title, escape_html);
+                }
+                if let Some(class) = self.span_class(SpanType::Image) {
+                    self.push("\" class=\"");
+                    escape_html(&mut self.out, class);
+                }
+                self.push("\"");
+                attrs.write(&mut self.out);
+                self.push(if self.xhtml() { " />" } else { ">" });
+            }
+            SpanType::Code => self.push("</code>"),
+            SpanType::Strikethrough => self.push("</del>"),
+            SpanType::Mark => self.push("</mark>"),
+            SpanType::Superscript => self.push("</sup>"),
+            SpanType::Subscript => self.push("</sub>"),
+            SpanType::Unknown(_) => {}
+            SpanType::LatexMath | SpanType::LatexMathDisplay => {
+                let display = span_type == SpanType::LatexMathDisplay;
+                self.push(match (self.math_mode(), display) {
+                    (MathMode::Equation, _) => "</x-equation>",
+                    (MathMode::Delimiters, false) => "\\)",
+                    (MathMode::Delimiters, true) => "\\]",
+                    (MathMode::Spans, false) => "\\)</span>",
+                    (MathMode::Spans, true) => "\\]</span>",
+                });
+            }
+            SpanType::WikiLink => {
+                let close = self.wiki_links.pop().unwrap_or("</x-wikilink>");
+                self.push(close);
+            }
+        }
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let start = self.out.len();
+        self.write_text(text_type, text);
+
+        if let Some((_, ref mut heading)) = self.heading {
+            push_heading_text(heading, text_type, text);
+        }
+        if let Some((_, _, ref mut code)) = self.code_block {
+            code.push_str(text);
+        }
+
+        if let Some(offset) = self.source.and_then(|input| source_offset(input, text)) {
+            self.mappings.push(SourceMapping {
+                output: start..self.out.len(),
+                input: offset..offset + text.len(),
+            });
+        }
+        true
+    }
+}
+
+fn open_cell(tag: &str, alignment: Alignment) -> &'static str {
+    match (tag, alignment) {
+        ("th", Alignment::Left) => "<th align=\"left\">",
+        ("th", Alignment::Center) => "<th align=\"center\">",
+        ("th", Alignment::Right) => "<th align=\"right\">",
+        ("th", _) => "<th>",
+        (_, Alignment::Left) => "<td align=\"left\">",
+        (_, Alignment::Center) => "<td align=\"center\">",
+        (_, Alignment::Right) => "<td align=\"right\">",
+        _ => "<td>",
+    }
+}
+
+/// Escape characters that are special in HTML text and attributes.
+pub(crate) fn escape_html(out: &mut String, text: &str) {
+    let mut last = 0;
+    for (i, c) in text.char_indices() {
+        let escaped = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            _ => continue,
+        };
+        out.push_str(&text[last..i]);
+        out.push_str(escaped);
+        last = i + 1;
+    }
+    out.push_str(&text[last..]);
+}
+
+/// Schemes kept by [`HtmlFlags::safe_urls`].
+const SAFE_SCHEMES: &[&str] =
+    &["http", "https", "mailto", "ftp", "ftps", "tel", "irc", "ircs", "xmpp"];
+
+/// Media types allowed in `data:` image URLs by [`HtmlFlags::safe_urls`].
+const SAFE_DATA_IMAGES: &[&str] = &["image/png", "image/gif", "image/jpeg", "image/webp"];
+
+/// Whether a URL, with its entities decoded, is relative or has a scheme
+/// that can't run script.
+fn is_safe_url(url: &str, image: bool) -> bool {
+    // Browsers skip tabs and newlines anywhere in a URL, and leading controls
+    let mut url = url.to_string();
+    url.retain(|c| !matches!(c, '\t' | '\n' | '\r'));
+    let url = url.trim_start_matches(|c: char| c <= ' ');
+
+    let Some((scheme, data)) = url.split_once(':') else {
+        return true;
+    };
+    if scheme.contains(['/', '?', '#']) {
+        // A path with a colon in it
+        return true;
+    }
+    let scheme = scheme.to_ascii_lowercase();
+    if SAFE_SCHEMES.contains(&scheme.as_str()) {
+        return true;
+    }
+    let data = data.to_ascii_lowercase();
+    image
+        && scheme == "data"
+        && SAFE_DATA_IMAGES.iter().any(|media_type| {
+            data.strip_prefix(media_type).is_some_and(|rest| rest.starts_with([';', ',']))
+        })
+}
+
+/// Escape a URL for an `href` or `src` attribute.
+///
+/// Bytes outside the set MD4C leaves alone are percent-encoded, except `&`
+/// which becomes `&amp;`.
+pub(crate) fn escape_url(out: &mut String, url: &str) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    for b in url.bytes() {
+        if b.is_ascii_alphanumeric() || b"~-_.+!*(),%#@?=;:/$".contains(&b) {
+            out.push(b as char);
+        } else if b == b'&' {
+            out.push_str("&amp;");
+        } else {
+            out.push('%');
+            out.push(HEX[(b >> 4) as usize] as char);
+            out.push(HEX[(b & 0xf) as usize] as char);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html::render_html;
+    use crate::parser::{parse, ParserFlags};
+
+    fn write(input: &str, flags: ParserFlags) -> String {
+        let mut writer = HtmlWriter::new(HtmlFlags::new(), input.len());
+        parse(input, flags, &mut writer).unwrap();
+        writer.out
+    }
+
+    #[test]
+    fn test_matches_c_renderer() {
+        let samples = [
+            "# Title\n\nSome *emphasis*, **strong** and `code`.\n",
+            "> quote\n>\n> - a\n> - [x] done\n\n3. three\n4. four\n",
+            "[link](http://a.com/ä b?x=1&y=2 \"T&amp;itle\") ![alt *text*](i.png \"t\")\n",
+            "&copy; &#65; &#x42; &bogus; &ngE;\n\n<div>\nraw\n</div>\n",
+            "| a | b |\n|:-|-:|\n| 1 | 2 |\n\n~~del~~ www.example.com\n",
+            "```rust\nfn main() {}\n```\n\n    indented\n\nline  \nbreak\n\n---\n",
+            // Escaped ampersands are not entities
+            "[a](/x\\&amp;y&#x41; \"\\&quot;\") ![i](/\\&lt;.png)\n\n``` a\\&amp;b\nx\n```\n",
+        ];
+        for md in samples {
+            let expected = render_html(md, ParserFlags::github(), HtmlFlags::new()).unwrap();
+            assert_eq!(write(md, ParserFlags::github()), expected, "for {:?}", md);
+        }
+    }
+
+    /// The examples of an MD4C spec file, with the flags each is run with.
+    fn spec_examples(spec: &str) -> Vec<(String, ParserFlags)> {
+        let fence = format!("{} example", "`".repeat(32));
+        let mut examples = Vec::new();
+        let mut lines = spec.lines();
+        while let Some(line) = lines.next() {
+            if line != fence {
+                continue;
+            }
+            let body: Vec<_> = lines.by_ref().take_while(|line| *line != &fence[..32]).collect();
+            let mut parts = body.split(|line| *line == ".");
+            let md: String =
+                parts.next().unwrap().iter().map(|line| format!("{}\n", line)).collect();
+            let flags = parts.nth(1).unwrap_or_default().iter().flat_map(|line| line.split(' '));
+            let flags = flags
+                .map(|option| option.strip_prefix("--f").unwrap().parse::<ParserFlags>().unwrap())
+                .fold(ParserFlags::commonmark(), |flags, flag| flags | flag);
+            examples.push((md.replace('\u{2192}', "\t"), flags));
+        }
+        examples
+    }
+
+    #[test]
+    fn test_matches_c_renderer_on_spec() {
+        let specs = [
+            include_str!("../../../test/spec.txt"),
+            include_str!("../../../test/spec-tables.txt"),
+            include_str!("../../../test/spec-tasklists.txt"),
+            include_str!("../../../test/spec-strikethrough.txt"),
+            include_str!("../../../test/spec-latex-math.txt"),
+            include_str!("../../../test/spec-wiki-links.txt"),
+            include_str!("../../../test/spec-underline.txt"),
+            include_str!("../../../test/spec-permissive-autolinks.txt"),
+            include_str!("../../../test/spec-hard-soft-breaks.txt"),
+        ];
+        let mut count = 0;
+        for (md, flags) in specs.into_iter().flat_map(spec_examples) {
+            let expected = render_html(&md, flags, HtmlFlags::new()).unwrap();
+            assert_eq!(write(&md, flags), expected, "for {:?}", md);
+            count += 1;
+        }
+        assert_eq!(count, 723);
+    }
+}
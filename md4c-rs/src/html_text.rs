@@ -0,0 +1,135 @@
+//! Reducing raw HTML to its text content.
+
+use crate::entity::{decode_entity, entity_len};
+
+/// Where [`HtmlToText`] is within the HTML seen so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Text,
+    // Inside a tag, possibly inside a quoted attribute value
+    Tag(Option<char>),
+    Comment,
+}
+
+/// Strips tags and comments from raw HTML and decodes entities.
+///
+/// HTML reaches parser handlers in pieces (one line of an HTML block, or one
+/// inline tag at a time), so the state carries over between calls. The
+/// content of `<script>` and `<style>` elements is dropped.
+#[derive(Debug)]
+pub(crate) struct HtmlToText {
+    state: State,
+    // Tag being read, to recognize elements whose content is skipped
+    tag: String,
+    // Closing tag that ends skipped content
+    skip_until: Option<&'static str>,
+}
+
+impl HtmlToText {
+    pub fn new() -> Self {
+        HtmlToText {
+            state: State::Text,
+            tag: String::new(),
+            skip_until: None,
+        }
+    }
+
+    /// Append the text content of the next piece of HTML to `out`.
+    pub fn push(&mut self, out: &mut String, html: &str) {
+        let mut rest = html;
+        while let Some(c) = rest.chars().next() {
+            let mut len = c.len_utf8();
+            match self.state {
+                State::Comment => {
+                    if rest.starts_with("-->") {
+                        self.state = State::Text;
+                        len = 3;
+                    }
+                }
+                State::Tag(Some(quote)) => {
+                    if c == quote {
+                        self.state = State::Tag(None);
+                    }
+                }
+                State::Tag(None) => match c {
+                    '"' | '\'' => self.state = State::Tag(Some(c)),
+                    '>' => self.close_tag(out),
+                    _ => self.tag.push(c),
+                },
+                State::Text if rest.starts_with("<!--") => {
+                    self.state = State::Comment;
+                    len = 4;
+                }
+                // Skipped content only ends at a closing tag
+                State::Text
+                    if c == '<'
+                        && is_tag_start(&rest[1..])
+                        && (self.skip_until.is_none() || rest[1..].starts_with('/')) =>
+                {
+                    self.state = State::Tag(None);
+                    self.tag.clear();
+                }
+                State::Text if self.skip_until.is_some() => {}
+                State::Text if c == '&' && entity_len(rest) > 0 => {
+                    len = entity_len(rest);
+                    out.push_str(&decode_entity(&rest[..len]));
+                }
+                State::Text => out.push(c),
+            }
+            rest = &rest[len..];
+        }
+    }
+
+    fn close_tag(&mut self, out: &mut String) {
+        self.state = State::Text;
+        let name: String = self
+            .tag
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '/')
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if let Some(end) = self.skip_until {
+            if name == end {
+                self.skip_until = None;
+            }
+            return;
+        }
+        match name.as_str() {
+            "script" => self.skip_until = Some("/script"),
+            "style" => self.skip_until = Some("/style"),
+            // Keep words on either side of a line break apart
+            "br" | "br/" => out.push('\n'),
+            _ => {}
+        }
+    }
+}
+
+/// Whether text following `<` starts a tag, comment or declaration.
+fn is_tag_start(rest: &str) -> bool {
+    let rest = rest.strip_prefix('/').unwrap_or(rest);
+    rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '!' || c == '?')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(pieces: &[&str]) -> String {
+        let mut stripper = HtmlToText::new();
+        let mut out = String::new();
+        for piece in pieces {
+            stripper.push(&mut out, piece);
+        }
+        out
+    }
+
+    #[test]
+    fn test_strip_tags() {
+        assert_eq!(strip(&["<p align=\"a>b\">Tom &amp; <b>Jerry</b></p>"]), "Tom & Jerry");
+        let block = ["<div\n", "  class='x'>\n", "a < b<br/>c\n", "</div>\n"];
+        assert_eq!(strip(&block), "\na < b\nc\n\n");
+        assert_eq!(strip(&["x<!-- <b>", "hidden -->y"]), "xy");
+        assert_eq!(strip(&["<script>if (a<b) {}</script>", "<STYLE>p {}</STYLE>ok"]), "ok");
+    }
+}
@@ -0,0 +1,181 @@
+//! JSON export of the syntax tree.
+//!
+//! [`to_json`] writes a parsed document as a JSON tree, in the spirit of
+//! Pandoc's JSON output, for tools outside Rust. Requires the `serde`
+//! feature.
+//!
+//! Each node is an object:
+//!
+//! - blocks have a `"block"` key naming the [`BlockType`], their detail
+//!   fields, and `"children"`
+//! - spans have a `"span"` key naming the [`SpanType`], their detail fields,
+//!   and `"children"`
+//! - text has a `"text"` key naming the [`TextType`], and its `"content"`
+//!
+//! Enum values are written by name, like `"Dash"` or `"Checked"`. Types
+//! from a newer MD4C are named `"Unknown"`, with their number in `"raw"`.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::{json, ParserFlags};
+//!
+//! let doc = json::to_json("# *Hi*", ParserFlags::commonmark()).unwrap();
+//! assert_eq!(
+//!     doc,
+//!     concat!(
+//!         r#"{"block":"Document","children":[{"block":"Heading","level":1,"children":"#,
+//!         r#"[{"span":"Emphasis","children":[{"text":"Normal","content":"Hi"}]}]}]}"#,
+//!     )
+//! );
+//! ```
+
+use crate::ast::{parse_to_ast, Node, NodeKind};
+use crate::parser::{ParseResult, ParserFlags};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+use serde_json::{json, Map, Value};
+
+/// Parse markdown and write its syntax tree as compact JSON.
+pub fn to_json(input: &str, flags: ParserFlags) -> ParseResult<String> {
+    Ok(to_value(&parse_to_ast(input, flags)?).to_string())
+}
+
+/// Convert a syntax tree to a JSON value.
+pub fn to_value(node: &Node) -> Value {
+    let mut object = Map::new();
+    match node.kind {
+        NodeKind::Block(ref block) => {
+            insert_name(&mut object, "block", block_name(block.block_type()));
+            block_detail(&mut object, block);
+        }
+        NodeKind::Span(ref span) => {
+            insert_name(&mut object, "span", span_name(span.span_type()));
+            span_detail(&mut object, span);
+        }
+        NodeKind::Text(text_type, ref text) => {
+            insert_name(&mut object, "text", text_name(text_type));
+            object.insert("content".into(), text.as_str().into());
+            return object.into();
+        }
+    }
+    object.insert("children".into(), node.children.iter().map(to_value).collect());
+    object.into()
+}
+
+/// A type's name, and its raw number if it is unknown.
+type Name = (String, Option<u32>);
+
+fn insert_name(object: &mut Map<String, Value>, key: &str, (name, raw): Name) {
+    object.insert(key.into(), name.into());
+    if let Some(raw) = raw {
+        object.insert("raw".into(), raw.into());
+    }
+}
+
+fn block_name(block_type: BlockType) -> Name {
+    match block_type {
+        BlockType::Unknown(raw) => ("Unknown".into(), Some(raw)),
+        known => (format!("{known:?}"), None),
+    }
+}
+
+fn span_name(span_type: SpanType) -> Name {
+    match span_type {
+        SpanType::Unknown(raw) => ("Unknown".into(), Some(raw)),
+        known => (format!("{known:?}"), None),
+    }
+}
+
+fn text_name(text_type: TextType) -> Name {
+    match text_type {
+        TextType::Unknown(raw) => ("Unknown".into(), Some(raw)),
+        known => (format!("{known:?}"), None),
+    }
+}
+
+/// Enum value written by name.
+fn name(value: impl std::fmt::Debug) -> Value {
+    format!("{value:?}").into()
+}
+
+fn extend(object: &mut Map<String, Value>, fields: Value) {
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+}
+
+fn block_detail(object: &mut Map<String, Value>, block: &Block) {
+    let fields = match block {
+        Block::UnorderedList(d) => json!({ "is_tight": d.is_tight, "mark": name(d.mark) }),
+        Block::OrderedList(d) => json!({
+            "start": d.start,
+            "is_tight": d.is_tight,
+            "delimiter": name(d.delimiter),
+        }),
+        Block::ListItem(d) => json!({ "task_state": name(d.task_state) }),
+        Block::Heading(d) => json!({ "level": d.level }),
+        Block::Code(d) => json!({
+            "info": d.info.as_str(),
+            "lang": d.lang.as_str(),
+            "fence_char": name(d.fence_char),
+        }),
+        Block::Table(d) => json!({
+            "column_count": d.column_count,
+            "head_row_count": d.head_row_count,
+            "body_row_count": d.body_row_count,
+        }),
+        Block::TableHeaderCell(d) | Block::TableCell(d) => {
+            json!({ "alignment": name(d.alignment) })
+        }
+        Block::Admonition(kind) => json!({ "kind": name(kind) }),
+        _ => return,
+    };
+    extend(object, fields);
+}
+
+fn span_detail(object: &mut Map<String, Value>, span: &Span) {
+    let fields = match span {
+        Span::Link(d) => json!({
+            "href": d.href.as_str(),
+            "title": d.title.as_str(),
+            "is_autolink": d.is_autolink,
+        }),
+        Span::Image(d) => json!({ "src": d.src.as_str(), "title": d.title.as_str() }),
+        Span::WikiLink(d) => json!({
+            "target": d.target.as_str(),
+            "label": d.label,
+            "is_embed": d.is_embed,
+        }),
+        _ => return,
+    };
+    extend(object, fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_tree() {
+        let md = "- [x] [a](/u \"t\")\n\n| b |\n|:-:|\n\n```rust x\ny\n```\n";
+        let json = to_json(md, ParserFlags::github()).unwrap();
+        let doc: Value = serde_json::from_str(&json).unwrap();
+
+        let item = &doc["children"][0]["children"][0];
+        assert_eq!(item["block"], "ListItem");
+        assert_eq!(item["task_state"], "Checked");
+        let link = &item["children"][0];
+        assert_eq!(link["span"], "Link");
+        assert_eq!(link["href"], "/u");
+        assert_eq!(link["title"], "t");
+        assert_eq!(link["children"][0], json!({ "text": "Normal", "content": "a" }));
+
+        let cell = &doc["children"][1]["children"][0]["children"][0]["children"][0];
+        assert_eq!(cell["block"], "TableHeaderCell");
+        assert_eq!(cell["alignment"], "Center");
+        let code = &doc["children"][2];
+        assert_eq!(code["lang"], "rust");
+        assert_eq!(code["info"], "rust x");
+        assert_eq!(code["children"][0]["text"], "Code");
+    }
+}
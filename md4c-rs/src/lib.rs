@@ -15,6 +15,9 @@
 //!   - Wiki links
 //!   - LaTeX math spans
 //!   - Underline
+//!   - Alerts (`> [!NOTE]`)
+//!   - Highlighted text (`==text==`)
+//!   - Superscript and subscript (`^sup^`, `~sub~`)
 //!
 //! ## Quick Start
 //!
@@ -121,7 +124,33 @@
 //!     .permissive_autolinks();
 //! ```
 
+mod admonition;
+pub mod anchors;
+mod delimiters;
+pub mod ast;
+pub mod autolink;
+pub mod combinator;
+mod embeds;
+pub mod escapes;
+pub mod fmt;
+pub mod links;
+pub mod lint;
+#[cfg(feature = "serde")]
+pub mod json;
+mod entity;
+mod html_text;
+mod mark_map;
+pub mod slug;
 pub mod parser;
+pub mod plaintext;
+pub mod refs;
+#[cfg(feature = "pulldown-cmark")]
+pub mod pulldown;
+pub mod source_map;
+pub mod split;
+pub mod stats;
+pub mod summary;
+pub mod toc;
 pub mod sys;
 pub mod types;
 
@@ -129,15 +158,30 @@ pub mod types;
 pub mod html;
 
 // Re-export main types at crate root
-pub use parser::{parse, parse_to_events, ParseError, ParseResult, ParserFlags, ParserHandler};
+pub use parser::{
+    clear_warning_hook, parse, parse_bytes, parse_iter, parse_to_events, parse_with_cancel,
+    parse_with_debug, set_warning_hook, try_parse, CancellationToken, EventIter, Limit, ParseError,
+    ParseResult, Parser, ParserFlags, ParserHandler, TryParserHandler, UnknownFlag, Utf8Policy,
+    Warning,
+};
+pub use anchors::{validate_anchors, BrokenAnchor};
+pub use combinator::{Chain, Tee};
+pub use slug::{SlugStyle, Slugger, Slugify};
+pub use source_map::{parse_with_source_map, BlockSourceMap};
+pub use split::{split_by_headings, Section, SectionHeading};
 pub use types::{
-    Alignment, Block, BlockType, CodeBlockDetail, FenceChar, HeadingDetail, ImageDetail,
-    LinkDetail, ListItemDetail, ListMark, OrderedListDelimiter, OrderedListDetail, Span, SpanType,
-    TableCellDetail, TableDetail, TaskState, TextType, UnorderedListDetail, WikiLinkDetail,
+    AdmonitionKind, Alignment, Attribute, Block, BlockType, CodeBlockDetail, FenceChar,
+    HeadingDetail, ImageDetail, LinkDetail, ListItemDetail, ListMark, OrderedListDelimiter,
+    OrderedListDetail, Span, SpanType, TableCellDetail, TableDetail, TaskState, TextType,
+    UnorderedListDetail, WikiLinkDetail,
 };
 
 #[cfg(feature = "html")]
-pub use html::{render_html, render_html_streaming, HtmlError, HtmlFlags, HtmlResult};
+pub use html::{
+    render_html, render_html_inline, render_html_into, render_html_streaming,
+    render_html_to_writer, render_html_with_sourcemap, HtmlError, HtmlFlags, HtmlRenderer,
+    HtmlResult,
+};
 
 /// Convenience function to render markdown to HTML with default settings
 ///
@@ -174,6 +218,8 @@ pub fn gfm_to_html(markdown: &str) -> HtmlResult<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::borrow::Cow;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_to_html() {
@@ -233,6 +279,241 @@ mod tests {
         assert!(has_strong);
     }
 
+    #[test]
+    fn test_parse_to_events_borrows_input() {
+        use std::borrow::Cow;
+
+        let input = String::from("Some text\n\n    code\n");
+        let events = parse_to_events(&input, ParserFlags::commonmark()).unwrap();
+        let texts: Vec<&Cow<str>> = events
+            .iter()
+            .filter_map(|e| match e {
+                parser::Event::Text(_, t) => Some(t),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["Some text", "code", "\n"]);
+        assert!(matches!(texts[0], Cow::Borrowed(_)));
+        // The code line break is supplied by MD4C rather than the input
+        assert!(matches!(texts[2], Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_parse_iter() {
+        let input = "# A\n\ntext *em*\n";
+        let lazy: Vec<String> = parse_iter(input, ParserFlags::commonmark())
+            .map(|e| format!("{:?}", e))
+            .collect();
+        let eager: Vec<String> = parse_to_events(input, ParserFlags::commonmark())
+            .unwrap()
+            .iter()
+            .map(|e| format!("{:?}", e))
+            .collect();
+        assert_eq!(lazy, eager);
+
+        // Stopping early abandons the rest of a large document
+        let big = "para\n\n".repeat(100_000);
+        let mut iter = parse_iter(&big, ParserFlags::commonmark());
+        assert_eq!(iter.by_ref().take(3).count(), 3);
+        assert!(iter.error().is_none());
+    }
+
+    #[test]
+    fn test_reusable_parser() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        let parser = Parser::new(ParserFlags::github())
+            .with_debug_log(move |msg| sink.lock().unwrap().push(msg.to_string()));
+        let copy = parser.clone();
+        assert_eq!(copy.flags(), ParserFlags::github());
+        assert_eq!(
+            format!("{:?}", copy.parse_to_events("~~a~~").unwrap()),
+            format!("{:?}", parse_to_events("~~a~~", ParserFlags::github()).unwrap())
+        );
+        assert!(copy
+            .parse_iter("| a |\n|---|")
+            .any(|event| matches!(event, parser::Event::LeaveBlock(BlockType::Table))));
+
+        struct Stop;
+        impl ParserHandler for Stop {
+            fn enter_block(&mut self, _: Block) -> bool {
+                false
+            }
+        }
+        assert!(parser.parse("# Hi", &mut Stop).is_err());
+        assert_eq!(*messages.lock().unwrap(), ["Aborted from enter_block() callback."]);
+    }
+
+    #[test]
+    fn test_cancel_from_other_thread() {
+        use std::sync::mpsc::{channel, Receiver, Sender};
+
+        // Signals the first paragraph, then waits until told to go on
+        struct Paced(Sender<()>, Receiver<()>, usize);
+        impl ParserHandler for Paced {
+            fn enter_block(&mut self, block: Block) -> bool {
+                if block.block_type() == BlockType::Paragraph {
+                    self.2 += 1;
+                    if self.2 == 1 {
+                        self.0.send(()).unwrap();
+                        self.1.recv().unwrap();
+                    }
+                }
+                true
+            }
+        }
+
+        let token = CancellationToken::new();
+        let (started, on_start) = channel();
+        let (go_on, on_go) = channel();
+        let worker = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                let mut handler = Paced(started, on_go, 0);
+                let big = "para\n\n".repeat(1000);
+                let flags = ParserFlags::commonmark();
+                let result = parse_with_cancel(&big, flags, &mut handler, &token);
+                (result, handler.2)
+            })
+        };
+        on_start.recv().unwrap();
+        token.cancel();
+        go_on.send(()).unwrap();
+        let (result, paragraphs) = worker.join().unwrap();
+        assert!(matches!(result, Err(ParseError::Cancelled)));
+        assert_eq!(paragraphs, 1);
+
+        // A cancelled token stops parsers before they start
+        let parser = Parser::new(ParserFlags::commonmark()).with_cancellation(token);
+        assert!(matches!(parser.parse_to_events("text"), Err(ParseError::Cancelled)));
+        let mut iter = parser.parse_iter("text");
+        assert!(iter.next().is_none());
+        assert!(matches!(iter.error(), Some(ParseError::Cancelled)));
+    }
+
+    #[test]
+    fn test_utf8_bom() {
+        let input = "\u{FEFF}# Title";
+        let events = parse_to_events(input, ParserFlags::commonmark()).unwrap();
+        assert!(matches!(events[1], parser::Event::EnterBlock(Block::Heading(_))));
+        match &events[2] {
+            parser::Event::Text(_, text) => assert_eq!(parser::source_offset(input, text), Some(5)),
+            event => panic!("{:?}", event),
+        }
+
+        let events = parse_to_events(input, ParserFlags::commonmark().keep_utf8_bom()).unwrap();
+        assert!(matches!(events[1], parser::Event::EnterBlock(Block::Paragraph)));
+        assert!(matches!(&events[2], parser::Event::Text(_, text) if text == input));
+    }
+
+    #[test]
+    fn test_limits() {
+        let parser = Parser::new(ParserFlags::github());
+        let limited = |parser: Parser, input| match parser.parse_to_events(input) {
+            Err(ParseError::LimitExceeded(limit)) => Some(limit),
+            _ => None,
+        };
+        let nested = "> - *a*";
+        assert_eq!(limited(parser.clone().with_max_nesting(4), nested), None);
+        assert_eq!(limited(parser.clone().with_max_nesting(3), nested), Some(Limit::Nesting));
+        let table = "| a | b | c |\n|---|---|---|";
+        assert_eq!(limited(parser.clone().with_max_table_columns(3), table), None);
+        assert_eq!(
+            limited(parser.clone().with_max_table_columns(2), table),
+            Some(Limit::TableColumns)
+        );
+        assert_eq!(limited(parser.clone().with_max_input_len(4), "abcd"), None);
+        assert_eq!(limited(parser.with_max_input_len(4), "abcde"), Some(Limit::InputLen));
+    }
+
+    #[test]
+    fn test_deep_nesting_limit() {
+        // Open blocks and spans inside the document, the deepest and the
+        // text seen
+        struct Depth(usize, usize, usize);
+
+        impl ParserHandler for Depth {
+            fn enter_block(&mut self, block: Block) -> bool {
+                if !matches!(block, Block::Document) {
+                    self.0 += 1;
+                    self.1 = self.1.max(self.0);
+                }
+                true
+            }
+
+            fn leave_block(&mut self, block_type: BlockType) -> bool {
+                if block_type != BlockType::Document {
+                    self.0 -= 1;
+                }
+                true
+            }
+
+            fn text(&mut self, _: TextType, _: &str) -> bool {
+                self.2 += 1;
+                true
+            }
+        }
+
+        let input = format!("{} deep", ">".repeat(1000));
+        let mut handler = Depth(0, 0, 0);
+        let result = Parser::new(ParserFlags::commonmark())
+            .with_max_nesting(8)
+            .parse(&input, &mut handler);
+        assert!(matches!(result, Err(ParseError::LimitExceeded(Limit::Nesting))));
+        assert_eq!((handler.1, handler.2), (8, 0));
+    }
+
+    #[test]
+    fn test_try_parse_error() {
+        #[derive(Debug, PartialEq)]
+        struct TooDeep(u8);
+
+        struct MaxLevel(u8, usize);
+
+        impl TryParserHandler for MaxLevel {
+            type Error = TooDeep;
+
+            fn enter_block(&mut self, block: Block) -> Result<(), TooDeep> {
+                self.1 += 1;
+                match block {
+                    Block::Heading(detail) if detail.level > self.0 => Err(TooDeep(detail.level)),
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        let mut handler = MaxLevel(2, 0);
+        let err = try_parse("# a\n\n### b\n\n# c\n", ParserFlags::commonmark(), &mut handler);
+        assert!(matches!(err, Err(ParseError::Handler(TooDeep(3)))));
+        // Document, first heading, failing heading
+        assert_eq!(handler.1, 3);
+        assert!(try_parse("# a", ParserFlags::commonmark(), &mut MaxLevel(2, 0)).is_ok());
+    }
+
+    #[test]
+    fn test_handler_panic_resumes() {
+        struct Panicky(usize);
+
+        impl ParserHandler for Panicky {
+            fn text(&mut self, _: TextType, text: &str) -> bool {
+                self.0 += 1;
+                if text == "boom" {
+                    panic!("handler failed");
+                }
+                true
+            }
+        }
+
+        let mut handler = Panicky(0);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            parse("a\n\nboom\n\nb", ParserFlags::commonmark(), &mut handler)
+        }));
+        let payload = result.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"handler failed"));
+        // Parsing stopped at the panic
+        assert_eq!(handler.0, 2);
+    }
+
     #[test]
     fn test_heading_levels() {
         struct HeadingChecker {
@@ -253,6 +534,20 @@ mod tests {
         assert_eq!(checker.levels, vec![1, 2, 3]);
     }
 
+    #[test]
+    fn test_crlf_line_endings() {
+        let lf = "# Title\n\nline one  \nline two\n\n```\ncode\n```\n\n<div>\nraw\n</div>\n";
+        let crlf = lf.replace('\n', "\r\n");
+        let cr = lf.replace('\n', "\r");
+        for input in [&crlf, &cr] {
+            assert_eq!(to_html(input).unwrap(), to_html(lf).unwrap());
+            let events = parse_to_events(input, ParserFlags::github()).unwrap();
+            assert!(!events
+                .iter()
+                .any(|e| matches!(e, parser::Event::Text(_, t) if t.contains('\r'))));
+        }
+    }
+
     #[test]
     fn test_link_details() {
         struct LinkChecker {
@@ -263,8 +558,8 @@ mod tests {
         impl ParserHandler for LinkChecker {
             fn enter_span(&mut self, span: Span) -> bool {
                 if let Span::Link(detail) = span {
-                    self.href = detail.href;
-                    self.title = detail.title;
+                    self.href = detail.href.to_string();
+                    self.title = detail.title.to_string();
                 }
                 true
             }
@@ -294,7 +589,7 @@ mod tests {
         impl ParserHandler for CodeChecker {
             fn enter_block(&mut self, block: Block) -> bool {
                 if let Block::Code(detail) = block {
-                    self.lang = detail.lang;
+                    self.lang = detail.lang.to_string();
                 }
                 true
             }
@@ -305,6 +600,31 @@ mod tests {
         assert_eq!(checker.lang, "rust");
     }
 
+    #[test]
+    fn test_code_block_params() {
+        let params = |input: &str| {
+            let events = parse_to_events(input, ParserFlags::commonmark()).unwrap();
+            match &events[1] {
+                parser::Event::EnterBlock(Block::Code(detail)) => detail.params(),
+                event => panic!("not a code block: {:?}", event),
+            }
+        };
+        let param = |name: &str, value: Option<&str>| (name.to_string(), value.map(String::from));
+        assert_eq!(
+            params("```rust,no_run,edition2021\n```"),
+            [param("no_run", None), param("edition2021", None)]
+        );
+        assert_eq!(
+            params("```{.python #snippet}\n```"),
+            [param(".python", None), param("#snippet", None)]
+        );
+        assert_eq!(
+            params("```sh  hl_lines='1, 3' x=&quot;\n```"),
+            [param("hl_lines", Some("1, 3")), param("x", Some("\""))]
+        );
+        assert!(params("```\n```").is_empty());
+    }
+
     #[test]
     fn test_table_parsing() {
         struct TableChecker {
@@ -355,4 +675,110 @@ mod tests {
         assert_eq!(checker.tasks[0], TaskState::Checked);
         assert_eq!(checker.tasks[1], TaskState::Unchecked);
     }
+
+    #[test]
+    fn test_task_mark_offset() {
+        let input = "- plain\n- [X] Done\n  * [ ] nested\n";
+        let marks: Vec<_> = parse_to_events(input, ParserFlags::github())
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                parser::Event::EnterBlock(Block::ListItem(item)) => {
+                    Some((item.task_mark, item.task_mark_offset))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(marks, [(None, None), (Some('X'), Some(11)), (Some(' '), Some(24))]);
+        assert_eq!(&input[11..12], "X");
+        assert_eq!(&input[24..25], " ");
+    }
+
+    #[test]
+    fn test_wiki_link_embed() {
+        struct EmbedChecker {
+            links: Vec<(String, bool)>,
+        }
+
+        impl ParserHandler for EmbedChecker {
+            fn enter_span(&mut self, span: Span) -> bool {
+                if let Span::WikiLink(detail) = span {
+                    self.links.push((detail.target.to_string(), detail.is_embed));
+                }
+                true
+            }
+        }
+
+        let mut checker = EmbedChecker { links: vec![] };
+        parse(
+            "See ![[Note]] and [[Page]]",
+            ParserFlags::new().wiki_links(),
+            &mut checker,
+        )
+        .unwrap();
+        assert_eq!(
+            checker.links,
+            vec![("Note".to_string(), true), ("Page".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_wiki_link_label() {
+        let input = "[[Page|the *page*]] [[Other|]] [[Plain]]";
+        let labels: Vec<Option<Cow<str>>> = parse_to_events(input, ParserFlags::new().wiki_links())
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                parser::Event::EnterSpan(Span::WikiLink(detail)) => Some(detail.label),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels, [Some(Cow::Borrowed("the *page*")), None, None]);
+    }
+
+    #[test]
+    fn test_flag_operators() {
+        let notes = ParserFlags::obsidian();
+        assert!(notes.contains(ParserFlags::github() | ParserFlags::new().wiki_links()));
+        assert!(!ParserFlags::github().contains(notes));
+        assert_eq!(notes & ParserFlags::new().mark(), ParserFlags::new().mark());
+
+        let mut flags = ParserFlags::commonmark();
+        flags |= ParserFlags::new().latex_math_spans();
+        let flags = ParserFlags::from_raw(flags.raw());
+        let html = html::render_html("$x$ [[Page]]", flags, HtmlFlags::new()).unwrap();
+        assert_eq!(html, "<p><x-equation>x</x-equation> [[Page]]</p>\n");
+    }
+
+    #[test]
+    fn test_flag_names() {
+        let notes = ParserFlags::obsidian();
+        assert_eq!(notes.to_string().parse(), Ok(notes));
+        assert_eq!(notes.iter().count(), 9);
+        assert!(notes.iter().all(|flag| notes.contains(flag) && flag.name().is_some()));
+        assert_eq!(ParserFlags::new().to_string(), "");
+        assert_eq!(ParserFlags::new().no_html().name(), None);
+
+        assert_eq!(
+            "GitHub Wiki_Links,super-sub".parse(),
+            Ok(ParserFlags::github().wiki_links().super_sub())
+        );
+        assert_eq!("no-html".parse(), Ok(ParserFlags::new().no_html()));
+        assert_eq!("".parse(), Ok(ParserFlags::new()));
+        assert_eq!("tables,,nope".parse::<ParserFlags>(), Err(UnknownFlag("nope".into())));
+    }
+
+    #[test]
+    fn test_presets() {
+        let render = |flags| html::render_html("x^2^ ~~no~~ www.a.org", flags, HtmlFlags::new());
+        assert_eq!(
+            render(ParserFlags::pandoc()).unwrap(),
+            "<p>x<sup>2</sup> <del>no</del> www.a.org</p>\n"
+        );
+        assert_eq!(
+            render(ParserFlags::multimarkdown()).unwrap(),
+            "<p>x<sup>2</sup> ~~no~~ www.a.org</p>\n"
+        );
+        assert_eq!("pandoc".parse(), Ok(ParserFlags::pandoc()));
+    }
 }
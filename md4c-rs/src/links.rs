@@ -0,0 +1,218 @@
+//! Collecting and classifying a document's links.
+//!
+//! [`collect`] lists every link and image with its destination, text and
+//! position, sorted into [`LinkKind`]s, for link checkers and site
+//! validators.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::links::{collect, LinkKind};
+//! use md4c::ParserFlags;
+//!
+//! let doc = "See [the docs](https://example.org), [setup](#setup)\nand <me@example.org>.\n";
+//! let links = collect(doc, ParserFlags::commonmark());
+//! let kinds: Vec<_> = links.iter().map(|link| link.kind).collect();
+//! assert_eq!(kinds, [LinkKind::External, LinkKind::Anchor, LinkKind::Email]);
+//! assert_eq!(links[2].text, "me@example.org");
+//! assert_eq!(links[2].line, 2);
+//! ```
+
+use crate::entity::decode_entity;
+use crate::parser::{parse, source_offset, ParserFlags, ParserHandler};
+use crate::types::{Span, SpanType, TextType};
+
+/// What a link points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// A URL with a scheme, like `https://example.org`, or starting with
+    /// `//`
+    External,
+    /// A path relative to the document or site, like `../guide.md`
+    Relative,
+    /// A fragment within the document, like `#setup`
+    Anchor,
+    /// A `mailto:` link or email autolink
+    Email,
+    /// A wiki link, like `[[Page]]`
+    Wiki,
+}
+
+impl LinkKind {
+    /// Classify a link destination; wiki links are only told apart by their
+    /// syntax.
+    pub fn of(url: &str) -> Self {
+        let scheme = url
+            .split_once(':')
+            .map(|(scheme, _)| scheme)
+            .filter(|scheme| {
+                let mut chars = scheme.chars();
+                chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                    && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+            });
+        match scheme {
+            Some(scheme) if scheme.eq_ignore_ascii_case("mailto") => LinkKind::Email,
+            Some(_) => LinkKind::External,
+            None if url.starts_with("//") => LinkKind::External,
+            None if url.starts_with('#') => LinkKind::Anchor,
+            None => LinkKind::Relative,
+        }
+    }
+}
+
+/// A link or image found by [`collect`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRef {
+    /// The destination, with entities decoded; the target for wiki links
+    pub url: String,
+    /// The link text, or an image's description, as plain text
+    pub text: String,
+    /// What the link points at
+    pub kind: LinkKind,
+    /// Whether this is an image rather than a link
+    pub image: bool,
+    /// Byte offset of the link's text in the input, or of the text after
+    /// it if it has none
+    pub source_offset: usize,
+    /// Line of the link, starting at 1
+    pub line: usize,
+}
+
+/// Collect the links and images of a document, in order.
+///
+/// If parsing fails, no links are returned.
+pub fn collect(input: &str, flags: ParserFlags) -> Vec<LinkRef> {
+    let mut collector = Collector {
+        input,
+        links: Vec::new(),
+        open: Vec::new(),
+        unplaced: Vec::new(),
+        cursor: 0,
+    };
+    if parse(input, flags, &mut collector).is_err() {
+        return Vec::new();
+    }
+    collector.place(collector.cursor);
+
+    let mut links = collector.links;
+    for link in &mut links {
+        link.line = input[..link.source_offset].matches('\n').count() + 1;
+    }
+    links
+}
+
+struct Collector<'a> {
+    input: &'a str,
+    links: Vec<LinkRef>,
+    // Indices of the links and images being read, innermost last
+    open: Vec<usize>,
+    // Indices of the links whose text has not been seen yet
+    unplaced: Vec<usize>,
+    // End of the last text from the input
+    cursor: usize,
+}
+
+impl Collector<'_> {
+    fn push(&mut self, url: String, kind: LinkKind, image: bool) {
+        self.open.push(self.links.len());
+        self.unplaced.push(self.links.len());
+        self.links.push(LinkRef {
+            url,
+            text: String::new(),
+            kind,
+            image,
+            source_offset: 0,
+            line: 0,
+        });
+    }
+
+    fn place(&mut self, offset: usize) {
+        for index in self.unplaced.drain(..) {
+            self.links[index].source_offset = offset;
+        }
+    }
+}
+
+impl ParserHandler for Collector<'_> {
+    fn enter_span(&mut self, span: Span) -> bool {
+        match span {
+            Span::Link(link) => {
+                let url = link.href.decoded().into_owned();
+                let kind = LinkKind::of(&url);
+                self.push(url, kind, false);
+            }
+            Span::Image(image) => {
+                let url = image.src.decoded().into_owned();
+                let kind = LinkKind::of(&url);
+                self.push(url, kind, true);
+            }
+            Span::WikiLink(wiki) => {
+                self.push(wiki.target.decoded().into_owned(), LinkKind::Wiki, wiki.is_embed);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        if matches!(span_type, SpanType::Link | SpanType::Image | SpanType::WikiLink) {
+            self.open.pop();
+        }
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        if let Some(offset) = source_offset(self.input, text) {
+            self.place(offset);
+            self.cursor = offset + text.len();
+        }
+        let text = match text_type {
+            TextType::SoftBreak | TextType::HardBreak => " ".into(),
+            TextType::Entity => decode_entity(text),
+            TextType::Html => return true,
+            _ => text.to_string(),
+        };
+        for &index in &self.open {
+            self.links[index].text.push_str(&text);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_kinds() {
+        assert_eq!(LinkKind::of("HTTPS://x.org"), LinkKind::External);
+        assert_eq!(LinkKind::of("//cdn.x.org/a.js"), LinkKind::External);
+        assert_eq!(LinkKind::of("MailTo:a@b.c"), LinkKind::Email);
+        assert_eq!(LinkKind::of("#top"), LinkKind::Anchor);
+        assert_eq!(LinkKind::of("../a:b.md"), LinkKind::Relative);
+        assert_eq!(LinkKind::of("docs/a.md#x"), LinkKind::Relative);
+    }
+
+    #[test]
+    fn test_collect() {
+        let doc = "[![logo *big*](img/l.png)](/home \"t\") [**a**\nb](a&amp;b.md)\n\n\
+                   [[Wiki Page|label]] ![[embed.png]] www.x.org []()";
+        let flags = ParserFlags::github().wiki_links();
+        let found: Vec<_> = collect(doc, flags)
+            .into_iter()
+            .map(|l| (l.url, l.text, l.kind, l.image, l.source_offset, l.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/home".into(), "logo big".into(), LinkKind::Relative, false, 3, 1),
+                ("img/l.png".into(), "logo big".into(), LinkKind::Relative, true, 3, 1),
+                ("a&b.md".into(), "a b".into(), LinkKind::Relative, false, 41, 1),
+                ("Wiki Page".into(), "label".into(), LinkKind::Wiki, false, 73, 4),
+                ("embed.png".into(), "embed.png".into(), LinkKind::Wiki, true, 84, 4),
+                ("http://www.x.org".into(), "www.x.org".into(), LinkKind::External, false, 96, 4),
+                ("".into(), "".into(), LinkKind::Relative, false, 106, 4),
+            ]
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Linting Markdown on top of parser events.
+//!
+//! A [`Linter`] runs [`Rule`]s over a document's events, each seeing the
+//! source offset of the text events, and collects their [`Diagnostic`]s.
+//! [`lint`] runs the built-in rules:
+//!
+//! - [`HeadingIncrement`]: heading levels go up one at a time
+//! - [`BareUrls`]: URLs are written as links or `<autolinks>`
+//! - [`MissingAltText`]: images have a description
+//! - [`TrailingSpaces`]: lines don't end in whitespace, except for two
+//!   spaces making a hard line break
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::lint::lint;
+//! use md4c::ParserFlags;
+//!
+//! let doc = "# Title\n\n### Details\n\nSee https://example.org\n";
+//! let diagnostics = lint(doc, ParserFlags::commonmark()).unwrap();
+//! let found: Vec<_> = diagnostics.iter().map(|d| (d.rule, d.line)).collect();
+//! assert_eq!(found, [("heading-increment", 3), ("no-bare-urls", 5)]);
+//! ```
+//!
+//! Rules of your own implement [`Rule`]:
+//!
+//! ```
+//! use md4c::lint::{LintContext, Linter, Rule};
+//! use md4c::parser::Event;
+//! use md4c::{ParserFlags, TextType};
+//!
+//! struct NoTodo;
+//!
+//! impl Rule for NoTodo {
+//!     fn name(&self) -> &'static str {
+//!         "no-todo"
+//!     }
+//!
+//!     fn check(&mut self, event: &Event<'_>, ctx: &mut LintContext<'_>) {
+//!         if let Event::Text(TextType::Normal, text) = event {
+//!             if let Some(i) = text.find("TODO") {
+//!                 ctx.report_at(ctx.offset().unwrap_or(0) + i, "unfinished section");
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let mut linter = Linter::new().with_rule(NoTodo);
+//! let diagnostics = linter.check("Intro\n\nTODO: write", ParserFlags::commonmark()).unwrap();
+//! assert_eq!(diagnostics[0].offset, 7);
+//! ```
+
+use crate::parser::{source_offset, Event, ParseResult, Parser, ParserFlags};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+use std::fmt;
+use std::ops::Range;
+
+/// A problem found by a [`Rule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Name of the rule that found it
+    pub rule: &'static str,
+    /// What is wrong
+    pub message: String,
+    /// Byte offset in the input
+    pub offset: usize,
+    /// Line of the problem, starting at 1
+    pub line: usize,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} [{}]", self.line, self.message, self.rule)
+    }
+}
+
+/// A lint rule, fed the events of a document in order.
+pub trait Rule {
+    /// The rule's name, such as `heading-increment`
+    fn name(&self) -> &'static str;
+
+    /// Check the next event; [`LintContext::offset`] has its source offset
+    /// if it is text from the input.
+    fn check(&mut self, event: &Event<'_>, ctx: &mut LintContext<'_>);
+
+    /// Called after the last event, e.g. for checks on the source text.
+    ///
+    /// Rules are reused across documents, so they should reset their state
+    /// here.
+    fn finish(&mut self, ctx: &mut LintContext<'_>) {
+        let _ = ctx;
+    }
+}
+
+/// What a [`Rule`] sees of the document, and where it reports problems.
+pub struct LintContext<'a> {
+    input: &'a str,
+    offset: Option<usize>,
+    rule: &'static str,
+    diagnostics: &'a mut Vec<Diagnostic>,
+    // Diagnostics waiting for the offset of the next text
+    unplaced: &'a mut Vec<usize>,
+}
+
+impl LintContext<'_> {
+    /// The document's source.
+    pub fn input(&self) -> &str {
+        self.input
+    }
+
+    /// Byte offset of the current event, for text from the input.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Report a problem at the current event.
+    ///
+    /// Blocks and spans have no source offsets, so a problem reported on
+    /// them is placed at the text that follows.
+    pub fn report(&mut self, message: impl Into<String>) {
+        match self.offset {
+            Some(offset) => self.report_at(offset, message),
+            None => {
+                self.unplaced.push(self.diagnostics.len());
+                self.report_at(0, message);
+            }
+        }
+    }
+
+    /// Report a problem at a byte offset in the input.
+    pub fn report_at(&mut self, offset: usize, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            rule: self.rule,
+            message: message.into(),
+            offset,
+            line: 0,
+        });
+    }
+}
+
+/// Runs lint rules over documents.
+///
+/// [`Linter::default`] has the built-in rules, [`Linter::new`] none.
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Linter {
+    /// Create a linter without rules.
+    pub fn new() -> Self {
+        Linter { rules: Vec::new() }
+    }
+
+    /// Add a rule.
+    pub fn with_rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Lint a document, returning the problems in source order.
+    pub fn check(&mut self, input: &str, flags: ParserFlags) -> ParseResult<Vec<Diagnostic>> {
+        let events = Parser::new(flags).parse_to_events(input)?;
+        let mut diagnostics = Vec::new();
+        let mut unplaced = Vec::new();
+        let mut cursor = 0;
+        let place = |diagnostics: &mut Vec<Diagnostic>, unplaced: &mut Vec<usize>, offset| {
+            for index in unplaced.drain(..) {
+                diagnostics[index].offset = offset;
+            }
+        };
+
+        for event in &events {
+            let offset = match event {
+                Event::Text(_, text) => source_offset(input, text),
+                _ => None,
+            };
+            if let (Some(offset), Event::Text(_, text)) = (offset, event) {
+                place(&mut diagnostics, &mut unplaced, offset);
+                cursor = offset + text.len();
+            }
+            for rule in &mut self.rules {
+                let mut ctx = LintContext {
+                    input,
+                    offset,
+                    rule: rule.name(),
+                    diagnostics: &mut diagnostics,
+                    unplaced: &mut unplaced,
+                };
+                rule.check(event, &mut ctx);
+            }
+        }
+        for rule in &mut self.rules {
+            let mut ctx = LintContext {
+                input,
+                offset: None,
+                rule: rule.name(),
+                diagnostics: &mut diagnostics,
+                unplaced: &mut unplaced,
+            };
+            rule.finish(&mut ctx);
+        }
+        place(&mut diagnostics, &mut unplaced, cursor);
+
+        diagnostics.sort_by_key(|d| d.offset);
+        for diagnostic in &mut diagnostics {
+            diagnostic.line = input[..diagnostic.offset].matches('\n').count() + 1;
+        }
+        Ok(diagnostics)
+    }
+}
+
+impl Default for Linter {
+    /// A linter with the built-in rules.
+    fn default() -> Self {
+        Linter::new()
+            .with_rule(HeadingIncrement::default())
+            .with_rule(BareUrls::default())
+            .with_rule(MissingAltText::default())
+            .with_rule(TrailingSpaces::default())
+    }
+}
+
+/// Lint a document with the built-in rules.
+pub fn lint(input: &str, flags: ParserFlags) -> ParseResult<Vec<Diagnostic>> {
+    Linter::default().check(input, flags)
+}
+
+/// Headings go up one level at a time, e.g. no `###` right after `#`.
+#[derive(Debug, Default)]
+pub struct HeadingIncrement {
+    last: Option<u8>,
+}
+
+impl Rule for HeadingIncrement {
+    fn name(&self) -> &'static str {
+        "heading-increment"
+    }
+
+    fn check(&mut self, event: &Event<'_>, ctx: &mut LintContext<'_>) {
+        if let Event::EnterBlock(Block::Heading(heading)) = event {
+            if let Some(last) = self.last.filter(|&last| heading.level > last + 1) {
+                ctx.report(format!("heading level jumps from {} to {}", last, heading.level));
+            }
+            self.last = Some(heading.level);
+        }
+    }
+
+    fn finish(&mut self, _ctx: &mut LintContext<'_>) {
+        self.last = None;
+    }
+}
+
+/// URLs are written as links or `<autolinks>`, not bare.
+///
+/// Flags permissive autolinks, and `http://` and `https://` URLs in text
+/// where they aren't enabled.
+#[derive(Debug, Default)]
+pub struct BareUrls {
+    links: usize,
+    // An autolink was entered, and its text will tell whether it is bare
+    autolink: bool,
+}
+
+impl Rule for BareUrls {
+    fn name(&self) -> &'static str {
+        "no-bare-urls"
+    }
+
+    fn check(&mut self, event: &Event<'_>, ctx: &mut LintContext<'_>) {
+        match event {
+            Event::EnterSpan(Span::Link(link)) => {
+                self.links += 1;
+                self.autolink = link.is_autolink;
+            }
+            Event::EnterSpan(Span::WikiLink(_)) => self.links += 1,
+            Event::LeaveSpan(SpanType::Link | SpanType::WikiLink) => {
+                self.links = self.links.saturating_sub(1);
+            }
+            Event::Text(_, _) if std::mem::take(&mut self.autolink) => {
+                if let Some(offset) = ctx.offset() {
+                    if !ctx.input()[..offset].ends_with('<') {
+                        ctx.report("bare URL; write it as <URL> or a link");
+                    }
+                }
+            }
+            Event::Text(TextType::Normal, text) if self.links == 0 => {
+                let Some(offset) = ctx.offset() else { return };
+                for (i, _) in text.match_indices("http") {
+                    let url = &text[i..];
+                    let at_word = text[..i].chars().next_back().is_none_or(char::is_whitespace);
+                    if at_word && (url.starts_with("http://") || url.starts_with("https://")) {
+                        ctx.report_at(offset + i, "bare URL; write it as <URL> or a link");
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, _ctx: &mut LintContext<'_>) {
+        *self = Self::default();
+    }
+}
+
+/// Images have a description for readers who can't see them.
+#[derive(Debug, Default)]
+pub struct MissingAltText {
+    // Descriptions of the images being read, innermost last
+    images: Vec<String>,
+}
+
+impl Rule for MissingAltText {
+    fn name(&self) -> &'static str {
+        "no-missing-alt-text"
+    }
+
+    fn check(&mut self, event: &Event<'_>, ctx: &mut LintContext<'_>) {
+        match event {
+            Event::EnterSpan(Span::Image(_)) => self.images.push(String::new()),
+            Event::Text(_, text) => {
+                for alt in &mut self.images {
+                    alt.push_str(text);
+                }
+            }
+            Event::LeaveSpan(SpanType::Image)
+                if self.images.pop().is_some_and(|alt| alt.trim().is_empty()) =>
+            {
+                ctx.report("image without alt text");
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, _ctx: &mut LintContext<'_>) {
+        self.images.clear();
+    }
+}
+
+/// Lines don't end in whitespace, except for exactly two spaces making a
+/// hard line break. Code blocks are left alone.
+#[derive(Debug, Default)]
+pub struct TrailingSpaces {
+    in_code: bool,
+    // Source ranges of code block text
+    code: Vec<Range<usize>>,
+}
+
+impl Rule for TrailingSpaces {
+    fn name(&self) -> &'static str {
+        "no-trailing-spaces"
+    }
+
+    fn check(&mut self, event: &Event<'_>, ctx: &mut LintContext<'_>) {
+        match event {
+            Event::EnterBlock(Block::Code(_)) => self.in_code = true,
+            Event::LeaveBlock(BlockType::Code) => self.in_code = false,
+            Event::Text(_, text) if self.in_code => {
+                if let Some(offset) = ctx.offset() {
+                    self.code.push(offset..offset + text.len());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, ctx: &mut LintContext<'_>) {
+        let input = ctx.input();
+        let mut found = Vec::new();
+        let mut start = 0;
+        for line in input.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let trimmed = content.trim_end_matches([' ', '\t']);
+            let spaces = &content[trimmed.len()..];
+            let at = start + trimmed.len();
+            let hard_break = spaces == "  " && !trimmed.is_empty();
+            if !spaces.is_empty() && !hard_break && !self.code.iter().any(|r| r.contains(&at)) {
+                found.push(at);
+            }
+            start += line.len();
+        }
+        for at in found {
+            ctx.report_at(at, "trailing whitespace");
+        }
+        self.code.clear();
+        self.in_code = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(input: &str, flags: ParserFlags) -> Vec<(&'static str, usize)> {
+        lint(input, flags).unwrap().iter().map(|d| (d.rule, d.offset)).collect()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let doc = "# A\n## B\n#### C \n\ntext  \n\
+                   https://a.org <https://b.org> [c](https://c.org)\n\n\
+                   ![](x.png) ![ok](y.png) \t\n\n```\ncode   \n```\n";
+        assert_eq!(
+            rules(doc, ParserFlags::commonmark()),
+            [
+                ("heading-increment", 14),
+                ("no-trailing-spaces", 15),
+                ("no-bare-urls", 25),
+                ("no-missing-alt-text", 85),
+                ("no-trailing-spaces", 98),
+            ]
+        );
+        // With permissive autolinks, bare URLs are links
+        assert_eq!(rules("see www.x.org", ParserFlags::github()), [("no-bare-urls", 4)]);
+    }
+
+    #[test]
+    fn test_linter_is_reusable() {
+        let mut linter = Linter::new().with_rule(HeadingIncrement::default());
+        let doc = "# A\n\n### B\n";
+        let first = linter.check(doc, ParserFlags::commonmark()).unwrap();
+        assert_eq!(first, linter.check(doc, ParserFlags::commonmark()).unwrap());
+        assert_eq!(first[0].to_string(), "3: heading level jumps from 1 to 3 [heading-increment]");
+    }
+}
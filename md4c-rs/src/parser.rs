@@ -1,11 +1,60 @@
 //! Safe Rust wrapper for the MD4C parser.
 
+use crate::admonition::Admonitions;
+use crate::delimiters::Delimiters;
+use crate::embeds::Embeds;
 use crate::sys;
 use crate::types::*;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+use std::any::Any;
 use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+
+/// Flags for extensions handled on the Rust side; kept clear of MD4C's
+/// own flags.
+const FLAG_ADMONITIONS: u32 = 0x0100_0000;
+pub(crate) const FLAG_MARK: u32 = 0x0200_0000;
+pub(crate) const FLAG_SUPER_SUB: u32 = 0x0400_0000;
+pub(crate) const FLAG_KEEP_UTF8_BOM: u32 = 0x0800_0000;
 
 /// Parser configuration flags
-#[derive(Debug, Clone, Copy, Default)]
+///
+/// The builder methods are `const`, so an application can name its own
+/// dialect as a constant, and flags combine with `|` and `&`:
+///
+/// ```
+/// use md4c::ParserFlags;
+///
+/// const NOTES: ParserFlags = ParserFlags::commonmark().wiki_links().task_lists();
+///
+/// let flags = NOTES | ParserFlags::new().tables();
+/// assert!(flags.contains(NOTES));
+/// assert_eq!(ParserFlags::from_raw(flags.raw()), flags);
+/// ```
+///
+/// For command lines and config files, flags also parse from and display
+/// as a list of names, as used by MD4C's `md2html`:
+///
+/// ```
+/// use md4c::ParserFlags;
+///
+/// let flags: ParserFlags = "tables, strikethrough,tasklists".parse().unwrap();
+/// assert_eq!(flags, ParserFlags::new().tables().strikethrough().task_lists());
+/// assert_eq!(flags.to_string(), "tables,strikethrough,tasklists");
+/// assert_eq!("github".parse(), Ok(ParserFlags::github()));
+/// assert!("tables,footnotes".parse::<ParserFlags>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct ParserFlags {
     flags: u32,
 }
@@ -16,6 +65,11 @@ impl ParserFlags {
         ParserFlags { flags: 0 }
     }
 
+    /// Flags from a raw value, as returned by [`raw`](Self::raw)
+    pub const fn from_raw(raw: u32) -> Self {
+        ParserFlags { flags: raw }
+    }
+
     /// CommonMark dialect (no extensions)
     pub const fn commonmark() -> Self {
         ParserFlags { flags: sys::MD_DIALECT_COMMONMARK }
@@ -26,6 +80,31 @@ impl ParserFlags {
         ParserFlags { flags: sys::MD_DIALECT_GITHUB }
     }
 
+    /// Obsidian-style notes: GitHub-flavored Markdown with wiki links,
+    /// LaTeX math and `==highlighted==` text
+    pub const fn obsidian() -> Self {
+        Self::github().wiki_links().latex_math_spans().mark()
+    }
+
+    /// MultiMarkdown's extensions as far as MD4C has them: tables, LaTeX
+    /// math, `^superscript^` and `~subscript~`
+    pub const fn multimarkdown() -> Self {
+        Self::commonmark().tables().latex_math_spans().super_sub()
+    }
+
+    /// The common subset of Pandoc's Markdown: pipe tables, strikethrough,
+    /// task lists, `$math$`, `^superscript^` and `~subscript~`
+    ///
+    /// Unlike GitHub, Pandoc doesn't link bare URLs.
+    pub const fn pandoc() -> Self {
+        Self::commonmark()
+            .tables()
+            .strikethrough()
+            .task_lists()
+            .latex_math_spans()
+            .super_sub()
+    }
+
     /// Collapse non-trivial whitespace
     pub const fn collapse_whitespace(mut self) -> Self {
         self.flags |= sys::MD_FLAG_COLLAPSEWHITESPACE;
@@ -128,52 +207,264 @@ impl ParserFlags {
         self
     }
 
+    /// Recognize GitHub-style alerts (`> [!NOTE]`) as
+    /// [`Block::Admonition`] (extension)
+    pub const fn admonitions(mut self) -> Self {
+        self.flags |= FLAG_ADMONITIONS;
+        self
+    }
+
+    /// Enable `==highlighted==` text as [`Span::Mark`] (extension)
+    pub const fn mark(mut self) -> Self {
+        self.flags |= FLAG_MARK;
+        self
+    }
+
+    /// Enable `^superscript^` and `~subscript~` text as
+    /// [`Span::Superscript`] and [`Span::Subscript`] (extension)
+    ///
+    /// A single tilde then always means subscript, also where
+    /// strikethrough would take it.
+    pub const fn super_sub(mut self) -> Self {
+        self.flags |= FLAG_SUPER_SUB;
+        self
+    }
+
+    /// Pass a leading UTF-8 byte order mark to the handler as text
+    ///
+    /// By default it is skipped. With this flag the HTML renderers keep it
+    /// in the output too, unless `HtmlFlags::skip_utf8_bom` is set.
+    pub const fn keep_utf8_bom(mut self) -> Self {
+        self.flags |= FLAG_KEEP_UTF8_BOM;
+        self
+    }
+
     /// Get the raw flags value
     pub const fn raw(self) -> u32 {
         self.flags
     }
+
+    /// Whether all flags set in `other` are set
+    pub const fn contains(self, other: ParserFlags) -> bool {
+        self.flags & other.flags == other.flags
+    }
+
+    /// Whether no flags are set
+    pub const fn is_empty(self) -> bool {
+        self.flags == 0
+    }
+
+    /// The enabled flags, one at a time
+    ///
+    /// Flags that stand for several others, like
+    /// [`permissive_autolinks`](Self::permissive_autolinks), come out as
+    /// their parts.
+    pub fn iter(self) -> impl Iterator<Item = ParserFlags> {
+        FLAG_NAMES
+            .iter()
+            .map(|&(_, flags)| ParserFlags { flags })
+            .filter(move |&flag| self.contains(flag))
+    }
+
+    /// The name of a single flag, as parsed by [`FromStr`]
+    pub fn name(self) -> Option<&'static str> {
+        FLAG_NAMES.iter().find(|&&(_, flags)| flags == self.flags).map(|&(name, _)| name)
+    }
+
+    /// The flags understood by MD4C itself.
+    pub(crate) const fn md4c(self) -> u32 {
+        self.flags & !(FLAG_ADMONITIONS | FLAG_MARK | FLAG_SUPER_SUB | FLAG_KEEP_UTF8_BOM)
+    }
+}
+
+impl BitOr for ParserFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        ParserFlags { flags: self.flags | other.flags }
+    }
+}
+
+impl BitOrAssign for ParserFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.flags |= other.flags;
+    }
+}
+
+impl BitAnd for ParserFlags {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        ParserFlags { flags: self.flags & other.flags }
+    }
+}
+
+impl BitAndAssign for ParserFlags {
+    fn bitand_assign(&mut self, other: Self) {
+        self.flags &= other.flags;
+    }
+}
+
+/// Names of the single flags, after MD4C's `md2html` options.
+const FLAG_NAMES: &[(&str, u32)] = &[
+    ("collapse-whitespace", sys::MD_FLAG_COLLAPSEWHITESPACE),
+    ("permissive-atx-headers", sys::MD_FLAG_PERMISSIVEATXHEADERS),
+    ("permissive-url-autolinks", sys::MD_FLAG_PERMISSIVEURLAUTOLINKS),
+    ("permissive-email-autolinks", sys::MD_FLAG_PERMISSIVEEMAILAUTOLINKS),
+    ("permissive-www-autolinks", sys::MD_FLAG_PERMISSIVEWWWAUTOLINKS),
+    ("no-indented-code", sys::MD_FLAG_NOINDENTEDCODEBLOCKS),
+    ("no-html-blocks", sys::MD_FLAG_NOHTMLBLOCKS),
+    ("no-html-spans", sys::MD_FLAG_NOHTMLSPANS),
+    ("tables", sys::MD_FLAG_TABLES),
+    ("strikethrough", sys::MD_FLAG_STRIKETHROUGH),
+    ("tasklists", sys::MD_FLAG_TASKLISTS),
+    ("latex-math", sys::MD_FLAG_LATEXMATHSPANS),
+    ("wiki-links", sys::MD_FLAG_WIKILINKS),
+    ("underline", sys::MD_FLAG_UNDERLINE),
+    ("hard-soft-breaks", sys::MD_FLAG_HARD_SOFT_BREAKS),
+    ("admonitions", FLAG_ADMONITIONS),
+    ("mark", FLAG_MARK),
+    ("super-sub", FLAG_SUPER_SUB),
+    ("keep-utf8-bom", FLAG_KEEP_UTF8_BOM),
+];
+
+/// Names standing for several flags.
+const FLAG_GROUPS: &[(&str, ParserFlags)] = &[
+    ("commonmark", ParserFlags::commonmark()),
+    ("github", ParserFlags::github()),
+    ("obsidian", ParserFlags::obsidian()),
+    ("multimarkdown", ParserFlags::multimarkdown()),
+    ("pandoc", ParserFlags::pandoc()),
+    ("permissive-autolinks", ParserFlags::new().permissive_autolinks()),
+    ("no-html", ParserFlags::new().no_html()),
+];
+
+impl fmt::Display for ParserFlags {
+    /// Names of the enabled flags, separated by commas.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, flag) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(flag.name().unwrap_or_default())?;
+        }
+        Ok(())
+    }
+}
+
+/// Error parsing [`ParserFlags`] from a string: a name wasn't recognized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFlag(pub String);
+
+impl fmt::Display for UnknownFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown parser flag: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownFlag {}
+
+impl FromStr for ParserFlags {
+    type Err = UnknownFlag;
+
+    /// Parse flag names, or the names of presets like `github` and
+    /// `pandoc`, separated by commas or whitespace.
+    ///
+    /// Names are matched ignoring case, `-` and `_`, so `task_lists` and
+    /// `TaskLists` work too.
+    fn from_str(s: &str) -> Result<Self, UnknownFlag> {
+        let key = |name: &str| name.replace(['-', '_'], "").to_ascii_lowercase();
+        let mut flags = ParserFlags::new();
+        for name in s.split(|c: char| c == ',' || c.is_whitespace()).filter(|n| !n.is_empty()) {
+            let wanted = key(name);
+            let single = FLAG_NAMES
+                .iter()
+                .find(|(known, _)| key(known) == wanted)
+                .map(|&(_, flags)| ParserFlags { flags });
+            let group = || {
+                FLAG_GROUPS.iter().find(|(known, _)| key(known) == wanted).map(|&(_, flags)| flags)
+            };
+            flags |= single.or_else(group).ok_or_else(|| UnknownFlag(name.to_string()))?;
+        }
+        Ok(flags)
+    }
 }
 
 /// Events emitted during parsing
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event<'a> {
     /// Entering a block element
-    EnterBlock(Block),
+    EnterBlock(Block<'a>),
     /// Leaving a block element
     LeaveBlock(BlockType),
     /// Entering an inline span
-    EnterSpan(Span),
+    EnterSpan(Span<'a>),
     /// Leaving an inline span
     LeaveSpan(SpanType),
-    /// Text content
-    Text(TextType, &'a str),
+    /// Text content, borrowed from the input where possible
+    Text(TextType, Cow<'a, str>),
 }
 
 /// Result type for parser operations
 pub type ParseResult<T> = Result<T, ParseError>;
 
 /// Error type for parsing operations
+///
+/// `E` is the error type of a [`TryParserHandler`]; plain [`parse`] never
+/// produces [`ParseError::Handler`].
 #[derive(Debug, Clone)]
-pub enum ParseError {
+#[non_exhaustive]
+pub enum ParseError<E = Infallible> {
     /// Parser encountered a runtime error
     RuntimeError,
     /// Callback returned an error
     CallbackError(i32),
     /// Invalid UTF-8 in input
     InvalidUtf8,
+    /// A [`TryParserHandler`] returned an error, aborting the parse
+    Handler(E),
+    /// The parse was stopped through a [`CancellationToken`]
+    Cancelled,
+    /// The document exceeded a limit set on the [`Parser`]
+    LimitExceeded(Limit),
+}
+
+impl<E> ParseError<E> {
+    /// Convert a parser error without a handler error into this type.
+    fn from_parse(err: ParseError) -> Self {
+        match err {
+            ParseError::RuntimeError => ParseError::RuntimeError,
+            ParseError::CallbackError(code) => ParseError::CallbackError(code),
+            ParseError::InvalidUtf8 => ParseError::InvalidUtf8,
+            ParseError::Handler(never) => match never {},
+            ParseError::Cancelled => ParseError::Cancelled,
+            ParseError::LimitExceeded(limit) => ParseError::LimitExceeded(limit),
+        }
+    }
 }
 
-impl std::fmt::Display for ParseError {
+impl<E: std::fmt::Display> std::fmt::Display for ParseError<E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ParseError::RuntimeError => write!(f, "MD4C runtime error"),
             ParseError::CallbackError(code) => write!(f, "Callback error: {}", code),
             ParseError::InvalidUtf8 => write!(f, "Invalid UTF-8 in input"),
+            ParseError::Handler(err) => write!(f, "Handler error: {}", err),
+            ParseError::Cancelled => write!(f, "Parsing cancelled"),
+            ParseError::LimitExceeded(limit) => write!(f, "Limit exceeded: {}", limit),
         }
     }
 }
 
-impl std::error::Error for ParseError {}
+impl<E: std::error::Error + 'static> std::error::Error for ParseError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Handler(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
 /// Trait for handling parser events
 pub trait ParserHandler {
@@ -206,29 +497,891 @@ pub trait ParserHandler {
         let _ = (text_type, text);
         true
     }
+
+    /// Called when leaving a block element, with the block it was entered
+    /// with
+    ///
+    /// Calls [`leave_block`](Self::leave_block) by default. Override this
+    /// instead to get the leaving block's detail, e.g. a heading's level or a
+    /// code block's language, without keeping a stack of open blocks, and
+    /// return `true` from [`wants_leave_details`](Self::wants_leave_details).
+    fn leave_block_with_detail(&mut self, block: Block) -> bool {
+        self.leave_block(block.block_type())
+    }
+
+    /// Called when leaving an inline span, with the span it was entered with
+    ///
+    /// Calls [`leave_span`](Self::leave_span) by default; see
+    /// [`leave_block_with_detail`](Self::leave_block_with_detail).
+    fn leave_span_with_detail(&mut self, span: Span) -> bool {
+        self.leave_span(span.span_type())
+    }
+
+    /// Whether leave events should carry the detail blocks and spans were
+    /// entered with
+    ///
+    /// Only then does the parser keep a copy of each open block and span to
+    /// pass to [`leave_block_with_detail`](Self::leave_block_with_detail)
+    /// and [`leave_span_with_detail`](Self::leave_span_with_detail);
+    /// otherwise they get a detail-less stand-in. `false` by default, so
+    /// handlers that don't need the details don't pay for them.
+    fn wants_leave_details(&self) -> bool {
+        false
+    }
+}
+
+impl<H: ParserHandler + ?Sized> ParserHandler for &mut H {
+    fn enter_block(&mut self, block: Block) -> bool {
+        (**self).enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        (**self).leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        (**self).enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        (**self).leave_span(span_type)
+    }
+
+    fn leave_block_with_detail(&mut self, block: Block) -> bool {
+        (**self).leave_block_with_detail(block)
+    }
+
+    fn leave_span_with_detail(&mut self, span: Span) -> bool {
+        (**self).leave_span_with_detail(span)
+    }
+
+    fn wants_leave_details(&self) -> bool {
+        (**self).wants_leave_details()
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        (**self).text(text_type, text)
+    }
+}
+
+/// Handler whose methods can fail with a typed error
+///
+/// Like [`ParserHandler`], but returning `Err` aborts the parse and the error
+/// is surfaced by [`try_parse`] as [`ParseError::Handler`].
+pub trait TryParserHandler {
+    /// Error aborting the parse
+    type Error;
+
+    /// Called when entering a block element
+    fn enter_block(&mut self, block: Block) -> Result<(), Self::Error> {
+        let _ = block;
+        Ok(())
+    }
+
+    /// Called when leaving a block element
+    fn leave_block(&mut self, block_type: BlockType) -> Result<(), Self::Error> {
+        let _ = block_type;
+        Ok(())
+    }
+
+    /// Called when entering an inline span
+    fn enter_span(&mut self, span: Span) -> Result<(), Self::Error> {
+        let _ = span;
+        Ok(())
+    }
+
+    /// Called when leaving an inline span
+    fn leave_span(&mut self, span_type: SpanType) -> Result<(), Self::Error> {
+        let _ = span_type;
+        Ok(())
+    }
+
+    /// Called with text content
+    fn text(&mut self, text_type: TextType, text: &str) -> Result<(), Self::Error> {
+        let _ = (text_type, text);
+        Ok(())
+    }
+}
+
+/// Adapts a [`TryParserHandler`] to [`ParserHandler`], keeping the first
+/// error.
+struct TryAdapter<'h, H: TryParserHandler> {
+    handler: &'h mut H,
+    error: Option<H::Error>,
+}
+
+impl<H: TryParserHandler> TryAdapter<'_, H> {
+    fn check(&mut self, result: Result<(), H::Error>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                self.error = Some(err);
+                false
+            }
+        }
+    }
+}
+
+impl<H: TryParserHandler> ParserHandler for TryAdapter<'_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        let result = self.handler.enter_block(block);
+        self.check(result)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        let result = self.handler.leave_block(block_type);
+        self.check(result)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        let result = self.handler.enter_span(span);
+        self.check(result)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        let result = self.handler.leave_span(span_type);
+        self.check(result)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let result = self.handler.text(text_type, text);
+        self.check(result)
+    }
+}
+
+/// Parse markdown text with a fallible handler
+///
+/// # Example
+/// ```
+/// use md4c::parser::{try_parse, ParseError, ParserFlags, TryParserHandler};
+/// use md4c::TextType;
+///
+/// struct NoHtml;
+///
+/// impl TryParserHandler for NoHtml {
+///     type Error = String;
+///
+///     fn text(&mut self, text_type: TextType, text: &str) -> Result<(), String> {
+///         match text_type {
+///             TextType::Html => Err(format!("raw HTML not allowed: {}", text)),
+///             _ => Ok(()),
+///         }
+///     }
+/// }
+///
+/// let err = try_parse("Hi <b>there</b>", ParserFlags::commonmark(), &mut NoHtml).unwrap_err();
+/// assert!(matches!(err, ParseError::Handler(msg) if msg.contains("<b>")));
+/// ```
+pub fn try_parse<H: TryParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+) -> Result<(), ParseError<H::Error>> {
+    Parser::new(flags).try_parse(input, handler)
+}
+
+/// Something the parser met that this wrapper doesn't understand, most
+/// likely because MD4C is newer than the wrapper.
+///
+/// The construct is still passed to the handler, as an `Unknown` variant;
+/// see [`set_warning_hook`] to have these reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A block of an unknown raw type
+    UnknownBlock(u32),
+    /// A span of an unknown raw type
+    UnknownSpan(u32),
+    /// Text of an unknown raw type
+    UnknownText(u32),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnknownBlock(raw) => write!(f, "unknown MD4C block type {}", raw),
+            Warning::UnknownSpan(raw) => write!(f, "unknown MD4C span type {}", raw),
+            Warning::UnknownText(raw) => write!(f, "unknown MD4C text type {}", raw),
+        }
+    }
+}
+
+type WarningHook = Box<dyn Fn(&Warning) + Send + Sync>;
+
+static WARNING_HOOK: RwLock<Option<WarningHook>> = RwLock::new(None);
+
+/// Call `hook` with each [`Warning`] raised while parsing, on any thread,
+/// replacing any previous hook.
+///
+/// Without a hook, warnings are dropped.
+///
+/// # Example
+/// ```
+/// md4c::parser::set_warning_hook(|warning| eprintln!("md4c: {}", warning));
+/// ```
+pub fn set_warning_hook<F>(hook: F)
+where
+    F: Fn(&Warning) + Send + Sync + 'static,
+{
+    *WARNING_HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(hook));
+}
+
+/// Remove the hook set by [`set_warning_hook`].
+pub fn clear_warning_hook() {
+    *WARNING_HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn warn(warning: Warning) {
+    if let Some(hook) = WARNING_HOOK.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        hook(&warning);
+    }
 }
 
 /// Parse markdown text with a custom handler
+///
+/// `\n`, `\r\n` and a lone `\r` are all recognized as line endings, and the
+/// handler only ever sees `\n`, so input need not be normalized first.
+///
+/// If the handler panics, parsing stops and the panic resumes once control is
+/// back in Rust.
 pub fn parse<H: ParserHandler>(input: &str, flags: ParserFlags, handler: &mut H) -> ParseResult<()> {
-    struct Context<'a, H: ParserHandler> {
+    Parser::new(flags).parse(input, handler)
+}
+
+type DebugLog = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Parser configuration, reusable across documents
+///
+/// Holds the flags and settings that the free functions like [`parse`] take
+/// on every call. Cloning is cheap.
+///
+/// # Example
+/// ```
+/// use md4c::parser::{Parser, ParserFlags};
+///
+/// let parser = Parser::new(ParserFlags::github());
+/// for doc in ["# One", "Two"] {
+///     let events = parser.parse_to_events(doc).unwrap();
+///     assert_eq!(events.len(), 5);
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Parser {
+    flags: ParserFlags,
+    debug_log: Option<DebugLog>,
+    cancel: Option<CancellationToken>,
+    limits: Limits,
+}
+
+impl Parser {
+    /// Create a parser with the given flags.
+    pub fn new(flags: ParserFlags) -> Self {
+        Parser {
+            flags,
+            debug_log: None,
+            cancel: None,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Set the parser flags.
+    pub fn with_flags(mut self, flags: ParserFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Pass MD4C's debug messages to `log`, see [`parse_with_debug`].
+    pub fn with_debug_log(mut self, log: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.debug_log = Some(Arc::new(log));
+        self
+    }
+
+    /// Stop parsing once `token` is cancelled, see [`parse_with_cancel`].
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Refuse input longer than `len` bytes.
+    ///
+    /// This and the other limits fail the parse with
+    /// [`ParseError::LimitExceeded`]. Only the input length is checked before
+    /// MD4C runs, so it is the one bounding the work done for untrusted
+    /// documents. The others are checked as MD4C reports blocks and spans,
+    /// after it has built them, and only keep the handler from seeing
+    /// anything past the limit.
+    pub fn with_max_input_len(mut self, len: usize) -> Self {
+        self.limits.input_len = Some(len);
+        self
+    }
+
+    /// Stop at blocks and spans nested more than `depth` deep, not counting
+    /// the document.
+    ///
+    /// The handler sees nothing from the first block or span past the limit
+    /// on, but MD4C has parsed all of the nesting by then.
+    pub fn with_max_nesting(mut self, depth: usize) -> Self {
+        self.limits.nesting = Some(depth);
+        self
+    }
+
+    /// Stop at tables with more than `columns` columns.
+    ///
+    /// MD4C itself gives up on tables wider than 128 columns, leaving them
+    /// as paragraphs.
+    pub fn with_max_table_columns(mut self, columns: usize) -> Self {
+        self.limits.table_columns = Some(columns);
+        self
+    }
+
+    /// The parser flags.
+    pub fn flags(&self) -> ParserFlags {
+        self.flags
+    }
+
+    /// Parse `input` with a custom handler, see [`parse`].
+    pub fn parse<H: ParserHandler>(&self, input: &str, handler: &mut H) -> ParseResult<()> {
+        self.parse_until(input, handler, None)
+    }
+
+    /// Parse as [`Parser::parse`] does, also stopping once `dropped` is
+    /// cancelled.
+    fn parse_until<H: ParserHandler>(
+        &self,
+        input: &str,
+        handler: &mut H,
+        dropped: Option<&CancellationToken>,
+    ) -> ParseResult<()> {
+        if self.limits.input_len.is_some_and(|max| input.len() > max) {
+            return Err(ParseError::LimitExceeded(Limit::InputLen));
+        }
+        let Limits {
+            nesting,
+            table_columns,
+            ..
+        } = self.limits;
+        let cancel = [self.cancel.as_ref(), dropped];
+        if cancel.iter().all(Option::is_none) && nesting.is_none() && table_columns.is_none() {
+            return self.parse_logged(input, handler);
+        }
+        let mut guard = Guard {
+            handler,
+            cancel,
+            limits: self.limits,
+            depth: 0,
+            stop: None,
+        };
+        if !guard.go_on() {
+            return Err(ParseError::Cancelled);
+        }
+        let result = self.parse_logged(input, &mut guard);
+        match guard.stop {
+            Some(err) => Err(err),
+            None => result,
+        }
+    }
+
+    fn parse_logged<H: ParserHandler>(&self, input: &str, handler: &mut H) -> ParseResult<()> {
+        match &self.debug_log {
+            Some(log) => parse_logged(input, self.flags, handler, Some(&mut |msg: &str| log(msg))),
+            None => parse_logged(input, self.flags, handler, None),
+        }
+    }
+
+    /// Parse `input` from bytes, see [`parse_bytes`].
+    pub fn parse_bytes<H: ParserHandler>(
+        &self,
+        input: &[u8],
+        handler: &mut H,
+        policy: Utf8Policy,
+    ) -> ParseResult<()> {
+        match policy {
+            Utf8Policy::Strict => {
+                let input = std::str::from_utf8(input).map_err(|_| ParseError::InvalidUtf8)?;
+                self.parse(input, handler)
+            }
+            Utf8Policy::Lossy => self.parse(&String::from_utf8_lossy(input), handler),
+        }
+    }
+
+    /// Parse `input` with a fallible handler, see [`try_parse`].
+    pub fn try_parse<H: TryParserHandler>(
+        &self,
+        input: &str,
+        handler: &mut H,
+    ) -> Result<(), ParseError<H::Error>> {
+        let mut adapter = TryAdapter {
+            handler,
+            error: None,
+        };
+        let result = self.parse(input, &mut adapter);
+        match adapter.error {
+            Some(err) => Err(ParseError::Handler(err)),
+            None => result.map_err(ParseError::from_parse),
+        }
+    }
+
+    /// Parse `input` and collect all events, see [`parse_to_events`].
+    pub fn parse_to_events<'a>(&self, input: &'a str) -> ParseResult<Vec<Event<'a>>> {
+        let mut collector = EventCollector {
+            input,
+            events: Vec::new(),
+        };
+        self.parse(input, &mut collector)?;
+        Ok(collector.events)
+    }
+
+    /// Parse `input` lazily, see [`parse_iter`].
+    pub fn parse_iter(&self, input: &str) -> EventIter {
+        let (sender, receiver) = sync_channel(EVENT_ITER_BUFFER);
+        let input = input.to_string();
+        let parser = self.clone();
+        let dropped = CancellationToken::new();
+        let thread = {
+            let dropped = dropped.clone();
+            thread::spawn(move || {
+                parser.parse_until(&input, &mut EventSender(sender), Some(&dropped))
+            })
+        };
+
+        EventIter {
+            receiver: Some(receiver),
+            thread: Some(thread),
+            error: None,
+            dropped,
+        }
+    }
+}
+
+impl fmt::Debug for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parser")
+            .field("flags", &self.flags)
+            .field("debug_log", &self.debug_log.is_some())
+            .field("cancel", &self.cancel)
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
+/// Parse markdown text with a custom handler, passing MD4C's debug messages
+/// to `log`
+///
+/// MD4C logs why it stopped early, such as a failed allocation or a
+/// handler returning `false`, and with debug builds of the C library,
+/// failed internal assertions.
+///
+/// # Example
+/// ```
+/// use md4c::parser::{parse_with_debug, ParserFlags, ParserHandler};
+/// use md4c::Block;
+///
+/// struct Stop;
+///
+/// impl ParserHandler for Stop {
+///     fn enter_block(&mut self, _: Block) -> bool {
+///         false
+///     }
+/// }
+///
+/// let mut log = Vec::new();
+/// let result = parse_with_debug("# Hi", ParserFlags::commonmark(), &mut Stop, |msg| {
+///     log.push(msg.to_string())
+/// });
+/// assert!(result.is_err());
+/// assert_eq!(log, ["Aborted from enter_block() callback."]);
+/// ```
+pub fn parse_with_debug<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    mut log: impl FnMut(&str),
+) -> ParseResult<()> {
+    parse_logged(input, flags, handler, Some(&mut log))
+}
+
+/// What [`parse_bytes`] does with input that isn't valid UTF-8
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Fail with [`ParseError::InvalidUtf8`]
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD, copying the input only if it
+    /// has any
+    Lossy,
+}
+
+/// Parse markdown from bytes, e.g. a file read from disk
+///
+/// Valid UTF-8 is parsed in place, saving a separate validation step;
+/// `policy` decides about the rest. Handlers receive `&str`, so invalid
+/// sequences can't be passed through as they are.
+///
+/// # Example
+/// ```
+/// use md4c::parser::{parse_bytes, ParseError, ParserFlags, Utf8Policy};
+/// use md4c::{ParserHandler, TextType};
+///
+/// struct Text(String);
+///
+/// impl ParserHandler for Text {
+///     fn text(&mut self, _: TextType, text: &str) -> bool {
+///         self.0.push_str(text);
+///         true
+///     }
+/// }
+///
+/// let input = b"caf\xe9 *latin-1*";
+/// let mut text = Text(String::new());
+/// let strict = parse_bytes(input, ParserFlags::commonmark(), &mut text, Utf8Policy::Strict);
+/// assert!(matches!(strict, Err(ParseError::InvalidUtf8)));
+///
+/// parse_bytes(input, ParserFlags::commonmark(), &mut text, Utf8Policy::Lossy).unwrap();
+/// assert_eq!(text.0, "caf\u{FFFD} latin-1");
+/// ```
+pub fn parse_bytes<H: ParserHandler>(
+    input: &[u8],
+    flags: ParserFlags,
+    handler: &mut H,
+    policy: Utf8Policy,
+) -> ParseResult<()> {
+    Parser::new(flags).parse_bytes(input, handler, policy)
+}
+
+/// Flag for stopping a parse from another thread
+///
+/// Clones share the flag. An editor reparsing on every keystroke can
+/// cancel the parse of an outdated version once the next one starts.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that isn't cancelled yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the parses using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Parse markdown, stopping with [`ParseError::Cancelled`] once `token`
+/// is cancelled.
+///
+/// The token is checked before each callback, so the handler sees no
+/// events after the cancellation. MD4C finds the document's blocks before
+/// the first callback, which for a huge document takes a moment of its own.
+///
+/// # Example
+/// ```
+/// use md4c::parser::{parse_with_cancel, CancellationToken, ParseError, ParserFlags};
+/// use md4c::{Block, ParserHandler};
+///
+/// struct CancelAfter(usize, CancellationToken);
+///
+/// impl ParserHandler for CancelAfter {
+///     fn enter_block(&mut self, _: Block) -> bool {
+///         self.0 -= 1;
+///         if self.0 == 0 {
+///             self.1.cancel();
+///         }
+///         true
+///     }
+/// }
+///
+/// let token = CancellationToken::new();
+/// let mut handler = CancelAfter(2, token.clone());
+/// let result = parse_with_cancel("a\n\nb\n\nc", ParserFlags::commonmark(), &mut handler, &token);
+/// assert!(matches!(result, Err(ParseError::Cancelled)));
+/// ```
+pub fn parse_with_cancel<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    token: &CancellationToken,
+) -> ParseResult<()> {
+    Parser::new(flags).with_cancellation(token.clone()).parse(input, handler)
+}
+
+/// A limit set on a [`Parser`], see [`ParseError::LimitExceeded`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// [`Parser::with_max_input_len`]
+    InputLen,
+    /// [`Parser::with_max_nesting`]
+    Nesting,
+    /// [`Parser::with_max_table_columns`]
+    TableColumns,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::InputLen => "input length",
+            Limit::Nesting => "nesting depth",
+            Limit::TableColumns => "table columns",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Limits {
+    input_len: Option<usize>,
+    nesting: Option<usize>,
+    table_columns: Option<usize>,
+}
+
+/// Handler adapter aborting the parse once a token is cancelled or a limit
+/// is exceeded.
+struct Guard<'h, 't, H> {
+    handler: &'h mut H,
+    // The parser's token and an [`EventIter`]'s own
+    cancel: [Option<&'t CancellationToken>; 2],
+    limits: Limits,
+    // Open blocks and spans inside the document
+    depth: usize,
+    stop: Option<ParseError>,
+}
+
+impl<H: ParserHandler> Guard<'_, '_, H> {
+    fn go_on(&mut self) -> bool {
+        if self.cancel.iter().flatten().any(|token| token.is_cancelled()) {
+            self.stop = Some(ParseError::Cancelled);
+        }
+        self.stop.is_none()
+    }
+
+    // MD4C reports a block or span after it has parsed it, so this only
+    // keeps it from the handler
+    fn enter(&mut self) -> bool {
+        self.depth += 1;
+        if self.limits.nesting.is_some_and(|max| self.depth > max) {
+            self.stop = Some(ParseError::LimitExceeded(Limit::Nesting));
+        }
+        self.go_on()
+    }
+
+    fn leave(&mut self) -> bool {
+        self.depth = self.depth.saturating_sub(1);
+        self.go_on()
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for Guard<'_, '_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        let columns = match &block {
+            Block::Document => return self.go_on() && self.handler.enter_block(block),
+            Block::Table(table) => table.column_count as usize,
+            _ => 0,
+        };
+        if self.limits.table_columns.is_some_and(|max| columns > max) {
+            self.stop = Some(ParseError::LimitExceeded(Limit::TableColumns));
+        }
+        self.enter() && self.handler.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        if block_type == BlockType::Document {
+            return self.go_on() && self.handler.leave_block(block_type);
+        }
+        self.leave() && self.handler.leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.enter() && self.handler.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.leave() && self.handler.leave_span(span_type)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        self.go_on() && self.handler.text(text_type, text)
+    }
+
+    fn leave_block_with_detail(&mut self, block: Block) -> bool {
+        if block.block_type() == BlockType::Document {
+            return self.go_on() && self.handler.leave_block_with_detail(block);
+        }
+        self.leave() && self.handler.leave_block_with_detail(block)
+    }
+
+    fn leave_span_with_detail(&mut self, span: Span) -> bool {
+        self.leave() && self.handler.leave_span_with_detail(span)
+    }
+
+    fn wants_leave_details(&self) -> bool {
+        self.handler.wants_leave_details()
+    }
+}
+
+fn parse_logged<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    log: Option<&mut dyn FnMut(&str)>,
+) -> ParseResult<()> {
+    let input = if flags.raw() & FLAG_KEEP_UTF8_BOM != 0 {
+        input
+    } else {
+        input.strip_prefix('\u{FEFF}').unwrap_or(input)
+    };
+    if !handler.wants_leave_details() {
+        return parse_delimiters(input, flags, handler, log);
+    }
+    let mut handler = LeaveDetails {
+        handler,
+        input,
+        blocks: Vec::new(),
+        spans: Vec::new(),
+    };
+    parse_delimiters(input, flags, &mut handler, log)
+}
+
+fn parse_delimiters<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    log: Option<&mut dyn FnMut(&str)>,
+) -> ParseResult<()> {
+    let mark = flags.raw() & FLAG_MARK != 0;
+    let super_sub = flags.raw() & FLAG_SUPER_SUB != 0;
+    if mark || super_sub {
+        let mut delimiters = Delimiters::new(handler, input, mark, super_sub);
+        return parse_embeds(input, flags, &mut delimiters, log);
+    }
+    parse_embeds(input, flags, handler, log)
+}
+
+fn parse_embeds<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    log: Option<&mut dyn FnMut(&str)>,
+) -> ParseResult<()> {
+    if flags.contains(ParserFlags::new().wiki_links()) {
+        return parse_admonitions(input, flags, &mut Embeds::new(handler), log);
+    }
+    parse_admonitions(input, flags, handler, log)
+}
+
+/// Handler adapter remembering the open blocks and spans, so leave events
+/// reach the handler with the detail they were entered with.
+///
+/// MD4C passes details on leave events too, but not faithfully (an ordered
+/// list's start is 0 by then), and the extensions handled in Rust don't.
+struct LeaveDetails<'i, 'h, H> {
+    handler: &'h mut H,
+    input: &'i str,
+    blocks: Vec<Block<'i>>,
+    spans: Vec<Span<'i>>,
+}
+
+impl<H: ParserHandler> ParserHandler for LeaveDetails<'_, '_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        let input = self.input;
+        let block = block.map_strs(&mut |s| reborrow(input, s));
+        self.blocks.push(block.clone());
+        self.handler.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        match self.blocks.pop() {
+            Some(block) if block.block_type() == block_type => {
+                self.handler.leave_block_with_detail(block)
+            }
+            _ => self.handler.leave_block(block_type),
+        }
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        let input = self.input;
+        let span = span.map_strs(&mut |s| reborrow(input, s));
+        self.spans.push(span.clone());
+        self.handler.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        match self.spans.pop() {
+            Some(span) if span.span_type() == span_type => {
+                self.handler.leave_span_with_detail(span)
+            }
+            _ => self.handler.leave_span(span_type),
+        }
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        self.handler.text(text_type, text)
+    }
+}
+
+fn parse_admonitions<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    log: Option<&mut dyn FnMut(&str)>,
+) -> ParseResult<()> {
+    if flags.raw() & FLAG_ADMONITIONS != 0 {
+        return parse_md4c(input, flags, &mut Admonitions::new(handler, input), log);
+    }
+    parse_md4c(input, flags, handler, log)
+}
+
+/// Run MD4C's parser, without the extensions handled in Rust.
+fn parse_md4c<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+    log: Option<&mut dyn FnMut(&str)>,
+) -> ParseResult<()> {
+    // MD4C's internal checks only propagate negative return values, so a
+    // positive one merely ends the current block and parsing carries on.
+    const ABORT: c_int = -1;
+
+    struct Context<'a, 'l, H: ParserHandler> {
         handler: &'a mut H,
+        input: &'a str,
+        log: Option<&'l mut dyn FnMut(&str)>,
         error: Option<i32>,
+        // Panic payload from a handler, resumed once MD4C has returned
+        panic: Option<Box<dyn Any + Send>>,
         _marker: std::marker::PhantomData<&'a ()>,
     }
 
+    impl<H: ParserHandler> Context<'_, '_, H> {
+        /// Call the handler, turning `false` or a panic into an abort, since
+        /// unwinding through MD4C's frames is undefined behavior.
+        fn call(&mut self, f: impl FnOnce(&mut H) -> bool) -> c_int {
+            match panic::catch_unwind(AssertUnwindSafe(|| f(self.handler))) {
+                Ok(true) => 0,
+                Ok(false) => {
+                    self.error = Some(1);
+                    ABORT
+                }
+                Err(payload) => {
+                    self.panic = Some(payload);
+                    ABORT
+                }
+            }
+        }
+    }
+
     unsafe extern "C" fn enter_block_cb<H: ParserHandler>(
         block_type: sys::MD_BLOCKTYPE,
         detail: *mut c_void,
         userdata: *mut c_void,
     ) -> c_int {
         let ctx = &mut *(userdata as *mut Context<H>);
-        let block = parse_block(block_type, detail);
-        if ctx.handler.enter_block(block) {
-            0
-        } else {
-            ctx.error = Some(1);
-            1
-        }
+        let block = parse_block(block_type, detail, ctx.input);
+        ctx.call(|h| h.enter_block(block))
     }
 
     unsafe extern "C" fn leave_block_cb<H: ParserHandler>(
@@ -237,13 +1390,8 @@ pub fn parse<H: ParserHandler>(input: &str, flags: ParserFlags, handler: &mut H)
         userdata: *mut c_void,
     ) -> c_int {
         let ctx = &mut *(userdata as *mut Context<H>);
-        let bt = BlockType::from_raw(block_type).unwrap_or(BlockType::Document);
-        if ctx.handler.leave_block(bt) {
-            0
-        } else {
-            ctx.error = Some(1);
-            1
-        }
+        let bt = BlockType::from_raw(block_type);
+        ctx.call(|h| h.leave_block(bt))
     }
 
     unsafe extern "C" fn enter_span_cb<H: ParserHandler>(
@@ -252,13 +1400,8 @@ pub fn parse<H: ParserHandler>(input: &str, flags: ParserFlags, handler: &mut H)
         userdata: *mut c_void,
     ) -> c_int {
         let ctx = &mut *(userdata as *mut Context<H>);
-        let span = parse_span(span_type, detail);
-        if ctx.handler.enter_span(span) {
-            0
-        } else {
-            ctx.error = Some(1);
-            1
-        }
+        let span = parse_span(span_type, detail, ctx.input);
+        ctx.call(|h| h.enter_span(span))
     }
 
     unsafe extern "C" fn leave_span_cb<H: ParserHandler>(
@@ -267,12 +1410,20 @@ pub fn parse<H: ParserHandler>(input: &str, flags: ParserFlags, handler: &mut H)
         userdata: *mut c_void,
     ) -> c_int {
         let ctx = &mut *(userdata as *mut Context<H>);
-        let st = SpanType::from_raw(span_type).unwrap_or(SpanType::Emphasis);
-        if ctx.handler.leave_span(st) {
-            0
-        } else {
-            ctx.error = Some(1);
-            1
+        let st = SpanType::from_raw(span_type);
+        ctx.call(|h| h.leave_span(st))
+    }
+
+    unsafe extern "C" fn debug_log_cb<H: ParserHandler>(
+        msg: *const std::os::raw::c_char,
+        userdata: *mut c_void,
+    ) {
+        let ctx = &mut *(userdata as *mut Context<H>);
+        let msg = std::ffi::CStr::from_ptr(msg).to_string_lossy();
+        if let Some(log) = ctx.log.as_mut() {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| log(&msg))) {
+                ctx.panic.get_or_insert(payload);
+            }
         }
     }
 
@@ -283,32 +1434,33 @@ pub fn parse<H: ParserHandler>(input: &str, flags: ParserFlags, handler: &mut H)
         userdata: *mut c_void,
     ) -> c_int {
         let ctx = &mut *(userdata as *mut Context<H>);
-        let tt = TextType::from_raw(text_type).unwrap_or(TextType::Normal);
+        let tt = TextType::from_raw(text_type);
+        if let TextType::Unknown(raw) = tt {
+            warn(Warning::UnknownText(raw));
+        }
         let slice = std::slice::from_raw_parts(text as *const u8, size as usize);
         let text_str = std::str::from_utf8_unchecked(slice);
-        if ctx.handler.text(tt, text_str) {
-            0
-        } else {
-            ctx.error = Some(1);
-            1
-        }
+        ctx.call(|h| h.text(tt, text_str))
     }
 
     let parser = sys::MD_PARSER {
         abi_version: 0,
-        flags: flags.raw(),
+        flags: flags.md4c(),
         enter_block: Some(enter_block_cb::<H>),
         leave_block: Some(leave_block_cb::<H>),
         enter_span: Some(enter_span_cb::<H>),
         leave_span: Some(leave_span_cb::<H>),
         text: Some(text_cb::<H>),
-        debug_log: None,
+        debug_log: log.is_some().then_some(debug_log_cb::<H> as sys::DebugLogFn),
         syntax: None,
     };
 
     let mut ctx = Context {
         handler,
+        input,
+        log,
         error: None,
+        panic: None,
         _marker: std::marker::PhantomData,
     };
 
@@ -321,61 +1473,199 @@ pub fn parse<H: ParserHandler>(input: &str, flags: ParserFlags, handler: &mut H)
         )
     };
 
-    match result {
-        0 => Ok(()),
-        -1 => Err(ParseError::RuntimeError),
-        code => {
-            if let Some(err) = ctx.error {
-                Err(ParseError::CallbackError(err))
-            } else {
-                Err(ParseError::CallbackError(code))
-            }
-        }
+    if let Some(payload) = ctx.panic {
+        panic::resume_unwind(payload);
+    }
+
+    match (result, ctx.error) {
+        (0, _) => Ok(()),
+        (_, Some(err)) => Err(ParseError::CallbackError(err)),
+        (-1, None) => Err(ParseError::RuntimeError),
+        (code, None) => Err(ParseError::CallbackError(code)),
     }
 }
 
 /// Parse markdown and collect all events
-pub fn parse_to_events(input: &str, flags: ParserFlags) -> ParseResult<Vec<Event<'static>>> {
-    struct EventCollector {
-        events: Vec<Event<'static>>,
+///
+/// Text events borrow from `input`, except for the few strings MD4C supplies
+/// itself (such as the line breaks in code blocks), which are owned.
+pub fn parse_to_events(input: &str, flags: ParserFlags) -> ParseResult<Vec<Event<'_>>> {
+    Parser::new(flags).parse_to_events(input)
+}
+
+/// Handler collecting events, for [`parse_to_events`].
+struct EventCollector<'a> {
+    input: &'a str,
+    events: Vec<Event<'a>>,
+}
+
+impl ParserHandler for EventCollector<'_> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        let input = self.input;
+        let block = block.map_strs(&mut |s| reborrow(input, s));
+        self.events.push(Event::EnterBlock(block));
+        true
     }
 
-    impl ParserHandler for EventCollector {
-        fn enter_block(&mut self, block: Block) -> bool {
-            self.events.push(Event::EnterBlock(block));
-            true
-        }
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.events.push(Event::LeaveBlock(block_type));
+        true
+    }
 
-        fn leave_block(&mut self, block_type: BlockType) -> bool {
-            self.events.push(Event::LeaveBlock(block_type));
-            true
-        }
+    fn enter_span(&mut self, span: Span) -> bool {
+        let input = self.input;
+        let span = span.map_strs(&mut |s| reborrow(input, s));
+        self.events.push(Event::EnterSpan(span));
+        true
+    }
 
-        fn enter_span(&mut self, span: Span) -> bool {
-            self.events.push(Event::EnterSpan(span));
-            true
-        }
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.events.push(Event::LeaveSpan(span_type));
+        true
+    }
 
-        fn leave_span(&mut self, span_type: SpanType) -> bool {
-            self.events.push(Event::LeaveSpan(span_type));
-            true
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let text = reborrow(self.input, Cow::Borrowed(text));
+        self.events.push(Event::Text(text_type, text));
+        true
+    }
+}
+
+/// Number of events buffered ahead of an [`EventIter`]'s consumer.
+const EVENT_ITER_BUFFER: usize = 256;
+
+/// Lazy iterator over parser events, created by [`parse_iter`].
+///
+/// MD4C pushes events through callbacks, so the parser runs on a background
+/// thread (with its own copy of the input) and hands events over as they are
+/// consumed, keeping only a small buffer in memory.
+///
+/// Dropping the iterator cancels the parse, which stops at its next event.
+/// The thread isn't joined, though: MD4C finds all of the document's blocks
+/// before the first event, and a thread dropped during that stays busy
+/// until it is done.
+pub struct EventIter {
+    receiver: Option<Receiver<Event<'static>>>,
+    thread: Option<JoinHandle<ParseResult<()>>>,
+    error: Option<ParseError>,
+    // Cancelled on drop, stopping the parser thread
+    dropped: CancellationToken,
+}
+
+impl EventIter {
+    /// The error that ended parsing early, once the iterator is exhausted.
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
+}
+
+impl Iterator for EventIter {
+    type Item = Event<'static>;
+
+    fn next(&mut self) -> Option<Event<'static>> {
+        if let Ok(event) = self.receiver.as_ref()?.recv() {
+            return Some(event);
         }
 
-        fn text(&mut self, text_type: TextType, text: &str) -> bool {
-            self.events
-                .push(Event::Text(text_type, Box::leak(text.to_string().into_boxed_str())));
-            true
+        // The parser finished (or failed); collect its result
+        self.receiver = None;
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(Err(err))) => self.error = Some(err),
+            Some(Err(_)) => self.error = Some(ParseError::RuntimeError),
+            _ => {}
         }
+        None
     }
+}
 
-    let mut collector = EventCollector { events: Vec::new() };
-    parse(input, flags, &mut collector)?;
-    Ok(collector.events)
+impl Drop for EventIter {
+    fn drop(&mut self) {
+        self.dropped.cancel();
+    }
+}
+
+/// Parse markdown lazily, yielding events as they are consumed
+///
+/// Unlike [`parse_to_events`], events are not collected up front, so a huge
+/// document can be processed in bounded memory and abandoned early, e.g.
+/// with `take_while`. Text events are owned.
+///
+/// Each call copies `input` and spawns an OS thread to run the parser, so
+/// for small documents [`parse_to_events`] is cheaper. Dropping the
+/// iterator early stops that thread, see [`EventIter`].
+///
+/// # Example
+/// ```
+/// use md4c::parser::{parse_iter, Event, ParserFlags};
+/// use md4c::Block;
+///
+/// let first_heading = parse_iter("Intro\n\n# Title\n\nLots more...", ParserFlags::commonmark())
+///     .skip_while(|e| !matches!(e, Event::EnterBlock(Block::Heading(_))))
+///     .find_map(|e| match e {
+///         Event::Text(_, text) => Some(text.into_owned()),
+///         _ => None,
+///     });
+/// assert_eq!(first_heading.as_deref(), Some("Title"));
+/// ```
+pub fn parse_iter(input: &str, flags: ParserFlags) -> EventIter {
+    Parser::new(flags).parse_iter(input)
+}
+
+/// Handler sending owned events to an [`EventIter`].
+struct EventSender(SyncSender<Event<'static>>);
+
+impl ParserHandler for EventSender {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.0.send(Event::EnterBlock(block.into_owned())).is_ok()
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.0.send(Event::LeaveBlock(block_type)).is_ok()
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.0.send(Event::EnterSpan(span.into_owned())).is_ok()
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.0.send(Event::LeaveSpan(span_type)).is_ok()
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let text = Cow::Owned(text.to_string());
+        self.0.send(Event::Text(text_type, text)).is_ok()
+    }
+}
+
+/// Byte offset of `text` within `input`, if `text` points into it.
+///
+/// Text passed to handler callbacks usually borrows from the input buffer,
+/// which allows recovering approximate source positions.
+pub(crate) fn source_offset(input: &str, text: &str) -> Option<usize> {
+    let base = input.as_ptr() as usize;
+    let ptr = text.as_ptr() as usize;
+    if ptr >= base && ptr + text.len() <= base + input.len() {
+        Some(ptr - base)
+    } else {
+        None
+    }
+}
+
+/// `text` borrowed from `input` if it points into it, else owned.
+pub(crate) fn reborrow<'a>(input: &'a str, text: Cow<'_, str>) -> Cow<'a, str> {
+    match source_offset(input, &text) {
+        Some(offset) => Cow::Borrowed(&input[offset..offset + text.len()]),
+        None => Cow::Owned(text.into_owned()),
+    }
 }
 
 // Helper functions to parse detail structures
 
-unsafe fn parse_block(block_type: sys::MD_BLOCKTYPE, detail: *mut c_void) -> Block {
+unsafe fn parse_block<'a>(
+    block_type: sys::MD_BLOCKTYPE,
+    detail: *mut c_void,
+    input: &'a str,
+) -> Block<'a> {
     match block_type {
         sys::MD_BLOCK_DOC => Block::Document,
         sys::MD_BLOCK_QUOTE => Block::Quote,
@@ -404,7 +1694,12 @@ unsafe fn parse_block(block_type: sys::MD_BLOCKTYPE, detail: *mut c_void) -> Blo
                     _ => TaskState::Unchecked,
                 }
             };
-            Block::ListItem(ListItemDetail { task_state })
+            let is_task = d.is_task != 0;
+            Block::ListItem(ListItemDetail {
+                task_state,
+                task_mark: is_task.then_some(d.task_mark as u8 as char),
+                task_mark_offset: is_task.then_some(d.task_mark_offset as usize),
+            })
         }
         sys::MD_BLOCK_HR => Block::HorizontalRule,
         sys::MD_BLOCK_H => {
@@ -416,8 +1711,8 @@ unsafe fn parse_block(block_type: sys::MD_BLOCKTYPE, detail: *mut c_void) -> Blo
         sys::MD_BLOCK_CODE => {
             let d = &*(detail as *const sys::MD_BLOCK_CODE_DETAIL);
             Block::Code(CodeBlockDetail {
-                info: attribute_to_string(&d.info),
-                lang: attribute_to_string(&d.lang),
+                info: Attribute::from_raw(&d.info, input),
+                lang: Attribute::from_raw(&d.lang, input),
                 fence_char: FenceChar::from_raw(d.fence_char),
             })
         }
@@ -446,27 +1741,34 @@ unsafe fn parse_block(block_type: sys::MD_BLOCKTYPE, detail: *mut c_void) -> Blo
                 alignment: Alignment::from_raw(d.align),
             })
         }
-        _ => Block::Document,
+        _ => {
+            warn(Warning::UnknownBlock(block_type));
+            Block::Unknown(block_type)
+        }
     }
 }
 
-unsafe fn parse_span(span_type: sys::MD_SPANTYPE, detail: *mut c_void) -> Span {
+unsafe fn parse_span<'a>(
+    span_type: sys::MD_SPANTYPE,
+    detail: *mut c_void,
+    input: &'a str,
+) -> Span<'a> {
     match span_type {
         sys::MD_SPAN_EM => Span::Emphasis,
         sys::MD_SPAN_STRONG => Span::Strong,
         sys::MD_SPAN_A => {
             let d = &*(detail as *const sys::MD_SPAN_A_DETAIL);
             Span::Link(LinkDetail {
-                href: attribute_to_string(&d.href),
-                title: attribute_to_string(&d.title),
+                href: Attribute::from_raw(&d.href, input),
+                title: Attribute::from_raw(&d.title, input),
                 is_autolink: d.is_autolink != 0,
             })
         }
         sys::MD_SPAN_IMG => {
             let d = &*(detail as *const sys::MD_SPAN_IMG_DETAIL);
             Span::Image(ImageDetail {
-                src: attribute_to_string(&d.src),
-                title: attribute_to_string(&d.title),
+                src: Attribute::from_raw(&d.src, input),
+                title: Attribute::from_raw(&d.title, input),
             })
         }
         sys::MD_SPAN_CODE => Span::Code,
@@ -476,10 +1778,151 @@ unsafe fn parse_span(span_type: sys::MD_SPANTYPE, detail: *mut c_void) -> Span {
         sys::MD_SPAN_WIKILINK => {
             let d = &*(detail as *const sys::MD_SPAN_WIKILINK_DETAIL);
             Span::WikiLink(WikiLinkDetail {
-                target: attribute_to_string(&d.target),
+                target: Attribute::from_raw(&d.target, input),
+                label: wiki_label(input, &d.target),
+                is_embed: false,
             })
         }
         sys::MD_SPAN_U => Span::Underline,
-        _ => Span::Emphasis,
+        _ => {
+            warn(Warning::UnknownSpan(span_type));
+            Span::Unknown(span_type)
+        }
+    }
+}
+
+/// The label of a wiki link, read from the input right after its target.
+///
+/// MD4C only reports the target; the label is the text between the pipe
+/// and the closing `]]`. Targets MD4C had to copy (e.g. for entities) don't
+/// point into the input, so their label is not found.
+fn wiki_label<'a>(input: &'a str, target: &sys::MD_ATTRIBUTE) -> Option<Cow<'a, str>> {
+    let start = (target.text as usize).checked_sub(input.as_ptr() as usize)?;
+    let rest = input.get(start + target.size as usize..)?;
+    let label = rest.strip_prefix('|')?;
+    let label = &label[..label.find("]]")?];
+    (!label.is_empty()).then_some(Cow::Borrowed(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_drop_event_iter() {
+        let token = CancellationToken::new();
+        let parser = Parser::new(ParserFlags::commonmark()).with_cancellation(token.clone());
+        let mut iter = parser.parse_iter(&"para\n\n".repeat(100_000));
+        assert_eq!(iter.by_ref().take(3).count(), 3);
+        let dropped = iter.dropped.clone();
+        let thread = iter.thread.take().unwrap();
+        drop(iter);
+
+        // The parser thread stops, without cancelling the parser's own token
+        assert!(dropped.is_cancelled());
+        assert!(thread.join().unwrap().is_err());
+        assert!(!token.is_cancelled());
+        assert_eq!(parser.parse_to_events("text").unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_leave_with_detail() {
+        #[derive(Default)]
+        struct Leaves(Vec<String>);
+
+        impl ParserHandler for Leaves {
+            fn leave_block_with_detail(&mut self, block: Block) -> bool {
+                match block {
+                    Block::OrderedList(d) => self.0.push(format!("ol {}", d.start)),
+                    Block::Heading(d) => self.0.push(format!("h{}", d.level)),
+                    _ => {}
+                }
+                true
+            }
+
+            fn leave_span_with_detail(&mut self, span: Span) -> bool {
+                match span {
+                    Span::Link(d) => self.0.push(format!("a {}", d.href.as_str())),
+                    Span::Mark => self.0.push("mark".into()),
+                    _ => {}
+                }
+                true
+            }
+
+            fn wants_leave_details(&self) -> bool {
+                true
+            }
+        }
+
+        let mut leaves = Leaves::default();
+        let input = "## [x](/u) ==m==\n\n3. a\n4. b\n";
+        parse(input, ParserFlags::commonmark().mark(), &mut leaves).unwrap();
+        assert_eq!(leaves.0, ["a /u", "mark", "h2", "ol 3"]);
+
+        // Through adapters too
+        let mut leaves = Leaves::default();
+        let mut chain = crate::combinator::Chain::new().with(&mut leaves);
+        parse(input, ParserFlags::commonmark().mark(), &mut chain).unwrap();
+        drop(chain);
+        assert_eq!(leaves.0, ["a /u", "mark", "h2", "ol 3"]);
+    }
+
+    #[test]
+    fn test_unknown_types() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        set_warning_hook(move |warning| sink.lock().unwrap().push(*warning));
+        let block = unsafe { parse_block(1000, std::ptr::null_mut(), "") };
+        let span = unsafe { parse_span(1001, std::ptr::null_mut(), "") };
+        clear_warning_hook();
+
+        assert!(matches!(block, Block::Unknown(1000)));
+        assert_eq!(span.span_type(), SpanType::Unknown(1001));
+        assert_eq!(BlockType::from_raw(1000), BlockType::Unknown(1000));
+        assert_eq!(TextType::from_raw(1002), TextType::Unknown(1002));
+        let warnings = [Warning::UnknownBlock(1000), Warning::UnknownSpan(1001)];
+        assert_eq!(*seen.lock().unwrap(), warnings);
+        assert_eq!(warnings[0].to_string(), "unknown MD4C block type 1000");
+    }
+
+    #[test]
+    fn test_details_borrow_from_input() {
+        let input = "[a](/plain \"t\") [b](/esc\\_aped)\n\n```rust x\ncode\n```\n";
+        let events = parse_to_events(input, ParserFlags::commonmark()).unwrap();
+        let links: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::EnterSpan(Span::Link(detail)) => Some(detail),
+                _ => None,
+            })
+            .collect();
+        fn text<'a>(attribute: &Attribute<'a>) -> Cow<'a, str> {
+            attribute.clone().into_decoded()
+        }
+        assert!(matches!(text(&links[0].href), Cow::Borrowed("/plain")));
+        assert!(matches!(text(&links[0].title), Cow::Borrowed("t")));
+        assert!(matches!(text(&links[1].href), Cow::Owned(ref href) if href == "/esc_aped"));
+
+        let code = events.iter().find_map(|e| match e {
+            Event::EnterBlock(Block::Code(detail)) => Some(detail),
+            _ => None,
+        });
+        assert!(matches!(text(&code.unwrap().info), Cow::Borrowed("rust x")));
+        assert!(matches!(text(&code.unwrap().clone().into_owned().lang), Cow::Owned(_)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let flags = ParserFlags::github().wiki_links();
+        let events = parse_to_events("# *Hi* [[page|Page]]\n\n- [x] `done`\n", flags).unwrap();
+        let json = serde_json::to_string(&events).unwrap();
+        let decoded: Vec<Event<'_>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(format!("{decoded:?}"), format!("{events:?}"));
+
+        assert_eq!(serde_json::to_string(&flags).unwrap(), flags.raw().to_string());
+        let decoded: ParserFlags = serde_json::from_str(&flags.raw().to_string()).unwrap();
+        assert_eq!(decoded, flags);
     }
 }
@@ -0,0 +1,125 @@
+//! Plain-text extraction.
+//!
+//! [`to_plain_text`] reduces a document to its words, for search indexes,
+//! notification previews and the like.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::plaintext::to_plain_text;
+//! use md4c::ParserFlags;
+//!
+//! let markdown = "# Release *1.0*\n\nSee [the   notes](https://example.com) &amp; enjoy.\n";
+//! assert_eq!(
+//!     to_plain_text(markdown, ParserFlags::commonmark()),
+//!     "Release 1.0\nSee the notes & enjoy."
+//! );
+//! ```
+
+use crate::entity::decode_entity;
+use crate::html_text::HtmlToText;
+use crate::parser::{parse, ParserFlags, ParserHandler};
+use crate::types::{Block, BlockType, TextType};
+
+/// Collects plain text, one line per block.
+pub(crate) struct PlainText {
+    out: String,
+    // Text of the current block
+    line: String,
+    html: HtmlToText,
+}
+
+impl PlainText {
+    pub fn new(capacity: usize) -> Self {
+        PlainText {
+            out: String::with_capacity(capacity),
+            line: String::new(),
+            html: HtmlToText::new(),
+        }
+    }
+
+    /// The text collected.
+    pub fn finish(mut self) -> String {
+        self.flush();
+        self.out
+    }
+
+    /// End the current line, collapsing its whitespace.
+    fn flush(&mut self) {
+        let mut words = self.line.split_whitespace();
+        if let Some(first) = words.next() {
+            if !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            self.out.push_str(first);
+            for word in words {
+                self.out.push(' ');
+                self.out.push_str(word);
+            }
+        }
+        self.line.clear();
+    }
+}
+
+impl ParserHandler for PlainText {
+    fn enter_block(&mut self, block: Block) -> bool {
+        match block {
+            // Cells share their row's line
+            Block::TableHeaderCell(_) | Block::TableCell(_) => self.line.push(' '),
+            _ => self.flush(),
+        }
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        if !matches!(block_type, BlockType::TableHeaderCell | BlockType::TableCell) {
+            self.flush();
+        }
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        match text_type {
+            TextType::SoftBreak | TextType::HardBreak => self.line.push(' '),
+            TextType::NullChar => self.line.push('\u{FFFD}'),
+            TextType::Entity => self.line.push_str(&decode_entity(text)),
+            TextType::Html => self.html.push(&mut self.line, text),
+            _ => self.line.push_str(text),
+        }
+        true
+    }
+}
+
+/// Extract the plain text of a document.
+///
+/// Formatting is dropped, links and images are reduced to their text, raw
+/// HTML to its text content, and entities are decoded. Each block (a
+/// paragraph, heading, list item, table row, ...) becomes one line with its
+/// whitespace collapsed to single spaces; empty blocks are left out.
+pub fn to_plain_text(markdown: &str, flags: ParserFlags) -> String {
+    let mut handler = PlainText::new(markdown.len());
+    let _ = parse(markdown, flags, &mut handler);
+    handler.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_and_inlines() {
+        let md = "> - **bold**  and `code`\n>   - nested ![alt *text*](i.png)\n\n\
+                  | a | b |\n|---|---|\n| 1 | 2 |\n\n```\nfn  main()\n```\n\n---\n";
+        assert_eq!(
+            to_plain_text(md, ParserFlags::github()),
+            "bold and code\nnested alt text\na b\n1 2\nfn main()"
+        );
+    }
+
+    #[test]
+    fn test_html_and_entities() {
+        let md = "<div align=\"center\">\n  <img src=\"logo.png\">\n  <b>Tool</b> &copy; 2024\n\
+                  </div>\n\nPress <kbd>Ctrl</kbd>+<kbd>C</kbd>&nbsp;now\n";
+        assert_eq!(to_plain_text(md, ParserFlags::github()), "Tool © 2024\nPress Ctrl+C now");
+    }
+}
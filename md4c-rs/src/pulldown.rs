@@ -0,0 +1,287 @@
+//! Adapter to pulldown-cmark events.
+//!
+//! [`events`] parses with MD4C and returns the document as
+//! [`pulldown_cmark::Event`]s, so tools built on pulldown-cmark, like mdBook
+//! preprocessors or its HTML writer, can use MD4C as their parser. Requires
+//! the `pulldown-cmark` feature.
+//!
+//! The events follow pulldown-cmark's conventions: code spans and math are
+//! single events, entities are decoded into text, task list markers follow
+//! the start of their item, and header cells sit directly in the table head.
+//! Elements pulldown-cmark has no tag for, such as underline and `==mark==`
+//! spans, are left out, keeping their content.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::{pulldown, ParserFlags};
+//! use pulldown_cmark::{Event, Tag, TagEnd};
+//!
+//! let events: Vec<Event> = pulldown::events("*Hi*", ParserFlags::commonmark()).unwrap().collect();
+//! assert_eq!(
+//!     events,
+//!     [
+//!         Event::Start(Tag::Paragraph),
+//!         Event::Start(Tag::Emphasis),
+//!         Event::Text("Hi".into()),
+//!         Event::End(TagEnd::Emphasis),
+//!         Event::End(TagEnd::Paragraph),
+//!     ]
+//! );
+//! ```
+
+use crate::entity::decode_entity;
+use crate::parser::{parse_to_events, Event as Md4cEvent, ParseResult, ParserFlags};
+use crate::types::{
+    AdmonitionKind, Alignment as Md4cAlignment, Block, BlockType, FenceChar, Span, SpanType,
+    TaskState, TextType,
+};
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Tag, TagEnd,
+};
+use std::borrow::Cow;
+
+/// Parse markdown into pulldown-cmark events.
+pub fn events(input: &str, flags: ParserFlags) -> ParseResult<std::vec::IntoIter<Event<'_>>> {
+    let source = parse_to_events(input, flags)?;
+    let mut converter = Converter {
+        events: Vec::with_capacity(source.len()),
+        ends: Vec::new(),
+        in_table_head: false,
+        code: None,
+    };
+    for (i, event) in source.iter().enumerate() {
+        converter.convert(event.clone(), &source[i + 1..]);
+    }
+    Ok(converter.events.into_iter())
+}
+
+struct Converter<'a> {
+    events: Vec<Event<'a>>,
+    // End of each open block and span, `None` for those left out
+    ends: Vec<Option<TagEnd>>,
+    in_table_head: bool,
+    // Text of an open code or math span, which is a single event
+    code: Option<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn convert(&mut self, event: Md4cEvent<'a>, rest: &[Md4cEvent<'a>]) {
+        match event {
+            Md4cEvent::EnterBlock(block) => self.enter_block(block, rest),
+            Md4cEvent::LeaveBlock(block_type) => {
+                if block_type == BlockType::TableHead {
+                    self.in_table_head = false;
+                }
+                self.end();
+            }
+            Md4cEvent::EnterSpan(span) => self.enter_span(span),
+            Md4cEvent::LeaveSpan(span_type) => self.leave_span(span_type),
+            Md4cEvent::Text(text_type, text) => self.text(text_type, text),
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'a>) {
+        self.ends.push(Some(tag.to_end()));
+        self.events.push(Event::Start(tag));
+    }
+
+    fn skip(&mut self) {
+        self.ends.push(None);
+    }
+
+    fn end(&mut self) {
+        if let Some(Some(end)) = self.ends.pop() {
+            self.events.push(Event::End(end));
+        }
+    }
+
+    fn enter_block(&mut self, block: Block<'a>, rest: &[Md4cEvent<'_>]) {
+        match block {
+            Block::Quote => self.start(Tag::BlockQuote(None)),
+            Block::Admonition(kind) => self.start(Tag::BlockQuote(Some(match kind {
+                AdmonitionKind::Note => BlockQuoteKind::Note,
+                AdmonitionKind::Tip => BlockQuoteKind::Tip,
+                AdmonitionKind::Important => BlockQuoteKind::Important,
+                AdmonitionKind::Warning => BlockQuoteKind::Warning,
+                AdmonitionKind::Caution => BlockQuoteKind::Caution,
+            }))),
+            Block::UnorderedList(_) => self.start(Tag::List(None)),
+            Block::OrderedList(detail) => self.start(Tag::List(Some(detail.start.into()))),
+            Block::ListItem(detail) => {
+                self.start(Tag::Item);
+                if detail.task_state != TaskState::NotTask {
+                    let checked = detail.task_state == TaskState::Checked;
+                    self.events.push(Event::TaskListMarker(checked));
+                }
+            }
+            Block::HorizontalRule => {
+                self.events.push(Event::Rule);
+                self.skip();
+            }
+            Block::Heading(detail) => self.start(Tag::Heading {
+                level: HeadingLevel::try_from(detail.level as usize).unwrap_or(HeadingLevel::H6),
+                id: None,
+                classes: Vec::new(),
+                attrs: Vec::new(),
+            }),
+            Block::Code(detail) => self.start(Tag::CodeBlock(match detail.fence_char {
+                FenceChar::None => CodeBlockKind::Indented,
+                _ => CodeBlockKind::Fenced(cow_str(detail.info.into_decoded())),
+            })),
+            Block::Html => self.start(Tag::HtmlBlock),
+            Block::Paragraph => self.start(Tag::Paragraph),
+            Block::Table(_) => self.start(Tag::Table(first_row_alignments(rest))),
+            Block::TableHead => {
+                self.in_table_head = true;
+                self.start(Tag::TableHead);
+            }
+            // Header cells sit directly in the table head
+            Block::TableRow if self.in_table_head => self.skip(),
+            Block::TableRow => self.start(Tag::TableRow),
+            Block::TableHeaderCell(_) | Block::TableCell(_) => self.start(Tag::TableCell),
+            Block::Document | Block::TableBody | Block::Unknown(_) => self.skip(),
+        }
+    }
+
+    fn enter_span(&mut self, span: Span<'a>) {
+        match span {
+            Span::Emphasis => self.start(Tag::Emphasis),
+            Span::Strong => self.start(Tag::Strong),
+            Span::Strikethrough => self.start(Tag::Strikethrough),
+            Span::Superscript => self.start(Tag::Superscript),
+            Span::Subscript => self.start(Tag::Subscript),
+            Span::Link(detail) => {
+                let link_type = match detail.is_autolink {
+                    true if detail.href.starts_with("mailto:") => LinkType::Email,
+                    true => LinkType::Autolink,
+                    false => LinkType::Inline,
+                };
+                self.start(Tag::Link {
+                    link_type,
+                    dest_url: cow_str(detail.href.into_decoded()),
+                    title: cow_str(detail.title.into_decoded()),
+                    id: CowStr::Borrowed(""),
+                });
+            }
+            Span::Image(detail) => self.start(Tag::Image {
+                link_type: LinkType::Inline,
+                dest_url: cow_str(detail.src.into_decoded()),
+                title: cow_str(detail.title.into_decoded()),
+                id: CowStr::Borrowed(""),
+            }),
+            Span::WikiLink(detail) => self.start(Tag::Link {
+                link_type: LinkType::WikiLink { has_pothole: detail.label.is_some() },
+                dest_url: cow_str(detail.target.into_decoded()),
+                title: CowStr::Borrowed(""),
+                id: CowStr::Borrowed(""),
+            }),
+            Span::Code | Span::LatexMath | Span::LatexMathDisplay => {
+                self.code = Some(String::new());
+                self.skip();
+            }
+            Span::Underline | Span::Mark | Span::Unknown(_) => self.skip(),
+        }
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) {
+        if let Some(code) = self.code.take() {
+            self.events.push(match span_type {
+                SpanType::LatexMath => Event::InlineMath(code.into()),
+                SpanType::LatexMathDisplay => Event::DisplayMath(code.into()),
+                _ => Event::Code(code.into()),
+            });
+        }
+        self.end();
+    }
+
+    fn text(&mut self, text_type: TextType, text: Cow<'a, str>) {
+        if let Some(ref mut code) = self.code {
+            match text_type {
+                TextType::SoftBreak | TextType::HardBreak => code.push(' '),
+                TextType::NullChar => code.push('\u{FFFD}'),
+                _ => code.push_str(&text),
+            }
+            return;
+        }
+        let in_html_block = self.ends.last() == Some(&Some(TagEnd::HtmlBlock));
+        // pulldown-cmark keeps each line of an HTML block with its newline
+        if in_html_block && text == "\n" {
+            if let Some(Event::Html(line)) = self.events.last_mut() {
+                *line = format!("{line}\n").into();
+                return;
+            }
+        }
+        self.events.push(match text_type {
+            TextType::SoftBreak => Event::SoftBreak,
+            TextType::HardBreak => Event::HardBreak,
+            TextType::NullChar => Event::Text(CowStr::Borrowed("\u{FFFD}")),
+            TextType::Entity => Event::Text(decode_entity(&text).into()),
+            TextType::Html if in_html_block => Event::Html(cow_str(text)),
+            TextType::Html => Event::InlineHtml(cow_str(text)),
+            _ => Event::Text(cow_str(text)),
+        });
+    }
+}
+
+fn cow_str(text: Cow<'_, str>) -> CowStr<'_> {
+    match text {
+        Cow::Borrowed(text) => CowStr::Borrowed(text),
+        Cow::Owned(text) => text.into(),
+    }
+}
+
+/// Alignments of the cells in a table's first row, from the events after
+/// the table starts.
+fn first_row_alignments(rest: &[Md4cEvent<'_>]) -> Vec<Alignment> {
+    rest.iter()
+        .take_while(|event| !matches!(event, Md4cEvent::LeaveBlock(BlockType::TableRow)))
+        .filter_map(|event| match event {
+            Md4cEvent::EnterBlock(Block::TableHeaderCell(cell) | Block::TableCell(cell)) => {
+                Some(match cell.alignment {
+                    Md4cAlignment::Default => Alignment::None,
+                    Md4cAlignment::Left => Alignment::Left,
+                    Md4cAlignment::Center => Alignment::Center,
+                    Md4cAlignment::Right => Alignment::Right,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Options, Parser};
+
+    /// Events with adjacent text merged, since the parsers split text
+    /// differently.
+    fn merged<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+        let mut merged: Vec<Event<'a>> = Vec::new();
+        for event in events {
+            match (merged.last_mut(), event) {
+                (Some(Event::Text(last)), Event::Text(text)) => {
+                    *last = format!("{last}{text}").into();
+                }
+                (_, event) => merged.push(event),
+            }
+        }
+        merged
+    }
+
+    #[test]
+    fn test_matches_pulldown_cmark() {
+        let doc = "# Title &amp; more\n\n> quote with `code  span` and [link](/u \"t\")\n\n\
+                   1. one\n2. ![img *alt*](i.png)\n\n- [x] done\n- [ ] todo\n\n\
+                   | a | b |\n|:--|--:|\n| ~~c~~ | <https://x.org> |\n\n\
+                   ```rust\nfn main() {}\n```\n\n    indented\n\n<div>\nhtml\n</div>\n\n\
+                   ***\nline  \nbreak <b>inline</b>\n";
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS;
+        let expected = merged(Parser::new_ext(doc, options));
+        let actual = merged(events(doc, ParserFlags::github()).unwrap());
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,357 @@
+//! Link reference definitions.
+//!
+//! MD4C resolves `[label]: /url "title"` definitions into the links that
+//! use them and reports nothing for the definitions themselves.
+//! [`find_definitions`] recovers them from the source, used or not, so
+//! documentation tools can check their destinations and find duplicates.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::refs::find_definitions;
+//! use md4c::ParserFlags;
+//!
+//! let doc = "See [docs].\n\n[docs]: https://example.org \"Docs\"\n[Docs]: /other\n";
+//! let defs = find_definitions(doc, ParserFlags::commonmark()).unwrap();
+//! assert_eq!(defs.len(), 2);
+//! assert_eq!(defs[0].destination, "https://example.org");
+//! assert_eq!(defs[0].title.as_deref(), Some("Docs"));
+//! assert_eq!(defs[1].line, 4);
+//! assert!(defs[1].duplicate);
+//! ```
+
+use crate::entity::{decode_entity, entity_len};
+use crate::parser::{parse, source_offset, ParseResult, ParserFlags, ParserHandler};
+use crate::types::TextType;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// A link reference definition in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkDefinition<'a> {
+    /// The label as written, without the brackets
+    pub label: &'a str,
+    /// The destination, with escapes and entities resolved
+    pub destination: String,
+    /// The title, with escapes and entities resolved
+    pub title: Option<String>,
+    /// Byte range of the definition in the input, from `[` to the end of
+    /// its last line
+    pub range: Range<usize>,
+    /// Line of the definition, starting at 1
+    pub line: usize,
+    /// Whether an earlier definition has the same label, so links use that
+    /// one instead
+    pub duplicate: bool,
+}
+
+impl LinkDefinition<'_> {
+    /// The label as links match it: case-folded, with runs of whitespace
+    /// collapsed.
+    pub fn key(&self) -> String {
+        normalize_label(self.label)
+    }
+}
+
+/// Find the link reference definitions in a document, in order.
+///
+/// Definitions in block quotes and list items are found too; text that
+/// only looks like a definition, such as in code blocks or in the middle of
+/// a paragraph, is not.
+pub fn find_definitions(input: &str, flags: ParserFlags) -> ParseResult<Vec<LinkDefinition<'_>>> {
+    // Definitions produce no events, so a candidate overlapping any text
+    // the parser reported is something else
+    let mut texts = TextRanges {
+        input,
+        ranges: Vec::new(),
+    };
+    parse(input, flags, &mut texts)?;
+    let mut ranges = texts.ranges;
+    ranges.sort_by_key(|range| range.start);
+
+    let mut definitions = Vec::new();
+    let mut keys = HashSet::new();
+    let mut line_start = 0;
+    let mut line = 1;
+    while line_start < input.len() {
+        let start = skip_containers(input, line_start);
+        if let Some(def) = parse_definition(input, start) {
+            let first = ranges.partition_point(|range| range.end <= start);
+            if ranges[first..].first().is_none_or(|range| range.start >= def.end) {
+                let label = &input[def.label];
+                definitions.push(LinkDefinition {
+                    label,
+                    destination: def.destination,
+                    title: def.title,
+                    range: start..def.end,
+                    line,
+                    duplicate: !keys.insert(normalize_label(label)),
+                });
+            }
+        }
+        let next = input[line_start..].find('\n').map_or(input.len(), |i| line_start + i + 1);
+        line += 1;
+        line_start = next;
+    }
+    Ok(definitions)
+}
+
+/// Handler recording the byte ranges of text taken from the input.
+struct TextRanges<'a> {
+    input: &'a str,
+    ranges: Vec<Range<usize>>,
+}
+
+impl ParserHandler for TextRanges<'_> {
+    fn text(&mut self, _text_type: TextType, text: &str) -> bool {
+        if let Some(offset) = source_offset(self.input, text) {
+            if !text.is_empty() {
+                self.ranges.push(offset..offset + text.len());
+            }
+        }
+        true
+    }
+}
+
+/// A definition parsed from the source.
+struct Candidate {
+    label: Range<usize>,
+    destination: String,
+    title: Option<String>,
+    end: usize,
+}
+
+/// Skip the indentation, block quote markers and list markers at the start
+/// of a line.
+pub(crate) fn skip_containers(input: &str, mut pos: usize) -> usize {
+    let b = input.as_bytes();
+    loop {
+        let indent = b[pos..].iter().take_while(|&&c| c == b' ').count();
+        if indent > 3 {
+            return pos;
+        }
+        let at = pos + indent;
+        let marker = match b.get(at) {
+            Some(b'>') => 1,
+            Some(b'-' | b'*' | b'+') => 1,
+            Some(c) if c.is_ascii_digit() => {
+                let digits = b[at..].iter().take_while(|c| c.is_ascii_digit()).count();
+                match b.get(at + digits) {
+                    Some(b'.' | b')') if digits <= 9 => digits + 1,
+                    _ => return at,
+                }
+            }
+            _ => return at,
+        };
+        let after = at + marker;
+        match b.get(after) {
+            Some(b' ' | b'\t') => pos = after + 1,
+            _ if b[at] == b'>' => pos = after,
+            _ => return at,
+        }
+    }
+}
+
+/// Skip spaces and tabs, and at most one line break with the container
+/// markers after it.
+fn skip_space(input: &str, mut pos: usize) -> usize {
+    let b = input.as_bytes();
+    pos += b[pos..].iter().take_while(|&&c| c == b' ' || c == b'\t').count();
+    if b.get(pos) != Some(&b'\n') {
+        return pos;
+    }
+    pos = skip_containers(input, pos + 1);
+    pos + b[pos..].iter().take_while(|&&c| c == b' ' || c == b'\t').count()
+}
+
+/// Whether only spaces and tabs follow `pos` up to the end of its line,
+/// and where the line's content ends.
+fn line_end(input: &str, pos: usize) -> Option<usize> {
+    let b = input.as_bytes();
+    let end = pos + b[pos..].iter().take_while(|&&c| c == b' ' || c == b'\t').count();
+    match b.get(end) {
+        None | Some(b'\n') => Some(end),
+        Some(b'\r') if matches!(b.get(end + 1), None | Some(b'\n')) => Some(end),
+        _ => None,
+    }
+}
+
+/// Parse a definition starting with the `[` at `start`.
+fn parse_definition(input: &str, start: usize) -> Option<Candidate> {
+    let b = input.as_bytes();
+    if b.get(start) != Some(&b'[') {
+        return None;
+    }
+
+    // Label: up to 999 characters, no unescaped brackets, not blank
+    let mut pos = start + 1;
+    loop {
+        match *b.get(pos)? {
+            b'\\' if b.get(pos + 1).is_some_and(u8::is_ascii_punctuation) => pos += 2,
+            b'[' => return None,
+            b']' => break,
+            _ => pos += 1,
+        }
+    }
+    let label = start + 1..pos;
+    if label.len() > 999 || input[label.clone()].trim().is_empty() {
+        return None;
+    }
+    if b.get(pos + 1) != Some(&b':') {
+        return None;
+    }
+
+    // Destination
+    let dest_start = skip_space(input, pos + 2);
+    let (destination, dest_end) = match b.get(dest_start)? {
+        b'<' => {
+            let len = b[dest_start + 1..]
+                .iter()
+                .position(|&c| matches!(c, b'>' | b'<' | b'\n'))
+                .filter(|&len| b[dest_start + 1 + len] == b'>')?;
+            (&input[dest_start + 1..dest_start + 1 + len], dest_start + len + 2)
+        }
+        _ => {
+            let mut depth = 0usize;
+            let mut end = dest_start;
+            while let Some(&c) = b.get(end) {
+                match c {
+                    b'\\' if b.get(end + 1).is_some_and(u8::is_ascii_punctuation) => end += 1,
+                    b'(' => depth += 1,
+                    b')' if depth == 0 => break,
+                    b')' => depth -= 1,
+                    c if c <= b' ' || c == 0x7f => break,
+                    _ => {}
+                }
+                end += 1;
+            }
+            if end == dest_start || depth > 0 {
+                return None;
+            }
+            (&input[dest_start..end], end)
+        }
+    };
+    let destination = unescape(destination);
+
+    // Title, on the same line or the next; without one the definition ends
+    // with its line
+    let title_start = skip_space(input, dest_end);
+    let separated = title_start > dest_end;
+    let title = separated.then(|| parse_title(input, title_start)).flatten();
+    if let Some((title, end)) = title.and_then(|(t, end)| Some((t, line_end(input, end)?))) {
+        return Some(Candidate {
+            label,
+            destination,
+            title: Some(title),
+            end,
+        });
+    }
+    // A title that started on the same line had to be valid
+    let end = line_end(input, dest_end)?;
+    Some(Candidate {
+        label,
+        destination,
+        title: None,
+        end,
+    })
+}
+
+/// Parse a title starting at `start`, returning it and the position after
+/// its closing delimiter.
+fn parse_title(input: &str, start: usize) -> Option<(String, usize)> {
+    let b = input.as_bytes();
+    let close = match *b.get(start)? {
+        b'"' => b'"',
+        b'\'' => b'\'',
+        b'(' => b')',
+        _ => return None,
+    };
+    let mut pos = start + 1;
+    loop {
+        match *b.get(pos)? {
+            b'\\' if b.get(pos + 1).is_some_and(u8::is_ascii_punctuation) => pos += 2,
+            c if c == close => break,
+            b'(' if close == b')' => return None,
+            // Titles can't contain blank lines
+            b'\n' if input[pos + 1..].trim_start_matches([' ', '\t']).starts_with('\n') => {
+                return None;
+            }
+            _ => pos += 1,
+        }
+    }
+    Some((unescape(&input[start + 1..pos]), pos + 1))
+}
+
+/// Resolve backslash escapes and entity references.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(['\\', '&']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let len = match rest.as_bytes() {
+            [b'\\', c, ..] if c.is_ascii_punctuation() => {
+                out.push(*c as char);
+                2
+            }
+            [b'&', ..] => match entity_len(rest) {
+                0 => {
+                    out.push('&');
+                    1
+                }
+                len => {
+                    out.push_str(&decode_entity(&rest[..len]));
+                    len
+                }
+            },
+            _ => {
+                out.push('\\');
+                1
+            }
+        };
+        rest = &rest[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Normalize a label for matching, as CommonMark does.
+fn normalize_label(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(input: &str) -> Vec<&str> {
+        let defs = find_definitions(input, ParserFlags::github()).unwrap();
+        defs.iter().map(|def| def.label).collect()
+    }
+
+    #[test]
+    fn test_finds_only_definitions() {
+        let doc = "[a]: /a\n\n> [b]: </b c> 'B'\n\n- [c]: /c\n  (C)\n\n\
+                   ```\n[d]: /d\n```\n\n    [e]: /e\n\ntext\n[f]: /f\n\n\
+                   [g]: /g \"unterminated\n\n[h]:\n/h\n";
+        assert_eq!(labels(doc), ["a", "b", "c", "h"]);
+
+        let defs = find_definitions(doc, ParserFlags::github()).unwrap();
+        assert_eq!(defs[1].destination, "/b c");
+        assert_eq!(defs[1].title.as_deref(), Some("B"));
+        assert_eq!(defs[2].title.as_deref(), Some("C"));
+        assert_eq!(&doc[defs[2].range.clone()], "[c]: /c\n  (C)");
+        assert_eq!((defs[3].line, defs[3].title.as_ref()), (19, None));
+    }
+
+    #[test]
+    fn test_escapes_and_keys() {
+        let doc = "[Foo\\]  Bar]: /a&amp;b\\_c \"t &quot;q&quot;\"\n[foo\\] bar]: /x\n";
+        let defs = find_definitions(doc, ParserFlags::commonmark()).unwrap();
+        assert_eq!(defs[0].destination, "/a&b_c");
+        assert_eq!(defs[0].title.as_deref(), Some("t \"q\""));
+        assert_eq!(defs[0].key(), defs[1].key());
+        assert!(!defs[0].duplicate && defs[1].duplicate);
+        assert_eq!(labels("[a]: /u title\n"), Vec::<&str>::new());
+    }
+}
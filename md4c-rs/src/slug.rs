@@ -0,0 +1,213 @@
+//! Heading slugs for anchors.
+//!
+//! [`github`] and [`gitlab`] derive anchors the way those sites do, and a
+//! [`Slugger`] appends `-1`, `-2`, ... to repeated ones. The HTML
+//! renderer's heading ids and the TOC extractor use them through
+//! [`SlugStyle`], so their anchors match links written for either site.
+//!
+//! ```
+//! use md4c::slug::{github, gitlab, Slugger};
+//!
+//! assert_eq!(github("Setup -- Linux"), "setup----linux");
+//! assert_eq!(gitlab("Setup -- Linux"), "setup-linux");
+//!
+//! let mut slugger = Slugger::new();
+//! assert_eq!(slugger.unique(github("Notes")), "notes");
+//! assert_eq!(slugger.unique(github("Notes")), "notes-1");
+//! ```
+
+use crate::entity::decode_entity;
+use crate::mark_map::MARK_MAP;
+use crate::types::TextType;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Turns heading text into an anchor slug.
+///
+/// [`HtmlRenderer::with_heading_ids`](crate::html::HtmlRenderer::with_heading_ids),
+/// [`toc::extract_with`](crate::toc::extract_with) and ratatui-md's
+/// `RenderOptions::with_slugifier` all take one, so web ids, TOC links and
+/// terminal anchors agree when given the same slugifier. Implemented by
+/// [`SlugStyle`], closures and `Arc`s of either.
+///
+/// Slugs needn't be unique: repeated ones get `-1`, `-2`, ... appended by a
+/// [`Slugger`].
+pub trait Slugify: Send + Sync {
+    /// Slug for `text`, ignoring duplicates.
+    fn slugify(&self, text: &str) -> String;
+}
+
+impl<F: Fn(&str) -> String + Send + Sync> Slugify for F {
+    fn slugify(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+impl<S: Slugify + ?Sized> Slugify for Arc<S> {
+    fn slugify(&self, text: &str) -> String {
+        (**self).slugify(text)
+    }
+}
+
+/// Built-in ways of deriving slugs from heading text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SlugStyle {
+    /// GitHub's anchors: lowercase, punctuation removed, spaces as hyphens,
+    /// and `-1`, `-2`, ... appended to repeated slugs
+    #[default]
+    GitHub,
+    /// GitLab's anchors: like GitHub's, but with runs of hyphens collapsed
+    GitLab,
+}
+
+impl SlugStyle {
+    /// Slug for `text`, ignoring duplicates.
+    pub fn slugify(self, text: &str) -> String {
+        match self {
+            SlugStyle::GitHub => github(text),
+            SlugStyle::GitLab => gitlab(text),
+        }
+    }
+}
+
+impl Slugify for SlugStyle {
+    fn slugify(&self, text: &str) -> String {
+        SlugStyle::slugify(*self, text)
+    }
+}
+
+/// GitHub's slug for heading text: lowercase, with spaces turned into
+/// hyphens and everything but letters, digits, combining marks, `-` and
+/// `_` removed.
+///
+/// Repeated slugs are left alone; see [`Slugger`].
+pub fn github(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' => Some(c),
+            _ if is_word(c) => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// GitLab's slug for heading text: as [`github`], with runs of hyphens
+/// collapsed into one.
+///
+/// Repeated slugs are left alone; see [`Slugger`].
+pub fn gitlab(text: &str) -> String {
+    let mut slug = github(text);
+    let mut last = None;
+    slug.retain(|c| {
+        let keep = !(c == '-' && last == Some('-'));
+        last = Some(c);
+        keep
+    });
+    slug
+}
+
+/// Whether `c` is a word character as regular expressions see it: a
+/// letter, digit, combining mark or connector like `_`.
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric()
+        || matches!(c, '_' | '\u{203F}' | '\u{2040}' | '\u{2054}' | '\u{FE33}' | '\u{FE34}')
+        || matches!(c, '\u{FE4D}'..='\u{FE4F}' | '\u{FF3F}')
+        || is_mark(c)
+}
+
+/// Whether `c` is a combining mark, like an accent, vowel sign or virama.
+fn is_mark(c: char) -> bool {
+    let code = c as u32;
+    MARK_MAP
+        .binary_search_by(|&(first, last)| {
+            if last < code {
+                Ordering::Less
+            } else if first > code {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Append a heading's text event to the text its slug is made from.
+///
+/// Raw HTML is left out, entities are decoded and line breaks become
+/// spaces, so `# Foo <b>bar</b>` is slugged as `Foo bar`. Everything that
+/// derives anchors from headings goes through this, so they agree.
+pub fn push_heading_text(out: &mut String, text_type: TextType, text: &str) {
+    match text_type {
+        TextType::NullChar => out.push('\u{FFFD}'),
+        TextType::SoftBreak | TextType::HardBreak => out.push(' '),
+        TextType::Html => {}
+        TextType::Entity => out.push_str(&decode_entity(text)),
+        _ => out.push_str(text),
+    }
+}
+
+/// Hands out unique slugs within a document.
+#[derive(Debug, Default)]
+pub struct Slugger {
+    used: HashSet<String>,
+}
+
+impl Slugger {
+    /// Create a slugger with no slugs used yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `slug` unique among the slugs so far by appending `-1`, `-2`, ...
+    pub fn unique(&mut self, slug: String) -> String {
+        let mut id = slug.clone();
+        let mut n = 0;
+        while self.used.contains(&id) {
+            n += 1;
+            id = format!("{}-{}", slug, n);
+        }
+        self.used.insert(id.clone());
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_slugs() {
+        // Decomposed accents, Devanagari viramas and Hebrew points stay
+        assert_eq!(github("Cafe\u{301} Menu"), "cafe\u{301}-menu");
+        assert_eq!(github("हिन्दी शब्द"), "हिन्दी-शब्द");
+        assert_eq!(github("שָׁלוֹם"), "שָׁלוֹם");
+        // Symbols, emoji and other punctuation go
+        assert_eq!(github("C++ & Rust 🦀 — «fast»"), "c--rust---fast");
+        assert_eq!(github("ΣΊΣΥΦΟΣ snake_case"), "σίσυφοσ-snake_case");
+        assert_eq!(github("।Done।"), "done");
+        // Marks outside the combining blocks: Myanmar asat, Khmer coeng and
+        // Sinhala vowel signs
+        assert_eq!(github("မြန်မာ"), "မြန်မာ");
+        assert_eq!(github("ខ្មែរ"), "ខ្មែរ");
+        assert_eq!(github("ශ්‍රී ලංකා"), "ශ්රී-ලංකා");
+        assert_eq!(github("\u{0DD8}\u{0DDF}\u{0DF2}"), "\u{0DD8}\u{0DDF}\u{0DF2}");
+    }
+
+    #[test]
+    fn test_gitlab_slugs() {
+        assert_eq!(gitlab("C++ & Rust 🦀 — «fast»"), "c-rust-fast");
+        assert_eq!(gitlab("a - b"), "a-b");
+        assert_eq!(SlugStyle::GitLab.slugify("Über -- Uns"), "über-uns");
+
+        let mut slugger = Slugger::new();
+        let ids: Vec<_> = ["A b", "A  b", "a-b"]
+            .iter()
+            .map(|text| slugger.unique(gitlab(text)))
+            .collect();
+        assert_eq!(ids, ["a-b", "a-b-1", "a-b-2"]);
+    }
+}
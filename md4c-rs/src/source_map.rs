@@ -0,0 +1,337 @@
+//! Approximate source ranges of blocks.
+//!
+//! MD4C doesn't report where blocks start and end in the input. Text events
+//! mostly borrow from it, though, so [`parse_with_source_map`] widens each
+//! block to the lines its text comes from, and places blocks without text,
+//! like thematic breaks, on the next non-blank line. The result is exact
+//! for most documents and close for the rest, which is what a preview
+//! needs to keep its scroll position in step with the editor.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::source_map::parse_with_source_map;
+//! use md4c::{BlockType, ParserFlags, ParserHandler};
+//!
+//! struct Ignore;
+//! impl ParserHandler for Ignore {}
+//!
+//! let doc = "# Title\n\nSome\ntext.\n\n---\n";
+//! let map = parse_with_source_map(doc, ParserFlags::commonmark(), &mut Ignore).unwrap();
+//! let top: Vec<_> = map.top_level().map(|b| (b.block_type, b.lines.clone())).collect();
+//! assert_eq!(
+//!     top,
+//!     [
+//!         (BlockType::Heading, 1..=1),
+//!         (BlockType::Paragraph, 3..=4),
+//!         (BlockType::HorizontalRule, 6..=6),
+//!     ]
+//! );
+//! assert_eq!(map.block_at(doc.find("text").unwrap()).unwrap().block_type, BlockType::Paragraph);
+//! ```
+
+use crate::parser::{parse, source_offset, ParseResult, ParserFlags, ParserHandler};
+use crate::refs::skip_containers;
+use crate::types::{Block, BlockType, FenceChar, Span, SpanType, TextType};
+use std::ops::{Range, RangeInclusive};
+
+/// Where a block is in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockRange {
+    /// The block's type
+    pub block_type: BlockType,
+    /// Nesting depth; top-level blocks are at 1, the document at 0
+    pub depth: usize,
+    /// Byte range, from the start of the block's first line to the end of
+    /// its last, without the line break
+    pub range: Range<usize>,
+    /// Lines of the block, starting at 1
+    pub lines: RangeInclusive<usize>,
+}
+
+/// Source ranges of a document's blocks, from [`parse_with_source_map`].
+#[derive(Debug, Clone, Default)]
+pub struct BlockSourceMap {
+    blocks: Vec<BlockRange>,
+}
+
+impl BlockSourceMap {
+    /// All blocks, the document first, in the order they were entered.
+    pub fn blocks(&self) -> &[BlockRange] {
+        &self.blocks
+    }
+
+    /// The blocks directly under the document, in order.
+    pub fn top_level(&self) -> impl Iterator<Item = &BlockRange> {
+        self.blocks.iter().filter(|block| block.depth == 1)
+    }
+
+    /// The innermost block containing `offset`, ignoring the document.
+    pub fn block_at(&self, offset: usize) -> Option<&BlockRange> {
+        self.blocks
+            .iter()
+            .filter(|block| block.depth > 0 && block.range.start <= offset)
+            .filter(|block| offset <= block.range.end)
+            .max_by_key(|block| block.depth)
+    }
+}
+
+/// Parse `input` with `handler` as [`parse`] does, and work out the
+/// source range of each block along the way.
+pub fn parse_with_source_map<H: ParserHandler>(
+    input: &str,
+    flags: ParserFlags,
+    handler: &mut H,
+) -> ParseResult<BlockSourceMap> {
+    let mut tracker = Tracker {
+        handler,
+        input,
+        open: Vec::new(),
+        ranges: Vec::new(),
+        cursor: 0,
+    };
+    parse(input, flags, &mut tracker)?;
+
+    let blocks = tracker
+        .ranges
+        .into_iter()
+        .map(|(block_type, depth, range)| {
+            let range = range.unwrap_or(0..0);
+            let first = input[..range.start].matches('\n').count() + 1;
+            let last = first + input[range.clone()].matches('\n').count();
+            BlockRange {
+                block_type,
+                depth,
+                range,
+                lines: first..=last,
+            }
+        })
+        .collect();
+    Ok(BlockSourceMap { blocks })
+}
+
+/// A block being read.
+struct Open {
+    // Index in `ranges`
+    index: usize,
+    block_type: BlockType,
+    fence: bool,
+    has_text: bool,
+    // Lines covered by its own text and its children so far
+    range: Option<Range<usize>>,
+}
+
+/// Handler adapter following the blocks' text through the input.
+struct Tracker<'h, 'a, H> {
+    handler: &'h mut H,
+    input: &'a str,
+    open: Vec<Open>,
+    // Type, depth and range of each block, in the order entered
+    ranges: Vec<(BlockType, usize, Option<Range<usize>>)>,
+    // Start of the line after the last block that ended
+    cursor: usize,
+}
+
+impl<H> Tracker<'_, '_, H> {
+    fn enter(&mut self, block: &Block) {
+        let block_type = block.block_type();
+        self.open.push(Open {
+            index: self.ranges.len(),
+            block_type,
+            fence: matches!(block, Block::Code(code) if code.fence_char != FenceChar::None),
+            has_text: false,
+            range: None,
+        });
+        self.ranges.push((block_type, self.open.len() - 1, None));
+    }
+
+    fn leave(&mut self) {
+        let Some(mut block) = self.open.pop() else { return };
+        let input = self.input;
+        if block.block_type == BlockType::Document {
+            self.ranges[block.index].2 = Some(0..input.len());
+            return;
+        }
+
+        let is_cell = matches!(block.block_type, BlockType::TableCell | BlockType::TableHeaderCell);
+        if is_cell && block.range.is_none() {
+            // An empty cell is somewhere on its row, filled in below
+            return;
+        }
+        let mut range = block.range.take().unwrap_or_else(|| {
+            // Nothing of its own: take the next line with anything on it
+            let start = next_line_with_content(input, self.cursor);
+            start..line_end(input, start)
+        });
+        if block.fence {
+            // The fences are lines of their own around the content
+            if range.start > 0 && block.has_text {
+                range.start = line_start(input, range.start - 1);
+            }
+            let next = next_line(input, range.end);
+            if next < input.len() && is_fence(&input[next..line_end(input, next)]) {
+                range.end = line_end(input, next);
+            }
+        }
+        if block.block_type == BlockType::Heading {
+            // A setext heading's underline is on the line after its text
+            let first = &input[skip_containers(input, range.start)..range.end];
+            let next = next_line(input, range.end);
+            if !first.starts_with('#') && next < input.len() {
+                range.end = line_end(input, next);
+            }
+        }
+        if block.block_type == BlockType::TableRow {
+            for entry in &mut self.ranges[block.index + 1..] {
+                entry.2.get_or_insert_with(|| range.clone());
+            }
+        }
+
+        self.cursor = next_line(input, range.end);
+        self.ranges[block.index].2 = Some(range.clone());
+        if let Some(parent) = self.open.last_mut() {
+            extend(&mut parent.range, range);
+        }
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for Tracker<'_, '_, H> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.enter(&block);
+        self.handler.enter_block(block)
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.leave();
+        self.handler.leave_block(block_type)
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.handler.enter_span(span)
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.handler.leave_span(span_type)
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let offset = source_offset(self.input, text);
+        if let (Some(offset), Some(block)) = (offset, self.open.last_mut()) {
+            // Code lines come with their line break
+            let last = offset + text.strip_suffix('\n').map_or(text.len(), str::len);
+            let range = line_start(self.input, offset)..line_end(self.input, last);
+            extend(&mut block.range, range);
+            block.has_text = true;
+        }
+        self.handler.text(text_type, text)
+    }
+
+    fn leave_block_with_detail(&mut self, block: Block) -> bool {
+        self.leave();
+        self.handler.leave_block_with_detail(block)
+    }
+
+    fn leave_span_with_detail(&mut self, span: Span) -> bool {
+        self.handler.leave_span_with_detail(span)
+    }
+
+    fn wants_leave_details(&self) -> bool {
+        self.handler.wants_leave_details()
+    }
+}
+
+fn extend(range: &mut Option<Range<usize>>, other: Range<usize>) {
+    *range = Some(match range.take() {
+        Some(range) => range.start.min(other.start)..range.end.max(other.end),
+        None => other,
+    });
+}
+
+fn line_start(input: &str, offset: usize) -> usize {
+    input[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// End of the line at `offset`, before its line break.
+fn line_end(input: &str, offset: usize) -> usize {
+    let end = input[offset..].find('\n').map_or(input.len(), |i| offset + i);
+    if input[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    }
+}
+
+/// Start of the line after the one at `offset`, or the end of the input.
+fn next_line(input: &str, offset: usize) -> usize {
+    input[offset..].find('\n').map_or(input.len(), |i| offset + i + 1)
+}
+
+/// Start of the first line at or after `offset` with more than whitespace
+/// and container markers on it.
+fn next_line_with_content(input: &str, mut offset: usize) -> usize {
+    while offset < input.len() {
+        let end = line_end(input, offset);
+        if !input[offset..end].trim_matches([' ', '\t', '>']).is_empty() {
+            return offset;
+        }
+        offset = next_line(input, offset);
+    }
+    input.len()
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start_matches([' ', '\t', '>']);
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Ignore;
+    impl ParserHandler for Ignore {}
+
+    fn ranges(input: &str, flags: ParserFlags) -> Vec<(BlockType, usize, &str)> {
+        let map = parse_with_source_map(input, flags, &mut Ignore).unwrap();
+        map.blocks()
+            .iter()
+            .skip(1)
+            .map(|b| (b.block_type, b.depth, &input[b.range.clone()]))
+            .collect()
+    }
+
+    #[test]
+    fn test_block_ranges() {
+        let doc = "Title\n=====\n\n> - a\n>   b\n>\n> ```rs\n> x\n> ```\n\n***\n\n~~~\n~~~\n\n-\n";
+        use BlockType::*;
+        assert_eq!(
+            ranges(doc, ParserFlags::commonmark()),
+            [
+                (Heading, 1, "Title\n====="),
+                (Quote, 1, "> - a\n>   b\n>\n> ```rs\n> x\n> ```"),
+                (UnorderedList, 2, "> - a\n>   b"),
+                (ListItem, 3, "> - a\n>   b"),
+                (Code, 2, "> ```rs\n> x\n> ```"),
+                (HorizontalRule, 1, "***"),
+                (Code, 1, "~~~\n~~~"),
+                (UnorderedList, 1, "-"),
+                (ListItem, 2, "-"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_and_lines() {
+        let doc = "| a | |\n|---|---|\n| b | c |\r\n";
+        let cells: Vec<_> = ranges(doc, ParserFlags::github())
+            .into_iter()
+            .filter(|(t, _, _)| matches!(t, BlockType::TableHeaderCell | BlockType::TableCell))
+            .map(|(_, _, text)| text)
+            .collect();
+        assert_eq!(cells, ["| a | |", "| a | |", "| b | c |", "| b | c |"]);
+
+        let map = parse_with_source_map(doc, ParserFlags::github(), &mut Ignore).unwrap();
+        assert_eq!(map.top_level().next().unwrap().lines, 1..=3);
+        assert!(map.block_at(doc.len() + 1).is_none());
+    }
+}
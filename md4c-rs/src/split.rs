@@ -0,0 +1,293 @@
+//! Splitting documents into sections along heading boundaries.
+//!
+//! Useful for chunking documents (e.g. for search indexing) along their
+//! structure rather than at arbitrary character counts.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::split::split_by_headings;
+//!
+//! let doc = "Intro\n\n# One\n\nFirst\n\n## Sub\n\nMore\n\n# Two\n\nSecond\n";
+//! let sections = split_by_headings(doc, 1);
+//! assert_eq!(sections.len(), 3);
+//! assert!(sections[0].heading.is_none());
+//! assert_eq!(sections[1].heading.as_ref().unwrap().text, "One");
+//! assert!(sections[1].source.contains("## Sub"));
+//! ```
+
+use crate::parser::{parse, source_offset, ParserFlags, ParserHandler};
+use crate::slug::push_heading_text;
+use crate::types::{Block, BlockType, TextType};
+use std::ops::Range;
+
+/// A heading that starts a [`Section`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionHeading {
+    /// Heading level (1-6)
+    pub level: u8,
+    /// Heading text content
+    pub text: String,
+    /// Byte range of the heading's source lines within the input
+    pub range: Range<usize>,
+}
+
+/// A section of a document, from one heading up to the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// The heading starting this section, or `None` for content before the
+    /// first heading
+    pub heading: Option<SectionHeading>,
+    /// Byte range of the section body (everything after the heading) within
+    /// the input
+    pub body_range: Range<usize>,
+    /// Full source of the section, including its heading
+    pub source: &'a str,
+}
+
+impl<'a> Section<'a> {
+    /// Source of the section body, without the heading.
+    pub fn body(&self) -> &'a str {
+        let start = self.body_range.end - self.source.len();
+        &self.source[self.body_range.start - start..]
+    }
+}
+
+/// Split markdown into sections at headings of `max_level` or shallower.
+///
+/// Uses the CommonMark dialect. Only top-level headings split the document;
+/// headings nested in block quotes or lists stay within their section.
+pub fn split_by_headings(markdown: &str, max_level: u8) -> Vec<Section<'_>> {
+    split_by_headings_with_flags(markdown, ParserFlags::commonmark(), max_level)
+}
+
+/// Split markdown into sections using the given parser flags.
+pub fn split_by_headings_with_flags(
+    markdown: &str,
+    flags: ParserFlags,
+    max_level: u8,
+) -> Vec<Section<'_>> {
+    let Some(headings) = collect_headings(markdown, flags) else {
+        return Vec::new();
+    };
+
+    let splits: Vec<SectionHeading> = headings
+        .into_iter()
+        .filter(|h| h.level <= max_level)
+        .collect();
+
+    let mut sections = Vec::with_capacity(splits.len() + 1);
+    let first_start = splits.first().map(|h| h.range.start).unwrap_or(markdown.len());
+    if !markdown[..first_start].trim().is_empty() {
+        sections.push(Section {
+            heading: None,
+            body_range: 0..first_start,
+            source: &markdown[..first_start],
+        });
+    }
+
+    let mut iter = splits.into_iter().peekable();
+    while let Some(heading) = iter.next() {
+        let end = iter.peek().map(|h| h.range.start).unwrap_or(markdown.len());
+        let start = heading.range.start;
+        let body_start = heading.range.end.min(end);
+        sections.push(Section {
+            heading: Some(heading),
+            body_range: body_start..end,
+            source: &markdown[start..end],
+        });
+    }
+
+    sections
+}
+
+/// All top-level headings of a document, or `None` if parsing fails.
+pub(crate) fn collect_headings(markdown: &str, flags: ParserFlags) -> Option<Vec<SectionHeading>> {
+    let headings = collect_all_headings(markdown, flags)?;
+    Some(headings.into_iter().filter(|(top, _)| *top).map(|(_, h)| h).collect())
+}
+
+/// All headings of a document, nested ones too, each with whether it is at
+/// the top level.
+pub(crate) fn collect_all_headings(
+    markdown: &str,
+    flags: ParserFlags,
+) -> Option<Vec<(bool, SectionHeading)>> {
+    let mut collector = HeadingCollector {
+        input: markdown,
+        depth: 0,
+        current: None,
+        cursor: 0,
+        headings: Vec::new(),
+    };
+    parse(markdown, flags, &mut collector).ok()?;
+    Some(collector.headings)
+}
+
+struct PendingHeading {
+    top_level: bool,
+    level: u8,
+    text: String,
+    first: Option<usize>,
+    last_end: usize,
+}
+
+struct HeadingCollector<'a> {
+    input: &'a str,
+    depth: usize,
+    current: Option<PendingHeading>,
+    cursor: usize,
+    headings: Vec<(bool, SectionHeading)>,
+}
+
+impl HeadingCollector<'_> {
+    fn finish_heading(&mut self, pending: PendingHeading) {
+        let text = pending.text.trim().to_string();
+        if !pending.top_level {
+            // Nested headings only count towards slugs, so need no range
+            let heading = SectionHeading { level: pending.level, text, range: 0..0 };
+            self.headings.push((false, heading));
+            return;
+        }
+        let input = self.input;
+        let range = match pending.first {
+            Some(first) => {
+                let start = line_start(input, first);
+                let mut end = line_end(input, pending.last_end);
+                // Setext headings own their underline. An ATX heading's text
+                // follows its `#`s and a space on the same line, while a
+                // setext heading's text may start with `#` itself
+                let before = input[start..first].trim_start_matches(' ');
+                let hashes = before.len() - before.trim_start_matches('#').len();
+                let atx = (1..=6).contains(&hashes) && before[hashes..].starts_with([' ', '\t']);
+                if !atx {
+                    let next = &input[end..line_end(input, end)];
+                    let underline = next.trim();
+                    if !underline.is_empty() && underline.chars().all(|c| c == '=' || c == '-') {
+                        end = line_end(input, end);
+                    }
+                }
+                start..end
+            }
+            // Empty headings produce no text; find the ATX line from the cursor
+            None => find_atx_line(input, self.cursor, pending.level)
+                .unwrap_or(self.cursor..self.cursor),
+        };
+        self.cursor = range.end;
+        let heading = SectionHeading { level: pending.level, text, range };
+        self.headings.push((true, heading));
+    }
+}
+
+impl ParserHandler for HeadingCollector<'_> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.depth += 1;
+        if let Block::Heading(detail) = block {
+            self.current = Some(PendingHeading {
+                // Depth 2 is directly under the document
+                top_level: self.depth == 2,
+                level: detail.level,
+                text: String::new(),
+                first: None,
+                last_end: 0,
+            });
+        }
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.depth -= 1;
+        if block_type == BlockType::Heading {
+            if let Some(pending) = self.current.take() {
+                self.finish_heading(pending);
+            }
+        }
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let input = self.input;
+        if let Some(ref mut pending) = self.current {
+            push_heading_text(&mut pending.text, text_type, text);
+            if let Some(offset) = source_offset(input, text) {
+                pending.first.get_or_insert(offset);
+                pending.last_end = pending.last_end.max(offset + text.len());
+            }
+        }
+        true
+    }
+}
+
+pub(crate) fn line_start(input: &str, offset: usize) -> usize {
+    input[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+pub(crate) fn line_end(input: &str, offset: usize) -> usize {
+    input[offset..]
+        .find('\n')
+        .map(|i| offset + i + 1)
+        .unwrap_or(input.len())
+}
+
+fn find_atx_line(input: &str, from: usize, level: u8) -> Option<Range<usize>> {
+    let mut start = line_start(input, from);
+    while start < input.len() {
+        let end = line_end(input, start);
+        let line = input[start..end].trim_start_matches(' ');
+        let hashes = line.chars().take_while(|&c| c == '#').count();
+        if hashes == level as usize && !line[hashes..].starts_with(|c: char| !c.is_whitespace()) {
+            return Some(start..end);
+        }
+        start = end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sections() {
+        let doc = "# A\n\ntext a\n\n## A.1\n\nsub\n\n# B\n\ntext b";
+        let sections = split_by_headings(doc, 1);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].source, "# A\n\ntext a\n\n## A.1\n\nsub\n\n");
+        assert_eq!(sections[0].body(), "\ntext a\n\n## A.1\n\nsub\n\n");
+        assert_eq!(sections[1].heading.as_ref().unwrap().text, "B");
+        assert_eq!(&doc[sections[1].body_range.clone()], "\ntext b");
+
+        let sections = split_by_headings(doc, 2);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[1].heading.as_ref().unwrap().level, 2);
+    }
+
+    #[test]
+    fn test_split_setext_and_nested() {
+        let doc = "Title\n=====\n\n> # Quoted\n\nbody\n\nNext\n----\nend\n";
+        let sections = split_by_headings(doc, 2);
+        assert_eq!(sections.len(), 2);
+        let first = sections[0].heading.as_ref().unwrap();
+        assert_eq!(&doc[first.range.clone()], "Title\n=====\n");
+        assert!(sections[0].source.contains("# Quoted"));
+        assert_eq!(sections[1].source, "Next\n----\nend\n");
+
+        // Setext headings whose text starts with `#`
+        let docs = [("#5 bolts\n===\n\nbody\n", "==="), ("#hashtag\n---\n\nbody\n", "---")];
+        for (doc, underline) in docs {
+            let sections = split_by_headings(doc, 2);
+            let heading = sections[0].heading.as_ref().unwrap();
+            assert!(doc[heading.range.clone()].ends_with(&format!("{}\n", underline)), "{}", doc);
+            assert_eq!(sections[0].body(), "\nbody\n");
+        }
+    }
+
+    #[test]
+    fn test_split_empty_heading() {
+        let doc = "intro\n\n#\n\nafter\n";
+        let sections = split_by_headings(doc, 1);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].source, "intro\n\n");
+        assert_eq!(sections[1].source, "#\n\nafter\n");
+    }
+}
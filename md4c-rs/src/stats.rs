@@ -0,0 +1,202 @@
+//! Document statistics.
+//!
+//! [`analyze`] counts a document's words, headings, code blocks, links and
+//! images in one pass over its events, for editors' status bars, blog
+//! reading-time estimates and the like.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::stats::analyze;
+//! use md4c::ParserFlags;
+//!
+//! let doc = "# Intro\n\nSome **bold** words and [a link](/x).\n\n```rust\nfn main() {}\n```\n";
+//! let stats = analyze(doc, ParserFlags::commonmark());
+//! assert_eq!(stats.words, 7);
+//! assert_eq!(stats.headings, [1, 0, 0, 0, 0, 0]);
+//! assert_eq!(stats.code_languages, ["rust"]);
+//! assert_eq!(stats.links, 1);
+//! ```
+
+use crate::entity::decode_entity;
+use crate::parser::{parse, ParserFlags, ParserHandler};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+use std::time::Duration;
+
+/// Reading speed assumed by [`DocStats::reading_time`].
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Statistics of a document, from [`analyze`].
+///
+/// Code blocks, raw HTML and image descriptions don't count as text; code
+/// spans do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocStats {
+    /// Number of words, i.e. runs of non-whitespace with a letter or digit
+    pub words: usize,
+    /// Number of characters in the text, not counting whitespace
+    pub characters: usize,
+    /// Number of headings of each level, from level 1 to 6
+    pub headings: [usize; 6],
+    /// Number of code blocks
+    pub code_blocks: usize,
+    /// Languages of the code blocks, each once, in order of first use
+    pub code_languages: Vec<String>,
+    /// Number of links, including autolinks and wiki links
+    pub links: usize,
+    /// Number of images
+    pub images: usize,
+}
+
+impl DocStats {
+    /// Time to read the text at [`WORDS_PER_MINUTE`], rounded up to whole
+    /// seconds.
+    pub fn reading_time(&self) -> Duration {
+        self.reading_time_at(WORDS_PER_MINUTE)
+    }
+
+    /// Time to read the text at `words_per_minute`, rounded up to whole
+    /// seconds.
+    pub fn reading_time_at(&self, words_per_minute: usize) -> Duration {
+        let seconds = (self.words * 60).div_ceil(words_per_minute.max(1));
+        Duration::from_secs(seconds as u64)
+    }
+}
+
+/// Compute the statistics of a document.
+///
+/// If parsing fails, empty statistics are returned.
+pub fn analyze(input: &str, flags: ParserFlags) -> DocStats {
+    let mut counter = StatsCounter::new();
+    if parse(input, flags, &mut counter).is_err() {
+        return DocStats::default();
+    }
+    counter.finish()
+}
+
+/// The handler behind [`analyze`], for gathering statistics from events
+/// that are also going elsewhere, such as to a renderer.
+#[derive(Debug, Default)]
+pub struct StatsCounter {
+    stats: DocStats,
+    // Text of the current block, counted once it ends since words can be
+    // split across events
+    text: String,
+    // Depth of code blocks, HTML blocks and images being skipped
+    skip: usize,
+}
+
+impl StatsCounter {
+    /// Create a counter with nothing counted yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The statistics of the events so far.
+    pub fn finish(mut self) -> DocStats {
+        self.flush();
+        self.stats
+    }
+
+    fn flush(&mut self) {
+        for word in self.text.split_whitespace() {
+            if word.chars().any(char::is_alphanumeric) {
+                self.stats.words += 1;
+            }
+            self.stats.characters += word.chars().count();
+        }
+        self.text.clear();
+    }
+}
+
+impl ParserHandler for StatsCounter {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.flush();
+        match block {
+            Block::Heading(heading) => {
+                let level = usize::from(heading.level.clamp(1, 6));
+                self.stats.headings[level - 1] += 1;
+            }
+            Block::Code(code) => {
+                self.stats.code_blocks += 1;
+                let lang = code.lang.decoded();
+                if !lang.is_empty() && !self.stats.code_languages.iter().any(|l| *l == lang) {
+                    self.stats.code_languages.push(lang.into_owned());
+                }
+                self.skip += 1;
+            }
+            Block::Html => self.skip += 1,
+            _ => {}
+        }
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.flush();
+        if matches!(block_type, BlockType::Code | BlockType::Html) {
+            self.skip -= 1;
+        }
+        true
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        match span {
+            Span::Link(_) | Span::WikiLink(_) => self.stats.links += 1,
+            Span::Image(_) => {
+                self.stats.images += 1;
+                self.skip += 1;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        if span_type == SpanType::Image {
+            self.skip -= 1;
+        }
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        if self.skip > 0 {
+            return true;
+        }
+        match text_type {
+            TextType::Html => {}
+            TextType::Entity => self.text.push_str(&decode_entity(text)),
+            TextType::SoftBreak | TextType::HardBreak => self.text.push(' '),
+            _ => self.text.push_str(text),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts() {
+        let doc = "## Two *sp*lit\n\n### x\n\n- item &amp; — more\n\n\
+                   ![alt words](i.png) <b>html</b> `code span`\n\n\
+                   ```rust\nlet x;\n```\n\n~~~ rust\n~~~\n\n    indented\n\n\
+                   | a | b |\n|---|---|\n| c | <https://x.org> |\n";
+        let stats = analyze(doc, ParserFlags::github());
+        assert_eq!(stats.headings, [0, 1, 1, 0, 0, 0]);
+        // "Two split x item more html code span a b c https://x.org"
+        assert_eq!(stats.words, 12);
+        assert_eq!(stats.characters, 47);
+        assert_eq!(stats.code_blocks, 3);
+        assert_eq!(stats.code_languages, ["rust"]);
+        assert_eq!((stats.links, stats.images), (1, 1));
+    }
+
+    #[test]
+    fn test_reading_time() {
+        let stats = analyze(&"word ".repeat(450), ParserFlags::commonmark());
+        assert_eq!(stats.reading_time(), Duration::from_secs(135));
+        assert_eq!(stats.reading_time_at(300), Duration::from_secs(90));
+        assert_eq!(analyze("", ParserFlags::commonmark()).reading_time(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,221 @@
+//! Excerpts for blog listings and previews.
+//!
+//! [`first_paragraph`] finds a document's first top-level paragraph, and
+//! [`excerpt`] everything before a `<!-- more -->` marker, falling back to
+//! the first paragraph. Both come as their Markdown source, to render with
+//! the rest of a site, and as plain text.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::summary::{excerpt, first_paragraph};
+//! use md4c::ParserFlags;
+//!
+//! let post = "# Release 2.0\n\nThe *fastest* release\nyet.\n\nDetails follow.\n";
+//! let summary = first_paragraph(post, ParserFlags::commonmark()).unwrap();
+//! assert_eq!(summary.markdown, "The *fastest* release\nyet.");
+//! assert_eq!(summary.text, "The fastest release yet.");
+//!
+//! let short = excerpt(post, ParserFlags::commonmark(), 16).unwrap();
+//! assert_eq!(short.text, "The fastest…");
+//! ```
+
+use crate::parser::{parse, source_offset, ParserFlags, ParserHandler};
+use crate::plaintext::PlainText;
+use crate::split::{line_end, line_start};
+use crate::types::{Block, BlockType, Span, SpanType, TextType};
+
+/// An excerpt of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary<'a> {
+    /// Source of the excerpt, without the line break at its end
+    pub markdown: &'a str,
+    /// The excerpt as plain text, one line per block
+    pub text: String,
+}
+
+/// The document's first top-level paragraph, or `None` if it has none.
+pub fn first_paragraph(input: &str, flags: ParserFlags) -> Option<Summary<'_>> {
+    let finder = find(input, flags)?;
+    let (start, end) = finder.paragraph?;
+    Some(Summary {
+        markdown: input[start..end].trim_end(),
+        text: finder.paragraph_text.finish(),
+    })
+}
+
+/// The part of the document before a `<!-- more -->` marker, or its first
+/// paragraph if it has no marker, with the text cut to `max_chars`.
+///
+/// The text is cut at a word boundary and ends with `…` if it was longer;
+/// the Markdown is left whole.
+pub fn excerpt(input: &str, flags: ParserFlags, max_chars: usize) -> Option<Summary<'_>> {
+    let finder = find(input, flags)?;
+    let mut summary = match finder.marker {
+        Some(marker) => Summary {
+            markdown: input[..marker].trim_end(),
+            text: finder.before_marker.finish(),
+        },
+        None => {
+            let (start, end) = finder.paragraph?;
+            Summary {
+                markdown: input[start..end].trim_end(),
+                text: finder.paragraph_text.finish(),
+            }
+        }
+    };
+    summary.text = truncate(&summary.text, max_chars);
+    Some(summary)
+}
+
+fn find(input: &str, flags: ParserFlags) -> Option<Finder<'_>> {
+    let mut finder = Finder {
+        input,
+        depth: 0,
+        paragraph: None,
+        in_paragraph: false,
+        paragraph_text: PlainText::new(0),
+        html: None,
+        marker: None,
+        before_marker: PlainText::new(0),
+    };
+    parse(input, flags, &mut finder).ok()?;
+    Some(finder)
+}
+
+struct Finder<'a> {
+    input: &'a str,
+    depth: usize,
+    // Source range of the first paragraph, once its text has been seen
+    paragraph: Option<(usize, usize)>,
+    in_paragraph: bool,
+    paragraph_text: PlainText,
+    // Text and offset of the top-level HTML block being read
+    html: Option<(String, Option<usize>)>,
+    // Start of the line with the `<!-- more -->` marker
+    marker: Option<usize>,
+    before_marker: PlainText,
+}
+
+impl Finder<'_> {
+    /// Feed an event to the plain text collectors still reading.
+    fn forward(&mut self, f: impl Fn(&mut PlainText) -> bool) {
+        if self.in_paragraph {
+            f(&mut self.paragraph_text);
+        }
+        if self.marker.is_none() {
+            f(&mut self.before_marker);
+        }
+    }
+}
+
+impl ParserHandler for Finder<'_> {
+    fn enter_block(&mut self, block: Block) -> bool {
+        self.depth += 1;
+        // Depth 2 is directly under the document
+        if self.depth == 2 {
+            match block {
+                Block::Paragraph if self.paragraph.is_none() => self.in_paragraph = true,
+                Block::Html => self.html = Some((String::new(), None)),
+                _ => {}
+            }
+        }
+        self.forward(|text| text.enter_block(block.clone()));
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        self.depth -= 1;
+        self.forward(|text| text.leave_block(block_type));
+        if self.depth == 1 {
+            self.in_paragraph = false;
+            if let Some((html, Some(offset))) = self.html.take() {
+                if self.marker.is_none() && is_more_marker(&html) {
+                    self.marker = Some(line_start(self.input, offset));
+                }
+            }
+        }
+        true
+    }
+
+    fn enter_span(&mut self, span: Span) -> bool {
+        self.forward(|text| text.enter_span(span.clone()));
+        true
+    }
+
+    fn leave_span(&mut self, span_type: SpanType) -> bool {
+        self.forward(|text| text.leave_span(span_type));
+        true
+    }
+
+    fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        let offset = source_offset(self.input, text);
+        if let (true, Some(offset)) = (self.in_paragraph, offset) {
+            let end = line_end(self.input, offset + text.len());
+            let start = self.paragraph.map_or(line_start(self.input, offset), |(start, _)| start);
+            self.paragraph = Some((start, end));
+        }
+        if let Some((html, first)) = &mut self.html {
+            html.push_str(text);
+            first.get_or_insert_with(|| offset.unwrap_or(0));
+        }
+        self.forward(|plain| plain.text(text_type, text));
+        true
+    }
+}
+
+/// Whether an HTML block is a `<!-- more -->` marker, in any case and
+/// spacing.
+fn is_more_marker(html: &str) -> bool {
+    html.trim()
+        .strip_prefix("<!--")
+        .and_then(|rest| rest.strip_suffix("-->"))
+        .is_some_and(|word| word.trim().eq_ignore_ascii_case("more"))
+}
+
+/// Cut `text` to at most `max_chars` characters at a word boundary,
+/// marking the cut with `…`.
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let keep = max_chars.saturating_sub(1);
+    let cut = text.char_indices().nth(keep).map_or(text.len(), |(i, _)| i);
+    let mut head = &text[..cut];
+    if !text[cut..].starts_with(char::is_whitespace) {
+        if let Some(space) = head.rfind(char::is_whitespace) {
+            head = &head[..space];
+        }
+    }
+    format!("{}…", head.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_paragraph() {
+        let doc = "<div>x</div>\n\n> quoted\n\nFirst [link][r] &amp;\n**more**\n\n\
+                   Second\n\n[r]: /u\n";
+        let summary = first_paragraph(doc, ParserFlags::commonmark()).unwrap();
+        assert_eq!(summary.markdown, "First [link][r] &amp;\n**more**");
+        assert_eq!(summary.text, "First link & more");
+        assert!(first_paragraph("# Only\n\n- a list\n", ParserFlags::commonmark()).is_none());
+    }
+
+    #[test]
+    fn test_excerpt_marker() {
+        let doc = "# Title\n\nIntro *one*.\n\n- point\n\n<!--  More -->\n\nRest\n";
+        let summary = excerpt(doc, ParserFlags::commonmark(), 100).unwrap();
+        assert_eq!(summary.markdown, "# Title\n\nIntro *one*.\n\n- point");
+        assert_eq!(summary.text, "Title\nIntro one.\npoint");
+
+        // A marker in a code block doesn't count
+        let doc = "Intro.\n\n```\n<!-- more -->\n```\n";
+        assert_eq!(excerpt(doc, ParserFlags::commonmark(), 3).unwrap().markdown, "Intro.");
+        assert_eq!(truncate("word longer", 8), "word…");
+        assert_eq!(truncate("word longer", 6), "word…");
+        assert_eq!(truncate("unbroken", 4), "unb…");
+    }
+}
@@ -236,3 +236,15 @@ extern "C" {
         renderer_flags: c_uint,
     ) -> c_int;
 }
+
+/// HTML entity entry from the entity table (`entity.h`)
+#[repr(C)]
+pub struct ENTITY {
+    pub name: *const c_char,
+    pub codepoints: [c_uint; 2],
+}
+
+extern "C" {
+    /// Look up a named entity such as `&amp;` (including `&` and `;`)
+    pub fn entity_lookup(name: *const c_char, name_size: usize) -> *const ENTITY;
+}
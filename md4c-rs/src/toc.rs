@@ -0,0 +1,212 @@
+//! Table of contents extraction.
+//!
+//! [`extract`] lists a document's headings with GitHub-compatible anchor
+//! slugs, and [`to_markdown`] or [`to_html`] turn the list into a nested
+//! table of contents.
+//!
+//! # Example
+//!
+//! ```
+//! use md4c::toc;
+//! use md4c::ParserFlags;
+//!
+//! let doc = "# Guide\n\n## Install\n\n## Usage\n";
+//! let entries = toc::extract(doc, ParserFlags::commonmark());
+//! assert_eq!(entries[1].slug, "install");
+//! assert_eq!(entries[1].byte_offset, 9);
+//!
+//! assert_eq!(
+//!     toc::to_markdown(&entries),
+//!     "- [Guide](#guide)\n  - [Install](#install)\n  - [Usage](#usage)\n"
+//! );
+//! ```
+
+use crate::parser::ParserFlags;
+use crate::slug::{SlugStyle, Slugger, Slugify};
+use crate::split::collect_all_headings;
+
+/// A heading in the table of contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// Heading level (1-6)
+    pub level: u8,
+    /// Heading text content
+    pub text: String,
+    /// Anchor slug, unique within the document
+    pub slug: String,
+    /// Byte offset of the heading's first line in the input
+    pub byte_offset: usize,
+}
+
+/// List the headings of a document in order.
+///
+/// Like [`split_by_headings`](crate::split::split_by_headings), only
+/// top-level headings are included, not those nested in block quotes or
+/// lists. Slugs follow GitHub's scheme, with `-1`, `-2`, ... appended to
+/// repeated headings, nested ones included, so they match the ids from
+/// `HtmlRenderer::with_heading_ids`.
+pub fn extract(input: &str, flags: ParserFlags) -> Vec<TocEntry> {
+    extract_with(input, flags, &SlugStyle::GitHub)
+}
+
+/// Like [`extract`], with slugs derived by `slugifier`, so they match the
+/// ids of an `HtmlRenderer` given the same one.
+pub fn extract_with(input: &str, flags: ParserFlags, slugifier: &dyn Slugify) -> Vec<TocEntry> {
+    let mut slugger = Slugger::default();
+    collect_all_headings(input, flags)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(top_level, heading)| {
+            // Nested headings take their slugs, but have no entries
+            let slug = slugger.unique(slugifier.slugify(&heading.text));
+            top_level.then_some(TocEntry {
+                slug,
+                level: heading.level,
+                text: heading.text,
+                byte_offset: heading.range.start,
+            })
+        })
+        .collect()
+}
+
+/// Nesting depth of each entry.
+///
+/// An entry nests under the closest preceding entry of a lower level, so
+/// skipped levels (a `###` right after a `#`) don't produce empty items.
+fn depths(entries: &[TocEntry]) -> Vec<usize> {
+    let mut open: Vec<u8> = Vec::new();
+    entries
+        .iter()
+        .map(|entry| {
+            while open.last().is_some_and(|&level| level >= entry.level) {
+                open.pop();
+            }
+            open.push(entry.level);
+            open.len() - 1
+        })
+        .collect()
+}
+
+/// Render the table of contents as a nested Markdown list of links.
+pub fn to_markdown(entries: &[TocEntry]) -> String {
+    let mut out = String::new();
+    for (entry, depth) in entries.iter().zip(depths(entries)) {
+        out.push_str(&"  ".repeat(depth));
+        out.push_str("- [");
+        for c in entry.text.chars() {
+            if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '&') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push_str("](#");
+        out.push_str(&entry.slug);
+        out.push_str(")\n");
+    }
+    out
+}
+
+/// Render the table of contents as nested HTML lists of links.
+pub fn to_html(entries: &[TocEntry]) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    for (i, (entry, target)) in entries.iter().zip(depths(entries)).enumerate() {
+        if i == 0 || target > depth {
+            out.push_str("<ul>\n");
+        } else {
+            out.push_str("</li>\n");
+            for _ in target..depth {
+                out.push_str("</ul>\n</li>\n");
+            }
+        }
+        depth = target;
+        out.push_str("<li><a href=\"#");
+        escape_html(&mut out, &entry.slug);
+        out.push_str("\">");
+        escape_html(&mut out, &entry.text);
+        out.push_str("</a>");
+    }
+    if !entries.is_empty() {
+        out.push_str("</li>\n");
+        for _ in 0..depth {
+            out.push_str("</ul>\n</li>\n");
+        }
+        out.push_str("</ul>\n");
+    }
+    out
+}
+
+fn escape_html(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Tom &amp; Jerry\n\n### Deep\n\n## Same\n\n## Same\n\nBack\n====\n";
+
+    #[test]
+    fn test_extract() {
+        let entries = extract(DOC, ParserFlags::commonmark());
+        let summary: Vec<(u8, &str, &str, usize)> = entries
+            .iter()
+            .map(|e| (e.level, e.text.as_str(), e.slug.as_str(), e.byte_offset))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, "Tom & Jerry", "tom--jerry", 0),
+                (3, "Deep", "deep", 19),
+                (2, "Same", "same", 29),
+                (2, "Same", "same-1", 38),
+                (1, "Back", "back", 47),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slugs_count_nested_headings() {
+        let doc = "> # Quoted\n\n# Quoted\n\n# Foo <b>bar</b>\n";
+        let slugs: Vec<String> =
+            extract(doc, ParserFlags::commonmark()).into_iter().map(|e| e.slug).collect();
+        assert_eq!(slugs, ["quoted-1", "foo-bar"]);
+    }
+
+    #[test]
+    fn test_extract_with_slugifier() {
+        let slugify = |text: &str| text.to_uppercase().replace(' ', "_");
+        let slugs: Vec<String> = extract_with(DOC, ParserFlags::commonmark(), &slugify)
+            .into_iter()
+            .map(|e| e.slug)
+            .collect();
+        assert_eq!(slugs, ["TOM_&_JERRY", "DEEP", "SAME", "SAME-1", "BACK"]);
+    }
+
+    #[test]
+    fn test_render() {
+        let entries = extract(DOC, ParserFlags::commonmark());
+        assert_eq!(
+            to_markdown(&entries),
+            "- [Tom \\& Jerry](#tom--jerry)\n  - [Deep](#deep)\n  - [Same](#same)\n  \
+             - [Same](#same-1)\n- [Back](#back)\n"
+        );
+        assert_eq!(
+            to_html(&entries),
+            "<ul>\n<li><a href=\"#tom--jerry\">Tom &amp; Jerry</a><ul>\n\
+             <li><a href=\"#deep\">Deep</a></li>\n\
+             <li><a href=\"#same\">Same</a></li>\n\
+             <li><a href=\"#same-1\">Same</a></li>\n</ul>\n</li>\n\
+             <li><a href=\"#back\">Back</a></li>\n</ul>\n"
+        );
+        assert_eq!(to_html(&[]), "");
+    }
+}
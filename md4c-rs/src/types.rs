@@ -1,9 +1,14 @@
 //! Rust-friendly types for MD4C parsing.
 
+use crate::entity::decode_entity;
 use crate::sys;
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
 
 /// Block element types in Markdown
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum BlockType {
     /// Document root
@@ -38,11 +43,15 @@ pub enum BlockType {
     TableHeaderCell,
     /// Table data cell
     TableCell,
+    /// GitHub-style alert (extension)
+    Admonition,
+    /// Block type this wrapper doesn't know, from a newer MD4C
+    Unknown(u32),
 }
 
 impl BlockType {
-    pub(crate) fn from_raw(raw: sys::MD_BLOCKTYPE) -> Option<Self> {
-        match raw {
+    pub(crate) fn from_raw(raw: sys::MD_BLOCKTYPE) -> Self {
+        let known = match raw {
             sys::MD_BLOCK_DOC => Some(BlockType::Document),
             sys::MD_BLOCK_QUOTE => Some(BlockType::Quote),
             sys::MD_BLOCK_UL => Some(BlockType::UnorderedList),
@@ -60,12 +69,14 @@ impl BlockType {
             sys::MD_BLOCK_TH => Some(BlockType::TableHeaderCell),
             sys::MD_BLOCK_TD => Some(BlockType::TableCell),
             _ => None,
-        }
+        };
+        known.unwrap_or(BlockType::Unknown(raw))
     }
 }
 
 /// Inline span types in Markdown
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum SpanType {
     /// Emphasis (italic)
@@ -88,11 +99,19 @@ pub enum SpanType {
     WikiLink,
     /// Underline (extension)
     Underline,
+    /// Highlighted text (extension)
+    Mark,
+    /// Superscript (extension)
+    Superscript,
+    /// Subscript (extension)
+    Subscript,
+    /// Span type this wrapper doesn't know, from a newer MD4C
+    Unknown(u32),
 }
 
 impl SpanType {
-    pub(crate) fn from_raw(raw: sys::MD_SPANTYPE) -> Option<Self> {
-        match raw {
+    pub(crate) fn from_raw(raw: sys::MD_SPANTYPE) -> Self {
+        let known = match raw {
             sys::MD_SPAN_EM => Some(SpanType::Emphasis),
             sys::MD_SPAN_STRONG => Some(SpanType::Strong),
             sys::MD_SPAN_A => Some(SpanType::Link),
@@ -104,12 +123,14 @@ impl SpanType {
             sys::MD_SPAN_WIKILINK => Some(SpanType::WikiLink),
             sys::MD_SPAN_U => Some(SpanType::Underline),
             _ => None,
-        }
+        };
+        known.unwrap_or(SpanType::Unknown(raw))
     }
 }
 
 /// Text content types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum TextType {
     /// Normal text
@@ -128,11 +149,14 @@ pub enum TextType {
     Html,
     /// LaTeX math content
     LatexMath,
+    /// Text type this wrapper doesn't know, from a newer MD4C; best treated
+    /// as normal text
+    Unknown(u32),
 }
 
 impl TextType {
-    pub(crate) fn from_raw(raw: sys::MD_TEXTTYPE) -> Option<Self> {
-        match raw {
+    pub(crate) fn from_raw(raw: sys::MD_TEXTTYPE) -> Self {
+        let known = match raw {
             sys::MD_TEXT_NORMAL => Some(TextType::Normal),
             sys::MD_TEXT_NULLCHAR => Some(TextType::NullChar),
             sys::MD_TEXT_BR => Some(TextType::HardBreak),
@@ -142,12 +166,14 @@ impl TextType {
             sys::MD_TEXT_HTML => Some(TextType::Html),
             sys::MD_TEXT_LATEXMATH => Some(TextType::LatexMath),
             _ => None,
-        }
+        };
+        known.unwrap_or(TextType::Unknown(raw))
     }
 }
 
 /// Table cell alignment
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Alignment {
     /// Default alignment (typically left)
     #[default]
@@ -173,6 +199,7 @@ impl Alignment {
 
 /// List marker character for unordered lists
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ListMark {
     /// Dash `-`
     Dash,
@@ -194,6 +221,7 @@ impl ListMark {
 
 /// Ordered list delimiter
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderedListDelimiter {
     /// Period `.`
     Period,
@@ -212,6 +240,7 @@ impl OrderedListDelimiter {
 
 /// Fence character for code blocks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FenceChar {
     /// Backtick `` ` ``
     Backtick,
@@ -231,8 +260,63 @@ impl FenceChar {
     }
 }
 
+/// Kind of a GitHub-style alert, from its `[!KIND]` marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AdmonitionKind {
+    /// `[!NOTE]`
+    Note,
+    /// `[!TIP]`
+    Tip,
+    /// `[!IMPORTANT]`
+    Important,
+    /// `[!WARNING]`
+    Warning,
+    /// `[!CAUTION]`
+    Caution,
+}
+
+impl AdmonitionKind {
+    /// Parse a marker such as `[!NOTE]`, ignoring case.
+    pub(crate) fn from_marker(marker: &str) -> Option<Self> {
+        let name = marker.strip_prefix("[!")?.strip_suffix(']')?;
+        [
+            AdmonitionKind::Note,
+            AdmonitionKind::Tip,
+            AdmonitionKind::Important,
+            AdmonitionKind::Warning,
+            AdmonitionKind::Caution,
+        ]
+        .into_iter()
+        .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Lowercase name, as used in class names (`"note"`)
+    pub fn name(self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Important => "important",
+            AdmonitionKind::Warning => "warning",
+            AdmonitionKind::Caution => "caution",
+        }
+    }
+
+    /// Title shown above the content (`"Note"`)
+    pub fn title(self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Important => "Important",
+            AdmonitionKind::Warning => "Warning",
+            AdmonitionKind::Caution => "Caution",
+        }
+    }
+}
+
 /// Task list item state
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TaskState {
     /// Not a task list item
     NotTask,
@@ -242,8 +326,205 @@ pub enum TaskState {
     Checked,
 }
 
+/// A string attribute of a block or span, such as a link destination.
+///
+/// MD4C resolves backslash escapes in attributes but leaves entity
+/// references for the renderer, marking where they are.
+/// [`substrings`](Self::substrings) yields the text in typed pieces and
+/// [`decoded`](Self::decoded) resolves the entities. An attribute derefs to
+/// its text as written, with entities undecoded.
+///
+/// # Example
+///
+/// ```
+/// use md4c::parser::{parse_to_events, Event, ParserFlags};
+/// use md4c::{Span, TextType};
+///
+/// let events = parse_to_events(r"[a](/x&amp;y\&amp;)", ParserFlags::commonmark()).unwrap();
+/// let Event::EnterSpan(Span::Link(link)) = &events[2] else { panic!() };
+/// assert_eq!(link.href, "/x&amp;y&amp;");
+/// assert_eq!(link.href.substrings().nth(1), Some((TextType::Entity, "&amp;")));
+/// // The escaped `&` stays literal
+/// assert_eq!(link.href.decoded(), "/x&y&amp;");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute<'a> {
+    text: Cow<'a, str>,
+    // Start and type of each substring; empty if the whole text is normal
+    substrings: Vec<(usize, TextType)>,
+}
+
+impl<'a> Attribute<'a> {
+    /// An attribute of plain text, without entities.
+    pub fn new(text: impl Into<Cow<'a, str>>) -> Self {
+        Attribute { text: text.into(), substrings: Vec::new() }
+    }
+
+    /// The text as written, with entities undecoded.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The text in pieces of normal text, entity references and null
+    /// characters, in order.
+    pub fn substrings(&self) -> impl Iterator<Item = (TextType, &str)> {
+        const WHOLE: &[(usize, TextType)] = &[(0, TextType::Normal)];
+        let starts = if self.substrings.is_empty() { WHOLE } else { &self.substrings };
+        starts.iter().enumerate().map(move |(i, &(start, text_type))| {
+            let end = starts.get(i + 1).map_or(self.text.len(), |&(end, _)| end);
+            (text_type, &self.text[start..end])
+        })
+    }
+
+    /// The text with entity references decoded and null characters
+    /// replaced by U+FFFD, borrowed if there are none.
+    pub fn decoded(&self) -> Cow<'_, str> {
+        if self.is_plain() {
+            return Cow::Borrowed(&self.text);
+        }
+        Cow::Owned(self.decode())
+    }
+
+    /// Like [`decoded`](Self::decoded), but consuming the attribute, so
+    /// the text stays borrowed from the input where possible.
+    pub fn into_decoded(self) -> Cow<'a, str> {
+        if self.is_plain() {
+            return self.text;
+        }
+        Cow::Owned(self.decode())
+    }
+
+    /// Copy the text, so the attribute no longer borrows from the input.
+    pub fn into_owned(self) -> Attribute<'static> {
+        self.map_text(|text| Cow::Owned(text.into_owned()))
+    }
+
+    /// Rebuild the attribute with its text passed through `f`, which must
+    /// keep the text the same.
+    pub(crate) fn map_text<'b>(
+        self,
+        f: impl FnOnce(Cow<'a, str>) -> Cow<'b, str>,
+    ) -> Attribute<'b> {
+        Attribute { text: f(self.text), substrings: self.substrings }
+    }
+
+    /// Join pieces of text, such as consecutive text events, into an
+    /// attribute.
+    pub(crate) fn from_pieces<'p>(
+        pieces: impl IntoIterator<Item = (TextType, &'p str)>,
+    ) -> Attribute<'static> {
+        let mut text = String::new();
+        let mut substrings = Vec::new();
+        for (text_type, piece) in pieces {
+            substrings.push((text.len(), text_type));
+            text.push_str(piece);
+        }
+        if substrings.iter().all(|&(_, text_type)| text_type == TextType::Normal) {
+            substrings.clear();
+        }
+        Attribute { text: Cow::Owned(text), substrings }
+    }
+
+    fn is_plain(&self) -> bool {
+        self.substrings.iter().all(|&(_, text_type)| text_type == TextType::Normal)
+    }
+
+    fn decode(&self) -> String {
+        let mut decoded = String::with_capacity(self.text.len());
+        for (text_type, text) in self.substrings() {
+            match text_type {
+                TextType::Entity => decoded.push_str(&decode_entity(text)),
+                TextType::NullChar => decoded.push('\u{FFFD}'),
+                _ => decoded.push_str(text),
+            }
+        }
+        decoded
+    }
+
+    /// Read an MD4C attribute, borrowing its text from `input` if it points
+    /// there.
+    pub(crate) unsafe fn from_raw(attr: &sys::MD_ATTRIBUTE, input: &'a str) -> Self {
+        if attr.text.is_null() || attr.size == 0 {
+            return Attribute::default();
+        }
+        let slice = std::slice::from_raw_parts(attr.text as *const u8, attr.size as usize);
+        let start = (attr.text as usize).wrapping_sub(input.as_ptr() as usize);
+        let text = match input.get(start..start.wrapping_add(slice.len())) {
+            Some(text) if text.as_ptr() == slice.as_ptr() => Cow::Borrowed(text),
+            _ => Cow::Owned(String::from_utf8_lossy(slice).into_owned()),
+        };
+
+        // The offsets end with one equal to the size
+        let mut substrings = Vec::new();
+        if !attr.substr_offsets.is_null() && !attr.substr_types.is_null() {
+            let mut i = 0;
+            while *attr.substr_offsets.add(i) < attr.size {
+                let text_type = TextType::from_raw(*attr.substr_types.add(i));
+                substrings.push((*attr.substr_offsets.add(i) as usize, text_type));
+                i += 1;
+            }
+        }
+        if substrings.iter().all(|&(_, text_type)| text_type == TextType::Normal) {
+            substrings.clear();
+        }
+        Attribute { text, substrings }
+    }
+}
+
+impl Deref for Attribute<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl AsRef<str> for Attribute<'_> {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for Attribute<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl<'a> From<&'a str> for Attribute<'a> {
+    fn from(text: &'a str) -> Self {
+        Attribute::new(text)
+    }
+}
+
+impl From<String> for Attribute<'_> {
+    fn from(text: String) -> Self {
+        Attribute::new(text)
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Attribute<'a> {
+    fn from(text: Cow<'a, str>) -> Self {
+        Attribute::new(text)
+    }
+}
+
+impl PartialEq<str> for Attribute<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+impl PartialEq<&str> for Attribute<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
 /// Detail information for unordered lists
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnorderedListDetail {
     /// Whether this is a tight list (no blank lines between items)
     pub is_tight: bool,
@@ -253,6 +534,7 @@ pub struct UnorderedListDetail {
 
 /// Detail information for ordered lists
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrderedListDetail {
     /// Starting number
     pub start: u32,
@@ -264,13 +546,20 @@ pub struct OrderedListDetail {
 
 /// Detail information for list items
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ListItemDetail {
     /// Task state (if task list extension is enabled)
     pub task_state: TaskState,
+    /// The character between the brackets of a task's mark, e.g. `'x'`
+    pub task_mark: Option<char>,
+    /// Byte offset of `task_mark` in the input, for tools that toggle the
+    /// task by patching the source
+    pub task_mark_offset: Option<usize>,
 }
 
 /// Detail information for headings
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HeadingDetail {
     /// Heading level (1-6)
     pub level: u8,
@@ -278,17 +567,130 @@ pub struct HeadingDetail {
 
 /// Detail information for code blocks
 #[derive(Debug, Clone)]
-pub struct CodeBlockDetail {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeBlockDetail<'a> {
     /// Info string (everything after the opening fence)
-    pub info: String,
+    pub info: Attribute<'a>,
     /// Language identifier (first word of info string)
-    pub lang: String,
+    pub lang: Attribute<'a>,
     /// Fence character used
     pub fence_char: FenceChar,
 }
 
+impl CodeBlockDetail<'_> {
+    /// Copy the info string, so the detail no longer borrows from the input.
+    pub fn into_owned(self) -> CodeBlockDetail<'static> {
+        CodeBlockDetail {
+            info: self.info.into_owned(),
+            lang: self.lang.into_owned(),
+            fence_char: self.fence_char,
+        }
+    }
+
+    /// The info string's attributes after the language, in order.
+    ///
+    /// Words are separated by whitespace or commas, so both
+    /// ```` ```rust,no_run,edition2021 ```` and ```` ```rust no_run ```` give
+    /// `no_run` as a flag. `key=value` words have a value, with surrounding
+    /// `"` or `'` quotes removed; other words have none. In Pandoc's
+    /// ```` ```{.python #snippet} ```` form, every word in the braces is
+    /// returned, sigil included. Entities in the info string are decoded.
+    ///
+    /// ```
+    /// use md4c::parser::{parse_to_events, Event, ParserFlags};
+    /// use md4c::Block;
+    ///
+    /// let input = "```rust,no_run title=\"a b.rs\"\n```\n";
+    /// let events = parse_to_events(input, ParserFlags::commonmark()).unwrap();
+    /// let Event::EnterBlock(Block::Code(code)) = &events[1] else { panic!() };
+    /// assert_eq!(
+    ///     code.params(),
+    ///     [("no_run".to_string(), None), ("title".to_string(), Some("a b.rs".to_string()))]
+    /// );
+    /// ```
+    pub fn params(&self) -> Vec<(String, Option<String>)> {
+        let info = self.info.decoded();
+        let (_, params) = CodeBlockDetail::split_info(&info);
+        params
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.map(str::to_string)))
+            .collect()
+    }
+
+    /// Split an info string into its language and attributes, the way
+    /// [`params`](Self::params) does, e.g. for one stored apart from its
+    /// block.
+    ///
+    /// The language is `None` if the first word is an attribute or the
+    /// words are in braces. Entities are left as they are.
+    ///
+    /// ```
+    /// use md4c::CodeBlockDetail;
+    ///
+    /// let (lang, params) = CodeBlockDetail::split_info("py, linenums='1'");
+    /// assert_eq!(lang, Some("py"));
+    /// assert_eq!(params, [("linenums", Some("1"))]);
+    /// ```
+    pub fn split_info(info: &str) -> (Option<&str>, Vec<(&str, Option<&str>)>) {
+        let info = info.trim();
+        let (words, braced) = match info.strip_prefix('{').and_then(|i| i.strip_suffix('}')) {
+            Some(inner) => (info_words(inner), true),
+            None => (info_words(info), false),
+        };
+        let mut lang = None;
+        let mut params = Vec::new();
+        for (i, word) in words.into_iter().enumerate() {
+            match word.split_once('=') {
+                Some((key, value)) => params.push((key, Some(unquote(value)))),
+                None if i == 0 && !braced => lang = Some(word),
+                None => params.push((word, None)),
+            }
+        }
+        (lang, params)
+    }
+}
+
+/// Split an info string at whitespace and commas outside quotes.
+fn info_words(info: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    for (i, c) in info.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                start.get_or_insert(i);
+            }
+            None if c == ',' || c.is_whitespace() => {
+                if let Some(s) = start.take() {
+                    words.push(&info[s..i]);
+                }
+            }
+            None => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(s) = start {
+        words.push(&info[s..]);
+    }
+    words
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
 /// Detail information for tables
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableDetail {
     /// Number of columns
     pub column_count: u32,
@@ -300,6 +702,7 @@ pub struct TableDetail {
 
 /// Detail information for table cells
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TableCellDetail {
     /// Cell alignment
     pub alignment: Alignment,
@@ -307,34 +710,59 @@ pub struct TableCellDetail {
 
 /// Detail information for links
 #[derive(Debug, Clone)]
-pub struct LinkDetail {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkDetail<'a> {
     /// Link URL
-    pub href: String,
+    pub href: Attribute<'a>,
     /// Link title (optional)
-    pub title: String,
+    pub title: Attribute<'a>,
     /// Whether this is an autolink
     pub is_autolink: bool,
 }
 
+impl LinkDetail<'_> {
+    /// Copy the URL and title, so the detail no longer borrows from the
+    /// input.
+    pub fn into_owned(self) -> LinkDetail<'static> {
+        LinkDetail {
+            href: self.href.into_owned(),
+            title: self.title.into_owned(),
+            is_autolink: self.is_autolink,
+        }
+    }
+}
+
 /// Detail information for images
 #[derive(Debug, Clone)]
-pub struct ImageDetail {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageDetail<'a> {
     /// Image source URL
-    pub src: String,
+    pub src: Attribute<'a>,
     /// Image title (optional)
-    pub title: String,
+    pub title: Attribute<'a>,
 }
 
 /// Detail information for wiki links
 #[derive(Debug, Clone)]
-pub struct WikiLinkDetail {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WikiLinkDetail<'a> {
     /// Wiki link target
-    pub target: String,
+    pub target: Attribute<'a>,
+    /// Label after the pipe in `[[target|label]]`, as written; the span's
+    /// content is the label rendered, or the target if there is none
+    pub label: Option<Cow<'a, str>>,
+    /// Whether this is an embed (`![[target]]`) rather than a plain link
+    pub is_embed: bool,
 }
 
 /// Block element with its associated detail information
+///
+/// Strings in the details borrow from the parser's input where MD4C didn't
+/// have to rewrite them, e.g. to resolve escapes; [`Block::into_owned`]
+/// detaches a block from the input to keep it.
 #[derive(Debug, Clone)]
-pub enum Block {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Block<'a> {
     /// Document root
     Document,
     /// Block quote
@@ -350,7 +778,7 @@ pub enum Block {
     /// Heading
     Heading(HeadingDetail),
     /// Code block
-    Code(CodeBlockDetail),
+    Code(CodeBlockDetail<'a>),
     /// Raw HTML block
     Html,
     /// Paragraph
@@ -367,19 +795,92 @@ pub enum Block {
     TableHeaderCell(TableCellDetail),
     /// Table data cell
     TableCell(TableCellDetail),
+    /// GitHub-style alert, a block quote starting with a `[!KIND]` line
+    /// (extension)
+    Admonition(AdmonitionKind),
+    /// Block this wrapper doesn't know, from a newer MD4C, with its raw type
+    Unknown(u32),
+}
+
+impl Block<'_> {
+    /// Get the type of this block, without its detail information.
+    pub fn block_type(&self) -> BlockType {
+        match self {
+            Block::Document => BlockType::Document,
+            Block::Quote => BlockType::Quote,
+            Block::UnorderedList(_) => BlockType::UnorderedList,
+            Block::OrderedList(_) => BlockType::OrderedList,
+            Block::ListItem(_) => BlockType::ListItem,
+            Block::HorizontalRule => BlockType::HorizontalRule,
+            Block::Heading(_) => BlockType::Heading,
+            Block::Code(_) => BlockType::Code,
+            Block::Html => BlockType::Html,
+            Block::Paragraph => BlockType::Paragraph,
+            Block::Table(_) => BlockType::Table,
+            Block::TableHead => BlockType::TableHead,
+            Block::TableBody => BlockType::TableBody,
+            Block::TableRow => BlockType::TableRow,
+            Block::TableHeaderCell(_) => BlockType::TableHeaderCell,
+            Block::TableCell(_) => BlockType::TableCell,
+            Block::Admonition(_) => BlockType::Admonition,
+            Block::Unknown(raw) => BlockType::Unknown(*raw),
+        }
+    }
+}
+
+impl<'a> Block<'a> {
+    /// Copy the block's strings, so it no longer borrows from the input.
+    pub fn into_owned(self) -> Block<'static> {
+        self.map_strs(&mut |s| Cow::Owned(s.into_owned()))
+    }
+
+    /// Rebuild the block with each of its detail strings passed through `f`.
+    pub(crate) fn map_strs<'b>(
+        self,
+        f: &mut impl FnMut(Cow<'a, str>) -> Cow<'b, str>,
+    ) -> Block<'b> {
+        match self {
+            Block::Document => Block::Document,
+            Block::Quote => Block::Quote,
+            Block::UnorderedList(d) => Block::UnorderedList(d),
+            Block::OrderedList(d) => Block::OrderedList(d),
+            Block::ListItem(d) => Block::ListItem(d),
+            Block::HorizontalRule => Block::HorizontalRule,
+            Block::Heading(d) => Block::Heading(d),
+            Block::Code(d) => Block::Code(CodeBlockDetail {
+                info: d.info.map_text(&mut *f),
+                lang: d.lang.map_text(&mut *f),
+                fence_char: d.fence_char,
+            }),
+            Block::Html => Block::Html,
+            Block::Paragraph => Block::Paragraph,
+            Block::Table(d) => Block::Table(d),
+            Block::TableHead => Block::TableHead,
+            Block::TableBody => Block::TableBody,
+            Block::TableRow => Block::TableRow,
+            Block::TableHeaderCell(d) => Block::TableHeaderCell(d),
+            Block::TableCell(d) => Block::TableCell(d),
+            Block::Admonition(kind) => Block::Admonition(kind),
+            Block::Unknown(raw) => Block::Unknown(raw),
+        }
+    }
 }
 
 /// Inline span with its associated detail information
+///
+/// Like [`Block`], strings in the details borrow from the input where
+/// possible; [`Span::into_owned`] detaches a span from the input.
 #[derive(Debug, Clone)]
-pub enum Span {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Span<'a> {
     /// Emphasis (italic)
     Emphasis,
     /// Strong emphasis (bold)
     Strong,
     /// Link
-    Link(LinkDetail),
+    Link(LinkDetail<'a>),
     /// Image
-    Image(ImageDetail),
+    Image(ImageDetail<'a>),
     /// Inline code
     Code,
     /// Strikethrough
@@ -389,16 +890,78 @@ pub enum Span {
     /// Display LaTeX math
     LatexMathDisplay,
     /// Wiki link
-    WikiLink(WikiLinkDetail),
+    WikiLink(WikiLinkDetail<'a>),
     /// Underline
     Underline,
+    /// Highlighted text, `==like this==`
+    Mark,
+    /// Superscript, `^like this^`
+    Superscript,
+    /// Subscript, `~like this~`
+    Subscript,
+    /// Span this wrapper doesn't know, from a newer MD4C, with its raw type
+    Unknown(u32),
 }
 
-/// Helper to extract string from MD_ATTRIBUTE
-pub(crate) unsafe fn attribute_to_string(attr: &sys::MD_ATTRIBUTE) -> String {
-    if attr.text.is_null() || attr.size == 0 {
-        return String::new();
+impl Span<'_> {
+    /// Get the type of this span, without its detail information.
+    pub fn span_type(&self) -> SpanType {
+        match self {
+            Span::Emphasis => SpanType::Emphasis,
+            Span::Strong => SpanType::Strong,
+            Span::Link(_) => SpanType::Link,
+            Span::Image(_) => SpanType::Image,
+            Span::Code => SpanType::Code,
+            Span::Strikethrough => SpanType::Strikethrough,
+            Span::LatexMath => SpanType::LatexMath,
+            Span::LatexMathDisplay => SpanType::LatexMathDisplay,
+            Span::WikiLink(_) => SpanType::WikiLink,
+            Span::Underline => SpanType::Underline,
+            Span::Mark => SpanType::Mark,
+            Span::Superscript => SpanType::Superscript,
+            Span::Subscript => SpanType::Subscript,
+            Span::Unknown(raw) => SpanType::Unknown(*raw),
+        }
+    }
+}
+
+impl<'a> Span<'a> {
+    /// Copy the span's strings, so it no longer borrows from the input.
+    pub fn into_owned(self) -> Span<'static> {
+        self.map_strs(&mut |s| Cow::Owned(s.into_owned()))
+    }
+
+    /// Rebuild the span with each of its detail strings passed through `f`.
+    pub(crate) fn map_strs<'b>(
+        self,
+        f: &mut impl FnMut(Cow<'a, str>) -> Cow<'b, str>,
+    ) -> Span<'b> {
+        match self {
+            Span::Emphasis => Span::Emphasis,
+            Span::Strong => Span::Strong,
+            Span::Link(d) => Span::Link(LinkDetail {
+                href: d.href.map_text(&mut *f),
+                title: d.title.map_text(&mut *f),
+                is_autolink: d.is_autolink,
+            }),
+            Span::Image(d) => Span::Image(ImageDetail {
+                src: d.src.map_text(&mut *f),
+                title: d.title.map_text(&mut *f),
+            }),
+            Span::Code => Span::Code,
+            Span::Strikethrough => Span::Strikethrough,
+            Span::LatexMath => Span::LatexMath,
+            Span::LatexMathDisplay => Span::LatexMathDisplay,
+            Span::WikiLink(d) => Span::WikiLink(WikiLinkDetail {
+                target: d.target.map_text(&mut *f),
+                label: d.label.map(&mut *f),
+                is_embed: d.is_embed,
+            }),
+            Span::Underline => Span::Underline,
+            Span::Mark => Span::Mark,
+            Span::Superscript => Span::Superscript,
+            Span::Subscript => Span::Subscript,
+            Span::Unknown(raw) => Span::Unknown(raw),
+        }
     }
-    let slice = std::slice::from_raw_parts(attr.text as *const u8, attr.size as usize);
-    String::from_utf8_lossy(slice).into_owned()
 }
@@ -5,6 +5,7 @@
 //! Features:
 //! - Scrolling with j/k or arrow keys
 //! - Jump to headings with number keys
+//! - Jump back/forward with Ctrl+O/Ctrl+I
 //! - Navigate links with Tab/Shift+Tab
 //! - Show table of contents with 't'
 
@@ -40,6 +41,7 @@ Use the following keys to navigate:
 - `Tab` - Next link
 - `Shift+Tab` - Previous link
 - `1-9` - Jump to heading
+- `Ctrl+O` / `Ctrl+I` - Jump back / forward
 - `q` - Quit
 
 ## Features
@@ -229,6 +231,12 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<
                     KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         app.view.scroll_up(app.viewport_height / 2);
                     }
+                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.view.jump_back();
+                    }
+                    KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.view.jump_forward();
+                    }
                     KeyCode::PageDown => app.view.scroll_down(app.viewport_height),
                     KeyCode::PageUp => app.view.scroll_up(app.viewport_height),
                     KeyCode::Char('g') => app.view.scroll_to_top(),
@@ -0,0 +1,116 @@
+//! Code fence info strings.
+//!
+//! The info string after an opening fence often carries more than the
+//! language, e.g. ```` ```rust,no_run title="main.rs" hl_lines="2-3" ````.
+//! [`CodeInfo`] splits it into the language, bare flags and `key=value`
+//! attributes. The renderer uses it for code block headers and highlighted
+//! lines, and passes it to
+//! [`RenderOptions::with_code_header`](crate::RenderOptions::with_code_header).
+
+use md4c::CodeBlockDetail;
+use std::ops::Range;
+
+/// A code block's info string, split into its parts.
+///
+/// The first word is the language unless it is an attribute. Words are
+/// separated by whitespace or commas; attribute values may be quoted with
+/// `"` or `'` to hold either.
+///
+/// # Example
+///
+/// ```
+/// use ratatui_md::CodeInfo;
+///
+/// let info = CodeInfo::parse(r#"rust,no_run title="main.rs" hl_lines="1 3-4""#);
+/// assert_eq!(info.lang, "rust");
+/// assert_eq!(info.flags, ["no_run"]);
+/// assert_eq!(info.title(), Some("main.rs"));
+/// assert_eq!(info.highlight_lines(), [0..1, 2..4]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeInfo {
+    /// Language, empty if there is none
+    pub lang: String,
+    /// Words without a value, e.g. `no_run`
+    pub flags: Vec<String>,
+    /// `key=value` attributes, in order, with quotes removed
+    pub attributes: Vec<(String, String)>,
+}
+
+impl CodeInfo {
+    /// Parse an info string, as md4c's [`CodeBlockDetail::params`] does.
+    pub fn parse(info: &str) -> Self {
+        let (lang, params) = CodeBlockDetail::split_info(info);
+        let mut code_info = CodeInfo {
+            lang: lang.unwrap_or_default().to_string(),
+            ..CodeInfo::default()
+        };
+        for (key, value) in params {
+            match value {
+                Some(value) => code_info.attributes.push((key.to_string(), value.to_string())),
+                None => code_info.flags.push(key.to_string()),
+            }
+        }
+        code_info
+    }
+
+    /// Value of the first attribute named `key`.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the flag `flag` is set.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// The `title` attribute, e.g. a file name.
+    pub fn title(&self) -> Option<&str> {
+        self.attribute("title").filter(|t| !t.is_empty())
+    }
+
+    /// Lines to highlight, from the `hl_lines` or `highlight` attribute.
+    ///
+    /// The attribute lists line numbers and ranges starting at 1, e.g.
+    /// `"1 3-4"` or `"1,3-4"`; they are returned as zero-based, half-open
+    /// ranges. Malformed entries are skipped.
+    pub fn highlight_lines(&self) -> Vec<Range<usize>> {
+        let Some(lines) = self.attribute("hl_lines").or_else(|| self.attribute("highlight")) else {
+            return Vec::new();
+        };
+        lines
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|entry| {
+                let (start, end) = entry.split_once('-').unwrap_or((entry, entry));
+                let start: usize = start.trim().parse().ok()?;
+                let end: usize = end.trim().parse().ok()?;
+                (start >= 1 && end >= start).then(|| start - 1..end)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info() {
+        let info = CodeInfo::parse(r#"  python title='a b.py', linenums="1" hl_lines="2 3-x 5-4" "#);
+        assert_eq!(info.lang, "python");
+        assert!(info.flags.is_empty());
+        assert_eq!(info.title(), Some("a b.py"));
+        assert_eq!(info.attribute("linenums"), Some("1"));
+        assert_eq!(info.highlight_lines(), vec![1..2]);
+
+        let info = CodeInfo::parse("title=x.sh ignore");
+        assert_eq!(info.lang, "");
+        assert!(info.has_flag("ignore"));
+        assert_eq!(CodeInfo::parse(""), CodeInfo::default());
+
+        // Pandoc's braced form has no language
+        let info = CodeInfo::parse("{.python #snippet}");
+        assert_eq!(info.lang, "");
+        assert_eq!(info.flags, [".python", "#snippet"]);
+    }
+}
@@ -0,0 +1,104 @@
+//! Halfblock rendering of images.
+//!
+//! Terminals without a graphics protocol can still show a rough picture:
+//! each cell draws two vertically stacked pixels, the upper one as the
+//! foreground of `▀` and the lower one as the background. Requires the
+//! `image` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use image::{DynamicImage, Rgba, RgbaImage};
+//! use ratatui_md::halfblock::image_lines;
+//!
+//! let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255])));
+//! let lines = image_lines(&image, 80);
+//! assert_eq!(lines.len(), 2);
+//! assert_eq!(lines[0].width(), 4);
+//! ```
+
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// Pixels with less alpha than this are treated as transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Render an image as lines of halfblock characters at most `max_width`
+/// columns wide.
+///
+/// Images narrower than `max_width` keep their size, one pixel per column;
+/// wider ones are scaled down, keeping their aspect ratio. Transparent
+/// pixels show the terminal background.
+pub fn image_lines(image: &DynamicImage, max_width: usize) -> Vec<Line<'static>> {
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 || max_width == 0 {
+        return Vec::new();
+    }
+    let columns = width.min(u32::try_from(max_width).unwrap_or(u32::MAX));
+    let rows = (u64::from(height) * u64::from(columns) / u64::from(width)).max(1) as u32;
+    let pixels = image.resize_exact(columns, rows, FilterType::Triangle).to_rgba8();
+
+    (0..rows.div_ceil(2))
+        .map(|row| {
+            let spans: Vec<Span<'static>> = (0..columns)
+                .map(|x| {
+                    let top = *pixels.get_pixel(x, row * 2);
+                    let bottom = pixels.get_pixel_checked(x, row * 2 + 1).copied();
+                    cell(top, bottom)
+                })
+                .collect();
+            Line::from(merge(spans))
+        })
+        .collect()
+}
+
+/// The cell showing `top` above `bottom`.
+fn cell(top: Rgba<u8>, bottom: Option<Rgba<u8>>) -> Span<'static> {
+    let color = |p: Rgba<u8>| (p[3] >= ALPHA_THRESHOLD).then_some(Color::Rgb(p[0], p[1], p[2]));
+    match (color(top), bottom.and_then(color)) {
+        (Some(fg), Some(bg)) => Span::styled("▀", Style::new().fg(fg).bg(bg)),
+        (Some(fg), None) => Span::styled("▀", Style::new().fg(fg)),
+        (None, Some(fg)) => Span::styled("▄", Style::new().fg(fg)),
+        (None, None) => Span::raw(" "),
+    }
+}
+
+/// Join neighboring cells of the same style into one span.
+fn merge(spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+    let mut merged: Vec<Span<'static>> = Vec::new();
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if last.style == span.style => last.content.to_mut().push_str(&span.content),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_image_lines() {
+        // 2x3: red over blue, then a transparent-bottomed last row
+        let mut pixels = RgbaImage::from_pixel(2, 3, Rgba([255, 0, 0, 255]));
+        pixels.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+        pixels.put_pixel(1, 1, Rgba([0, 0, 255, 255]));
+        let lines = image_lines(&DynamicImage::ImageRgba8(pixels.clone()), 10);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].spans.len(), 1);
+        assert_eq!(lines[0].spans[0].content, "▀▀");
+        assert_eq!(lines[0].spans[0].style.bg, Some(Color::Rgb(0, 0, 255)));
+        assert_eq!(lines[1].spans[0].style, Style::new().fg(Color::Rgb(255, 0, 0)));
+
+        // Wider images are scaled down
+        let wide = DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 20, Rgba([0, 0, 0, 0])));
+        let lines = image_lines(&wide, 10);
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.width() == 10 && l.spans[0].content.trim().is_empty()));
+    }
+}
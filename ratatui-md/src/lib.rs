@@ -13,6 +13,7 @@
 //! - **Customizable Themes**: Built-in themes or create your own
 //! - **Interactive Widgets**: Scrolling, link navigation, heading jumping
 //! - **Syntax Highlighting**: Optional code block highlighting via syntect
+//! - **Images**: Optional halfblock rendering of images via the `image` feature
 //!
 //! ## Quick Start
 //!
@@ -109,16 +110,36 @@
 //! let lines = highlighter.highlight("fn main() {}", "rust");
 //! ```
 
+#[cfg(feature = "image")]
+pub mod halfblock;
+pub mod fence;
 pub mod highlight;
+pub mod page;
+pub mod profile;
 pub mod renderer;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod theme;
 pub mod widget;
 
 // Re-export main types
+pub use fence::CodeInfo;
 pub use highlight::SyntaxHighlighter;
-pub use renderer::{render, render_default, HeadingInfo, LinkInfo, RenderOptions, RenderedMarkdown, SearchMatch};
+pub use page::{paginate, Page, PageOptions};
+pub use profile::{CodeBlockTiming, RenderProfile};
+pub use renderer::{
+    measure, render, render_arc, render_default, AmbiguousWidth, CodeHeader, DeepHeadings,
+    DetailsInfo, DocumentStats, EmbedResolver, HeadingInfo, Hook, ImageMode, LayoutInfo,
+    LinkDisplay, LinkGroup, LinkInfo, LinkNotes, RenderOptions, RenderedMarkdown, SearchMatch,
+    SearchScope, Slugifier, SpellChecker, TableInfo, TextTransform, WikiResolver,
+};
+#[cfg(feature = "image")]
+pub use renderer::ImageLoader;
 pub use theme::Theme;
-pub use widget::{Markdown, MarkdownSpan, MarkdownView, MarkdownViewWidget};
+pub use widget::{
+    CellMove, LinkAction, LinkHandler, Markdown, MarkdownSpan, MarkdownView, MarkdownViewWidget,
+    OutlineFormat, ScrollPlacement, SelectedCell,
+};
 
 // Re-export md4c types that users might need
 pub use md4c::ParserFlags;
@@ -0,0 +1,177 @@
+//! Splitting rendered documents into pages.
+//!
+//! [`paginate`] renders a document and cuts it into pages of at most
+//! [`PageOptions::max_lines`] lines for print-like or e-reader style viewers.
+//! Page boundaries prefer to fall before a heading or between blocks rather
+//! than in the middle of a paragraph.
+//!
+//! # Example
+//!
+//! ```
+//! use ratatui_md::{paginate, PageOptions, RenderOptions, Theme};
+//!
+//! let markdown = "# One\n\nFirst chapter.\n\n# Two\n\nSecond chapter.";
+//! let pages = paginate(
+//!     markdown,
+//!     &Theme::default(),
+//!     &RenderOptions::default(),
+//!     PageOptions::new(20).break_on_headings(true),
+//! );
+//! assert_eq!(pages.len(), 2);
+//! ```
+
+use crate::renderer::{render, RenderOptions};
+use crate::theme::Theme;
+use ratatui::text::{Line, Text};
+use std::ops::Range;
+
+/// Options for [`paginate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageOptions {
+    /// Maximum number of lines per page (at least 1)
+    pub max_lines: usize,
+    /// Start every top-level heading (the shallowest level used in the
+    /// document) on a new page, like chapters in a book
+    pub break_on_headings: bool,
+}
+
+impl PageOptions {
+    /// Pages of at most `max_lines` lines, without forced breaks.
+    pub fn new(max_lines: usize) -> Self {
+        PageOptions {
+            max_lines,
+            break_on_headings: false,
+        }
+    }
+
+    /// Set whether top-level headings start a new page.
+    pub fn break_on_headings(mut self, enabled: bool) -> Self {
+        self.break_on_headings = enabled;
+        self
+    }
+}
+
+/// One page of a paginated document.
+#[derive(Debug, Clone)]
+pub struct Page<'a> {
+    /// Lines of the full rendered document on this page
+    pub lines: Range<usize>,
+    /// The page content
+    pub text: Text<'a>,
+}
+
+/// Render markdown and split it into pages.
+///
+/// When a page is full, it ends before the last heading or, failing that,
+/// after the last complete top-level block, as long as that fills at least
+/// half the page. Otherwise the content is cut at `max_lines`. Blank lines at
+/// a page boundary are dropped.
+pub fn paginate<'a>(
+    markdown: &str,
+    theme: &Theme,
+    options: &RenderOptions,
+    pages: PageOptions,
+) -> Vec<Page<'a>> {
+    let rendered = render(markdown, theme, options);
+    let lines: Vec<Line<'a>> = rendered.text.lines;
+
+    let blank: Vec<bool> = lines.iter().map(|l| l.width() == 0).collect();
+    let mut heading_lines: Vec<usize> = rendered.headings.iter().map(|h| h.line).collect();
+    heading_lines.dedup();
+    let top_level = rendered.headings.iter().map(|h| h.level).min();
+    let chapters: Vec<usize> = rendered
+        .headings
+        .iter()
+        .filter(|h| pages.break_on_headings && Some(h.level) == top_level)
+        .map(|h| h.line)
+        .collect();
+
+    let max_lines = pages.max_lines.max(1);
+    let mut result = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        if blank[start] {
+            start += 1;
+            continue;
+        }
+        let limit = (start + max_lines).min(lines.len());
+        let earliest = start + max_lines.div_ceil(2);
+
+        let end = if let Some(&chapter) = chapters.iter().find(|&&l| l > start && l < limit) {
+            chapter
+        } else if limit == lines.len() {
+            limit
+        } else if let Some(&heading) =
+            heading_lines.iter().rev().find(|&&l| l >= earliest && l <= limit)
+        {
+            heading
+        } else if let Some(&edge) =
+            rendered.block_boundaries.iter().rev().find(|&&l| l >= earliest && l <= limit)
+        {
+            edge
+        } else {
+            limit
+        };
+
+        // Trailing blank lines stay off the page
+        let mut last = end;
+        while last > start && blank[last - 1] {
+            last -= 1;
+        }
+        result.push(Page {
+            lines: start..last,
+            text: Text::from(lines[start..last].to_vec()),
+        });
+        start = end;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_lines(markdown: &str, options: &RenderOptions, pages: PageOptions) -> Vec<Vec<String>> {
+        paginate(markdown, &Theme::plain(), options, pages)
+            .into_iter()
+            .map(|page| {
+                page.text
+                    .lines
+                    .iter()
+                    .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_breaks_prefer_block_edges() {
+        let md = "aaa bbb ccc ddd\n\neee fff ggg hhh iii\n\n# H\n\nz\n";
+        let options = RenderOptions::default().with_width(10);
+        let pages = page_lines(md, &options, PageOptions::new(4));
+        assert_eq!(
+            pages,
+            [
+                vec!["aaa bbb", "ccc ddd"],
+                vec!["eee fff", "ggg hhh", "iii"],
+                vec!["# H", "z"],
+            ]
+        );
+
+        // Without block edges, content is cut at the limit
+        let options = RenderOptions::default().with_width(2);
+        let pages = page_lines("aa bb cc dd ee", &options, PageOptions::new(2));
+        assert_eq!(pages, [vec!["aa", "bb"], vec!["cc", "dd"], vec!["ee"]]);
+    }
+
+    #[test]
+    fn test_break_on_headings() {
+        let md = "# One\n\n## Sub\n\ntext\n\n# Two\n\nmore\n";
+        let options = RenderOptions::default();
+        let pages = page_lines(md, &options, PageOptions::new(50).break_on_headings(true));
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1][0], "# Two");
+        assert!(pages[0].contains(&"## Sub".to_string()));
+        assert_eq!(page_lines(md, &options, PageOptions::new(50)).len(), 1);
+    }
+}
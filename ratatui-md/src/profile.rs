@@ -0,0 +1,99 @@
+//! Render profiling.
+//!
+//! Enable [`RenderOptions::profile`](crate::RenderOptions::profile) to get a
+//! [`RenderProfile`] alongside the rendered document, showing where time was
+//! spent on a slow document.
+//!
+//! With the `alloc-tracking` feature, install [`CountingAllocator`] as the
+//! global allocator to also count allocations:
+//!
+//! ```rust,ignore
+//! #[global_allocator]
+//! static ALLOC: ratatui_md::profile::CountingAllocator = ratatui_md::profile::CountingAllocator;
+//! ```
+
+use std::time::Duration;
+
+/// Timing report for one call to [`render`](crate::render).
+#[derive(Debug, Clone, Default)]
+pub struct RenderProfile {
+    /// Total time spent rendering
+    pub total: Duration,
+    /// Time spent parsing and converting events, excluding highlighting and
+    /// wrapping
+    pub parse: Duration,
+    /// Time spent word wrapping lines
+    pub wrap: Duration,
+    /// Time spent rendering each code block, in document order
+    pub code_blocks: Vec<CodeBlockTiming>,
+    /// Number of allocations made while rendering, if [`CountingAllocator`]
+    /// is installed (requires the `alloc-tracking` feature). The count is
+    /// process-wide, so allocations on other threads are included.
+    pub allocations: Option<usize>,
+}
+
+impl RenderProfile {
+    /// Total time spent rendering code blocks.
+    pub fn highlight(&self) -> Duration {
+        self.code_blocks.iter().map(|c| c.duration).sum()
+    }
+}
+
+/// Time spent rendering a single code block.
+#[derive(Debug, Clone)]
+pub struct CodeBlockTiming {
+    /// Line index of the block's first line
+    pub line: usize,
+    /// Language from the fence info string
+    pub lang: String,
+    /// Number of source lines
+    pub line_count: usize,
+    /// Time spent highlighting and styling the block
+    pub duration: Duration,
+}
+
+#[cfg(feature = "alloc-tracking")]
+mod tracking {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+    /// Global allocator wrapping [`System`] that counts allocations.
+    pub struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            INSTALLED.store(true, Ordering::Relaxed);
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    pub(crate) fn allocation_count() -> Option<usize> {
+        INSTALLED
+            .load(Ordering::Relaxed)
+            .then(|| ALLOCATIONS.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(feature = "alloc-tracking")]
+pub use tracking::CountingAllocator;
+
+/// Current allocation count, if a counting allocator is installed.
+pub(crate) fn allocation_count() -> Option<usize> {
+    #[cfg(feature = "alloc-tracking")]
+    return tracking::allocation_count();
+    #[cfg(not(feature = "alloc-tracking"))]
+    None
+}
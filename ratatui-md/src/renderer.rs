@@ -2,18 +2,201 @@
 //!
 //! Converts parsed markdown into ratatui `Text` structures.
 
+use crate::fence::CodeInfo;
+use crate::profile::{allocation_count, CodeBlockTiming, RenderProfile};
 use crate::theme::Theme;
 #[cfg(feature = "syntect")]
 use crate::highlight::SyntaxHighlighter;
+use md4c::autolink::ExtraAutolinks;
+use md4c::combinator::Tee;
+use md4c::slug::push_heading_text;
+use md4c::stats::{DocStats, StatsCounter};
 use md4c::{
     parse, Alignment, Block, BlockType, CodeBlockDetail, HeadingDetail, ImageDetail, LinkDetail,
-    ListItemDetail, OrderedListDetail, ParserFlags, ParserHandler, Span, SpanType, TableCellDetail,
-    TableDetail, TaskState, TextType, UnorderedListDetail, WikiLinkDetail,
+    ListItemDetail, OrderedListDetail, ParserFlags, ParserHandler, SlugStyle, Slugger, Slugify,
+    Span, SpanType, TableCellDetail, TableDetail, TaskState, TextType, UnorderedListDetail,
+    WikiLinkDetail,
 };
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span as RSpan, Text};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+/// Maximum nesting depth for embedded notes.
+const MAX_EMBED_DEPTH: usize = 4;
+
+/// Narrowest text column left beside sidenotes; below it notes go to the
+/// end of the document.
+const MIN_SIDENOTE_TEXT_WIDTH: usize = 40;
+
+/// Columns between the text and the sidenote margin.
+const SIDENOTE_GAP: usize = 2;
+
+/// Width of halfblock images when lines are not wrapped.
+#[cfg(feature = "image")]
+const DEFAULT_IMAGE_WIDTH: usize = 80;
+
+/// A shareable callback, as stored in [`RenderOptions`] and
+/// [`MarkdownView`](crate::MarkdownView).
+pub struct Hook<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Hook(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook(..)")
+    }
+}
+
+impl<F: ?Sized> Deref for Hook<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.0
+    }
+}
+
+impl<F: ?Sized> From<Arc<F>> for Hook<F> {
+    fn from(f: Arc<F>) -> Self {
+        Hook(f)
+    }
+}
+
+/// Callback supplying the markdown content of an embedded note (`![[target]]`).
+///
+/// Returns `None` if the target cannot be resolved, in which case the embed
+/// is rendered like a plain wiki link.
+pub type EmbedResolver = Hook<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Check whether the target of a wiki link (`[[target]]`) exists, see
+/// [`RenderOptions::with_wiki_resolver`].
+pub type WikiResolver = Hook<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Formatter for link URLs shown after link text, see
+/// [`RenderOptions::with_link_display`].
+pub type LinkDisplay = Hook<dyn Fn(&LinkDetail) -> String + Send + Sync>;
+
+/// Loader for the image behind an image's `src`, see
+/// [`RenderOptions::with_image_loader`].
+#[cfg(feature = "image")]
+pub type ImageLoader = Hook<dyn Fn(&str) -> Option<image::DynamicImage> + Send + Sync>;
+
+/// Spell checker returning the byte ranges of misspelled words in a run of
+/// text, see [`RenderOptions::with_spellchecker`].
+pub type SpellChecker = Hook<dyn Fn(&str) -> Vec<Range<usize>> + Send + Sync>;
+
+/// Builder of the header line shown above a code block, see
+/// [`RenderOptions::with_code_header`].
+pub type CodeHeader = Hook<dyn Fn(&CodeInfo) -> Option<Line<'static>> + Send + Sync>;
+
+/// Rewrite of each run of text before it is styled, see
+/// [`RenderOptions::with_text_transform`].
+pub type TextTransform = Hook<dyn Fn(TextType, &str) -> Cow<'_, str> + Send + Sync>;
+
+/// Slugifier for heading anchors, see [`RenderOptions::with_slugifier`].
+pub type Slugifier = Hook<dyn Slugify>;
+
+/// Display width of East Asian ambiguous-width characters, such as `°`,
+/// `±` and box-drawing glyphs.
+///
+/// Match the terminal's setting so tables and wrapped lines line up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmbiguousWidth {
+    /// One column, as in most Western locales
+    #[default]
+    Narrow,
+    /// Two columns, as in CJK locales
+    Wide,
+}
+
+impl AmbiguousWidth {
+    /// Display width of a string.
+    pub fn str_width(self, s: &str) -> usize {
+        match self {
+            AmbiguousWidth::Narrow => s.width(),
+            AmbiguousWidth::Wide => s.width_cjk(),
+        }
+    }
+
+    /// Display width of a character, `None` for control characters.
+    pub fn char_width(self, c: char) -> Option<usize> {
+        match self {
+            AmbiguousWidth::Narrow => c.width(),
+            AmbiguousWidth::Wide => c.width_cjk(),
+        }
+    }
+
+    /// Display width of a line.
+    pub fn line_width(self, line: &Line<'_>) -> usize {
+        line.spans.iter().map(|s| self.str_width(&s.content)).sum()
+    }
+
+    /// A horizontal rule of `glyph` exactly `width` columns wide, padded
+    /// with spaces if the glyph is wider than one column.
+    fn rule(self, glyph: &str, width: usize) -> String {
+        let glyph_width = self.str_width(glyph).max(1);
+        format!("{}{}", glyph.repeat(width / glyph_width), " ".repeat(width % glyph_width))
+    }
+
+    /// Pad `text` to `width` columns with the given alignment.
+    fn pad(self, text: &str, width: usize, align: Alignment) -> String {
+        let fill = width.saturating_sub(self.str_width(text));
+        let (left, right) = match align {
+            Alignment::Center => (fill / 2, fill - fill / 2),
+            Alignment::Right => (fill, 0),
+            _ => (0, fill),
+        };
+        format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+    }
+}
+
+/// How images are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageMode {
+    /// The alt text and source
+    #[default]
+    Text,
+    /// A raster of halfblock characters, two pixels per cell, for images
+    /// that `RenderOptions::with_image_loader` can load; others are shown
+    /// as text. Without the `image` feature, all images are shown as text.
+    Halfblock,
+}
+
+/// What happens to headings deeper than
+/// [`RenderOptions::max_heading_depth`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeepHeadings {
+    /// Show them as bold paragraphs, without the `#` marker
+    #[default]
+    Demote,
+    /// Leave them out
+    Hide,
+}
+
+/// Where link URLs go when `theme.show_link_urls` is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkNotes {
+    /// In parentheses after the link text
+    #[default]
+    Inline,
+    /// Numbered, with the URLs listed at the end of the document
+    Endnotes,
+    /// Numbered, with the URLs right-aligned in a margin column this many
+    /// columns wide, beside the line of their link. Falls back to endnotes
+    /// when `width` leaves too little room for the text, or the margin is 0.
+    Sidenotes(usize),
+}
+
 /// Render options for the markdown renderer.
 #[derive(Debug, Clone, Default)]
 pub struct RenderOptions {
@@ -33,10 +216,63 @@ pub struct RenderOptions {
     pub search_pattern: Option<String>,
     /// Style for search highlights
     pub search_highlight_style: Style,
+    /// Parts of the document the search pattern is matched in
+    pub search_scope: SearchScope,
     /// Whether to use syntax highlighting for code blocks
     pub syntax_highlighting: bool,
     /// Syntax highlighting theme name (if syntect feature enabled)
     pub syntax_theme: Option<String>,
+    /// Builder of code block headers, replacing the default
+    pub code_header: Option<CodeHeader>,
+    /// Block types whose lines are never wrapped.
+    ///
+    /// When `width` is set, lines inside these blocks are clipped to the
+    /// width instead of being wrapped, e.g. to keep tables and code intact
+    /// while prose still wraps.
+    pub no_wrap_blocks: HashSet<BlockType>,
+    /// Resolver for embedded notes (`![[target]]`, requires wiki links)
+    pub embed_resolver: Option<EmbedResolver>,
+    /// Check of whether wiki link targets exist
+    pub wiki_resolver: Option<WikiResolver>,
+    /// Width of East Asian ambiguous-width characters
+    pub ambiguous_width: AmbiguousWidth,
+    /// Formatter for URLs shown when `theme.show_link_urls` is set
+    pub link_display: Option<LinkDisplay>,
+    /// Where URLs shown with `theme.show_link_urls` go
+    pub link_notes: LinkNotes,
+    /// Spell checker flagging words in normal text
+    pub spellchecker: Option<SpellChecker>,
+    /// Rewrite of text runs before they are styled
+    pub text_transform: Option<TextTransform>,
+    /// Slugifier for heading anchors; GitHub's scheme if unset
+    pub slugifier: Option<Slugifier>,
+    /// Extra URL prefixes recognized as autolinks (e.g. `"gemini://"`)
+    pub autolink_schemes: Vec<String>,
+    /// Indices of `<details>` blocks whose open state is flipped from the
+    /// document's (collapsed unless the tag has an `open` attribute)
+    pub toggled_details: HashSet<usize>,
+    /// Slugs of top-level headings whose sections are folded, hiding
+    /// everything up to the next heading of the same or a higher level
+    pub folded_headings: HashSet<String>,
+    /// Whether to record a [`RenderProfile`] in `RenderedMarkdown::profile`
+    pub profile: bool,
+    /// Whether to draw indent guides (`theme.indent_guide_char`) for nested
+    /// lists and quotes
+    pub indent_guides: bool,
+    /// How images are shown
+    pub image_mode: ImageMode,
+    /// Deepest heading level shown as a heading; deeper ones are handled
+    /// as `deep_headings` says and left out of `RenderedMarkdown::headings`
+    pub max_heading_depth: Option<u8>,
+    /// What happens to headings deeper than `max_heading_depth`
+    pub deep_headings: DeepHeadings,
+    /// Whether the whole text of checked task items gets
+    /// `theme.task_done_text`, not just the checkbox
+    pub style_done_tasks: bool,
+    /// Loader for images shown as rasters; without one, images are shown
+    /// as text
+    #[cfg(feature = "image")]
+    pub image_loader: Option<ImageLoader>,
 }
 
 impl RenderOptions {
@@ -54,8 +290,30 @@ impl RenderOptions {
                 .bg(Color::Yellow)
                 .fg(Color::Black)
                 .add_modifier(Modifier::BOLD),
+            search_scope: SearchScope::All,
             syntax_highlighting: true,
             syntax_theme: None,
+            code_header: None,
+            no_wrap_blocks: HashSet::new(),
+            embed_resolver: None,
+            wiki_resolver: None,
+            ambiguous_width: AmbiguousWidth::Narrow,
+            link_display: None,
+            link_notes: LinkNotes::Inline,
+            spellchecker: None,
+            text_transform: None,
+            slugifier: None,
+            autolink_schemes: Vec::new(),
+            toggled_details: HashSet::new(),
+            folded_headings: HashSet::new(),
+            profile: false,
+            indent_guides: false,
+            image_mode: ImageMode::Text,
+            max_heading_depth: None,
+            deep_headings: DeepHeadings::Demote,
+            style_done_tasks: false,
+            #[cfg(feature = "image")]
+            image_loader: None,
         }
     }
 
@@ -105,6 +363,12 @@ impl RenderOptions {
         self
     }
 
+    /// Restrict search to part of the document, e.g. code blocks.
+    pub fn with_search_scope(mut self, scope: SearchScope) -> Self {
+        self.search_scope = scope;
+        self
+    }
+
     /// Enable or disable syntax highlighting.
     pub fn with_syntax_highlighting(mut self, enabled: bool) -> Self {
         self.syntax_highlighting = enabled;
@@ -116,6 +380,245 @@ impl RenderOptions {
         self.syntax_theme = Some(theme.into());
         self
     }
+
+    /// Set the builder of the header line shown above code blocks.
+    ///
+    /// The builder gets the block's parsed info string and returns `None`
+    /// to show no header. By default, the header shows the `title`
+    /// attribute and the language, followed by the flags as badges, e.g.
+    /// `main.rs · rust: [no_run]`.
+    ///
+    /// ```
+    /// use ratatui::text::Line;
+    /// use ratatui_md::RenderOptions;
+    ///
+    /// let options = RenderOptions::new().with_code_header(|info| {
+    ///     info.title().map(|title| Line::from(format!("── {title} ──")))
+    /// });
+    /// ```
+    pub fn with_code_header(
+        mut self,
+        header: impl Fn(&CodeInfo) -> Option<Line<'static>> + Send + Sync + 'static,
+    ) -> Self {
+        self.code_header = Some(Hook(Arc::new(header)));
+        self
+    }
+
+    /// Set the resolver supplying content for embedded notes.
+    ///
+    /// Resolved embeds are rendered inline, framed with `theme.embed_border`.
+    pub fn with_embed_resolver(
+        mut self,
+        resolver: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.embed_resolver = Some(Hook(Arc::new(resolver)));
+        self
+    }
+
+    /// Set the check of whether wiki link targets exist.
+    ///
+    /// Links to missing targets are styled with `theme.wiki_link_missing`
+    /// and have `exists` cleared in [`RenderedMarkdown::links`], like
+    /// unresolved links in note-taking apps.
+    pub fn with_wiki_resolver(
+        mut self,
+        resolver: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.wiki_resolver = Some(Hook(Arc::new(resolver)));
+        self
+    }
+
+    /// Style the text of checked task items with `theme.task_done_text`,
+    /// so done items recede.
+    pub fn with_style_done_tasks(mut self, enabled: bool) -> Self {
+        self.style_done_tasks = enabled;
+        self
+    }
+
+    /// Show only headings up to level `depth` as headings, e.g. 3 to turn
+    /// `####` and deeper into bold paragraphs with [`DeepHeadings::Demote`].
+    pub fn with_max_heading_depth(mut self, depth: u8, deep_headings: DeepHeadings) -> Self {
+        self.max_heading_depth = Some(depth);
+        self.deep_headings = deep_headings;
+        self
+    }
+
+    /// Set how images are shown.
+    pub fn with_image_mode(mut self, mode: ImageMode) -> Self {
+        self.image_mode = mode;
+        self
+    }
+
+    /// Set the loader for images shown as rasters.
+    ///
+    /// The loader gets the image's `src` and returns `None` to show the
+    /// image as text instead, e.g. for remote URLs. Images are only ever
+    /// loaded through it, so rendering a document reads no files unless
+    /// asked to, as with `|src| image::open(src).ok()`.
+    #[cfg(feature = "image")]
+    pub fn with_image_loader(
+        mut self,
+        loader: impl Fn(&str) -> Option<image::DynamicImage> + Send + Sync + 'static,
+    ) -> Self {
+        self.image_loader = Some(Hook(Arc::new(loader)));
+        self
+    }
+
+    /// Set the width of East Asian ambiguous-width characters.
+    pub fn with_ambiguous_width(mut self, width: AmbiguousWidth) -> Self {
+        self.ambiguous_width = width;
+        self
+    }
+
+    /// Set how link URLs are displayed when `theme.show_link_urls` is set.
+    ///
+    /// The formatter gets the link and returns the text shown in parentheses
+    /// after it, e.g. to drop the scheme or shorten long URLs for narrow
+    /// panes. `RenderedMarkdown::links` still holds the full URL.
+    ///
+    /// ```
+    /// use ratatui_md::RenderOptions;
+    ///
+    /// let options = RenderOptions::new().with_link_display(|link| {
+    ///     let url = link.href.trim_start_matches("https://");
+    ///     match url.char_indices().nth(40) {
+    ///         Some((end, _)) => format!("{}…", &url[..end]),
+    ///         None => url.to_string(),
+    ///     }
+    /// });
+    /// ```
+    pub fn with_link_display(
+        mut self,
+        display: impl Fn(&LinkDetail) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.link_display = Some(Hook(Arc::new(display)));
+        self
+    }
+
+    /// Set where link URLs go when `theme.show_link_urls` is set.
+    ///
+    /// With [`LinkNotes::Sidenotes`], text wraps at `width` minus the margin
+    /// so the notes fit beside it on wide terminals:
+    ///
+    /// ```
+    /// use ratatui_md::{LinkNotes, RenderOptions};
+    ///
+    /// let options = RenderOptions::new()
+    ///     .with_width(120)
+    ///     .with_link_notes(LinkNotes::Sidenotes(30));
+    /// ```
+    pub fn with_link_notes(mut self, notes: LinkNotes) -> Self {
+        self.link_notes = notes;
+        self
+    }
+
+    /// Set a spell checker for normal text.
+    ///
+    /// The checker is called with each run of text (code, math and raw HTML
+    /// are skipped) and returns the byte ranges of misspelled words, which
+    /// are styled with `theme.misspelling`.
+    pub fn with_spellchecker(
+        mut self,
+        checker: impl Fn(&str) -> Vec<Range<usize>> + Send + Sync + 'static,
+    ) -> Self {
+        self.spellchecker = Some(Hook(Arc::new(checker)));
+        self
+    }
+
+    /// Set a transform applied to every run of text before it is styled.
+    ///
+    /// The transform gets the type of the run and its text, and returns
+    /// the text to show, e.g. to redact secrets or normalize quotes without
+    /// writing a [`ParserHandler`]. Return `Cow::Borrowed` to keep a run
+    /// as it is. Breaks are passed too, but what is returned for them is
+    /// ignored.
+    ///
+    /// ```
+    /// use md4c::TextType;
+    /// use ratatui_md::RenderOptions;
+    ///
+    /// let options = RenderOptions::new().with_text_transform(|text_type, text| {
+    ///     match text_type {
+    ///         TextType::Normal => text.replace(['“', '”'], "\"").into(),
+    ///         _ => text.into(),
+    ///     }
+    /// });
+    /// ```
+    pub fn with_text_transform(
+        mut self,
+        transform: impl Fn(TextType, &str) -> Cow<'_, str> + Send + Sync + 'static,
+    ) -> Self {
+        self.text_transform = Some(Hook(Arc::new(transform)));
+        self
+    }
+
+    /// Derive heading slugs with `slugifier` instead of GitHub's scheme.
+    ///
+    /// Share the slugifier with `md4c`'s `HtmlRenderer::with_heading_ids`
+    /// and `toc::extract_with` to get the same anchors in the terminal, in
+    /// HTML and in tables of contents. Repeated slugs get `-1`, `-2`, ...
+    /// appended either way.
+    ///
+    /// ```
+    /// use md4c::Slugify;
+    /// use ratatui_md::RenderOptions;
+    /// use std::sync::Arc;
+    ///
+    /// let slugifier: Arc<dyn Slugify> = Arc::new(|text: &str| text.to_lowercase());
+    /// let options = RenderOptions::new().with_slugifier(slugifier.clone());
+    /// let html = md4c::HtmlRenderer::new().with_heading_ids(slugifier);
+    /// ```
+    pub fn with_slugifier(mut self, slugifier: impl Slugify + 'static) -> Self {
+        self.slugifier = Some(Hook(Arc::new(slugifier)));
+        self
+    }
+
+    /// Recognize URLs with additional prefixes as autolinks.
+    ///
+    /// Prefixes include the separator, e.g. `"ssh://"` or `"magnet:"`. The
+    /// links are reported in `RenderedMarkdown::links` like any other.
+    pub fn with_autolink_schemes<S: Into<String>>(
+        mut self,
+        schemes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.autolink_schemes.extend(schemes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Flip the open state of the `<details>` block with the given index.
+    pub fn toggle_details(mut self, index: usize) -> Self {
+        if !self.toggled_details.remove(&index) {
+            self.toggled_details.insert(index);
+        }
+        self
+    }
+
+    /// Fold or unfold the section under the heading with the given slug.
+    pub fn toggle_fold(mut self, slug: impl Into<String>) -> Self {
+        let slug = slug.into();
+        if !self.folded_headings.remove(&slug) {
+            self.folded_headings.insert(slug);
+        }
+        self
+    }
+
+    /// Enable or disable indent guides for nested lists and quotes.
+    pub fn with_indent_guides(mut self, enabled: bool) -> Self {
+        self.indent_guides = enabled;
+        self
+    }
+
+    /// Enable or disable render profiling.
+    pub fn with_profile(mut self, enabled: bool) -> Self {
+        self.profile = enabled;
+        self
+    }
+
+    /// Disable wrapping for the given block types.
+    pub fn with_no_wrap_blocks(mut self, blocks: impl IntoIterator<Item = BlockType>) -> Self {
+        self.no_wrap_blocks.extend(blocks);
+        self
+    }
 }
 
 /// A rendered markdown document.
@@ -134,6 +637,42 @@ pub struct RenderedMarkdown<'a> {
     pub line_count: usize,
     /// Search match locations: (line_index, start_col, end_col)
     pub search_matches: Vec<SearchMatch>,
+    /// Tables found in the document, with their header row locations
+    pub tables: Vec<TableInfo>,
+    /// Line indices where a top-level block ends, in order; these are the
+    /// natural places to split the document
+    pub block_boundaries: Vec<usize>,
+    /// Visible `<details>` blocks, in document order
+    pub details: Vec<DetailsInfo>,
+    /// Word count and other figures for a status bar
+    pub stats: DocumentStats,
+    /// Timing report, if `RenderOptions::profile` is set
+    pub profile: Option<RenderProfile>,
+}
+
+impl RenderedMarkdown<'_> {
+    /// Links grouped by URL, in order of first appearance.
+    ///
+    /// Useful for link pickers, where a URL referenced many times should
+    /// be listed once.
+    pub fn links_grouped(&self) -> Vec<LinkGroup> {
+        let mut groups: Vec<LinkGroup> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for (i, link) in self.links.iter().enumerate() {
+            let group = *index.entry(&link.url).or_insert_with(|| {
+                groups.push(LinkGroup {
+                    url: link.url.clone(),
+                    text: link.text.clone(),
+                    links: Vec::new(),
+                    lines: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[group].links.push(i);
+            groups[group].lines.push(link.line);
+        }
+        groups
+    }
 }
 
 /// Information about a link in the rendered document.
@@ -147,6 +686,29 @@ pub struct LinkInfo {
     pub text: String,
     /// Whether this is an autolink
     pub is_autolink: bool,
+    /// Whether the target exists; false only for wiki links that
+    /// [`RenderOptions::with_wiki_resolver`] reports missing
+    pub exists: bool,
+}
+
+/// All links to one URL, see [`RenderedMarkdown::links_grouped`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkGroup {
+    /// URL or target shared by the links
+    pub url: String,
+    /// Display text of the first link
+    pub text: String,
+    /// Indices of the links in `RenderedMarkdown::links`
+    pub links: Vec<usize>,
+    /// Line index of each link, in the same order
+    pub lines: Vec<usize>,
+}
+
+impl LinkGroup {
+    /// Number of links to the URL.
+    pub fn count(&self) -> usize {
+        self.links.len()
+    }
 }
 
 /// Information about a heading in the rendered document.
@@ -158,6 +720,97 @@ pub struct HeadingInfo {
     pub level: u8,
     /// Heading text content
     pub text: String,
+    /// Display column where the heading starts, after any quote prefix
+    pub column: usize,
+    /// Display width of the heading on its line, including the `#` marker
+    pub width: usize,
+    /// Anchor slug, unique within the document (e.g. `my-heading-1`)
+    pub slug: String,
+    /// Whether the heading's section is folded
+    pub folded: bool,
+}
+
+/// Information about a table in the rendered document.
+///
+/// Line ranges are half-open and index into `RenderedMarkdown::text`.
+#[derive(Debug, Clone)]
+pub struct TableInfo {
+    /// Line index of the table's top border
+    pub start_line: usize,
+    /// Lines holding the header row(s)
+    pub header_lines: Range<usize>,
+    /// Line index of the header separator
+    pub separator_line: usize,
+    /// Line index of the first body row
+    pub body_start: usize,
+    /// Line index just past the table's bottom border
+    pub end_line: usize,
+    /// Number of columns
+    pub column_count: usize,
+    /// Full text of each cell by row, header row first, even where the
+    /// rendered table is clipped
+    pub cells: Vec<Vec<String>>,
+    /// Display columns of each column's content on a row line, before
+    /// clipping
+    pub columns: Vec<Range<usize>>,
+}
+
+impl TableInfo {
+    /// Whether the given line falls within the table's body rows.
+    pub fn body_contains(&self, line: usize) -> bool {
+        line >= self.body_start && line + 1 < self.end_line
+    }
+
+    /// Line index of a row in `cells`.
+    pub fn row_line(&self, row: usize) -> usize {
+        match row {
+            0 => self.header_lines.start,
+            _ => self.body_start + row - 1,
+        }
+    }
+}
+
+/// Information about a `<details>` block in the rendered document.
+#[derive(Debug, Clone)]
+pub struct DetailsInfo {
+    /// Index of the block in the document, stable across collapsing
+    pub index: usize,
+    /// Line index of the summary line
+    pub line: usize,
+    /// Summary text
+    pub summary: String,
+    /// Whether the content is shown
+    pub expanded: bool,
+}
+
+/// Figures about the whole document, gathered while rendering.
+///
+/// ```
+/// use ratatui_md::{render, RenderOptions, Theme};
+///
+/// let rendered = render("Hello *big* world", &Theme::default(), &RenderOptions::new());
+/// let stats = &rendered.stats;
+/// let status = format!("{} words · {} min read", stats.words, stats.reading_minutes());
+/// assert_eq!(status, "3 words · 1 min read");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// Words of prose, inline code and math, counted as by
+    /// [`md4c::stats::analyze`]; code blocks, raw HTML and image
+    /// descriptions are not counted
+    pub words: usize,
+    /// Number of code blocks
+    pub code_blocks: usize,
+    /// Time to read the words at [`md4c::stats::WORDS_PER_MINUTE`]
+    pub reading_time: Duration,
+}
+
+impl DocumentStats {
+    /// Reading time in whole minutes, rounded up; at least 1 if there are
+    /// any words.
+    pub fn reading_minutes(&self) -> u64 {
+        (self.reading_time.as_millis() as u64).div_ceil(60_000)
+    }
 }
 
 /// Information about a search match.
@@ -169,10 +822,89 @@ pub struct SearchMatch {
     pub start: usize,
     /// End column (character index)
     pub end: usize,
+    /// Start display column, counting wide characters as two cells
+    pub start_column: usize,
+    /// End display column
+    pub end_column: usize,
+}
+
+/// Parts of a document searched by [`RenderOptions::search_pattern`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    /// All text, code blocks included
+    #[default]
+    All,
+    /// Paragraphs, lists and quotes, but not headings or code blocks
+    Prose,
+    /// Headings only
+    Headings,
+    /// Code blocks only, optionally just those in one language
+    /// (compared case-insensitively with the fence's language)
+    Code(Option<String>),
+}
+
+impl SearchScope {
+    /// Whether a line of text (a heading or not) is searched.
+    fn includes_text(&self, heading: bool) -> bool {
+        match self {
+            SearchScope::All => true,
+            SearchScope::Prose => !heading,
+            SearchScope::Headings => heading,
+            SearchScope::Code(_) => false,
+        }
+    }
+
+    /// Whether a code block in `lang` is searched.
+    fn includes_code(&self, lang: &str) -> bool {
+        match self {
+            SearchScope::All => true,
+            SearchScope::Code(filter) => {
+                filter.as_ref().is_none_or(|l| l.eq_ignore_ascii_case(lang))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The start of `indent` that fits in `max_width` columns.
+fn fit_indent(
+    indent: &[RSpan<'static>],
+    max_width: usize,
+    widths: AmbiguousWidth,
+) -> Vec<RSpan<'static>> {
+    let mut fitted = Vec::new();
+    let mut width = 0;
+    for span in indent {
+        let span_width = widths.str_width(&span.content);
+        if width + span_width <= max_width {
+            fitted.push(span.clone());
+            width += span_width;
+            continue;
+        }
+        let mut content = String::new();
+        for c in span.content.chars() {
+            let char_width = widths.char_width(c).unwrap_or(1);
+            if width + char_width > max_width {
+                break;
+            }
+            width += char_width;
+            content.push(c);
+        }
+        if !content.is_empty() {
+            fitted.push(RSpan::styled(content, span.style));
+        }
+        break;
+    }
+    fitted
 }
 
 /// Word wrap a line of spans to fit within the given width.
-fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec<Line<'static>> {
+fn wrap_line(
+    spans: Vec<RSpan<'static>>,
+    max_width: usize,
+    indent: &[RSpan<'static>],
+    widths: AmbiguousWidth,
+) -> Vec<Line<'static>> {
     if max_width == 0 {
         return vec![Line::from(spans)];
     }
@@ -180,7 +912,11 @@ fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec
     let mut result = Vec::new();
     let mut current_line: Vec<RSpan<'static>> = Vec::new();
     let mut current_width = 0;
-    let indent_str = " ".repeat(indent);
+    // Deep nesting at a narrow width could leave no room after the indent,
+    // so keep at least one column for text
+    let indent = fit_indent(indent, max_width - 1, widths);
+    let indent = indent.as_slice();
+    let indent_width: usize = indent.iter().map(|s| widths.str_width(&s.content)).sum();
 
     for span in spans {
         let text = span.content.to_string();
@@ -188,7 +924,7 @@ fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec
         let mut remaining = text.as_str();
 
         while !remaining.is_empty() {
-            let span_width = remaining.width();
+            let span_width = widths.str_width(remaining);
 
             if current_width + span_width <= max_width {
                 // Fits on current line
@@ -204,8 +940,8 @@ fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec
                 if !current_line.is_empty() {
                     result.push(Line::from(std::mem::take(&mut current_line)));
                 }
-                current_line.push(RSpan::raw(indent_str.clone()));
-                current_width = indent;
+                current_line.extend_from_slice(indent);
+                current_width = indent_width;
                 continue;
             }
 
@@ -215,7 +951,7 @@ fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec
             let mut last_space = None;
 
             for (i, c) in remaining.char_indices() {
-                let char_width = c.width().unwrap_or(1);
+                let char_width = widths.char_width(c).unwrap_or(1);
                 if width_so_far + char_width > available {
                     break;
                 }
@@ -247,8 +983,8 @@ fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec
 
             // Start new line
             result.push(Line::from(std::mem::take(&mut current_line)));
-            current_line.push(RSpan::raw(indent_str.clone()));
-            current_width = indent;
+            current_line.extend_from_slice(indent);
+            current_width = indent_width;
 
             // Skip leading whitespace on new line
             remaining = after.trim_start();
@@ -266,25 +1002,78 @@ fn wrap_line(spans: Vec<RSpan<'static>>, max_width: usize, indent: usize) -> Vec
     result
 }
 
+/// Clip a line of spans to the given display width, marking the cut with
+/// `ellipsis`, which is one column wide.
+fn clip_line(
+    line: Line<'static>,
+    max_width: usize,
+    widths: AmbiguousWidth,
+    ellipsis: &'static str,
+) -> Line<'static> {
+    if max_width == 0 || widths.line_width(&line) <= max_width {
+        return line;
+    }
+
+    let mut spans = Vec::new();
+    let mut width = 0;
+    let budget = max_width - 1;
+
+    'outer: for span in line.spans {
+        let mut kept = String::new();
+        for c in span.content.chars() {
+            let char_width = widths.char_width(c).unwrap_or(0);
+            if width + char_width > budget {
+                if !kept.is_empty() {
+                    spans.push(RSpan::styled(kept, span.style));
+                }
+                spans.push(RSpan::styled(ellipsis, span.style));
+                break 'outer;
+            }
+            width += char_width;
+            kept.push(c);
+        }
+        spans.push(RSpan::styled(kept, span.style));
+    }
+
+    Line::from(spans)
+}
+
 /// Apply search highlighting to spans.
-fn highlight_search(spans: Vec<RSpan<'static>>, pattern: &str, style: Style) -> (Vec<RSpan<'static>>, Vec<(usize, usize)>) {
+fn highlight_search(
+    spans: Vec<RSpan<'static>>,
+    pattern: &str,
+    style: Style,
+    widths: AmbiguousWidth,
+) -> (Vec<RSpan<'static>>, Vec<SearchMatch>) {
+    // Compared grapheme by grapheme, each lowercased on its own, so a match
+    // never splits a grapheme and lowercasing that changes a character's
+    // length (like `İ`) can't shift the offsets into the original text.
+    let pattern: Vec<String> = pattern.graphemes(true).map(str::to_lowercase).collect();
     if pattern.is_empty() {
         return (spans, vec![]);
     }
 
-    let pattern_lower = pattern.to_lowercase();
     let mut result = Vec::new();
     let mut matches = Vec::new();
     let mut char_offset = 0;
+    let mut column_offset = 0;
 
     for span in spans {
-        let text = span.content.to_string();
-        let text_lower = text.to_lowercase();
+        let text = span.content.as_ref();
         let base_style = span.style;
+        let graphemes: Vec<(usize, &str)> = text.grapheme_indices(true).collect();
+        let folded: Vec<String> = graphemes.iter().map(|(_, g)| g.to_lowercase()).collect();
 
         let mut last_end = 0;
-        for (match_start, _) in text_lower.match_indices(&pattern_lower) {
-            let match_end = match_start + pattern.len();
+        let mut i = 0;
+        while i + pattern.len() <= graphemes.len() {
+            if folded[i..i + pattern.len()] != pattern[..] {
+                i += 1;
+                continue;
+            }
+            let match_start = graphemes[i].0;
+            i += pattern.len();
+            let match_end = graphemes.get(i).map_or(text.len(), |&(offset, _)| offset);
 
             // Add non-matching part before
             if match_start > last_end {
@@ -292,8 +1081,17 @@ fn highlight_search(spans: Vec<RSpan<'static>>, pattern: &str, style: Style) ->
             }
 
             // Add matching part with highlight
-            result.push(RSpan::styled(text[match_start..match_end].to_string(), style));
-            matches.push((char_offset + match_start, char_offset + match_end));
+            let (before, matched) = (&text[..match_start], &text[match_start..match_end]);
+            result.push(RSpan::styled(matched.to_string(), style));
+            let start = char_offset + before.chars().count();
+            let start_column = column_offset + widths.str_width(before);
+            matches.push(SearchMatch {
+                line: 0,
+                start,
+                end: start + matched.chars().count(),
+                start_column,
+                end_column: start_column + widths.str_width(matched),
+            });
 
             last_end = match_end;
         }
@@ -303,16 +1101,187 @@ fn highlight_search(spans: Vec<RSpan<'static>>, pattern: &str, style: Style) ->
             result.push(RSpan::styled(text[last_end..].to_string(), base_style));
         }
 
-        char_offset += text.len();
+        char_offset += text.chars().count();
+        column_offset += widths.str_width(text);
     }
 
     (result, matches)
 }
 
+/// Pieces of an HTML block relevant to `<details>` handling.
+#[derive(Debug, PartialEq)]
+enum DetailsToken {
+    Open { open: bool },
+    Summary(String),
+    Close,
+    Text(String),
+}
+
+/// Split an HTML block into `<details>` tokens.
+///
+/// Returns `None` if the block has no `<details>` or `</details>` tag, in
+/// which case it is rendered verbatim.
+fn details_tokens(html: &str) -> Option<Vec<DetailsToken>> {
+    let lower = html.to_ascii_lowercase();
+    let mut tokens = Vec::new();
+    let mut found = false;
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    let tag_end = |from: usize| lower[from..].find('>').map(|i| from + i + 1);
+    let is_tag = |at: usize, name: &str| {
+        lower[at..].starts_with(name)
+            && lower[at + name.len()..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace())
+    };
+
+    while let Some(i) = lower[pos..].find('<') {
+        let at = pos + i;
+        let (token, end) = if is_tag(at, "<details") {
+            let Some(end) = tag_end(at) else { break };
+            let attrs = &lower[at + "<details".len()..end - 1];
+            let open = attrs.split_ascii_whitespace().any(|a| a == "open" || a.starts_with("open="));
+            (DetailsToken::Open { open }, end)
+        } else if is_tag(at, "</details") {
+            let Some(end) = tag_end(at) else { break };
+            (DetailsToken::Close, end)
+        } else if is_tag(at, "<summary") {
+            let Some(start) = tag_end(at) else { break };
+            let close = lower[start..].find("</summary").map(|i| start + i).unwrap_or(lower.len());
+            let end = tag_end(close).unwrap_or(lower.len());
+            (DetailsToken::Summary(strip_tags(&html[start..close])), end)
+        } else {
+            pos = at + 1;
+            continue;
+        };
+
+        found |= matches!(token, DetailsToken::Open { .. } | DetailsToken::Close);
+        if !html[text_start..at].trim().is_empty() {
+            tokens.push(DetailsToken::Text(html[text_start..at].trim().to_string()));
+        }
+        tokens.push(token);
+        text_start = end;
+        pos = end;
+    }
+
+    if !html[text_start..].trim().is_empty() {
+        tokens.push(DetailsToken::Text(html[text_start..].trim().to_string()));
+    }
+    found.then_some(tokens)
+}
+
+/// Remove HTML tags and collapse whitespace.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Characters the renderer draws itself, rather than taking from the
+/// document or theme.
+struct Glyphs {
+    horizontal: &'static str,
+    vertical: &'static str,
+    // Table border corners and junctions: top, middle and bottom rows,
+    // each left, inner and right
+    borders: [[&'static str; 3]; 3],
+    ellipsis: &'static str,
+    expanded: char,
+    collapsed: char,
+    separator: &'static str,
+}
+
+const UNICODE_GLYPHS: Glyphs = Glyphs {
+    horizontal: "─",
+    vertical: "│",
+    borders: [["┌", "┬", "┐"], ["├", "┼", "┤"], ["└", "┴", "┘"]],
+    ellipsis: "…",
+    expanded: '▼',
+    collapsed: '▶',
+    separator: " · ",
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    horizontal: "-",
+    vertical: "|",
+    borders: [["+", "+", "+"], ["+", "+", "+"], ["+", "+", "+"]],
+    ellipsis: "~",
+    expanded: 'v',
+    collapsed: '>',
+    separator: " - ",
+};
+
+/// A `<details>` block whose closing tag has not been seen yet.
+struct OpenDetails {
+    expanded: bool,
+    // First line of the summary, and whether it took the list item's bullet
+    summary_line: usize,
+    bullet: bool,
+    // First line of the block's content
+    content_start: usize,
+}
+
+/// Text in Unicode superscript or subscript characters, where they exist.
+fn script_text(text: &str, superscript: bool) -> String {
+    const SUPER: [(char, char); 17] = [
+        ('0', '⁰'), ('1', '¹'), ('2', '²'), ('3', '³'), ('4', '⁴'), ('5', '⁵'), ('6', '⁶'),
+        ('7', '⁷'), ('8', '⁸'), ('9', '⁹'), ('+', '⁺'), ('-', '⁻'), ('=', '⁼'), ('(', '⁽'),
+        (')', '⁾'), ('n', 'ⁿ'), ('i', 'ⁱ'),
+    ];
+    const SUB: [(char, char); 28] = [
+        ('0', '₀'), ('1', '₁'), ('2', '₂'), ('3', '₃'), ('4', '₄'), ('5', '₅'), ('6', '₆'),
+        ('7', '₇'), ('8', '₈'), ('9', '₉'), ('+', '₊'), ('-', '₋'), ('=', '₌'), ('(', '₍'),
+        (')', '₎'), ('a', 'ₐ'), ('e', 'ₑ'), ('h', 'ₕ'), ('k', 'ₖ'), ('l', 'ₗ'), ('m', 'ₘ'),
+        ('n', 'ₙ'), ('o', 'ₒ'), ('p', 'ₚ'), ('s', 'ₛ'), ('t', 'ₜ'), ('x', 'ₓ'), ('i', 'ᵢ'),
+    ];
+    let table: &[(char, char)] = if superscript { &SUPER } else { &SUB };
+    text.chars()
+        .map(|c| table.iter().find(|(from, _)| *from == c).map_or(c, |&(_, to)| to))
+        .collect()
+}
+
+/// Total byte length of the spans' content.
+fn spans_len(spans: &[RSpan<'_>]) -> usize {
+    spans.iter().map(|s| s.content.len()).sum()
+}
+
+/// Index of the wrapped line holding byte `offset` of the text the lines
+/// were wrapped from.
+///
+/// Continuation lines start with `indent_len` bytes of indentation, and
+/// whitespace at each break is dropped by [`wrap_line`].
+fn wrapped_line_of(lines: &[Line<'_>], indent_len: usize, text: &str, offset: usize) -> usize {
+    let mut pos = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let mut len = spans_len(&line.spans);
+        if i > 0 {
+            len = len.saturating_sub(indent_len);
+            let rest = &text[pos.min(text.len())..];
+            pos += rest.len() - rest.trim_start().len();
+        }
+        pos += len;
+        if offset < pos {
+            return i;
+        }
+    }
+    lines.len().saturating_sub(1)
+}
+
 /// Internal state for the renderer.
 struct RendererState<'a> {
     theme: &'a Theme,
     options: &'a RenderOptions,
+    // Width text wraps at, less than `options.width` beside sidenotes
+    width: usize,
+    // Width of the sidenote margin, if the notes fit beside the text
+    sidenote_margin: Option<usize>,
     #[cfg(feature = "syntect")]
     highlighter: Option<SyntaxHighlighter>,
 
@@ -320,23 +1289,44 @@ struct RendererState<'a> {
     lines: Vec<Line<'static>>,
     current_spans: Vec<RSpan<'static>>,
     links: Vec<LinkInfo>,
+    // URLs of numbered link notes, with the first link to each
+    notes: Vec<(String, usize)>,
     headings: Vec<HeadingInfo>,
     search_matches: Vec<SearchMatch>,
+    tables: Vec<TableInfo>,
+    block_boundaries: Vec<usize>,
 
     // Style stack for nested formatting
     style_stack: Vec<Style>,
 
+    // Currently open blocks, outermost first
+    block_stack: Vec<BlockType>,
+
     // Block context
     in_heading: Option<u8>,
-    in_blockquote: bool,
+    // Open heading deeper than `max_heading_depth`, with the number of
+    // links before it
+    deep_heading: Option<usize>,
+    // Text of the open heading its slug is made from
+    heading_text: Option<String>,
+    quote_depth: usize,
     in_code_block: bool,
-    code_block_lang: String,
+    code_block_info: CodeInfo,
     code_block_content: String,
     in_list: bool,
     list_depth: usize,
     list_counters: Vec<u32>,
     list_is_ordered: Vec<bool>,
+    // Hanging indent of each open list level
+    list_widths: Vec<usize>,
+    // Last item number of each ordered list not yet entered, in reverse
+    // document order
+    ordered_list_ends: Vec<u32>,
     current_task_state: Option<TaskState>,
+    // Set when a list item's bullet has not been emitted yet
+    list_prefix_pending: bool,
+    // For each open list item, whether it pushed `task_done_text`
+    done_tasks: Vec<bool>,
 
     // Table state
     in_table: bool,
@@ -348,11 +1338,38 @@ struct RendererState<'a> {
     in_table_header: bool,
 
     // Link tracking
-    current_link: Option<LinkDetail>,
+    current_link: Option<LinkDetail<'static>>,
     current_link_text: String,
+    current_link_offset: usize,
+    // Inside a superscript (true) or subscript (false)
+    script: Option<bool>,
+    // Links on the current line as (index into `links`, byte offset), placed
+    // on their wrapped line once the line is finished
+    line_links: Vec<(usize, usize)>,
+
+    // Embedded notes
+    embed_depth: usize,
+    in_embed: bool,
+    // `in_embed` outside each open image and wiki link span
+    saved_embed: Vec<bool>,
+
+    // HTML block text, buffered to interpret `<details>`
+    html_block: Option<String>,
+    open_details: Vec<OpenDetails>,
+    details: Vec<DetailsInfo>,
+    stats: DocStats,
+    details_count: usize,
+
+    // Heading folds: slugs used so far, and the level and first hidden line
+    // of the open fold
+    slugger: Slugger,
+    open_fold: Option<(u8, usize)>,
 
     // Paragraph tracking
     pending_newline: bool,
+
+    // Timings, when profiling
+    profile: Option<RenderProfile>,
 }
 
 impl<'a> RendererState<'a> {
@@ -368,27 +1385,52 @@ impl<'a> RendererState<'a> {
             None
         };
 
+        let sidenote_margin = match options.link_notes {
+            LinkNotes::Sidenotes(margin)
+                if margin > 0 && options.width >= MIN_SIDENOTE_TEXT_WIDTH + SIDENOTE_GAP + margin =>
+            {
+                Some(margin)
+            }
+            _ => None,
+        };
+        let width = match sidenote_margin {
+            Some(margin) => options.width - margin - SIDENOTE_GAP,
+            None => options.width,
+        };
+
         Self {
             theme,
             options,
+            width,
+            sidenote_margin,
             #[cfg(feature = "syntect")]
             highlighter,
             lines: Vec::new(),
             current_spans: Vec::new(),
             links: Vec::new(),
+            notes: Vec::new(),
             headings: Vec::new(),
             search_matches: Vec::new(),
+            tables: Vec::new(),
+            block_boundaries: Vec::new(),
             style_stack: vec![theme.text],
+            block_stack: Vec::new(),
             in_heading: None,
-            in_blockquote: false,
+            deep_heading: None,
+            heading_text: None,
+            quote_depth: 0,
             in_code_block: false,
-            code_block_lang: String::new(),
+            code_block_info: CodeInfo::default(),
             code_block_content: String::new(),
             in_list: false,
             list_depth: 0,
             list_counters: Vec::new(),
             list_is_ordered: Vec::new(),
+            list_widths: Vec::new(),
+            ordered_list_ends: Vec::new(),
             current_task_state: None,
+            list_prefix_pending: false,
+            done_tasks: Vec::new(),
             in_table: false,
             table_columns: 0,
             table_alignments: Vec::new(),
@@ -398,7 +1440,21 @@ impl<'a> RendererState<'a> {
             in_table_header: false,
             current_link: None,
             current_link_text: String::new(),
+            current_link_offset: 0,
+            script: None,
+            line_links: Vec::new(),
+            embed_depth: 0,
+            in_embed: false,
+            saved_embed: Vec::new(),
+            html_block: None,
+            open_details: Vec::new(),
+            details: Vec::new(),
+            stats: DocStats::default(),
+            details_count: 0,
+            slugger: Slugger::new(),
+            open_fold: None,
             pending_newline: false,
+            profile: options.profile.then(RenderProfile::default),
         }
     }
 
@@ -418,11 +1474,48 @@ impl<'a> RendererState<'a> {
         }
     }
 
+    /// Width to clip lines to when inside a block excluded from wrapping.
+    fn clip_width(&self) -> Option<usize> {
+        if self.width == 0 || self.options.no_wrap_blocks.is_empty() {
+            return None;
+        }
+        self.block_stack
+            .iter()
+            .any(|b| self.options.no_wrap_blocks.contains(b))
+            .then_some(self.width)
+    }
+
+    fn glyphs(&self) -> &'static Glyphs {
+        if self.theme.ascii {
+            &ASCII_GLYPHS
+        } else {
+            &UNICODE_GLYPHS
+        }
+    }
+
+    /// Push finished lines of the current block, clipping them if needed.
+    fn push_block_lines(&mut self, lines: impl IntoIterator<Item = Line<'static>>) {
+        match self.clip_width() {
+            Some(width) => {
+                let (widths, ellipsis) = (self.options.ambiguous_width, self.glyphs().ellipsis);
+                self.lines.extend(
+                    lines.into_iter().map(|line| clip_line(line, width, widths, ellipsis)),
+                )
+            }
+            None => self.lines.extend(lines),
+        }
+    }
+
     fn push_text(&mut self, text: &str) {
         if text.is_empty() {
             return;
         }
 
+        // Text of a resolved embed is replaced by the embedded content
+        if self.in_embed {
+            return;
+        }
+
         // Handle code block content collection
         if self.in_code_block {
             self.code_block_content.push_str(text);
@@ -435,6 +1528,8 @@ impl<'a> RendererState<'a> {
             return;
         }
 
+        self.start_list_item_text();
+
         if self.current_link.is_some() {
             self.current_link_text.push_str(text);
         }
@@ -443,6 +1538,33 @@ impl<'a> RendererState<'a> {
             .push(RSpan::styled(text.to_string(), self.current_style()));
     }
 
+    /// Push normal text, styling words flagged by the spell checker.
+    fn push_checked_text(&mut self, text: &str) {
+        let Some(ref checker) = self.options.spellchecker else {
+            return self.push_text(text);
+        };
+        if self.in_code_block || self.in_embed {
+            return self.push_text(text);
+        }
+
+        let mut ranges = checker(text);
+        ranges.sort_by_key(|r| r.start);
+        let mut pos = 0;
+        for range in ranges {
+            let start = range.start.max(pos);
+            let end = range.end.min(text.len());
+            if start >= end || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+                continue;
+            }
+            self.push_text(&text[pos..start]);
+            self.push_style(self.theme.misspelling);
+            self.push_text(&text[start..end]);
+            self.pop_style();
+            pos = end;
+        }
+        self.push_text(&text[pos..]);
+    }
+
     fn finish_line(&mut self) {
         self.finish_line_with_wrap(0);
     }
@@ -453,16 +1575,15 @@ impl<'a> RendererState<'a> {
         }
 
         let mut spans = std::mem::take(&mut self.current_spans);
+        let line_links = std::mem::take(&mut self.line_links);
+        let base_line = self.lines.len();
 
         // Add blockquote prefix if needed
-        if self.in_blockquote && !spans.is_empty() {
-            spans.insert(
-                0,
-                RSpan::styled(
-                    self.theme.blockquote_prefix.to_string(),
-                    self.theme.blockquote_marker,
-                ),
-            );
+        let mut prefix_len = 0;
+        if !spans.is_empty() {
+            let prefix = self.quote_prefix();
+            prefix_len = spans_len(&prefix);
+            spans.splice(0..0, prefix);
         }
 
         if spans.is_empty() && !self.pending_newline {
@@ -470,23 +1591,46 @@ impl<'a> RendererState<'a> {
         }
 
         // Apply search highlighting
-        if let Some(ref pattern) = self.options.search_pattern {
+        if let Some(pattern) = self
+            .options
+            .search_pattern
+            .as_ref()
+            .filter(|_| self.options.search_scope.includes_text(self.in_heading.is_some()))
+        {
             let line_idx = self.lines.len();
-            let (highlighted_spans, matches) = highlight_search(spans, pattern, self.options.search_highlight_style);
+            let (highlighted_spans, matches) = highlight_search(
+                spans,
+                pattern,
+                self.options.search_highlight_style,
+                self.options.ambiguous_width,
+            );
             spans = highlighted_spans;
-            for (start, end) in matches {
-                self.search_matches.push(SearchMatch {
-                    line: line_idx,
-                    start,
-                    end,
-                });
-            }
+            self.search_matches
+                .extend(matches.into_iter().map(|m| SearchMatch { line: line_idx, ..m }));
         }
 
         // Apply word wrapping
-        if self.options.width > 0 && !spans.is_empty() {
-            let wrapped = wrap_line(spans, self.options.width, indent);
+        if let Some(width) = self.clip_width() {
+            let widths = self.options.ambiguous_width;
+            self.lines.push(clip_line(Line::from(spans), width, widths, self.glyphs().ellipsis));
+        } else if self.width > 0 && !spans.is_empty() {
+            let start = Instant::now();
+            // Continuation lines repeat the quote markers, and the indent
+            // counts against the width like any other text
+            let mut continuation = if prefix_len > 0 { self.quote_prefix() } else { Vec::new() };
+            continuation.extend(self.indent_spans(indent));
+            let indent = continuation;
+            let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+            let widths = self.options.ambiguous_width;
+            let wrapped = wrap_line(spans, self.width, &indent, widths);
+            for (link, offset) in line_links {
+                let line = wrapped_line_of(&wrapped, spans_len(&indent), &text, prefix_len + offset);
+                self.links[link].line = base_line + line;
+            }
             self.lines.extend(wrapped);
+            if let Some(ref mut profile) = self.profile {
+                profile.wrap += start.elapsed();
+            }
         } else {
             self.lines.push(Line::from(spans));
         }
@@ -494,20 +1638,121 @@ impl<'a> RendererState<'a> {
         self.pending_newline = false;
     }
 
+    /// Prefix spans for lines inside block quotes.
+    fn quote_prefix(&self) -> Vec<RSpan<'static>> {
+        let mut prefix = Vec::new();
+        if self.quote_depth == 0 {
+            return prefix;
+        }
+        if self.options.indent_guides && self.quote_depth > 1 {
+            let guides = format!("{} ", self.theme.indent_guide_char).repeat(self.quote_depth - 1);
+            prefix.push(RSpan::styled(guides, self.theme.indent_guide));
+        }
+        prefix.push(RSpan::styled(
+            self.theme.blockquote_prefix.to_string(),
+            self.theme.blockquote_marker,
+        ));
+        prefix
+    }
+
     fn add_blank_line(&mut self) {
         self.finish_line();
         self.lines.push(Line::from(vec![]));
     }
 
-    fn get_list_prefix(&mut self) -> String {
-        let indent = " ".repeat(self.list_depth.saturating_sub(1) * self.theme.list_indent);
-
-        if let Some(task_state) = self.current_task_state.take() {
+    /// Spans filling `width` columns of list indentation.
+    ///
+    /// With indent guides enabled, a guide is drawn in the bullet column of
+    /// each enclosing list level, so guides line up with the hanging indent.
+    fn indent_spans(&self, width: usize) -> Vec<RSpan<'static>> {
+        if width == 0 {
+            return Vec::new();
+        }
+        if !self.options.indent_guides || !self.in_list {
+            return vec![RSpan::raw(" ".repeat(width))];
+        }
+        let mut level_starts = Vec::with_capacity(self.list_widths.len());
+        let mut start = 0;
+        for &level_width in &self.list_widths {
+            level_starts.push(start);
+            start += level_width.max(1);
+        }
+        let guides: String = (0..width)
+            .map(|col| {
+                if level_starts.contains(&col) {
+                    self.theme.indent_guide_char
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        vec![RSpan::styled(guides, self.theme.indent_guide)]
+    }
+
+    /// Hanging indent of the innermost open list item.
+    fn list_indent(&self) -> usize {
+        self.list_widths.iter().sum()
+    }
+
+    /// Start a line of list item content.
+    ///
+    /// The first line of an item gets its bullet; later paragraphs are
+    /// indented to the item's hanging indent.
+    fn push_list_prefix(&mut self) {
+        if !self.in_list || self.list_depth == 0 {
+            return;
+        }
+        if !std::mem::take(&mut self.list_prefix_pending) {
+            let indent = self.indent_spans(self.list_indent());
+            self.current_spans.extend(indent);
+            return;
+        }
+
+        let prefix = self.get_list_prefix();
+        let style = if self.list_is_ordered.last().copied().unwrap_or(false) {
+            self.theme.list_number
+        } else {
+            self.theme.list_bullet
+        };
+        let indent = self.list_indent() - self.list_widths.last().copied().unwrap_or(0);
+        if self.options.indent_guides && indent > 0 {
+            let guides = self.indent_spans(indent);
+            self.current_spans.extend(guides);
+            self.current_spans
+                .push(RSpan::styled(prefix[indent..].to_string(), style));
+        } else {
+            self.current_spans.push(RSpan::styled(prefix, style));
+        }
+    }
+
+    /// Emit the bullet before the first text of a tight list item, which
+    /// has no paragraph block.
+    fn start_list_item_text(&mut self) {
+        if self.list_prefix_pending && self.current_spans.is_empty() {
+            self.push_list_prefix();
+        }
+    }
+
+    /// Finish text of a tight list item, wrapping at its hanging indent.
+    fn finish_list_item_line(&mut self) {
+        if self.in_list && !self.current_spans.is_empty() {
+            self.finish_line_with_wrap(self.list_indent());
+        }
+    }
+
+    fn get_list_prefix(&mut self) -> String {
+        let level_width = self.list_widths.last().copied().unwrap_or(0);
+        let indent = " ".repeat(self.list_indent() - level_width);
+
+        if let Some(task_state) = self.current_task_state.take() {
             let marker = match task_state {
                 TaskState::Checked => self.theme.task_checked_char,
                 TaskState::Unchecked => self.theme.task_unchecked_char,
                 TaskState::NotTask => self.theme.bullet_char,
             };
+            if self.theme.ascii {
+                return format!("{}[{}] ", indent, marker);
+            }
             return format!("{}{} ", indent, marker);
         }
 
@@ -518,15 +1763,17 @@ impl<'a> RendererState<'a> {
         let idx = self.list_depth - 1;
         if idx < self.list_is_ordered.len() && self.list_is_ordered[idx] {
             let num = self.list_counters.get(idx).copied().unwrap_or(1);
-            format!("{}{}. ", indent, num)
+            // Right-aligned in the room the list's widest number needs
+            let digits = level_width.saturating_sub(self.theme.list_indent) + 1;
+            format!("{}{:>digits$}. ", indent, num)
         } else {
             format!("{}{} ", indent, self.theme.bullet_char)
         }
     }
 
     fn render_horizontal_rule(&mut self) {
-        let width = if self.options.width > 0 {
-            self.options.width
+        let width = if self.width > 0 {
+            self.width
         } else {
             40
         };
@@ -535,40 +1782,370 @@ impl<'a> RendererState<'a> {
             .push(Line::from(vec![RSpan::styled(hr, self.theme.horizontal_rule)]));
     }
 
+    /// The default code block header: title and language, then flags.
+    fn code_header(&self) -> Option<Line<'static>> {
+        let info = &self.code_block_info;
+        let label: Vec<&str> = info
+            .title()
+            .into_iter()
+            .chain(Some(info.lang.as_str()))
+            .filter(|s| !s.is_empty())
+            .collect();
+        if label.is_empty() && info.flags.is_empty() {
+            return None;
+        }
+        let style = self.theme.code_block_info;
+        let label = label.join(self.glyphs().separator);
+        let mut spans = vec![RSpan::styled(format!("{}:", label), style)];
+        for flag in &info.flags {
+            spans.push(RSpan::styled(format!(" [{}]", flag), style));
+        }
+        Some(Line::from(spans))
+    }
+
     fn render_code_block(&mut self) {
+        if self.profile.is_none() {
+            self.render_code_block_lines();
+            return;
+        }
+        let timing = CodeBlockTiming {
+            line: self.lines.len(),
+            lang: self.code_block_info.lang.clone(),
+            line_count: self.code_block_content.lines().count(),
+            duration: Default::default(),
+        };
+
+        let start = Instant::now();
+        self.render_code_block_lines();
+        if let Some(ref mut profile) = self.profile {
+            profile.code_blocks.push(CodeBlockTiming {
+                duration: start.elapsed(),
+                ..timing
+            });
+        }
+    }
+
+    fn render_code_block_lines(&mut self) {
         let content = std::mem::take(&mut self.code_block_content);
-        #[allow(unused_variables)]
-        let lang = std::mem::take(&mut self.code_block_lang);
+        let info = std::mem::take(&mut self.code_block_info);
+        let lang = info.lang.as_str();
 
+        let mut lines: Vec<Line<'static>> = Vec::new();
         #[cfg(feature = "syntect")]
         if let Some(ref highlighter) = self.highlighter {
             if !lang.is_empty() {
-                let highlighted_lines = highlighter.highlight(&content, &lang);
-                self.lines.extend(highlighted_lines);
-                return;
+                lines = highlighter.highlight(&content, lang);
             }
         }
-
         // Fallback: render without highlighting
-        for line in content.lines() {
-            let mut spans = vec![RSpan::styled(line.to_string(), self.theme.code_block)];
-
-            // Apply search highlighting to code
-            if let Some(ref pattern) = self.options.search_pattern {
-                let line_idx = self.lines.len();
-                let (highlighted, matches) = highlight_search(spans, pattern, self.options.search_highlight_style);
-                spans = highlighted;
-                for (start, end) in matches {
-                    self.search_matches.push(SearchMatch {
-                        line: line_idx,
-                        start,
-                        end,
-                    });
+        if lines.is_empty() {
+            lines = content
+                .lines()
+                .map(|line| Line::from(RSpan::styled(line.to_string(), self.theme.code_block)))
+                .collect();
+        }
+
+        for range in info.highlight_lines() {
+            let end = range.end.min(lines.len());
+            for line in &mut lines[range.start.min(end)..end] {
+                line.style = line.style.patch(self.theme.code_line_highlight);
+                for span in &mut line.spans {
+                    span.style = span.style.patch(self.theme.code_line_highlight);
                 }
             }
+        }
+
+        // Apply search highlighting to code
+        if let Some(pattern) = self
+            .options
+            .search_pattern
+            .as_ref()
+            .filter(|_| self.options.search_scope.includes_code(lang))
+        {
+            let line_idx = self.lines.len();
+            for (i, line) in lines.iter_mut().enumerate() {
+                let spans = std::mem::take(&mut line.spans);
+                let (highlighted, matches) = highlight_search(
+                    spans,
+                    pattern,
+                    self.options.search_highlight_style,
+                    self.options.ambiguous_width,
+                );
+                line.spans = highlighted;
+                self.search_matches
+                    .extend(matches.into_iter().map(|m| SearchMatch { line: line_idx + i, ..m }));
+            }
+        }
+        self.push_block_lines(lines);
+    }
 
+    /// Render an embedded note inline, framed by a border.
+    ///
+    /// Returns false if the embed could not be resolved.
+    fn render_embed(&mut self, target: &str) -> bool {
+        if self.embed_depth >= MAX_EMBED_DEPTH {
+            return false;
+        }
+        let Some(content) = self.options.embed_resolver.as_ref().and_then(|r| r(target)) else {
+            return false;
+        };
+
+        let mut nested = RendererState::new(self.theme, self.options);
+        nested.embed_depth = self.embed_depth + 1;
+        // Notes are numbered across the whole document
+        nested.notes = std::mem::take(&mut self.notes);
+        let known_notes = nested.notes.len();
+        run_parser(&content, self.options, &mut nested);
+        nested.finish_line();
+        self.notes = std::mem::take(&mut nested.notes);
+        for (_, link) in &mut self.notes[known_notes..] {
+            *link += self.links.len();
+        }
+        while nested.lines.last().is_some_and(|l| l.width() == 0) {
+            nested.lines.pop();
+        }
+
+        self.finish_line();
+        let border = self.theme.embed_border;
+        let glyphs = self.glyphs();
+        let [top, _, bottom] = glyphs.borders.map(|row| row[0]);
+        let top = format!("{}{} {}", top, glyphs.horizontal, target);
+        self.lines.push(Line::from(vec![RSpan::styled(top, border)]));
+        let base = self.lines.len();
+        for line in nested.lines {
+            let mut spans = vec![RSpan::styled(format!("{} ", glyphs.vertical), border)];
+            spans.extend(line.spans);
             self.lines.push(Line::from(spans));
         }
+        let bottom = format!("{}{}", bottom, glyphs.horizontal);
+        self.lines.push(Line::from(vec![RSpan::styled(bottom, border)]));
+
+        self.links.extend(nested.links.into_iter().map(|mut link| {
+            link.line += base;
+            link
+        }));
+        true
+    }
+
+    /// Number of the note for a link URL, the same for repeated URLs.
+    ///
+    /// Called when the link was just added to `links`.
+    fn note_number(&mut self, url: String) -> usize {
+        if let Some(i) = self.notes.iter().position(|(known, _)| *known == url) {
+            return i + 1;
+        }
+        self.notes.push((url, self.links.len() - 1));
+        self.notes.len()
+    }
+
+    /// Add the link notes to the margin, or list them after the document.
+    fn place_notes(&mut self) {
+        let notes = std::mem::take(&mut self.notes);
+        if notes.is_empty() {
+            return;
+        }
+        let widths = self.options.ambiguous_width;
+        let Some(margin) = self.sidenote_margin else {
+            if self.lines.last().is_some_and(|l| l.width() > 0) {
+                self.lines.push(Line::default());
+            }
+            for (i, (url, _)) in notes.into_iter().enumerate() {
+                let note = RSpan::styled(format!("[{}] {}", i + 1, url), self.theme.link_url);
+                self.lines.extend(wrap_line(vec![note], self.width, &[], widths));
+            }
+            return;
+        };
+
+        // Each note starts beside its link, or below the previous note
+        let mut free = 0;
+        for (i, (url, link)) in notes.into_iter().enumerate() {
+            let note = RSpan::styled(format!("[{}] {}", i + 1, url), self.theme.link_url);
+            let mut line = self.links[link].line.max(free);
+            for part in wrap_line(vec![note], margin, &[], widths) {
+                // Skip lines too wide to leave the margin free, like tables
+                while self.lines.get(line).is_some_and(|l| widths.line_width(l) > self.width) {
+                    line += 1;
+                }
+                if line == self.lines.len() {
+                    self.lines.push(Line::default());
+                }
+                let target = &mut self.lines[line];
+                // A wide char in a narrow margin can overhang it
+                let pad = (self.width + SIDENOTE_GAP + margin)
+                    .saturating_sub(widths.line_width(target) + widths.line_width(&part));
+                target.spans.push(RSpan::raw(" ".repeat(pad)));
+                target.spans.extend(part.spans);
+                line += 1;
+            }
+            free = line;
+        }
+    }
+
+    /// Render an image as a halfblock raster on its own lines.
+    ///
+    /// Returns false if images are shown as text or this one could not be
+    /// loaded.
+    #[cfg(feature = "image")]
+    fn render_image(&mut self, src: &str) -> bool {
+        if self.options.image_mode != ImageMode::Halfblock || self.in_embed {
+            return false;
+        }
+        let Some(image) = self.options.image_loader.as_ref().and_then(|loader| loader(src)) else {
+            return false;
+        };
+
+        self.finish_line();
+        let prefix = self.quote_prefix();
+        let max_width = match self.width {
+            0 => DEFAULT_IMAGE_WIDTH,
+            width => {
+                let widths = self.options.ambiguous_width;
+                let indent: usize = prefix.iter().map(|s| widths.str_width(&s.content)).sum();
+                width.saturating_sub(indent).max(1)
+            }
+        };
+        let lines: Vec<Line<'static>> = crate::halfblock::image_lines(&image, max_width)
+            .into_iter()
+            .map(|line| {
+                let mut spans = prefix.clone();
+                spans.extend(line.spans);
+                Line::from(spans)
+            })
+            .collect();
+        self.push_block_lines(lines);
+        true
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn render_image(&mut self, _src: &str) -> bool {
+        false
+    }
+
+    /// Render a buffered HTML block, interpreting `<details>` tags.
+    fn render_html_block(&mut self, html: String) {
+        let Some(tokens) = details_tokens(&html) else {
+            for line in html.lines() {
+                self.push_html_line(RSpan::styled(line.to_string(), self.current_style()));
+            }
+            return;
+        };
+
+        for token in tokens {
+            match token {
+                DetailsToken::Open { open } => {
+                    let index = self.details_count;
+                    self.details_count += 1;
+                    let expanded = open != self.options.toggled_details.contains(&index);
+                    self.details.push(DetailsInfo {
+                        index,
+                        line: self.lines.len(),
+                        summary: "Details".to_string(),
+                        expanded,
+                    });
+                    let summary_line = self.lines.len();
+                    let bullet = self.list_prefix_pending;
+                    self.push_details_summary();
+                    self.open_details.push(OpenDetails {
+                        expanded,
+                        summary_line,
+                        bullet,
+                        content_start: self.lines.len(),
+                    });
+                }
+                DetailsToken::Summary(summary) => {
+                    // Only a summary directly after the opening tag names it
+                    let lines = self.lines.len();
+                    let Some(details) =
+                        self.open_details.last().filter(|d| d.content_start == lines)
+                    else {
+                        continue;
+                    };
+                    let start = details.summary_line;
+                    self.list_prefix_pending = details.bullet;
+                    self.lines.truncate(start);
+                    self.search_matches.retain(|m| m.line < start);
+                    if let Some(info) = self.details.last_mut() {
+                        info.summary = summary;
+                    }
+                    self.push_details_summary();
+                    if let Some(details) = self.open_details.last_mut() {
+                        details.content_start = self.lines.len();
+                    }
+                }
+                DetailsToken::Close => self.close_details(),
+                DetailsToken::Text(text) => {
+                    for line in text.lines() {
+                        self.push_html_line(RSpan::styled(line.to_string(), self.current_style()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Push the summary line of the most recently opened `<details>`.
+    fn push_details_summary(&mut self) {
+        let Some(info) = self.details.last() else {
+            return;
+        };
+        let glyphs = self.glyphs();
+        let marker = if info.expanded { glyphs.expanded } else { glyphs.collapsed };
+        let summary = format!("[{} {}]", marker, info.summary);
+        self.push_html_line(RSpan::styled(summary, self.theme.details_summary));
+    }
+
+    /// Push a line of an HTML block, inside any quotes and list items.
+    fn push_html_line(&mut self, span: RSpan<'static>) {
+        self.push_list_prefix();
+        self.current_spans.push(span);
+        let indent = if self.in_list { self.list_indent() } else { 0 };
+        self.finish_line_with_wrap(indent);
+    }
+
+    /// Close the innermost `<details>`, dropping its content if collapsed.
+    fn close_details(&mut self) {
+        let Some(details) = self.open_details.pop() else {
+            return;
+        };
+        if details.expanded {
+            return;
+        }
+
+        self.drop_lines_from(details.content_start);
+        if self.options.paragraph_space {
+            self.lines.push(Line::from(vec![]));
+        }
+    }
+
+    /// Remove lines from `start` on, along with everything recorded on them.
+    fn drop_lines_from(&mut self, start: usize) {
+        self.lines.truncate(start);
+        self.links.retain(|l| l.line < start);
+        self.headings.retain(|h| h.line < start);
+        self.search_matches.retain(|m| m.line < start);
+        self.tables.retain(|t| t.start_line < start);
+        self.block_boundaries.retain(|&line| line <= start);
+        self.details.retain(|d| d.line < start);
+    }
+
+    /// Close the open heading fold if a heading of `level` ends its section.
+    fn close_fold(&mut self, level: u8) {
+        if let Some((fold_level, start)) = self.open_fold {
+            if level <= fold_level {
+                self.open_fold = None;
+                self.drop_lines_from(start);
+            }
+        }
+    }
+
+    /// Unique slug for a heading, GitHub style unless the options have a
+    /// slugifier.
+    fn heading_slug(&mut self, text: &str) -> String {
+        let text = text.trim();
+        let slug = match &self.options.slugifier {
+            Some(slugifier) => slugifier.slugify(text),
+            None => SlugStyle::GitHub.slugify(text),
+        };
+        self.slugger.unique(slug)
     }
 
     fn render_table(&mut self) {
@@ -576,45 +2153,58 @@ impl<'a> RendererState<'a> {
             return;
         }
 
+        let widths = self.options.ambiguous_width;
         let mut col_widths: Vec<usize> = vec![0; self.table_columns];
         for row in &self.table_rows {
             for (i, cell) in row.iter().enumerate() {
                 if i < col_widths.len() {
-                    let cell_width: usize = cell.iter().map(|s| s.content.width()).sum();
+                    let cell_width: usize = cell.iter().map(|s| widths.str_width(&s.content)).sum();
                     col_widths[i] = col_widths[i].max(cell_width);
                 }
             }
         }
 
+        // Round columns up so borders of (possibly wide) `─` fit exactly
+        let glyphs = self.glyphs();
+        let glyph = widths.str_width(glyphs.horizontal).max(1);
         for w in &mut col_widths {
-            *w = (*w).max(3);
+            *w = ((*w).max(3) + 2).next_multiple_of(glyph) - 2;
         }
 
+        let start_line = self.lines.len();
+        let mut separator_line = start_line + 1;
+
+        // A border row of the given rules, between the row's corners
+        let border = |row: usize, rules: Vec<String>| {
+            let [left, inner, right] = glyphs.borders[row];
+            let line = format!("{}{}{}", left, rules.join(inner), right);
+            Line::from(vec![RSpan::styled(line, self.theme.table_border)])
+        };
+        let rules = || col_widths.iter().map(|w| widths.rule(glyphs.horizontal, *w + 2)).collect();
+
         // Top border
-        let top_border: String = col_widths
-            .iter()
-            .map(|w| "─".repeat(*w + 2))
-            .collect::<Vec<_>>()
-            .join("┬");
-        self.lines.push(Line::from(vec![RSpan::styled(
-            format!("┌{}┐", top_border),
-            self.theme.table_border,
-        )]));
+        self.lines.push(border(0, rules()));
+
+        let (left, inner) = (format!("{} ", glyphs.vertical), format!(" {} ", glyphs.vertical));
+        let mut columns = Vec::with_capacity(col_widths.len());
+        let mut x = widths.str_width(&left);
+        for w in &col_widths {
+            columns.push(x..x + w);
+            x += w + widths.str_width(&inner);
+        }
 
         // Rows
+        let mut cells = Vec::with_capacity(self.table_rows.len());
         for (row_idx, row) in self.table_rows.iter().enumerate() {
-            let mut line_spans = vec![RSpan::styled("│ ".to_string(), self.theme.table_border)];
+            let mut line_spans = vec![RSpan::styled(left.clone(), self.theme.table_border)];
+            let mut row_cells = Vec::with_capacity(row.len());
 
             for (col_idx, cell) in row.iter().enumerate() {
                 let cell_text: String = cell.iter().map(|s| s.content.to_string()).collect();
                 let width = col_widths.get(col_idx).copied().unwrap_or(3);
                 let align = self.table_alignments.get(col_idx).copied().unwrap_or(Alignment::Default);
 
-                let padded = match align {
-                    Alignment::Center => format!("{:^width$}", cell_text, width = width),
-                    Alignment::Right => format!("{:>width$}", cell_text, width = width),
-                    _ => format!("{:<width$}", cell_text, width = width),
-                };
+                let padded = widths.pad(&cell_text, width, align);
 
                 let style = if row_idx == 0 {
                     self.theme.table_header
@@ -623,43 +2213,52 @@ impl<'a> RendererState<'a> {
                 };
 
                 line_spans.push(RSpan::styled(padded, style));
-                line_spans.push(RSpan::styled(" │ ".to_string(), self.theme.table_border));
+                line_spans.push(RSpan::styled(inner.clone(), self.theme.table_border));
+                row_cells.push(cell_text);
             }
 
             self.lines.push(Line::from(line_spans));
+            cells.push(row_cells);
 
             if row_idx == 0 {
-                let sep: String = col_widths
+                let rule = |width| widths.rule(glyphs.horizontal, width);
+                let sep = col_widths
                     .iter()
                     .enumerate()
                     .map(|(i, w)| {
                         let align = self.table_alignments.get(i).copied().unwrap_or(Alignment::Default);
                         match align {
-                            Alignment::Left => format!(":{}─", "─".repeat(*w)),
-                            Alignment::Right => format!("{}─:", "─".repeat(*w)),
-                            Alignment::Center => format!(":{}:", "─".repeat(*w)),
-                            _ => "─".repeat(*w + 2),
+                            Alignment::Left => format!(":{}", rule(*w + 1)),
+                            Alignment::Right => format!("{}:", rule(*w + 1)),
+                            Alignment::Center => format!(":{}:", rule(*w)),
+                            _ => rule(*w + 2),
                         }
                     })
-                    .collect::<Vec<_>>()
-                    .join("┼");
-                self.lines.push(Line::from(vec![RSpan::styled(
-                    format!("├{}┤", sep),
-                    self.theme.table_border,
-                )]));
+                    .collect();
+                separator_line = self.lines.len();
+                self.lines.push(border(1, sep));
             }
         }
 
         // Bottom border
-        let bottom_border: String = col_widths
-            .iter()
-            .map(|w| "─".repeat(*w + 2))
-            .collect::<Vec<_>>()
-            .join("┴");
-        self.lines.push(Line::from(vec![RSpan::styled(
-            format!("└{}┘", bottom_border),
-            self.theme.table_border,
-        )]));
+        self.lines.push(border(2, rules()));
+
+        if let Some(width) = self.clip_width() {
+            for line in &mut self.lines[start_line..] {
+                *line = clip_line(std::mem::take(line), width, widths, glyphs.ellipsis);
+            }
+        }
+
+        self.tables.push(TableInfo {
+            start_line,
+            header_lines: start_line + 1..separator_line,
+            separator_line,
+            body_start: separator_line + 1,
+            end_line: self.lines.len(),
+            column_count: self.table_columns,
+            cells,
+            columns,
+        });
 
         self.table_rows.clear();
         self.table_columns = 0;
@@ -669,25 +2268,29 @@ impl<'a> RendererState<'a> {
 
 impl ParserHandler for RendererState<'_> {
     fn enter_block(&mut self, block: Block) -> bool {
+        self.block_stack.push(block.block_type());
         match block {
-            Block::Document => {}
+            Block::Document | Block::Unknown(_) => {}
 
             Block::Paragraph => {
-                if self.in_list && self.list_depth > 0 {
-                    let prefix = self.get_list_prefix();
-                    if !prefix.is_empty() {
-                        let style = if self.list_is_ordered.last().copied().unwrap_or(false) {
-                            self.theme.list_number
-                        } else {
-                            self.theme.list_bullet
-                        };
-                        self.current_spans.push(RSpan::styled(prefix, style));
-                    }
-                }
+                self.push_list_prefix();
+            }
+
+            Block::Heading(HeadingDetail { level })
+                if self.options.max_heading_depth.is_some_and(|depth| level > depth) =>
+            {
+                self.deep_heading = Some(self.links.len());
+                self.heading_text = Some(String::new());
+                self.push_style(self.theme.strong);
             }
 
             Block::Heading(HeadingDetail { level }) => {
+                // Only headings at the top level of the document fold
+                if self.block_stack.len() == 2 {
+                    self.close_fold(level);
+                }
                 self.in_heading = Some(level);
+                self.heading_text = Some(String::new());
                 self.push_style(self.theme.heading_style(level));
 
                 let prefix = "#".repeat(level as usize);
@@ -698,43 +2301,70 @@ impl ParserHandler for RendererState<'_> {
             }
 
             Block::Quote => {
-                self.in_blockquote = true;
+                self.quote_depth += 1;
+                self.push_style(self.theme.blockquote);
+            }
+
+            // Shown as a quote under a title line
+            Block::Admonition(kind) => {
+                self.quote_depth += 1;
                 self.push_style(self.theme.blockquote);
+                self.current_spans
+                    .push(RSpan::styled(kind.title(), self.theme.admonition_title));
+                self.finish_line();
             }
 
-            Block::Code(CodeBlockDetail { lang, .. }) => {
+            Block::Code(CodeBlockDetail { info, .. }) => {
                 self.in_code_block = true;
-                self.code_block_lang = lang.clone();
+                self.code_block_info = CodeInfo::parse(&info.decoded());
                 self.code_block_content.clear();
 
-                if !lang.is_empty() {
-                    self.lines.push(Line::from(vec![RSpan::styled(
-                        format!("{}:", lang),
-                        self.theme.code_block_info,
-                    )]));
-                }
+                let header = match self.options.code_header {
+                    Some(ref header) => header(&self.code_block_info),
+                    None => self.code_header(),
+                };
+                self.lines.extend(header);
 
                 self.push_style(self.theme.code_block);
             }
 
             Block::UnorderedList(UnorderedListDetail { .. }) => {
+                self.finish_list_item_line();
                 self.in_list = true;
                 self.list_depth += 1;
                 self.list_is_ordered.push(false);
                 self.list_counters.push(1);
+                self.list_widths.push(self.theme.list_indent);
             }
 
             Block::OrderedList(OrderedListDetail { start, .. }) => {
+                self.finish_list_item_line();
                 self.in_list = true;
                 self.list_depth += 1;
                 self.list_is_ordered.push(true);
                 self.list_counters.push(start);
+                // Longer numbers widen the hanging indent of the whole list
+                let end = self.ordered_list_ends.pop().unwrap_or(start).max(start);
+                let digits = end.to_string().len();
+                let mut level_width = self.theme.list_indent + digits - 1;
+                if self.width > 0 {
+                    // but no further than half the line, so text keeps room
+                    let room = (self.width / 2).saturating_sub(self.list_indent());
+                    level_width = level_width.min(room.max(1));
+                }
+                self.list_widths.push(level_width);
             }
 
-            Block::ListItem(ListItemDetail { task_state }) => {
+            Block::ListItem(ListItemDetail { task_state, .. }) => {
+                self.list_prefix_pending = true;
                 if task_state != TaskState::NotTask {
                     self.current_task_state = Some(task_state);
                 }
+                let done = self.options.style_done_tasks && task_state == TaskState::Checked;
+                if done {
+                    self.push_style(self.theme.task_done_text);
+                }
+                self.done_tasks.push(done);
             }
 
             Block::HorizontalRule => {
@@ -743,6 +2373,7 @@ impl ParserHandler for RendererState<'_> {
 
             Block::Html => {
                 self.push_style(self.theme.raw_html);
+                self.html_block = Some(String::new());
             }
 
             Block::Table(TableDetail { column_count, .. }) => {
@@ -776,23 +2407,69 @@ impl ParserHandler for RendererState<'_> {
 
     fn leave_block(&mut self, block_type: BlockType) -> bool {
         match block_type {
-            BlockType::Document => {}
+            BlockType::Document => self.close_fold(1),
 
             BlockType::Paragraph => {
-                let indent = if self.in_list { self.list_depth * self.theme.list_indent } else { 0 };
+                let indent = if self.in_list { self.list_indent() } else { 0 };
                 self.finish_line_with_wrap(indent);
                 if self.options.paragraph_space && !self.in_list {
                     self.add_blank_line();
                 }
             }
 
+            BlockType::Heading if self.deep_heading.is_some() => {
+                let links = self.deep_heading.take().unwrap_or_default();
+                // Not an anchor here, but its slug is taken in HTML
+                let text = self.heading_text.take().unwrap_or_default();
+                self.heading_slug(&text);
+                self.pop_style();
+                if self.options.deep_headings == DeepHeadings::Hide {
+                    self.current_spans.clear();
+                    self.line_links.clear();
+                    self.links.truncate(links);
+                } else {
+                    self.finish_line();
+                    if self.options.heading_space {
+                        self.add_blank_line();
+                    }
+                }
+            }
+
             BlockType::Heading => {
-                if let Some(level) = self.in_heading.take() {
+                let mut fold = None;
+                if let Some(level) = self.in_heading {
                     let text: String = self.current_spans.iter().map(|s| s.content.to_string()).collect();
+                    let text = text.trim_start_matches(['#', ' ']).to_string();
+                    let slug_text = self.heading_text.take().unwrap_or_default();
+                    let slug = self.heading_slug(&slug_text);
+                    let folded = self.block_stack.len() == 2
+                        && self.open_fold.is_none()
+                        && self.options.folded_headings.contains(&slug);
+                    if folded {
+                        let marker = format!(" {}", self.glyphs().ellipsis);
+                        let style = self.theme.heading_style(level);
+                        self.current_spans.push(RSpan::styled(marker, style));
+                        fold = Some(level);
+                    }
+                    let line = self.lines.len();
+                    let widths = self.options.ambiguous_width;
+                    let column: usize =
+                        self.quote_prefix().iter().map(|s| widths.str_width(&s.content)).sum();
+                    self.finish_line();
+                    self.in_heading = None;
+                    let width = self
+                        .lines
+                        .get(line)
+                        .map(|l| widths.line_width(l).saturating_sub(column))
+                        .unwrap_or(0);
                     self.headings.push(HeadingInfo {
-                        line: self.lines.len(),
+                        line,
                         level,
-                        text: text.trim_start_matches(['#', ' ']).to_string(),
+                        text,
+                        column,
+                        width,
+                        slug,
+                        folded,
                     });
                 }
                 self.finish_line();
@@ -800,11 +2477,14 @@ impl ParserHandler for RendererState<'_> {
                 if self.options.heading_space {
                     self.add_blank_line();
                 }
+                if let Some(level) = fold {
+                    self.open_fold = Some((level, self.lines.len()));
+                }
             }
 
-            BlockType::Quote => {
+            BlockType::Quote | BlockType::Admonition => {
                 self.finish_line();
-                self.in_blockquote = false;
+                self.quote_depth -= 1;
                 self.pop_style();
                 self.add_blank_line();
             }
@@ -822,6 +2502,7 @@ impl ParserHandler for RendererState<'_> {
                 self.list_depth = self.list_depth.saturating_sub(1);
                 self.list_is_ordered.pop();
                 self.list_counters.pop();
+                self.list_widths.pop();
                 if self.list_depth == 0 {
                     self.in_list = false;
                     if self.options.list_space {
@@ -831,7 +2512,14 @@ impl ParserHandler for RendererState<'_> {
             }
 
             BlockType::ListItem => {
-                self.finish_line();
+                // Empty items still get their bullet
+                if self.list_prefix_pending {
+                    self.push_list_prefix();
+                }
+                self.finish_list_item_line();
+                if self.done_tasks.pop() == Some(true) {
+                    self.pop_style();
+                }
                 if let Some(counter) = self.list_counters.last_mut() {
                     *counter += 1;
                 }
@@ -843,6 +2531,9 @@ impl ParserHandler for RendererState<'_> {
 
             BlockType::Html => {
                 self.finish_line();
+                if let Some(html) = self.html_block.take() {
+                    self.render_html_block(html);
+                }
                 self.pop_style();
             }
 
@@ -864,11 +2555,17 @@ impl ParserHandler for RendererState<'_> {
 
             _ => {}
         }
+        if self.block_stack.len() == 2 && self.block_boundaries.last() != Some(&self.lines.len()) {
+            self.block_boundaries.push(self.lines.len());
+        }
+        self.block_stack.pop();
         true
     }
 
     fn enter_span(&mut self, span: Span) -> bool {
         match span {
+            // Keeps the style stack balanced for `leave_span`
+            Span::Unknown(_) => self.push_style(Style::default()),
             Span::Emphasis => {
                 self.push_style(self.theme.emphasis);
             }
@@ -881,19 +2578,39 @@ impl ParserHandler for RendererState<'_> {
             Span::Underline => {
                 self.push_style(self.theme.underline);
             }
+            Span::Mark => {
+                self.push_style(self.theme.mark);
+            }
+            Span::Superscript | Span::Subscript => {
+                let superscript = matches!(span, Span::Superscript);
+                // Without Unicode scripts, marked like LaTeX
+                if self.theme.ascii {
+                    self.push_checked_text(if superscript { "^" } else { "_" });
+                }
+                self.script = Some(superscript);
+            }
             Span::Code => {
                 self.push_style(self.theme.code_inline);
             }
             Span::Link(detail) => {
-                self.current_link = Some(detail);
+                self.start_list_item_text();
+                self.current_link = Some(detail.into_owned());
                 self.current_link_text.clear();
+                self.current_link_offset = spans_len(&self.current_spans);
                 self.push_style(self.theme.link);
             }
             Span::Image(ImageDetail { src, title }) => {
+                let (src, title) = (src.decoded(), title.decoded());
                 self.push_style(self.theme.image);
+                self.saved_embed.push(self.in_embed);
+                if self.render_image(&src) {
+                    // Like an embed's, the alt text is not shown
+                    self.in_embed = true;
+                    return true;
+                }
                 let alt_text = if title.is_empty() { "image" } else { &title };
                 self.push_text(&format!("[{}]", alt_text));
-                if !src.is_empty() {
+                if !src.is_empty() && !self.in_embed {
                     self.current_spans
                         .push(RSpan::styled(format!("({})", src), self.theme.link_url));
                 }
@@ -901,13 +2618,25 @@ impl ParserHandler for RendererState<'_> {
             Span::LatexMath | Span::LatexMathDisplay => {
                 self.push_style(self.theme.latex_math);
             }
-            Span::WikiLink(WikiLinkDetail { target }) => {
-                self.push_style(self.theme.wiki_link);
+            Span::WikiLink(WikiLinkDetail { target, label, is_embed }) => {
+                let target = target.into_decoded();
+                let exists = self.options.wiki_resolver.as_ref().is_none_or(|r| r(&target));
+                self.push_style(if exists {
+                    self.theme.wiki_link
+                } else {
+                    self.theme.wiki_link_missing
+                });
+                self.saved_embed.push(self.in_embed);
+                if is_embed && self.render_embed(&target) {
+                    self.in_embed = true;
+                    return true;
+                }
                 self.links.push(LinkInfo {
                     line: self.lines.len(),
-                    url: target.clone(),
-                    text: target,
+                    text: label.unwrap_or_else(|| target.clone()).into_owned(),
+                    url: target.into_owned(),
                     is_autolink: false,
+                    exists,
                 });
             }
         }
@@ -920,31 +2649,48 @@ impl ParserHandler for RendererState<'_> {
                 if let Some(detail) = self.current_link.take() {
                     self.links.push(LinkInfo {
                         line: self.lines.len(),
-                        url: detail.href.clone(),
+                        url: detail.href.decoded().into_owned(),
                         text: std::mem::take(&mut self.current_link_text),
                         is_autolink: detail.is_autolink,
+                        exists: true,
                     });
+                    if !self.in_table && !self.in_code_block {
+                        self.line_links.push((self.links.len() - 1, self.current_link_offset));
+                    }
 
                     if self.theme.show_link_urls && !detail.href.is_empty() {
                         self.pop_style();
-                        self.current_spans
-                            .push(RSpan::styled(format!(" ({})", detail.href), self.theme.link_url));
+                        let url = match self.options.link_display {
+                            Some(ref display) => display(&detail),
+                            None => detail.href.decoded().into_owned(),
+                        };
+                        if !url.is_empty() {
+                            let note = match self.options.link_notes {
+                                LinkNotes::Inline => format!(" ({})", url),
+                                _ => format!("[{}]", self.note_number(url)),
+                            };
+                            self.current_spans.push(RSpan::styled(note, self.theme.link_url));
+                        }
                         return true;
                     }
                 }
                 self.pop_style();
             }
-            SpanType::Image => {
+            SpanType::Image | SpanType::WikiLink => {
+                self.in_embed = self.saved_embed.pop().unwrap_or(false);
                 self.pop_style();
             }
+            SpanType::Superscript | SpanType::Subscript => {
+                self.script = None;
+            }
             SpanType::Emphasis
             | SpanType::Strong
             | SpanType::Strikethrough
             | SpanType::Underline
+            | SpanType::Mark
             | SpanType::Code
             | SpanType::LatexMath
-            | SpanType::LatexMathDisplay
-            | SpanType::WikiLink => {
+            | SpanType::LatexMathDisplay => {
                 self.pop_style();
             }
             _ => {
@@ -955,8 +2701,26 @@ impl ParserHandler for RendererState<'_> {
     }
 
     fn text(&mut self, text_type: TextType, text: &str) -> bool {
+        if let Some(ref mut heading) = self.heading_text {
+            push_heading_text(heading, text_type, text);
+        }
+        let options = self.options;
+        let transformed;
+        let text = match options.text_transform {
+            Some(ref transform) => {
+                transformed = transform(text_type, text);
+                transformed.as_ref()
+            }
+            None => text,
+        };
         match text_type {
-            TextType::Normal | TextType::Code | TextType::LatexMath => {
+            TextType::Normal => match self.script {
+                Some(superscript) if !self.theme.ascii => {
+                    self.push_checked_text(&script_text(text, superscript))
+                }
+                _ => self.push_checked_text(text),
+            },
+            TextType::Code | TextType::LatexMath => {
                 self.push_text(text);
             }
             TextType::HardBreak => {
@@ -965,8 +2729,8 @@ impl ParserHandler for RendererState<'_> {
                 } else {
                     self.finish_line();
                     if self.in_list && self.list_depth > 0 {
-                        let indent = " ".repeat(self.list_depth * self.theme.list_indent);
-                        self.current_spans.push(RSpan::raw(indent));
+                        let indent = self.indent_spans(self.list_indent());
+                        self.current_spans.extend(indent);
                     }
                 }
             }
@@ -978,10 +2742,17 @@ impl ParserHandler for RendererState<'_> {
                 }
             }
             TextType::Entity => {
+                self.start_list_item_text();
                 self.current_spans
                     .push(RSpan::styled(text.to_string(), self.theme.html_entity));
             }
+            TextType::Html if self.html_block.is_some() => {
+                if let Some(ref mut html) = self.html_block {
+                    html.push_str(text);
+                }
+            }
             TextType::Html => {
+                self.start_list_item_text();
                 self.current_spans
                     .push(RSpan::styled(text.to_string(), self.theme.raw_html));
             }
@@ -1019,13 +2790,32 @@ pub fn render<'a>(
     theme: &Theme,
     options: &RenderOptions,
 ) -> RenderedMarkdown<'a> {
+    let start = Instant::now();
+    let allocations = allocation_count();
     let mut state = RendererState::new(theme, options);
 
-    let _ = parse(markdown, options.parser_flags, &mut state);
+    run_parser(markdown, options, &mut state);
 
     state.finish_line();
+    state.place_notes();
 
     let line_count = state.lines.len();
+    let stats = DocumentStats {
+        words: state.stats.words,
+        code_blocks: state.stats.code_blocks,
+        reading_time: state.stats.reading_time(),
+    };
+    let profile = state.profile.take().map(|mut profile| {
+        profile.total = start.elapsed();
+        profile.parse = profile
+            .total
+            .saturating_sub(profile.wrap)
+            .saturating_sub(profile.highlight());
+        profile.allocations = allocations
+            .zip(allocation_count())
+            .map(|(before, after)| after - before);
+        profile
+    });
 
     RenderedMarkdown {
         text: Text::from(state.lines),
@@ -1033,9 +2823,176 @@ pub fn render<'a>(
         headings: state.headings,
         line_count,
         search_matches: state.search_matches,
+        tables: state.tables,
+        block_boundaries: state.block_boundaries,
+        details: state.details,
+        stats,
+        profile,
+    }
+}
+
+/// Parse markdown into the renderer state, applying extra autolinks.
+fn run_parser(markdown: &str, options: &RenderOptions, state: &mut RendererState) {
+    // Ordered lists are sized before rendering, which needs a first pass
+    // when the input has something that could be a list number
+    let bytes = markdown.as_bytes();
+    if bytes.windows(2).any(|w| w[0].is_ascii_digit() && matches!(w[1], b'.' | b')')) {
+        let mut ends = OrderedListEnds::default();
+        let _ = parse(markdown, options.parser_flags, &mut ends);
+        state.ordered_list_ends = ends.ends;
+        state.ordered_list_ends.reverse();
+    }
+    // Counted alongside, the same way as by `md4c::stats::analyze`
+    let mut counter = StatsCounter::new();
+    if options.autolink_schemes.is_empty() {
+        let _ = parse(markdown, options.parser_flags, &mut Tee(&mut *state, &mut counter));
+    } else {
+        let handler = ExtraAutolinks::new(&mut *state, options.autolink_schemes.iter().cloned());
+        let _ = parse(markdown, options.parser_flags, &mut Tee(handler, &mut counter));
+    }
+    state.stats = counter.finish();
+}
+
+/// Finds the number of the last item of each ordered list.
+#[derive(Default)]
+struct OrderedListEnds {
+    ends: Vec<u32>,
+    // Index into `ends` of each open list, `None` for unordered lists
+    open: Vec<Option<usize>>,
+}
+
+impl ParserHandler for OrderedListEnds {
+    fn enter_block(&mut self, block: Block) -> bool {
+        match block {
+            Block::OrderedList(OrderedListDetail { start, .. }) => {
+                self.open.push(Some(self.ends.len()));
+                self.ends.push(start);
+            }
+            Block::UnorderedList(_) => self.open.push(None),
+            Block::ListItem(_) => {
+                if let Some(&Some(index)) = self.open.last() {
+                    self.ends[index] += 1;
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn leave_block(&mut self, block_type: BlockType) -> bool {
+        if matches!(block_type, BlockType::OrderedList | BlockType::UnorderedList) {
+            // Each item counted one past its number, and lists have items
+            if let Some(Some(index)) = self.open.pop() {
+                self.ends[index] -= 1;
+            }
+        }
+        true
+    }
+}
+
+/// Layout measurements for a markdown document.
+///
+/// Produced by [`measure`], this records the plain text and display width
+/// of each rendered line, so pane sizes can be computed for any width
+/// without keeping the styled lines around. It holds about as much text as
+/// the rendered document.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutInfo {
+    /// Total line count, before any wrapping by the widget
+    pub line_count: usize,
+    /// Display width of each rendered line
+    pub line_widths: Vec<usize>,
+    lines: Vec<String>,
+    widths: AmbiguousWidth,
+}
+
+impl LayoutInfo {
+    /// Height in rows when displayed in a wrapping pane of the given width.
+    ///
+    /// Lines are word wrapped with the routine the renderer uses for
+    /// [`RenderOptions::width`], which breaks at the same places as a
+    /// wrapping ratatui `Paragraph` for ordinary prose. A width of 0
+    /// disables wrapping.
+    pub fn height_for_width(&self, width: usize) -> usize {
+        if width == 0 {
+            return self.line_count;
+        }
+        self.lines
+            .iter()
+            .zip(&self.line_widths)
+            .map(|(line, &line_width)| {
+                if line_width <= width {
+                    1
+                } else {
+                    wrap_line(vec![RSpan::raw(line.clone())], width, &[], self.widths).len()
+                }
+            })
+            .sum()
+    }
+
+    /// Width of the widest line.
+    pub fn max_width(&self) -> usize {
+        self.line_widths.iter().copied().max().unwrap_or(0)
     }
 }
 
+/// Measure the layout of markdown, keeping only the text of each line.
+///
+/// This renders the whole document, skipping only search and syntax
+/// highlighting since they do not affect line widths, so it costs about as
+/// much as [`render`]. Use it to keep the layout of a document that isn't
+/// displayed; the styles are dropped once the lines are measured.
+///
+/// # Example
+///
+/// ```
+/// use ratatui_md::{measure, Theme, RenderOptions};
+///
+/// let layout = measure("# Title\n\nSome text.", &Theme::default(), &RenderOptions::default());
+/// assert!(layout.height_for_width(80) >= 2);
+/// ```
+pub fn measure(markdown: &str, theme: &Theme, options: &RenderOptions) -> LayoutInfo {
+    let options = RenderOptions {
+        search_pattern: None,
+        syntax_highlighting: false,
+        ..options.clone()
+    };
+    let mut state = RendererState::new(theme, &options);
+
+    run_parser(markdown, &options, &mut state);
+
+    state.finish_line();
+    state.place_notes();
+
+    let widths = options.ambiguous_width;
+    LayoutInfo {
+        line_count: state.lines.len(),
+        line_widths: state.lines.iter().map(|l| widths.line_width(l)).collect(),
+        lines: state.lines.iter().map(|l| l.spans.iter().map(|s| &*s.content).collect()).collect(),
+        widths,
+    }
+}
+
+/// Render markdown into a shareable document.
+///
+/// Like [`render`], but the result can back several panes or threads at
+/// once without being copied.
+pub fn render_arc(
+    markdown: &str,
+    theme: &Theme,
+    options: &RenderOptions,
+) -> Arc<RenderedMarkdown<'static>> {
+    Arc::new(render(markdown, theme, options))
+}
+
+// Documents, themes and options are shared between panes and threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<RenderedMarkdown<'static>>();
+    assert_send_sync::<Theme>();
+    assert_send_sync::<RenderOptions>();
+};
+
 /// Render markdown to ratatui Text with default options.
 ///
 /// Convenience function using default theme and options.
@@ -1082,7 +3039,23 @@ mod tests {
     #[test]
     fn test_code_block() {
         let result = render("```rust\nfn main() {}\n```", &Theme::default(), &RenderOptions::default());
-        assert!(result.text.lines.len() >= 1);
+        assert!(!result.text.lines.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_info() {
+        let md = "```rust,no_run title=\"main.rs\" hl_lines=\"2\"\na\nb\n```";
+        let theme = Theme::default();
+        let options = RenderOptions::new().with_syntax_highlighting(false);
+        let rendered = render(md, &theme, &options);
+        let lines = &rendered.text.lines;
+        assert_eq!(lines[0].to_string(), "main.rs · rust: [no_run]");
+        assert_eq!(lines[1].spans[0].style.bg, theme.code_block.bg);
+        assert_eq!(lines[2].spans[0].style.bg, theme.code_line_highlight.bg);
+
+        let options = options.with_code_header(|info| Some(Line::from(info.flags.join("+"))));
+        let lines = plain_lines("``` a b c\nx\n```", &options);
+        assert_eq!(lines[..2], ["b+c", "x"]);
     }
 
     #[test]
@@ -1095,6 +3068,29 @@ mod tests {
         assert!(result.text.lines.len() >= 3);
     }
 
+    #[test]
+    fn test_table_info() {
+        let result = render(
+            "Intro\n\n| A | B |\n|---|---|\n| 1 | 2 |\n| 3 | 4 |",
+            &Theme::default(),
+            &RenderOptions::github(),
+        );
+        assert_eq!(result.tables.len(), 1);
+        let table = &result.tables[0];
+        assert_eq!(table.column_count, 2);
+        assert_eq!(table.header_lines.len(), 1);
+        assert_eq!(table.body_start, table.separator_line + 1);
+        assert_eq!(table.end_line - table.body_start, 3);
+        let header: String = result.text.lines[table.header_lines.start]
+            .spans
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert!(header.contains('A'));
+        assert!(table.body_contains(table.body_start + 1));
+        assert!(!table.body_contains(table.end_line - 1));
+    }
+
     #[test]
     fn test_word_wrap() {
         let long_text = "This is a very long line that should be wrapped when the width is set.";
@@ -1104,6 +3100,458 @@ mod tests {
         assert!(result.text.lines.len() > 1);
     }
 
+    #[test]
+    fn test_no_wrap_blocks() {
+        let md = "A paragraph that is long enough to wrap at twenty columns.\n\n```\nlet code_line = \"this is a long line of code\";\n```";
+        let options = RenderOptions::new()
+            .with_width(20)
+            .with_no_wrap_blocks([BlockType::Code]);
+        let result = render(md, &Theme::default(), &options);
+
+        let code_lines: Vec<_> = result
+            .text
+            .lines
+            .iter()
+            .filter(|l| l.spans.iter().any(|s| s.content.contains("code_line")))
+            .collect();
+        assert_eq!(code_lines.len(), 1);
+        assert_eq!(code_lines[0].width(), 20);
+        assert!(code_lines[0].spans.last().unwrap().content.ends_with('…'));
+
+        // Prose still wraps
+        assert!(result.text.lines.iter().all(|l| l.width() <= 20));
+        assert!(result.text.lines.len() > 4);
+    }
+
+    #[test]
+    fn test_measure() {
+        let md = "# Title\n\nA paragraph of text.\n\n- one\n- two";
+        let options = RenderOptions::new();
+        let layout = measure(md, &Theme::default(), &options);
+        let result = render(md, &Theme::default(), &options);
+        assert_eq!(layout.line_count, result.line_count);
+        assert_eq!(layout.height_for_width(0), result.line_count);
+        assert_eq!(layout.height_for_width(1000), result.line_count);
+        assert!(layout.height_for_width(5) > result.line_count);
+
+        // Words move to the next row whole, unlike wrapping at every column
+        let layout = measure("aaa bbb ccc", &Theme::default(), &options);
+        assert_eq!(layout.height_for_width(5), layout.line_count + 2);
+        assert_eq!(layout.height_for_width(7), layout.line_count + 1);
+        assert_eq!(layout.height_for_width(11), layout.line_count);
+    }
+
+    #[test]
+    fn test_embed_resolver() {
+        let options = RenderOptions::new()
+            .with_parser_flags(ParserFlags::new().wiki_links())
+            .with_embed_resolver(|target| {
+                (target == "Note").then(|| "Embedded [link](http://x.com)".to_string())
+            });
+        let result = render("Before ![[Note]] after ![[Missing]]", &Theme::default(), &options);
+        let lines: Vec<String> = result
+            .text
+            .lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+
+        let top = lines.iter().position(|l| l == "┌─ Note").unwrap();
+        assert_eq!(lines[top - 1], "Before ");
+        assert_eq!(lines[top + 1], "│ Embedded link");
+        assert_eq!(lines[top + 2], "└─");
+        assert_eq!(lines[top + 3], " after Missing");
+
+        let link = result.links.iter().find(|l| l.url == "http://x.com").unwrap();
+        assert_eq!(link.line, top + 1);
+    }
+
+    #[test]
+    fn test_wiki_link_missing() {
+        let theme = Theme::default();
+        let options = RenderOptions::new()
+            .with_parser_flags(ParserFlags::new().wiki_links())
+            .with_wiki_resolver(|target| target == "Home");
+        let rendered = render("[[Home]] and [[Nowhere|there]]", &theme, &options);
+        let exists: Vec<(&str, bool)> =
+            rendered.links.iter().map(|l| (l.url.as_str(), l.exists)).collect();
+        assert_eq!(exists, [("Home", true), ("Nowhere", false)]);
+        let styles: Vec<Style> = rendered.text.lines[0].spans.iter().map(|s| s.style).collect();
+        assert_eq!(styles[0], theme.wiki_link);
+        assert_eq!(styles[2], theme.wiki_link_missing);
+    }
+
+    #[test]
+    fn test_wiki_link_label() {
+        let options = RenderOptions::new().with_parser_flags(ParserFlags::new().wiki_links());
+        let result = render("See [[Page Name|the page]].", &Theme::default(), &options);
+        assert_eq!(plain_lines("See [[Page Name|the page]].", &options)[0], "See the page.");
+        assert_eq!(result.links[0].url, "Page Name");
+        assert_eq!(result.links[0].text, "the page");
+    }
+
+    #[test]
+    fn test_autolink_schemes() {
+        let options = RenderOptions::new().with_autolink_schemes(["gemini://"]);
+        let result = render("Visit gemini://example.org/x.", &Theme::default(), &options);
+        assert_eq!(result.links.len(), 1);
+        assert_eq!(result.links[0].url, "gemini://example.org/x");
+    }
+
+    #[test]
+    fn test_details_tokens() {
+        let tokens = details_tokens("<details open><summary>A <b>b</b></summary>\nraw\n</details>").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                DetailsToken::Open { open: true },
+                DetailsToken::Summary("A b".to_string()),
+                DetailsToken::Text("raw".to_string()),
+                DetailsToken::Close,
+            ]
+        );
+        assert!(details_tokens("<div>\n<summary>x</summary></div>").is_none());
+    }
+
+    #[test]
+    fn test_details_rendering() {
+        let md = "<details>\n<summary>Spoiler</summary>\n\n# Secret\n\n</details>\n\nAfter";
+        let lines = |options: &RenderOptions| -> Vec<String> {
+            render(md, &Theme::default(), options)
+                .text
+                .lines
+                .iter()
+                .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+                .collect()
+        };
+
+        let collapsed = lines(&RenderOptions::new());
+        assert_eq!(collapsed[0], "[▶ Spoiler]");
+        assert!(!collapsed.iter().any(|l| l.contains("Secret")));
+        assert!(collapsed.iter().any(|l| l == "After"));
+
+        let expanded = lines(&RenderOptions::new().toggle_details(0));
+        assert_eq!(expanded[0], "[▼ Spoiler]");
+        assert!(expanded.iter().any(|l| l.contains("Secret")));
+    }
+
+    #[test]
+    fn test_nested_details() {
+        let body = "word ".repeat(12);
+        let md = format!(
+            "> <details open><summary>Quoted</summary>\n> {}\n> </details>\n\n\
+             - <details open><summary>Listed</summary>\n  {}\n  </details>\n",
+            body, body
+        );
+        let options = RenderOptions::new().with_width(20);
+        let result = render(&md, &Theme::default(), &options);
+        let lines: Vec<String> = result
+            .text
+            .lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+
+        let quoted = lines.iter().position(|l| l == "│ [▼ Quoted]").unwrap();
+        assert_eq!(lines[quoted + 1..quoted + 5], ["│ word word word"; 4]);
+        let listed = lines.iter().position(|l| l == "• [▼ Listed]").unwrap();
+        assert_eq!(lines[listed + 1], "  word word word");
+        assert!(lines.iter().all(|l| l.chars().count() <= 20), "{:?}", lines);
+        assert_eq!(result.details.iter().map(|d| d.line).collect::<Vec<_>>(), [quoted, listed]);
+
+        // The bodies are searched
+        let options = RenderOptions::new().with_search("word");
+        assert_eq!(render(&md, &Theme::default(), &options).search_matches.len(), 24);
+    }
+
+    #[test]
+    fn test_render_profile() {
+        let md = "Some text\n\n```rust\nfn main() {}\n```\n\n```\na\nb\n```\n";
+        assert!(render(md, &Theme::default(), &RenderOptions::new()).profile.is_none());
+
+        let options = RenderOptions::new().with_width(20).with_profile(true);
+        let profile = render(md, &Theme::default(), &options).profile.unwrap();
+        assert_eq!(profile.code_blocks.len(), 2);
+        assert_eq!(profile.code_blocks[0].lang, "rust");
+        assert_eq!(profile.code_blocks[1].line_count, 2);
+        assert!(profile.total >= profile.parse + profile.wrap);
+    }
+
+    #[test]
+    fn test_heading_columns() {
+        let result = render("## Title\n\n> # Quoted", &Theme::default(), &RenderOptions::new());
+        let h = &result.headings;
+        assert_eq!((h[0].column, h[0].width), (0, "## Title".len()));
+        assert_eq!((h[1].column, h[1].width), (2, "# Quoted".len()));
+
+        let options = RenderOptions::new().with_width(12);
+        let result = render("# A long heading", &Theme::default(), &options);
+        assert_eq!(result.headings[0].width, "# A long".len());
+    }
+
+    #[test]
+    fn test_wrapped_link_lines() {
+        let md = "one two three four five [six](http://six.com) seven [eight](http://eight.com)";
+        let options = RenderOptions::new().with_width(16);
+        let result = render(md, &Theme::default(), &options);
+        let lines = plain_lines(md, &options);
+        for link in &result.links {
+            assert!(lines[link.line].contains(&link.text), "{:?} not on {:?}", link, lines);
+        }
+        assert_ne!(result.links[0].line, 0);
+    }
+
+    #[test]
+    fn test_tight_list_bullets() {
+        let lines = plain_lines("- a\n- b\n  - c\n", &RenderOptions::new());
+        assert_eq!(&lines[..3], &["• a", "• b", "  • c"]);
+    }
+
+    #[test]
+    fn test_ordered_list_alignment() {
+        let md = "98. a\n99. b\n100. c\n101. d is long enough\n\n     1. e\n";
+        let lines = plain_lines(md, &RenderOptions::new().with_width(20));
+        assert_eq!(
+            &lines[..6],
+            &[" 98. a", " 99. b", "100. c", "101. d is long", "    enough", "    1. e"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_deep_indent_at_narrow_width() {
+        let md = "- a\n  - b\n    - c\n      - hello world\n\n\
+                  - [ ] a\n  - [ ] b\n    - [ ] task list item\n";
+        for width in 1..=8 {
+            let lines = plain_lines(md, &RenderOptions::new().with_width(width));
+            assert!(lines.iter().all(|line| line.chars().count() <= width), "width {}", width);
+            let text: String = lines.concat().split_whitespace().collect();
+            assert!(text.contains("helloworld") && text.contains("tasklistitem"));
+        }
+    }
+
+    #[test]
+    fn test_wide_ordered_marker_at_narrow_width() {
+        let md = "123456789. ok\n\n1. a\n2. b\n\n   99999. wrapped text\n";
+        for width in 1..=10 {
+            let lines = plain_lines(md, &RenderOptions::new().with_width(width));
+            assert!(lines.iter().all(|line| line.chars().count() <= width), "width {}", width);
+            let text: String = lines.concat().split_whitespace().collect();
+            assert!(text.starts_with("123456789.ok") && text.ends_with("wrappedtext"));
+        }
+        let lines = plain_lines(md, &RenderOptions::new().with_width(10));
+        assert_eq!(lines[..2], ["123456789.", "     ok"]);
+    }
+
+    #[test]
+    fn test_wrap_in_quotes() {
+        let md = "> quote text long enough to wrap\n\n> - item text in a quote that wraps\n";
+        let lines = plain_lines(md, &RenderOptions::new().with_width(16));
+        let expected = ["│ quote text", "│ long enough", "│ to wrap"];
+        assert_eq!(&lines[..3], &expected);
+        let item = lines.iter().position(|line| line.contains('•')).unwrap();
+        assert_eq!(&lines[item..item + 3], &["│ • item text", "│   in a quote", "│   that wraps"]);
+        assert!(lines.iter().all(|line| line.chars().count() <= 16));
+    }
+
+    #[test]
+    fn test_indent_guides() {
+        let md = "- a\n  - b long enough to wrap here\n    - c\n\n> > nested\n";
+        let options = RenderOptions::new().with_width(20).with_indent_guides(true);
+        let lines = plain_lines(md, &options);
+        assert_eq!(lines[1], "│ • b long enough");
+        assert_eq!(lines[2], "│ │ to wrap here");
+        assert_eq!(lines[3], "│ │ • c");
+        assert!(lines.iter().any(|l| l == "│ │ nested"));
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let lf = "# Title\n\n- item\n  continued\n\n```\na\nb\n```\n\n| x |\n|---|\n| 1 |\n";
+        let options = RenderOptions::new().with_width(30);
+        let theme = Theme::default();
+        let expected = render(lf, &theme, &options).text;
+        assert_eq!(render(&lf.replace('\n', "\r\n"), &theme, &options).text, expected);
+        assert_eq!(render(&lf.replace('\n', "\r"), &theme, &options).text, expected);
+    }
+
+    #[test]
+    fn test_heading_folds() {
+        let md = "# Intro\n\ntext\n\n## Setup\n\nsteps\n\n## Setup\n\nmore\n\n# End\n";
+        let theme = Theme::default();
+        let rendered = render(md, &theme, &RenderOptions::new());
+        let slugs: Vec<&str> = rendered.headings.iter().map(|h| h.slug.as_str()).collect();
+        assert_eq!(slugs, ["intro", "setup", "setup-1", "end"]);
+
+        let options = RenderOptions::new().toggle_fold("intro");
+        assert_eq!(plain_lines(md, &options), ["# Intro …", "", "# End", ""]);
+        let rendered = render(md, &theme, &options);
+        assert!(rendered.headings[0].folded);
+        assert_eq!(rendered.headings[1].slug, "end");
+
+        let options = RenderOptions::new().toggle_fold("setup");
+        let lines = plain_lines(md, &options);
+        assert!(!lines.iter().any(|l| l == "steps"));
+        assert!(lines.iter().any(|l| l == "more"));
+    }
+
+    #[test]
+    fn test_ascii_theme() {
+        let md = "# Intro\n\n- [x] done\n\n| a | b |\n|:--|--:|\n| 1 | x^2^ |\n\n\
+                  ```rust title=\"m.rs\"\n```\n\n<details><summary>More</summary>\n</details>\n";
+        let options = RenderOptions::new().with_parser_flags(ParserFlags::github().super_sub());
+        let lines = |options: &RenderOptions| -> Vec<String> {
+            let text = render(md, &Theme::ascii(), options).text;
+            let line = |l: &Line| l.spans.iter().map(|s| s.content.as_ref()).collect();
+            text.lines.iter().map(line).collect()
+        };
+        assert_eq!(
+            lines(&options),
+            [
+                "# Intro", "", "[x] done", "", "+-----+-----+", "| a   |   b | ",
+                "+:----+----:+", "| 1   | x^2 | ", "+-----+-----+", "", "m.rs - rust:", "",
+                "[> More]", "",
+            ]
+        );
+        assert_eq!(lines(&options.toggle_fold("intro"))[0], "# Intro ~");
+    }
+
+    #[test]
+    fn test_slugifier_matches_toc() {
+        let md = "# Intro\n\n## Set up\n\n## Set up\n";
+        let slugifier: Arc<dyn md4c::Slugify> = Arc::new(|text: &str| text.replace(' ', "_"));
+        let options = RenderOptions::new().with_slugifier(slugifier.clone());
+        let rendered = render(md, &Theme::default(), &options);
+        let slugs: Vec<&str> = rendered.headings.iter().map(|h| h.slug.as_str()).collect();
+        assert_eq!(slugs, ["Intro", "Set_up", "Set_up-1"]);
+        let toc = md4c::toc::extract_with(md, ParserFlags::github(), &slugifier);
+        assert!(toc.iter().map(|e| e.slug.as_str()).eq(slugs));
+    }
+
+    #[test]
+    fn test_slugs_match_html_and_toc() {
+        let md = "> # Quoted\n\n# Quoted\n\n# Foo <b>bar</b>\n\n###### Tom &amp; Jerry\n";
+        let html = md4c::html::HtmlRenderer::new()
+            .with_heading_ids(SlugStyle::GitHub)
+            .render(md)
+            .unwrap();
+        let ids: Vec<&str> = html
+            .split("id=\"")
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()])
+            .collect();
+        assert_eq!(ids, ["quoted", "quoted-1", "foo-bar", "tom--jerry"]);
+
+        let options = RenderOptions::new().with_max_heading_depth(5, DeepHeadings::Hide);
+        let rendered = render(md, &Theme::default(), &options);
+        let slugs: Vec<&str> = rendered.headings.iter().map(|h| h.slug.as_str()).collect();
+        assert_eq!(slugs, ids[..3]);
+        let toc = md4c::toc::extract(md, ParserFlags::commonmark());
+        assert!(toc.iter().map(|e| e.slug.as_str()).eq(ids[1..].iter().copied()));
+    }
+
+    #[test]
+    fn test_spellchecker() {
+        let options = RenderOptions::new().with_spellchecker(|text: &str| {
+            text.match_indices("teh").map(|(i, w)| i..i + w.len()).collect()
+        });
+        let theme = Theme::default();
+        let rendered = render("teh *cat* and teh `teh`", &theme, &options);
+        let flagged: Vec<&str> = rendered.text.lines[0]
+            .spans
+            .iter()
+            .filter(|s| s.style.add_modifier.contains(Modifier::UNDERLINED))
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(flagged, ["teh", "teh"]);
+    }
+
+    #[test]
+    fn test_document_stats() {
+        let md = "# Title\n\nSome **bo**ld `code`,\nand &amp; $x$.\n\n```\nnot counted\n```\n\n\
+                  <div>html</div>\n\n~~~\n~~~";
+        let rendered = render(md, &Theme::default(), &RenderOptions::new());
+        // A lone `&` is no word
+        assert_eq!(rendered.stats.words, 6);
+        assert_eq!(rendered.stats.code_blocks, 2);
+        let counted = md4c::stats::analyze(md, RenderOptions::new().parser_flags);
+        assert_eq!((counted.words, counted.code_blocks), (6, 2));
+
+        let md = "word ".repeat(1000);
+        let stats = render(&md, &Theme::default(), &RenderOptions::new()).stats;
+        assert_eq!(stats.reading_time, Duration::from_secs(300));
+        assert_eq!(stats.reading_minutes(), 5);
+    }
+
+    #[test]
+    fn test_text_transform() {
+        let options = RenderOptions::new().with_text_transform(|text_type, text| {
+            match text_type {
+                TextType::Normal if text.contains("sk-") => text.replace("sk-123", "***").into(),
+                TextType::Code => text.to_uppercase().into(),
+                _ => text.into(),
+            }
+        });
+        let lines = plain_lines("key sk-123 and `ok`\n\n```\nsk-9\n```", &options);
+        assert_eq!(lines[0], "key *** and OK");
+        assert!(lines.iter().any(|l| l.contains("SK-9")), "{lines:?}");
+    }
+
+    #[test]
+    fn test_link_display() {
+        let theme = Theme::default().with_link_urls(true);
+        let options = RenderOptions::new()
+            .with_link_display(|link| link.href.trim_start_matches("https://").to_string());
+        let rendered = render("[docs](https://example.com/a)", &theme, &options);
+        let line: String = rendered.text.lines[0].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(line, "docs (example.com/a)");
+        assert_eq!(rendered.links[0].url, "https://example.com/a");
+    }
+
+    #[test]
+    fn test_ambiguous_width() {
+        let md = "| Temp | Δ |\n|:-|-:|\n| 20° | ±1 |\n\n° ° ° ° °\n";
+        for widths in [AmbiguousWidth::Narrow, AmbiguousWidth::Wide] {
+            let options = RenderOptions::new().with_width(6).with_ambiguous_width(widths);
+            let rendered = render(md, &Theme::default(), &options);
+            let table = &rendered.tables[0];
+            let line_widths: Vec<usize> = rendered.text.lines[table.start_line..table.end_line]
+                .iter()
+                .map(|l| {
+                    let text: String = l.spans.iter().map(|s| s.content.as_ref()).collect();
+                    widths.str_width(text.trim_end())
+                })
+                .collect();
+            assert!(line_widths.iter().all(|&w| w == line_widths[0]), "{:?}", line_widths);
+
+            let prose = &rendered.text.lines[table.end_line + 1..];
+            assert!(prose.iter().all(|l| widths.line_width(l) <= 6));
+        }
+        assert_eq!(AmbiguousWidth::Wide.str_width("20°"), 4);
+    }
+
+    fn plain_lines(md: &str, options: &RenderOptions) -> Vec<String> {
+        render(md, &Theme::default(), options)
+            .text
+            .lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_html_block_lines() {
+        let md = "<pre>\nsome\n\ncode\n</pre>\n\n> <div>\n> quoted\n> </div>";
+        let lines = plain_lines(md, &RenderOptions::new());
+        assert!(lines.iter().all(|l| !l.contains('\n')), "{:?}", lines);
+        assert_eq!(lines[..5], ["<pre>", "some", "", "code", "</pre>"]);
+        assert!(lines.iter().any(|l| l.ends_with("quoted") && l != "quoted"));
+
+        let options = RenderOptions::new().with_search("code");
+        let result = render(md, &Theme::default(), &options);
+        let lines: Vec<_> = result.search_matches.iter().map(|m| m.line).collect();
+        assert_eq!(lines, [3]);
+    }
+
     #[test]
     fn test_search_highlighting() {
         let options = RenderOptions::default().with_search("hello");
@@ -1112,10 +3560,178 @@ mod tests {
         assert_eq!(result.search_matches.len(), 2);
     }
 
+    #[test]
+    fn test_search_non_ascii() {
+        // `İ` lowercases to two chars, and `日本` is two cells wide
+        let options = RenderOptions::default().with_search("i\u{307}stanbul");
+        let result = render("日本 İSTANBUL, then İstanbul", &Theme::default(), &options);
+        let ranges: Vec<_> = result
+            .search_matches
+            .iter()
+            .map(|m| (m.start, m.end, m.start_column, m.end_column))
+            .collect();
+        assert_eq!(ranges, [(3, 11, 5, 13), (18, 26, 20, 28)]);
+        let highlighted = result.text.lines[0].spans.iter().find(|s| s.content.starts_with('İ'));
+        assert_eq!(highlighted.map(|s| s.content.as_ref()), Some("İSTANBUL"));
+
+        let options = RenderOptions::default().with_search("İstanbul");
+        let result = render("İSTANBUL", &Theme::default(), &options);
+        assert_eq!(result.search_matches.len(), 1);
+    }
+
     #[test]
     fn test_search_no_matches() {
         let options = RenderOptions::default().with_search("xyz");
         let result = render("Hello world", &Theme::default(), &options);
         assert_eq!(result.search_matches.len(), 0);
     }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_halfblock_images() {
+        use image::{DynamicImage, Rgba, RgbaImage};
+        let options = RenderOptions::default()
+            .with_image_mode(ImageMode::Halfblock)
+            .with_image_loader(|src| {
+                let pixel = Rgba([0, 255, 0, 255]);
+                let image = RgbaImage::from_pixel(3, 4, pixel);
+                (src == "dot.png").then_some(DynamicImage::ImageRgba8(image))
+            });
+        let lines = plain_lines("> ![a dot](dot.png) ![missing](x.png)\n", &options);
+        assert_eq!(lines[..2], ["│ ▀▀▀", "│ ▀▀▀"]);
+        assert!(lines[2].contains("missing") && !lines[2].contains("a dot"));
+
+        // Images inside a shown image hide their alt text and source too
+        let lines = plain_lines("![a ![b](b.png) c](dot.png) after\n", &options);
+        assert_eq!(lines[2], " after");
+        assert!(!lines.concat().contains("b.png"));
+
+        // Nothing is loaded without a loader
+        let options = RenderOptions::default().with_image_mode(ImageMode::Halfblock);
+        assert_eq!(plain_lines("![a dot](dot.png)\n", &options), ["[image](dot.png)a dot"]);
+    }
+
+    #[test]
+    fn test_admonition() {
+        let flags = ParserFlags::github().admonitions();
+        let options = RenderOptions::default().with_parser_flags(flags);
+        let lines = plain_lines("> [!WARNING]\n> Hot.\n", &options);
+        assert_eq!(lines[..2], ["│ Warning", "│ Hot."]);
+    }
+
+    #[test]
+    fn test_links_grouped() {
+        let md = "[a](https://x.org) [b](https://y.org)\n\n[c](https://x.org) <https://x.org>\n";
+        let result = render(md, &Theme::default(), &RenderOptions::default());
+        let groups = result.links_grouped();
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0].url.as_str(), groups[0].text.as_str()), ("https://x.org", "a"));
+        assert_eq!(groups[0].count(), 3);
+        assert_eq!(groups[0].links, [0, 2, 3]);
+        assert_eq!(groups[0].lines, [0, 1, 1]);
+        assert_eq!(groups[1].lines, [0]);
+    }
+
+    #[test]
+    fn test_search_scope() {
+        let md = "# parse docs
+
+Call parse here.
+
+```rust
+parse(x);
+```
+
+```c
+parse(y);
+```
+";
+        let lines = |scope: SearchScope| -> Vec<usize> {
+            let options = RenderOptions::default().with_search("parse").with_search_scope(scope);
+            let result = render(md, &Theme::default(), &options);
+            result.search_matches.iter().map(|m| m.line).collect()
+        };
+        let all = lines(SearchScope::All);
+        assert_eq!(all.len(), 4);
+        assert_eq!(lines(SearchScope::Headings), all[..1]);
+        assert_eq!(lines(SearchScope::Prose), all[1..2]);
+        assert_eq!(lines(SearchScope::Code(None)), all[2..]);
+        assert_eq!(lines(SearchScope::Code(Some("Rust".into()))), all[2..3]);
+    }
+
+    #[test]
+    fn test_link_notes() {
+        let md = "See [a](https://a.org) and [b](https://b.org).\n\nAgain [a](https://a.org).\n";
+        let theme = Theme::default().with_link_urls(true);
+        let lines = |width: usize, notes: LinkNotes| -> Vec<String> {
+            let options = RenderOptions::new().with_width(width).with_link_notes(notes);
+            render(md, &theme, &options)
+                .text
+                .lines
+                .iter()
+                .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+                .collect()
+        };
+
+        let wide = lines(60, LinkNotes::Sidenotes(18));
+        assert_eq!(wide[0], format!("See a[1] and b[2].{}[1] https://a.org", " ".repeat(25)));
+        assert_eq!(wide[1], format!("{}[2] https://b.org", " ".repeat(43)));
+        assert_eq!(wide[2], "Again a[1].");
+        assert!(wide.iter().all(|l| l.width() <= 60));
+
+        let narrow = lines(40, LinkNotes::Sidenotes(18));
+        assert_eq!(narrow, lines(40, LinkNotes::Endnotes));
+        assert_eq!(narrow[3..], ["", "[1] https://a.org", "[2] https://b.org"]);
+
+        // No margin at all, beside a line filling the width
+        let full = "x".repeat(60);
+        let options = RenderOptions::new().with_width(60).with_link_notes(LinkNotes::Sidenotes(0));
+        let text = render(&format!("{}\n[a](https://a.org)", full), &theme, &options).text;
+        assert_eq!(text.lines.last().unwrap().to_string(), "[1] https://a.org");
+    }
+
+    #[test]
+    fn test_super_sub() {
+        let options = RenderOptions::default().with_parser_flags(ParserFlags::github().super_sub());
+        assert_eq!(plain_lines("x^2n^ + H~2~O^(x)^", &options), ["x²ⁿ + H₂O⁽x⁾"]);
+    }
+
+    #[test]
+    fn test_mark() {
+        let theme = Theme::default();
+        let options = RenderOptions::new().with_parser_flags(ParserFlags::github().mark());
+        let result = render("a ==b== c", &theme, &options);
+        let spans = &result.text.lines[0].spans;
+        assert_eq!(spans[1].content, "b");
+        assert_eq!(spans[1].style, theme.mark);
+    }
+
+    #[test]
+    fn test_style_done_tasks() {
+        let theme = Theme::default();
+        let md = "- [x] done\n- [ ] open\n";
+        let options = RenderOptions::new().with_style_done_tasks(true);
+        let result = render(md, &theme, &options);
+        let text_style = |line: usize| result.text.lines[line].spans.last().unwrap().style;
+        assert_eq!(text_style(0), theme.task_done_text);
+        assert_eq!(text_style(1), Style::default());
+
+        let result = render(md, &theme, &RenderOptions::new());
+        assert_eq!(result.text.lines[0].spans.last().unwrap().style, Style::default());
+    }
+
+    #[test]
+    fn test_max_heading_depth() {
+        let md = "# Top\n\n### [Deep](https://x.org)\n\nBody\n";
+        let options = RenderOptions::default().with_max_heading_depth(2, DeepHeadings::Demote);
+        assert_eq!(plain_lines(md, &options), ["# Top", "Deep", "Body"]);
+        let result = render(md, &Theme::default(), &options);
+        assert!(result.text.lines[1].spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(result.headings.len(), 1);
+
+        let options = options.with_max_heading_depth(2, DeepHeadings::Hide);
+        assert_eq!(plain_lines(md, &options), ["# Top", "Body"]);
+        let result = render(md, &Theme::default(), &options);
+        assert!(result.links.is_empty());
+    }
 }
@@ -0,0 +1,135 @@
+//! Snapshot testing helpers.
+//!
+//! [`render_to_plain_debug`] renders markdown to plain text with each styled
+//! element marked by a tag, so an application's rendering can be compared
+//! against a golden file without depending on colors or terminal output.
+//! Requires the `test-util` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use ratatui_md::test_util::render_to_plain_debug;
+//!
+//! let snapshot = render_to_plain_debug("# Title\n\nSome **bold** `code`.", 40);
+//! assert_eq!(snapshot, "[H1]# Title\n\nSome [strong]bold[/strong] |code|.");
+//! ```
+
+use crate::renderer::{render, RenderOptions};
+use crate::theme::Theme;
+use ratatui::style::{Color, Style};
+
+/// Tags of the marked elements, indexed by the foreground color their
+/// style has in the snapshot theme (minus one).
+const TAGS: [&str; 25] = [
+    "H1", "H2", "H3", "H4", "H5", "H6", "em", "strong", "del", "u", "code", "code", "info",
+    "link", "url", "image", "th", "html", "entity", "math", "wiki", "title", "summary", "typo",
+    "mark",
+];
+
+/// A plain theme whose marked elements are told apart by color.
+fn snapshot_theme() -> Theme {
+    let tag = |index: u8| Style::new().fg(Color::Indexed(index + 1));
+    Theme {
+        heading1: tag(0),
+        heading2: tag(1),
+        heading3: tag(2),
+        heading4: tag(3),
+        heading5: tag(4),
+        heading6: tag(5),
+        emphasis: tag(6),
+        strong: tag(7),
+        strikethrough: tag(8),
+        underline: tag(9),
+        code_inline: tag(10),
+        code_block: tag(11),
+        code_block_info: tag(12),
+        link: tag(13),
+        link_url: tag(14),
+        image: tag(15),
+        table_header: tag(16),
+        raw_html: tag(17),
+        html_entity: tag(18),
+        latex_math: tag(19),
+        wiki_link: tag(20),
+        admonition_title: tag(21),
+        details_summary: tag(22),
+        misspelling: tag(23),
+        mark: tag(24),
+        ..Theme::plain()
+    }
+}
+
+/// Tag of the element a span belongs to, `None` for plain text.
+fn tag_of(style: Style) -> Option<&'static str> {
+    match style.fg {
+        Some(Color::Indexed(index)) => TAGS.get(usize::from(index).checked_sub(1)?).copied(),
+        _ => None,
+    }
+}
+
+/// Render markdown to a deterministic, style-annotated text snapshot.
+///
+/// The document is rendered with [`RenderOptions::new`] wrapped at `width`
+/// columns (0 for no wrapping), without syntax highlighting, and with the
+/// ASCII markers of [`Theme::plain`]. Styled text is then marked by element:
+///
+/// - heading lines start with `[H1]` to `[H6]`
+/// - inline code and code block lines are wrapped in `|`
+/// - other elements are wrapped in `[tag]...[/tag]`, e.g. `[em]`,
+///   `[strong]`, `[link]`, `[url]` or `[th]` for table headers
+///
+/// Nested inline elements are marked with the innermost tag only. Trailing
+/// whitespace is removed from each line.
+pub fn render_to_plain_debug(markdown: &str, width: usize) -> String {
+    let options = RenderOptions::new().with_width(width).with_syntax_highlighting(false);
+    let rendered = render(markdown, &snapshot_theme(), &options);
+
+    let mut out = String::new();
+    for (i, line) in rendered.text.lines.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut runs: Vec<(Option<&str>, String)> = Vec::new();
+        for span in &line.spans {
+            let tag = tag_of(span.style);
+            match runs.last_mut() {
+                Some((last, text)) if *last == tag => text.push_str(&span.content),
+                _ => runs.push((tag, span.content.to_string())),
+            }
+        }
+
+        let start = out.len();
+        for (tag, text) in runs {
+            match tag {
+                None => out.push_str(&text),
+                Some(heading) if heading.starts_with('H') => {
+                    out.push_str(&format!("[{}]{}", heading, text))
+                }
+                Some("code") => out.push_str(&format!("|{}|", text)),
+                Some(tag) => out.push_str(&format!("[{}]{}[/{}]", tag, text, tag)),
+            }
+        }
+        out.truncate(start + out[start..].trim_end().len());
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let md = "## List\n\n- *done* and ~~gone~~\n- [docs](https://x.org)\n\n\
+                  ```\nfn main() {}\n```\n\n| a |\n|---|\n| 1 |\n";
+        assert_eq!(
+            render_to_plain_debug(md, 0),
+            "[H2]## List\n\n\
+             * [em]done[/em] and [del]gone[/del]\n\
+             * [link]docs[/link][url] (https://x.org)[/url]\n\n\
+             |fn main() {}|\n\n\
+             ┌─────┐\n│ [th]a  [/th] │\n├─────┤\n│ 1   │\n└─────┘"
+        );
+    }
+}
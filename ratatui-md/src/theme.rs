@@ -35,6 +35,9 @@ pub struct Theme {
     /// Underline style (MD4C extension)
     pub underline: Style,
 
+    /// Highlighted text style - ==text==
+    pub mark: Style,
+
     /// Inline code style - `code`
     pub code_inline: Style,
 
@@ -44,6 +47,9 @@ pub struct Theme {
     /// Code block info/language label style
     pub code_block_info: Style,
 
+    /// Code block lines picked out with `hl_lines` in the info string
+    pub code_line_highlight: Style,
+
     /// Link text style
     pub link: Style,
 
@@ -92,6 +98,10 @@ pub struct Theme {
     /// Task list checked marker [x] style
     pub task_checked: Style,
 
+    /// Text of checked task list items, with
+    /// `RenderOptions::style_done_tasks`
+    pub task_done_text: Style,
+
     /// Table header cell style
     pub table_header: Style,
 
@@ -101,6 +111,9 @@ pub struct Theme {
     /// Table border style
     pub table_border: Style,
 
+    /// Selected table cell in a `MarkdownView`
+    pub table_cell_selected: Style,
+
     /// HTML entity style (rendered literally if not decoded)
     pub html_entity: Style,
 
@@ -113,6 +126,27 @@ pub struct Theme {
     /// Wiki link style
     pub wiki_link: Style,
 
+    /// Wiki links whose target is missing (`RenderOptions::wiki_resolver`)
+    pub wiki_link_missing: Style,
+
+    /// Border style framing embedded notes (`![[note]]`)
+    pub embed_border: Style,
+
+    /// Summary line of a `<details>` block
+    pub details_summary: Style,
+
+    /// Title line of an alert (`> [!NOTE]`)
+    pub admonition_title: Style,
+
+    /// Indent guides for nested lists and quotes
+    pub indent_guide: Style,
+
+    /// Words flagged by the spell checker (`RenderOptions::spellchecker`)
+    pub misspelling: Style,
+
+    /// Line under the reading cursor of a `MarkdownView`
+    pub cursor_line: Style,
+
     // === Rendering options ===
     /// Character used for unordered list bullets
     pub bullet_char: char,
@@ -123,6 +157,9 @@ pub struct Theme {
     /// String used for blockquote markers
     pub blockquote_prefix: &'static str,
 
+    /// Character used for indent guides
+    pub indent_guide_char: char,
+
     /// Show link URLs inline after link text
     pub show_link_urls: bool,
 
@@ -134,6 +171,11 @@ pub struct Theme {
 
     /// Character for checked task list items
     pub task_checked_char: char,
+
+    /// Draw table borders, embed frames, fold and details markers and
+    /// ellipses with ASCII characters only, and show task markers in
+    /// brackets (`[x]`)
+    pub ascii: bool,
 }
 
 impl Default for Theme {
@@ -144,9 +186,11 @@ impl Default for Theme {
             strong: Style::default().add_modifier(Modifier::BOLD),
             strikethrough: Style::default().add_modifier(Modifier::CROSSED_OUT),
             underline: Style::default().add_modifier(Modifier::UNDERLINED),
+            mark: Style::default().fg(Color::Black).bg(Color::Yellow),
             code_inline: Style::default().fg(Color::Yellow),
             code_block: Style::default().fg(Color::White),
             code_block_info: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            code_line_highlight: Style::default().bg(Color::DarkGray),
             link: Style::default().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED),
             link_url: Style::default().fg(Color::DarkGray),
             image: Style::default().fg(Color::Magenta),
@@ -163,21 +207,36 @@ impl Default for Theme {
             list_number: Style::default().fg(Color::Cyan),
             task_unchecked: Style::default().fg(Color::DarkGray),
             task_checked: Style::default().fg(Color::Green),
+            task_done_text: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM | Modifier::CROSSED_OUT),
             table_header: Style::default().add_modifier(Modifier::BOLD),
             table_cell: Style::default(),
             table_border: Style::default().fg(Color::DarkGray),
+            table_cell_selected: Style::default().fg(Color::White).bg(Color::Blue),
             html_entity: Style::default().fg(Color::Yellow),
             raw_html: Style::default().fg(Color::DarkGray),
             latex_math: Style::default().fg(Color::Magenta),
             wiki_link: Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            wiki_link_missing: Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED),
+            embed_border: Style::default().fg(Color::DarkGray),
+            details_summary: Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            admonition_title: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            indent_guide: Style::default().fg(Color::DarkGray),
+            misspelling: Style::default()
+                .add_modifier(Modifier::UNDERLINED)
+                .underline_color(Color::Red),
+            cursor_line: Style::default().bg(Color::DarkGray),
 
             bullet_char: '•',
             hr_char: '─',
             blockquote_prefix: "│ ",
+            indent_guide_char: '│',
             show_link_urls: false,
             list_indent: 2,
             task_unchecked_char: '☐',
             task_checked_char: '☑',
+            ascii: false,
         }
     }
 }
@@ -196,9 +255,11 @@ impl Theme {
             strong: Style::default().add_modifier(Modifier::BOLD),
             strikethrough: Style::default().add_modifier(Modifier::CROSSED_OUT),
             underline: Style::default().add_modifier(Modifier::UNDERLINED),
+            mark: Style::default().add_modifier(Modifier::REVERSED),
             code_inline: Style::default(),
             code_block: Style::default(),
             code_block_info: Style::default().add_modifier(Modifier::DIM),
+            code_line_highlight: Style::default().add_modifier(Modifier::REVERSED),
             link: Style::default().add_modifier(Modifier::UNDERLINED),
             link_url: Style::default().add_modifier(Modifier::DIM),
             image: Style::default(),
@@ -215,21 +276,51 @@ impl Theme {
             list_number: Style::default(),
             task_unchecked: Style::default(),
             task_checked: Style::default(),
+            task_done_text: Style::default().add_modifier(Modifier::CROSSED_OUT),
             table_header: Style::default().add_modifier(Modifier::BOLD),
             table_cell: Style::default(),
             table_border: Style::default(),
+            table_cell_selected: Style::default().add_modifier(Modifier::REVERSED),
             html_entity: Style::default(),
             raw_html: Style::default().add_modifier(Modifier::DIM),
             latex_math: Style::default(),
             wiki_link: Style::default().add_modifier(Modifier::UNDERLINED),
+            wiki_link_missing: Style::default().add_modifier(Modifier::UNDERLINED | Modifier::DIM),
+            embed_border: Style::default(),
+            details_summary: Style::default().add_modifier(Modifier::BOLD),
+            admonition_title: Style::default().add_modifier(Modifier::BOLD),
+            indent_guide: Style::default().add_modifier(Modifier::DIM),
+            misspelling: Style::default().add_modifier(Modifier::UNDERLINED),
+            cursor_line: Style::default().add_modifier(Modifier::REVERSED),
 
             bullet_char: '*',
             hr_char: '-',
             blockquote_prefix: "> ",
+            indent_guide_char: '|',
             show_link_urls: true,
             list_indent: 2,
             task_unchecked_char: ' ',
             task_checked_char: 'x',
+            ascii: false,
+        }
+    }
+
+    /// Create a theme that draws only ASCII characters, for CI logs and
+    /// terminals without Unicode fonts.
+    ///
+    /// Styles are the defaults; bullets, rules, quote markers and task
+    /// markers are ASCII and [`ascii`](Self::ascii) is set. Text from the
+    /// document itself is shown as it is.
+    pub fn ascii() -> Self {
+        Self {
+            bullet_char: '*',
+            hr_char: '-',
+            blockquote_prefix: "> ",
+            indent_guide_char: '|',
+            task_unchecked_char: ' ',
+            task_checked_char: 'x',
+            ascii: true,
+            ..Self::default()
         }
     }
 
@@ -241,9 +332,11 @@ impl Theme {
             strong: Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
             strikethrough: Style::default().fg(Color::Gray).add_modifier(Modifier::CROSSED_OUT),
             underline: Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED),
+            mark: Style::default().fg(Color::Black).bg(Color::Yellow),
             code_inline: Style::default().fg(Color::LightYellow).bg(Color::DarkGray),
             code_block: Style::default().fg(Color::LightYellow),
             code_block_info: Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            code_line_highlight: Style::default().bg(Color::DarkGray),
             link: Style::default().fg(Color::LightCyan).add_modifier(Modifier::UNDERLINED),
             link_url: Style::default().fg(Color::Gray),
             image: Style::default().fg(Color::LightMagenta),
@@ -260,13 +353,28 @@ impl Theme {
             list_number: Style::default().fg(Color::LightCyan),
             task_unchecked: Style::default().fg(Color::Gray),
             task_checked: Style::default().fg(Color::LightGreen),
+            task_done_text: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::DIM | Modifier::CROSSED_OUT),
             table_header: Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
             table_cell: Style::default().fg(Color::White),
             table_border: Style::default().fg(Color::DarkGray),
+            table_cell_selected: Style::default().fg(Color::White).bg(Color::Blue),
             html_entity: Style::default().fg(Color::LightYellow),
             raw_html: Style::default().fg(Color::Gray),
             latex_math: Style::default().fg(Color::LightMagenta),
             wiki_link: Style::default().fg(Color::LightBlue).add_modifier(Modifier::UNDERLINED),
+            wiki_link_missing: Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::UNDERLINED),
+            embed_border: Style::default().fg(Color::DarkGray),
+            details_summary: Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            admonition_title: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            indent_guide: Style::default().fg(Color::DarkGray),
+            misspelling: Style::default()
+                .add_modifier(Modifier::UNDERLINED)
+                .underline_color(Color::Red),
+            cursor_line: Style::default().bg(Color::DarkGray),
             ..Self::default()
         }
     }
@@ -279,9 +387,11 @@ impl Theme {
             strong: Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
             strikethrough: Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT),
             underline: Style::default().fg(Color::Black).add_modifier(Modifier::UNDERLINED),
+            mark: Style::default().fg(Color::Black).bg(Color::LightYellow),
             code_inline: Style::default().fg(Color::Red),
             code_block: Style::default().fg(Color::Black),
             code_block_info: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            code_line_highlight: Style::default().bg(Color::Gray),
             link: Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
             link_url: Style::default().fg(Color::DarkGray),
             image: Style::default().fg(Color::Magenta),
@@ -298,13 +408,26 @@ impl Theme {
             list_number: Style::default().fg(Color::Blue),
             task_unchecked: Style::default().fg(Color::DarkGray),
             task_checked: Style::default().fg(Color::Green),
+            task_done_text: Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::DIM | Modifier::CROSSED_OUT),
             table_header: Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
             table_cell: Style::default().fg(Color::Black),
             table_border: Style::default().fg(Color::Gray),
+            table_cell_selected: Style::default().fg(Color::Black).bg(Color::LightBlue),
             html_entity: Style::default().fg(Color::Red),
             raw_html: Style::default().fg(Color::DarkGray),
             latex_math: Style::default().fg(Color::Magenta),
             wiki_link: Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+            wiki_link_missing: Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED),
+            embed_border: Style::default().fg(Color::Gray),
+            details_summary: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            admonition_title: Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            indent_guide: Style::default().fg(Color::Gray),
+            misspelling: Style::default()
+                .add_modifier(Modifier::UNDERLINED)
+                .underline_color(Color::Red),
+            cursor_line: Style::default().bg(Color::Gray),
             ..Self::default()
         }
     }
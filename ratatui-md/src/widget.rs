@@ -2,13 +2,19 @@
 //!
 //! Provides ready-to-use widgets for rendering markdown in terminal UIs.
 
-use crate::renderer::{render, HeadingInfo, LinkInfo, RenderOptions, RenderedMarkdown, SearchMatch};
+use crate::renderer::{
+    render, DetailsInfo, HeadingInfo, Hook, LinkGroup, LinkInfo, RenderOptions, RenderedMarkdown,
+    SearchMatch, SearchScope, TableInfo,
+};
 use crate::theme::Theme;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Alignment, Rect};
 use ratatui::style::Style;
-use ratatui::text::Text;
+use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Paragraph, Widget, Wrap};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
 
 /// A widget that renders markdown content.
 ///
@@ -132,25 +138,212 @@ impl Widget for Markdown<'_> {
 /// let mut view = MarkdownView::new("# Doc\n\nSome content...");
 /// view.scroll_down(5);
 /// ```
+///
+/// Cloning a view is cheap: the clone shares the document and its rendered
+/// form, but scrolls and selects independently, e.g. for a split pane.
+#[derive(Clone)]
 pub struct MarkdownView {
-    content: String,
+    content: Arc<str>,
     theme: Theme,
     options: RenderOptions,
-    rendered: Option<RenderedMarkdown<'static>>,
+    rendered: Option<Arc<RenderedMarkdown<'static>>>,
     scroll_offset: u16,
     selected_link: Option<usize>,
+    sticky_table_headers: bool,
+    jumps: Vec<Jump>,
+    // Position in `jumps`; equal to its length when not navigating history
+    jump_index: usize,
+    link_handler: Option<LinkHandler>,
+    link_footer: bool,
+    // Where jumps put their target line
+    jump_placement: ScrollPlacement,
+    // Rows of text shown when last rendered as a widget
+    viewport_height: u16,
+    // Line of the reading cursor, if shown
+    cursor: Option<usize>,
+    // Selected table cell as (table, row, column)
+    selected_cell: Option<(usize, usize, usize)>,
+    // Search match last scrolled to, with the scroll offset it left
+    current_match: Option<(usize, u16)>,
+}
+
+/// Where [`MarkdownView::scroll_to_line`] and jumps put the target line in
+/// the viewport.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScrollPlacement {
+    /// At the top row
+    #[default]
+    Top,
+    /// In the middle row
+    Center,
+    /// At the bottom row
+    Bottom,
+}
+
+/// What to do with a link activated in a [`MarkdownView`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkAction {
+    /// Leave the link to the application
+    Default,
+    /// Load the given markdown into the view, recording the current
+    /// document in the jump list
+    Load(String),
+    /// The callback handled the link itself
+    Handled,
+}
+
+/// Callback deciding how to follow a link, see
+/// [`MarkdownView::on_external_link`].
+pub type LinkHandler = Hook<dyn Fn(&LinkInfo) -> LinkAction + Send + Sync>;
+
+/// How [`MarkdownView::select_table_cell`] moves the selected cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellMove {
+    /// The next cell in the row, then the next row, then the next table
+    Next,
+    /// The previous cell, back through rows and tables
+    Prev,
+    /// The same column in the row below
+    Down,
+    /// The same column in the row above
+    Up,
+    /// A cell of the current table; row 0 is the header
+    To { row: usize, col: usize },
+}
+
+/// The selected table cell of a [`MarkdownView`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectedCell {
+    /// Index of the table in `RenderedMarkdown::tables`
+    pub table: usize,
+    /// Row in the table; 0 is the header
+    pub row: usize,
+    /// Column in the table
+    pub col: usize,
+    /// Line index of the row
+    pub line: usize,
+    /// Display columns of the cell on its line
+    pub columns: Range<usize>,
+    /// Full text of the cell, even where the table is clipped
+    pub content: String,
 }
 
+/// Format for [`MarkdownView::export_outline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineFormat {
+    /// Nested markdown list of `[text](#slug) (line N)` entries
+    Markdown,
+    /// JSON array of `{"level", "text", "slug", "line", "children"}` objects
+    Json,
+}
+
+/// A heading with the headings nested under it.
+struct OutlineNode<'a> {
+    heading: &'a HeadingInfo,
+    children: Vec<OutlineNode<'a>>,
+}
+
+/// Nest headings deeper than `parent_level`, starting at `*next`.
+fn outline_nodes<'a>(
+    headings: &'a [HeadingInfo],
+    next: &mut usize,
+    parent_level: u8,
+) -> Vec<OutlineNode<'a>> {
+    let mut nodes = Vec::new();
+    while let Some(heading) = headings.get(*next).filter(|h| h.level > parent_level) {
+        *next += 1;
+        nodes.push(OutlineNode {
+            heading,
+            children: outline_nodes(headings, next, heading.level),
+        });
+    }
+    nodes
+}
+
+fn outline_markdown(nodes: &[OutlineNode], depth: usize, out: &mut String) {
+    for node in nodes {
+        let h = node.heading;
+        let text = h.text.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+        out.push_str(&format!(
+            "{}- [{}](#{}) (line {})\n",
+            "  ".repeat(depth),
+            text,
+            h.slug,
+            h.line
+        ));
+        outline_markdown(&node.children, depth + 1, out);
+    }
+}
+
+fn outline_json(nodes: &[OutlineNode], out: &mut String) {
+    out.push('[');
+    for (i, node) in nodes.iter().enumerate() {
+        let h = node.heading;
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            "{{\"level\":{},\"text\":{},\"slug\":{},\"line\":{},\"children\":",
+            h.level,
+            json_string(&h.text),
+            json_string(&h.slug),
+            h.line
+        ));
+        outline_json(&node.children, out);
+        out.push('}');
+    }
+    out.push(']');
+}
+
+/// Quote and escape a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A position in the jump list.
+#[derive(Clone)]
+struct Jump {
+    content: Arc<str>,
+    scroll: u16,
+    toggled_details: HashSet<usize>,
+    folded_headings: HashSet<String>,
+}
+
+/// Maximum number of positions kept in the jump list.
+const MAX_JUMPS: usize = 100;
+
 impl MarkdownView {
     /// Create a new markdown view.
     pub fn new(content: impl Into<String>) -> Self {
         Self {
-            content: content.into(),
+            content: content.into().into(),
             theme: Theme::default(),
             options: RenderOptions::default(),
             rendered: None,
             scroll_offset: 0,
             selected_link: None,
+            sticky_table_headers: false,
+            jumps: Vec::new(),
+            jump_index: 0,
+            link_handler: None,
+            link_footer: false,
+            jump_placement: ScrollPlacement::Top,
+            viewport_height: 0,
+            cursor: None,
+            selected_cell: None,
+            current_match: None,
         }
     }
 
@@ -168,12 +361,81 @@ impl MarkdownView {
         self
     }
 
+    /// Keep a table's header row pinned to the top of the viewport while
+    /// scrolling through its body, like spreadsheet freeze panes.
+    pub fn sticky_table_headers(mut self, enabled: bool) -> Self {
+        self.sticky_table_headers = enabled;
+        self
+    }
+
+    /// Show link URLs in a footer row instead of inline.
+    ///
+    /// The footer lists the URLs of links on the focused line: the selected
+    /// link's line, or the top line of the viewport. This overrides
+    /// `theme.show_link_urls`.
+    pub fn link_footer(mut self, enabled: bool) -> Self {
+        self.set_link_footer(enabled);
+        self
+    }
+
+    /// Enable or disable the link URL footer.
+    pub fn set_link_footer(&mut self, enabled: bool) {
+        self.link_footer = enabled;
+        self.rendered = None;
+    }
+
+    /// Enable or disable sticky table headers.
+    pub fn set_sticky_table_headers(&mut self, enabled: bool) {
+        self.sticky_table_headers = enabled;
+    }
+
+    /// Where jumps to headings, tables and search matches put their target
+    /// line in the viewport; the top by default.
+    pub fn jump_placement(mut self, placement: ScrollPlacement) -> Self {
+        self.jump_placement = placement;
+        self
+    }
+
+    /// Set where jumps put their target line.
+    pub fn set_jump_placement(&mut self, placement: ScrollPlacement) {
+        self.jump_placement = placement;
+    }
+
+    /// Set the number of rows the view is shown in, for placing lines in
+    /// the viewport.
+    ///
+    /// Rendering the [`widget`](Self::widget) sets this, so it only needs
+    /// calling to place lines before the first render or when drawing the
+    /// view some other way.
+    pub fn set_viewport_height(&mut self, height: u16) {
+        self.viewport_height = height;
+    }
+
+    /// Set the callback deciding what happens when a link is activated with
+    /// [`activate_selected_link`](Self::activate_selected_link).
+    ///
+    /// Returning [`LinkAction::Load`] replaces the document, e.g. to follow a
+    /// link to another `.md` file; `jump_back` returns to the previous one.
+    pub fn on_external_link(
+        mut self,
+        handler: impl Fn(&LinkInfo) -> LinkAction + Send + Sync + 'static,
+    ) -> Self {
+        self.link_handler = Some(Hook(Arc::new(handler)));
+        self
+    }
+
     /// Set the markdown content.
+    ///
+    /// This starts a new document, clearing the jump list.
     pub fn set_content(&mut self, content: impl Into<String>) {
-        self.content = content.into();
+        self.content = content.into().into();
         self.rendered = None;
         self.scroll_offset = 0;
         self.selected_link = None;
+        self.jumps.clear();
+        self.jump_index = 0;
+        self.cursor = self.cursor.map(|_| 0);
+        self.selected_cell = None;
     }
 
     /// Get the current scroll offset.
@@ -211,13 +473,212 @@ impl MarkdownView {
         }
     }
 
+    /// Record the current position in the jump list.
+    ///
+    /// Heading, table and search jumps record automatically; call this
+    /// before moving the view in response to following a link.
+    pub fn record_jump(&mut self) {
+        self.jumps.truncate(self.jump_index);
+        let here = self.jumps.last().is_some_and(|j| {
+            j.scroll == self.scroll_offset && Arc::ptr_eq(&j.content, &self.content)
+        });
+        if !here {
+            self.jumps.push(Jump {
+                content: Arc::clone(&self.content),
+                scroll: self.scroll_offset,
+                toggled_details: self.options.toggled_details.clone(),
+                folded_headings: self.options.folded_headings.clone(),
+            });
+        }
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.jump_index = self.jumps.len();
+    }
+
+    /// Go back to the previous position in the jump list.
+    ///
+    /// Returns false if there is no earlier position.
+    pub fn jump_back(&mut self) -> bool {
+        // Remember where we left from so `jump_forward` can return
+        if self.jump_index == self.jumps.len() {
+            self.record_jump();
+            self.jump_index = self.jumps.len() - 1;
+        }
+        if self.jump_index == 0 {
+            return false;
+        }
+        self.jump_index -= 1;
+        self.restore_jump();
+        true
+    }
+
+    /// Go forward to the next position in the jump list.
+    ///
+    /// Returns false if there is no later position.
+    pub fn jump_forward(&mut self) -> bool {
+        if self.jump_index + 1 >= self.jumps.len() {
+            return false;
+        }
+        self.jump_index += 1;
+        self.restore_jump();
+        true
+    }
+
+    /// Move to the jump list entry at `jump_index`, switching documents if
+    /// needed.
+    fn restore_jump(&mut self) {
+        let jump = self.jumps[self.jump_index].clone();
+        if !Arc::ptr_eq(&jump.content, &self.content) {
+            self.content = jump.content;
+            self.options.toggled_details = jump.toggled_details;
+            self.options.folded_headings = jump.folded_headings;
+            self.rendered = None;
+            self.selected_link = None;
+        }
+        self.scroll_offset = jump.scroll;
+    }
+
+    /// Activate the selected link.
+    ///
+    /// Links other than `#fragment` links are passed to the
+    /// [`on_external_link`](Self::on_external_link) callback. Returns the link
+    /// if the application should follow it itself.
+    pub fn activate_selected_link(&mut self) -> Option<LinkInfo> {
+        let link = self.selected_link()?.clone();
+        if link.url.starts_with('#') {
+            return Some(link);
+        }
+        let action = match self.link_handler {
+            Some(ref handler) => handler(&link),
+            None => LinkAction::Default,
+        };
+
+        match action {
+            LinkAction::Default => Some(link),
+            LinkAction::Load(content) => {
+                self.record_jump();
+                self.content = content.into();
+                self.options.toggled_details.clear();
+                self.options.folded_headings.clear();
+                self.rendered = None;
+                self.selected_link = None;
+                self.scroll_offset = 0;
+                None
+            }
+            LinkAction::Handled => None,
+        }
+    }
+
+    /// Record the current position, then scroll to `line`.
+    fn jump_to(&mut self, line: usize) {
+        self.record_jump();
+        self.place_line(line, self.jump_placement);
+    }
+
+    /// Record the current position, then scroll so `line` is at
+    /// `placement` in the viewport, e.g. to open a document at a line.
+    ///
+    /// Lines near the start of the document may end up lower than
+    /// `placement`, since the view doesn't scroll above the first line.
+    pub fn scroll_to_line(&mut self, line: usize, placement: ScrollPlacement) {
+        self.ensure_rendered();
+        let last = self.rendered.as_ref().map_or(0, |r| r.line_count.saturating_sub(1));
+        self.record_jump();
+        self.place_line(line.min(last), placement);
+    }
+
+    /// Scroll so `line` is at `placement` in the viewport.
+    fn place_line(&mut self, line: usize, placement: ScrollPlacement) {
+        let above = self.rows_above(placement);
+        self.scroll_offset = line.saturating_sub(above).min(u16::MAX as usize) as u16;
+    }
+
+    /// Rows of the viewport above a line at `placement`.
+    fn rows_above(&self, placement: ScrollPlacement) -> usize {
+        let last_row = self.viewport_height.saturating_sub(1) as usize;
+        match placement {
+            ScrollPlacement::Top => 0,
+            ScrollPlacement::Center => last_row / 2,
+            ScrollPlacement::Bottom => last_row,
+        }
+    }
+
+    /// The line of the reading cursor, or `None` if it is hidden.
+    ///
+    /// The cursor marks a line with `theme.cursor_line` and moves
+    /// independently of scrolling, for acting on links and blocks with the
+    /// keyboard.
+    pub fn cursor_line(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    /// Show the reading cursor at `line`, scrolling to keep it in view.
+    pub fn set_cursor_line(&mut self, line: usize) {
+        self.ensure_rendered();
+        let last = self.rendered.as_ref().map_or(0, |r| r.line_count.saturating_sub(1));
+        let line = line.min(last);
+        self.cursor = Some(line);
+        self.scroll_into_view(line);
+    }
+
+    /// Scroll as little as possible to show `line`.
+    fn scroll_into_view(&mut self, line: usize) {
+        let top = self.scroll_offset as usize;
+        let height = self.viewport_height.max(1) as usize;
+        if line < top {
+            self.scroll_offset = line.min(u16::MAX as usize) as u16;
+        } else if line >= top + height {
+            self.scroll_offset = (line + 1 - height).min(u16::MAX as usize) as u16;
+        }
+    }
+
+    /// Hide the reading cursor.
+    pub fn hide_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Move the reading cursor down by the given number of lines.
+    ///
+    /// A hidden cursor is shown at the top line of the viewport first.
+    pub fn cursor_down(&mut self, lines: usize) {
+        let line = self.cursor.unwrap_or(self.scroll_offset as usize);
+        self.set_cursor_line(line.saturating_add(lines));
+    }
+
+    /// Move the reading cursor up by the given number of lines.
+    ///
+    /// A hidden cursor is shown at the top line of the viewport first.
+    pub fn cursor_up(&mut self, lines: usize) {
+        let line = self.cursor.unwrap_or(self.scroll_offset as usize);
+        self.set_cursor_line(line.saturating_sub(lines));
+    }
+
+    /// The first link on the cursor's line.
+    pub fn link_under_cursor(&mut self) -> Option<LinkInfo> {
+        let line = self.cursor?;
+        self.links_on_line(line).into_iter().next()
+    }
+
+    /// The lines of the top-level block under the cursor, including the
+    /// blank lines after it.
+    pub fn block_under_cursor(&mut self) -> Option<Range<usize>> {
+        let line = self.cursor?;
+        self.ensure_rendered();
+        let rendered = self.rendered.as_ref()?;
+        let boundaries = &rendered.block_boundaries;
+        let next = boundaries.partition_point(|&end| end <= line);
+        let start = next.checked_sub(1).map_or(0, |i| boundaries[i]);
+        let end = boundaries.get(next).copied().unwrap_or(rendered.line_count);
+        Some(start..end.max(line + 1))
+    }
+
     /// Scroll to a specific heading by index.
     pub fn scroll_to_heading(&mut self, index: usize) {
         self.ensure_rendered();
-        if let Some(ref rendered) = self.rendered {
-            if let Some(heading) = rendered.headings.get(index) {
-                self.scroll_offset = heading.line as u16;
-            }
+        let line = self.rendered.as_ref().and_then(|r| r.headings.get(index)).map(|h| h.line);
+        if let Some(line) = line {
+            self.jump_to(line);
         }
     }
 
@@ -230,6 +691,227 @@ impl MarkdownView {
             .unwrap_or_default()
     }
 
+    /// Export the heading structure as a nested outline, e.g. to feed a
+    /// fuzzy picker.
+    ///
+    /// Entries carry each heading's slug and line index. Headings inside
+    /// folded sections are omitted, matching what the view shows.
+    pub fn export_outline(&mut self, format: OutlineFormat) -> String {
+        let headings = self.headings();
+        let nodes = outline_nodes(&headings, &mut 0, 0);
+        let mut out = String::new();
+        match format {
+            OutlineFormat::Markdown => outline_markdown(&nodes, 0, &mut out),
+            OutlineFormat::Json => outline_json(&nodes, &mut out),
+        }
+        out
+    }
+
+    /// Get all tables in the document.
+    pub fn tables(&mut self) -> Vec<TableInfo> {
+        self.ensure_rendered();
+        self.rendered
+            .as_ref()
+            .map(|r| r.tables.clone())
+            .unwrap_or_default()
+    }
+
+    /// Scroll to a specific table by index.
+    pub fn scroll_to_table(&mut self, index: usize) {
+        self.ensure_rendered();
+        let line = self.rendered.as_ref().and_then(|r| r.tables.get(index)).map(|t| t.start_line);
+        if let Some(line) = line {
+            self.jump_to(line);
+        }
+    }
+
+    /// Move the table cell selection, scrolling to keep the cell in view.
+    ///
+    /// Without a selection, any move selects a cell of the first table
+    /// that ends below the cursor or the top of the viewport. Returns
+    /// whether a cell is selected.
+    ///
+    /// The selected cell is drawn with `theme.table_cell_selected`, and
+    /// [`selected_table_cell`](Self::selected_table_cell) returns its full
+    /// text, so cells cut off in a narrow terminal can still be read.
+    pub fn select_table_cell(&mut self, movement: CellMove) -> bool {
+        self.ensure_rendered();
+        let Some(rendered) = self.rendered.clone() else {
+            return false;
+        };
+        let tables = &rendered.tables;
+        if tables.is_empty() {
+            return false;
+        }
+
+        let current = self.selected_cell.filter(|&(t, _, _)| t < tables.len());
+        let (table, row, col) = match (current, movement) {
+            (None, movement) => {
+                let top = self.cursor.unwrap_or(self.scroll_offset as usize);
+                let t = tables.iter().position(|t| t.end_line > top).unwrap_or(tables.len() - 1);
+                match movement {
+                    CellMove::To { row, col } => (t, row, col),
+                    _ => (t, 0, 0),
+                }
+            }
+            (Some((t, r, c)), CellMove::Next) => {
+                let cells = &tables[t].cells;
+                if c + 1 < cells.get(r).map_or(0, Vec::len) {
+                    (t, r, c + 1)
+                } else if r + 1 < cells.len() {
+                    (t, r + 1, 0)
+                } else if t + 1 < tables.len() {
+                    (t + 1, 0, 0)
+                } else {
+                    (t, r, c)
+                }
+            }
+            (Some((t, r, c)), CellMove::Prev) => {
+                if c > 0 {
+                    (t, r, c - 1)
+                } else if r > 0 {
+                    (t, r - 1, usize::MAX)
+                } else if t > 0 {
+                    (t - 1, usize::MAX, usize::MAX)
+                } else {
+                    (t, r, c)
+                }
+            }
+            (Some((t, r, c)), CellMove::Down) => (t, r + 1, c),
+            (Some((t, r, c)), CellMove::Up) => (t, r.saturating_sub(1), c),
+            (Some((t, _, _)), CellMove::To { row, col }) => (t, row, col),
+        };
+
+        // Clamp to the table, e.g. `usize::MAX` for the last row or column
+        let cells = &tables[table].cells;
+        let row = row.min(cells.len().saturating_sub(1));
+        let col = col.min(cells.get(row).map_or(0, Vec::len).saturating_sub(1));
+        self.selected_cell = Some((table, row, col));
+        self.scroll_into_view(tables[table].row_line(row));
+        true
+    }
+
+    /// The selected table cell, if any.
+    pub fn selected_table_cell(&mut self) -> Option<SelectedCell> {
+        let (table, row, col) = self.selected_cell?;
+        self.ensure_rendered();
+        let info = self.rendered.as_ref()?.tables.get(table)?;
+        Some(SelectedCell {
+            table,
+            row,
+            col,
+            line: info.row_line(row),
+            columns: info.columns.get(col)?.clone(),
+            content: info.cells.get(row)?.get(col)?.clone(),
+        })
+    }
+
+    /// Clear the table cell selection.
+    pub fn clear_table_cell_selection(&mut self) {
+        self.selected_cell = None;
+    }
+
+    /// Get the table whose header should be pinned at the current scroll
+    /// position, if sticky headers are enabled.
+    pub fn sticky_table(&mut self) -> Option<&TableInfo> {
+        if !self.sticky_table_headers {
+            return None;
+        }
+        self.ensure_rendered();
+        let top = self.scroll_offset as usize;
+        self.rendered
+            .as_ref()?
+            .tables
+            .iter()
+            .find(|t| t.body_contains(top))
+    }
+
+    /// Get all visible `<details>` blocks in the document.
+    pub fn details(&mut self) -> Vec<DetailsInfo> {
+        self.ensure_rendered();
+        self.rendered
+            .as_ref()
+            .map(|r| r.details.clone())
+            .unwrap_or_default()
+    }
+
+    /// Expand or collapse the `<details>` block with the given index.
+    pub fn toggle_details(&mut self, index: usize) {
+        if !self.options.toggled_details.remove(&index) {
+            self.options.toggled_details.insert(index);
+        }
+        self.rendered = None;
+        self.selected_link = None;
+    }
+
+    /// Expand or collapse the first `<details>` block whose summary is at or
+    /// below `line`, e.g. the scroll offset when a key is pressed.
+    ///
+    /// Returns the index of the toggled block.
+    pub fn toggle_details_at(&mut self, line: usize) -> Option<usize> {
+        self.ensure_rendered();
+        let index = self
+            .rendered
+            .as_ref()?
+            .details
+            .iter()
+            .find(|d| d.line >= line)?
+            .index;
+        self.toggle_details(index);
+        Some(index)
+    }
+
+    /// Fold or unfold the section under the heading with the given slug.
+    ///
+    /// Folds are keyed by slug, so they survive edits through
+    /// [`set_content`](Self::set_content) that shift headings around.
+    pub fn toggle_fold(&mut self, slug: &str) {
+        if !self.options.folded_headings.remove(slug) {
+            self.options.folded_headings.insert(slug.to_string());
+        }
+        self.rendered = None;
+        self.selected_link = None;
+    }
+
+    /// Fold or unfold the section of the nearest heading at or above `line`.
+    ///
+    /// Returns the slug of the toggled heading.
+    pub fn toggle_fold_at(&mut self, line: usize) -> Option<String> {
+        self.ensure_rendered();
+        let slug = self
+            .rendered
+            .as_ref()?
+            .headings
+            .iter()
+            .rev()
+            .find(|h| h.line <= line)?
+            .slug
+            .clone();
+        self.toggle_fold(&slug);
+        Some(slug)
+    }
+
+    /// Fold every heading at `level` or deeper, leaving an outline of the
+    /// headings above `level` and the folded headings themselves.
+    pub fn fold_all(&mut self, level: u8) {
+        self.unfold_all();
+        let slugs: Vec<String> = self
+            .headings()
+            .into_iter()
+            .filter(|h| h.level >= level)
+            .map(|h| h.slug)
+            .collect();
+        self.options.folded_headings.extend(slugs);
+        self.rendered = None;
+    }
+
+    /// Unfold all headings.
+    pub fn unfold_all(&mut self) {
+        self.options.folded_headings.clear();
+        self.rendered = None;
+        self.selected_link = None;
+    }
+
     /// Get all links in the document.
     pub fn links(&mut self) -> Vec<LinkInfo> {
         self.ensure_rendered();
@@ -239,6 +921,15 @@ impl MarkdownView {
             .unwrap_or_default()
     }
 
+    /// Get the document's links grouped by URL.
+    pub fn links_grouped(&mut self) -> Vec<LinkGroup> {
+        self.ensure_rendered();
+        self.rendered
+            .as_ref()
+            .map(|r| r.links_grouped())
+            .unwrap_or_default()
+    }
+
     /// Select the next link.
     pub fn select_next_link(&mut self) {
         self.ensure_rendered();
@@ -285,6 +976,47 @@ impl MarkdownView {
             .and_then(|i| self.rendered.as_ref()?.links.get(i))
     }
 
+    /// Get the links on a rendered line.
+    pub fn links_on_line(&mut self, line: usize) -> Vec<LinkInfo> {
+        self.ensure_rendered();
+        self.rendered
+            .as_ref()
+            .map(|r| r.links.iter().filter(|l| l.line == line).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Build a footer line listing the URLs of links on a rendered line.
+    ///
+    /// Returns `None` if the line has no links.
+    pub fn link_footer_line(&mut self, line: usize) -> Option<Line<'static>> {
+        let links = self.links_on_line(line);
+        if links.is_empty() {
+            return None;
+        }
+
+        let mut spans = Vec::new();
+        for (i, link) in links.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("  "));
+            }
+            if !link.is_autolink && link.text != link.url {
+                spans.push(Span::styled(format!("{}: ", link.text), self.theme.link));
+            }
+            spans.push(Span::styled(link.url.clone(), self.theme.link_url));
+        }
+        Some(Line::from(spans))
+    }
+
+    /// The line whose links are shown in the link footer: the selected
+    /// link's line, then the cursor's line, then the top line of the
+    /// viewport.
+    pub fn focused_line(&mut self) -> usize {
+        self.selected_link()
+            .map(|l| l.line)
+            .or(self.cursor)
+            .unwrap_or(self.scroll_offset as usize)
+    }
+
     /// Get the total line count.
     pub fn line_count(&mut self) -> usize {
         self.ensure_rendered();
@@ -299,10 +1031,29 @@ impl MarkdownView {
 
     fn ensure_rendered(&mut self) {
         if self.rendered.is_none() {
-            self.rendered = Some(render(&self.content, &self.theme, &self.options));
+            // Matches are found afresh
+            self.current_match = None;
+            let rendered = if self.link_footer {
+                let theme = Theme {
+                    show_link_urls: false,
+                    ..self.theme.clone()
+                };
+                render(&self.content, &theme, &self.options)
+            } else {
+                render(&self.content, &self.theme, &self.options)
+            };
+            self.rendered = Some(Arc::new(rendered));
         }
     }
 
+    /// Get the rendered document, rendering it if needed.
+    ///
+    /// The result is shared with the view rather than copied.
+    pub fn rendered(&mut self) -> Arc<RenderedMarkdown<'static>> {
+        self.ensure_rendered();
+        Arc::clone(self.rendered.as_ref().expect("rendered above"))
+    }
+
     /// Create a widget for rendering this view.
     pub fn widget(&mut self) -> MarkdownViewWidget<'_> {
         self.ensure_rendered();
@@ -328,6 +1079,17 @@ impl MarkdownView {
         self.options.search_pattern.as_deref()
     }
 
+    /// Restrict search to part of the document, e.g. code blocks.
+    pub fn set_search_scope(&mut self, scope: SearchScope) {
+        self.options.search_scope = scope;
+        self.rendered = None;
+    }
+
+    /// Get the current search scope.
+    pub fn search_scope(&self) -> &SearchScope {
+        &self.options.search_scope
+    }
+
     /// Get all search matches.
     pub fn search_matches(&mut self) -> Vec<SearchMatch> {
         self.ensure_rendered();
@@ -346,7 +1108,8 @@ impl MarkdownView {
             .unwrap_or(0)
     }
 
-    /// Scroll to the next search match from the current scroll position.
+    /// Scroll to the search match after the one last scrolled to, or to the
+    /// first match from the top of the view if it has scrolled since.
     ///
     /// Returns the index of the match scrolled to, or None if no matches.
     pub fn scroll_to_next_match(&mut self) -> Option<usize> {
@@ -356,22 +1119,20 @@ impl MarkdownView {
             return None;
         }
 
-        let current_line = self.scroll_offset as usize;
-
-        // Find the next match after current position
-        for (i, m) in matches.iter().enumerate() {
-            if m.line > current_line {
-                self.scroll_offset = m.line as u16;
-                return Some(i);
+        let index = match self.current_match(matches.len()) {
+            Some(current) => (current + 1) % matches.len(),
+            // Wrap around to the first match if none are below
+            None => {
+                let top = self.scroll_offset as usize;
+                matches.iter().position(|m| m.line >= top).unwrap_or(0)
             }
-        }
-
-        // Wrap around to first match
-        self.scroll_offset = matches[0].line as u16;
-        Some(0)
+        };
+        self.scroll_to_match(index);
+        Some(index)
     }
 
-    /// Scroll to the previous search match from the current scroll position.
+    /// Scroll to the search match before the one last scrolled to, or to
+    /// the last match above the view if it has scrolled since.
     ///
     /// Returns the index of the match scrolled to, or None if no matches.
     pub fn scroll_to_prev_match(&mut self) -> Option<usize> {
@@ -381,31 +1142,35 @@ impl MarkdownView {
             return None;
         }
 
-        let current_line = self.scroll_offset as usize;
-
-        // Find the previous match before current position
-        for (i, m) in matches.iter().enumerate().rev() {
-            if m.line < current_line {
-                self.scroll_offset = m.line as u16;
-                return Some(i);
-            }
-        }
-
-        // Wrap around to last match
         let last = matches.len() - 1;
-        self.scroll_offset = matches[last].line as u16;
-        Some(last)
+        let index = match self.current_match(matches.len()) {
+            Some(current) => current.checked_sub(1).unwrap_or(last),
+            // Wrap around to the last match if none are above
+            None => {
+                let top = self.scroll_offset as usize;
+                matches.iter().rposition(|m| m.line < top).unwrap_or(last)
+            }
+        };
+        self.scroll_to_match(index);
+        Some(index)
     }
 
     /// Scroll to a specific search match by index.
     pub fn scroll_to_match(&mut self, index: usize) {
         self.ensure_rendered();
-        if let Some(ref rendered) = self.rendered {
-            if let Some(m) = rendered.search_matches.get(index) {
-                self.scroll_offset = m.line as u16;
-            }
+        let line = self.rendered.as_ref().and_then(|r| r.search_matches.get(index)).map(|m| m.line);
+        if let Some(line) = line {
+            self.jump_to(line);
+            self.current_match = Some((index, self.scroll_offset));
         }
     }
+
+    /// The match last scrolled to, unless the view has scrolled since.
+    fn current_match(&self, count: usize) -> Option<usize> {
+        self.current_match
+            .filter(|&(index, offset)| index < count && offset == self.scroll_offset)
+            .map(|(index, _)| index)
+    }
 }
 
 /// Widget wrapper for MarkdownView.
@@ -415,12 +1180,65 @@ pub struct MarkdownViewWidget<'a> {
 
 impl Widget for MarkdownViewWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let text = self.view.rendered.as_ref().map(|r| r.text.clone()).unwrap_or_default();
+        let sticky = self.view.sticky_table().cloned();
+        let selected_cell = self.view.selected_table_cell();
+
+        // Reserve the bottom row for the link footer
+        let mut area = area;
+        if self.view.link_footer && area.height > 1 {
+            area.height -= 1;
+            let footer_area = Rect {
+                y: area.bottom(),
+                height: 1,
+                ..area
+            };
+            let focused = self.view.focused_line();
+            if let Some(footer) = self.view.link_footer_line(focused) {
+                Paragraph::new(footer).render(footer_area, buf);
+            }
+        }
 
-        Paragraph::new(text)
+        self.view.viewport_height = area.height;
+        let Some(rendered) = self.view.rendered.as_ref() else {
+            return;
+        };
+
+        Paragraph::new(rendered.text.clone())
             .wrap(Wrap { trim: false })
             .scroll((self.view.scroll_offset, 0))
             .render(area, buf);
+
+        let top = self.view.scroll_offset as usize;
+        let row = self.view.cursor.and_then(|line| line.checked_sub(top));
+        if let Some(row) = row.filter(|&row| row < area.height as usize) {
+            let cursor_area = Rect { y: area.y + row as u16, height: 1, ..area };
+            buf.set_style(cursor_area, self.view.theme.cursor_line);
+        }
+
+        if let Some(cell) = selected_cell {
+            let row = cell.line.checked_sub(top).filter(|&row| row < area.height as usize);
+            let x = cell.columns.start.min(area.width as usize);
+            let width = cell.columns.len().min(area.width as usize - x);
+            if let Some(row) = row {
+                let cell_area = Rect {
+                    x: area.x + x as u16,
+                    y: area.y + row as u16,
+                    width: width as u16,
+                    height: 1,
+                };
+                buf.set_style(cell_area, self.view.theme.table_cell_selected);
+            }
+        }
+
+        // Redraw the header row and separator over the top of the viewport
+        if let Some(table) = sticky {
+            let header: Vec<_> = rendered.text.lines[table.header_lines.start..table.body_start]
+                .to_vec();
+            let height = (header.len() as u16).min(area.height);
+            let header_area = Rect { height, ..area };
+            ratatui::widgets::Clear.render(header_area, buf);
+            Paragraph::new(Text::from(header)).render(header_area, buf);
+        }
     }
 }
 
@@ -497,6 +1315,19 @@ mod tests {
         assert_eq!(view.scroll_offset(), 0);
     }
 
+    #[test]
+    fn test_markdown_view_shared_panes() {
+        let mut left = MarkdownView::new("# One\n\n# Two\n\n# Three");
+        let shared = left.rendered();
+        let mut right = left.clone();
+        right.scroll_down(2);
+        assert_eq!((left.scroll_offset(), right.scroll_offset()), (0, 2));
+        assert!(Arc::ptr_eq(&shared, &right.rendered()));
+
+        let lines = std::thread::spawn(move || shared.line_count).join().unwrap();
+        assert_eq!(lines, left.rendered().line_count);
+    }
+
     #[test]
     fn test_markdown_view_headings() {
         let mut view = MarkdownView::new("# H1\n\n## H2\n\n### H3");
@@ -507,6 +1338,43 @@ mod tests {
         assert_eq!(headings[2].level, 3);
     }
 
+    #[test]
+    fn test_markdown_view_folds() {
+        let md = "# A\n\n## A1\n\nx\n\n# B\n\n## B1\n\ny\n";
+        let mut view = MarkdownView::new(md);
+        view.fold_all(2);
+        let headings: Vec<_> = view.headings().into_iter().map(|h| (h.slug, h.folded)).collect();
+        assert_eq!(
+            headings,
+            [("a".into(), false), ("a1".into(), true), ("b".into(), false), ("b1".into(), true)]
+        );
+
+        // Folds follow their heading when an edit shifts it
+        view.unfold_all();
+        view.toggle_fold_at(0);
+        view.set_content(format!("# New\n\n{}", md));
+        let folded: Vec<_> = view.headings().into_iter().filter(|h| h.folded).collect();
+        assert_eq!(folded.len(), 1);
+        assert_eq!(folded[0].slug, "a");
+    }
+
+    #[test]
+    fn test_markdown_view_export_outline() {
+        let mut view = MarkdownView::new("# A\n\n### Deep\n\n## B \"q\"\n\n# [C]\n");
+        assert_eq!(
+            view.export_outline(OutlineFormat::Markdown),
+            "- [A](#a) (line 0)\n  - [Deep](#deep) (line 1)\n  - [B \"q\"](#b-q) (line 2)\n\
+             - [\\[C\\]](#c) (line 3)\n"
+        );
+        assert_eq!(
+            view.export_outline(OutlineFormat::Json),
+            r#"[{"level":1,"text":"A","slug":"a","line":0,"children":["#.to_string()
+                + r#"{"level":3,"text":"Deep","slug":"deep","line":1,"children":[]},"#
+                + r#"{"level":2,"text":"B \"q\"","slug":"b-q","line":2,"children":[]}]},"#
+                + r#"{"level":1,"text":"[C]","slug":"c","line":3,"children":[]}]"#
+        );
+    }
+
     #[test]
     fn test_markdown_view_links() {
         let mut view = MarkdownView::new("[a](http://a.com) and [b](http://b.com)");
@@ -514,6 +1382,234 @@ mod tests {
         assert_eq!(links.len(), 2);
     }
 
+    #[test]
+    fn test_markdown_view_sticky_table_header() {
+        let rows: String = (0..20).map(|i| format!("| {} | x |\n", i)).collect();
+        let md = format!("Intro\n\n| Num | Val |\n|---|---|\n{}", rows);
+        let mut view = MarkdownView::new(md)
+            .options(RenderOptions::github())
+            .sticky_table_headers(true);
+
+        let table = view.tables()[0].clone();
+        assert!(view.sticky_table().is_none());
+
+        view.set_scroll((table.body_start + 5) as u16);
+        assert_eq!(view.sticky_table().map(|t| t.start_line), Some(table.start_line));
+
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = Buffer::empty(area);
+        view.widget().render(area, &mut buf);
+        let top: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+        assert!(top.contains("Num"));
+
+        view.set_sticky_table_headers(false);
+        assert!(view.sticky_table().is_none());
+    }
+
+    #[test]
+    fn test_markdown_view_details() {
+        let md = "<details>\n<summary>More</summary>\n\nHidden [link](http://x.com)\n\n</details>\n\nAfter";
+        let mut view = MarkdownView::new(md);
+        let details = view.details();
+        assert_eq!(details.len(), 1);
+        assert!(!details[0].expanded);
+        assert!(view.links().is_empty());
+        let collapsed = view.line_count();
+
+        assert_eq!(view.toggle_details_at(0), Some(0));
+        assert!(view.details()[0].expanded);
+        assert_eq!(view.links().len(), 1);
+        assert!(view.line_count() > collapsed);
+
+        view.toggle_details(0);
+        assert_eq!(view.line_count(), collapsed);
+    }
+
+    #[test]
+    fn test_markdown_view_jump_list() {
+        let md: String = (1..=5).map(|i| format!("# H{}\n\ntext\n\n", i)).collect();
+        let mut view = MarkdownView::new(md);
+        assert!(!view.jump_back());
+
+        view.scroll_to_heading(2);
+        let h2 = view.scroll_offset();
+        view.scroll_to_heading(4);
+        let h4 = view.scroll_offset();
+
+        assert!(view.jump_back());
+        assert_eq!(view.scroll_offset(), h2);
+        assert!(view.jump_back());
+        assert_eq!(view.scroll_offset(), 0);
+        assert!(!view.jump_back());
+
+        assert!(view.jump_forward());
+        assert_eq!(view.scroll_offset(), h2);
+        assert!(view.jump_forward());
+        assert_eq!(view.scroll_offset(), h4);
+        assert!(!view.jump_forward());
+
+        // A new jump discards forward history
+        view.jump_back();
+        view.scroll_to_heading(1);
+        assert!(!view.jump_forward());
+    }
+
+    #[test]
+    fn test_markdown_view_external_link() {
+        let mut view = MarkdownView::new("# Index\n\nSee [guide](guide.md) or [site](http://x.com).")
+            .on_external_link(|link| {
+                if link.url.ends_with(".md") {
+                    LinkAction::Load(format!("# {}\n\n[back](index.md)", link.url))
+                } else {
+                    LinkAction::Default
+                }
+            });
+
+        view.select_next_link();
+        assert!(view.activate_selected_link().is_none());
+        assert_eq!(view.headings()[0].text, "guide.md");
+        assert_eq!(view.scroll_offset(), 0);
+
+        assert!(view.jump_back());
+        assert_eq!(view.headings()[0].text, "Index");
+        assert!(view.jump_forward());
+        assert_eq!(view.headings()[0].text, "guide.md");
+
+        view.jump_back();
+        view.select_next_link();
+        view.select_next_link();
+        let link = view.activate_selected_link().unwrap();
+        assert_eq!(link.url, "http://x.com");
+    }
+
+    #[test]
+    fn test_markdown_view_link_footer() {
+        let md = "Intro\n\nRead [docs](http://docs.rs) and <http://x.com>\n\nEnd";
+        let mut view = MarkdownView::new(md).theme(Theme::plain()).link_footer(true);
+        assert!(!view.text().lines.iter().any(|l| l.to_string().contains("(http")));
+
+        view.select_next_link();
+        let line = view.focused_line();
+        assert_eq!(view.links_on_line(line).len(), 2);
+        let footer = view.link_footer_line(line).unwrap().to_string();
+        assert_eq!(footer, "docs: http://docs.rs  http://x.com");
+
+        let area = Rect::new(0, 0, 60, 5);
+        let mut buf = Buffer::empty(area);
+        view.widget().render(area, &mut buf);
+        let bottom: String = (0..area.width).map(|x| buf[(x, 4)].symbol()).collect();
+        assert!(bottom.starts_with("docs: http://docs.rs"));
+    }
+
+    #[test]
+    fn test_markdown_view_scroll_to_line() {
+        let md: String = (0..50).map(|i| format!("line {}\n\n", i)).collect();
+        let mut view = MarkdownView::new(md).jump_placement(ScrollPlacement::Center);
+        let area = Rect::new(0, 0, 20, 11);
+        let mut buf = Buffer::empty(area);
+        view.widget().render(area, &mut buf);
+
+        view.scroll_to_line(40, ScrollPlacement::Bottom);
+        assert_eq!(view.scroll_offset(), 30);
+        view.scroll_to_line(3, ScrollPlacement::Center);
+        assert_eq!(view.scroll_offset(), 0);
+        assert!(view.jump_back());
+        assert_eq!(view.scroll_offset(), 30);
+
+        view.set_search("line 2");
+        view.scroll_to_top();
+        let lines: Vec<usize> = view.search_matches().iter().map(|m| m.line).collect();
+        assert_eq!(lines[..3], [2, 20, 21]);
+        // The first match can't be centered, but is still the one jumped to
+        assert_eq!(view.scroll_to_next_match(), Some(0));
+        assert_eq!(view.scroll_offset(), 0);
+        assert_eq!(view.scroll_to_next_match(), Some(1));
+        assert_eq!(view.scroll_offset(), 15);
+        assert_eq!(view.scroll_to_prev_match(), Some(0));
+        assert_eq!(view.scroll_to_prev_match(), Some(lines.len() - 1));
+
+        // After scrolling by hand, from the top of the view
+        view.scroll_to_line(21, ScrollPlacement::Top);
+        assert_eq!(view.scroll_to_next_match(), Some(2));
+    }
+
+    #[test]
+    fn test_match_cycle_near_top() {
+        let md = "a\n\nx\n\nx\n\na\n\na\n\na\n\na\n\nx\n";
+        let mut view = MarkdownView::new(md).jump_placement(ScrollPlacement::Center);
+        let area = Rect::new(0, 0, 20, 11);
+        let mut buf = Buffer::empty(area);
+        view.widget().render(area, &mut buf);
+        view.set_search("x");
+        let order: Vec<_> = (0..4).filter_map(|_| view.scroll_to_next_match()).collect();
+        assert_eq!(order, [0, 1, 2, 0]);
+        let order: Vec<_> = (0..3).filter_map(|_| view.scroll_to_prev_match()).collect();
+        assert_eq!(order, [2, 1, 0]);
+    }
+
+    #[test]
+    fn test_markdown_view_cursor() {
+        let md = "First [a](https://a.org)\nparagraph\n\n- one\n- two [b](https://b.org)\n\nLast";
+        let mut view = MarkdownView::new(md).options(RenderOptions::new());
+        let area = Rect::new(0, 0, 30, 3);
+        let mut buf = Buffer::empty(area);
+        view.widget().render(area, &mut buf);
+
+        assert_eq!(view.cursor_line(), None);
+        assert!(view.link_under_cursor().is_none());
+        view.cursor_down(0);
+        assert_eq!(view.link_under_cursor().unwrap().url, "https://a.org");
+        assert_eq!(view.block_under_cursor(), Some(0..2));
+
+        view.cursor_down(3);
+        assert_eq!(view.cursor_line(), Some(3));
+        assert_eq!(view.scroll_offset(), 1);
+        assert_eq!(view.link_under_cursor().unwrap().url, "https://b.org");
+        assert_eq!(view.block_under_cursor(), Some(2..5));
+
+        view.cursor_up(2);
+        assert_eq!(view.scroll_offset(), 1);
+        view.widget().render(area, &mut buf);
+        assert_eq!(buf[(0, 0)].bg, Theme::default().cursor_line.bg.unwrap());
+        assert_ne!(buf[(0, 1)].bg, buf[(0, 0)].bg);
+    }
+
+    #[test]
+    fn test_select_table_cell() {
+        let md = "| a | b |\n|---|---|\n| 1 | a very long cell |\n\n| c |\n|---|\n| 2 |";
+        let options = RenderOptions::new()
+            .with_width(16)
+            .with_no_wrap_blocks([md4c::BlockType::Table]);
+        let mut view = MarkdownView::new(md).options(options);
+        let area = Rect::new(0, 0, 20, 8);
+        let mut buf = Buffer::empty(area);
+        view.widget().render(area, &mut buf);
+        assert!(view.selected_table_cell().is_none());
+
+        assert!(view.select_table_cell(CellMove::Next));
+        assert_eq!(view.selected_table_cell().unwrap().content, "a");
+        view.select_table_cell(CellMove::Down);
+        view.select_table_cell(CellMove::Next);
+        let cell = view.selected_table_cell().unwrap();
+        assert_eq!((cell.table, cell.row, cell.col, cell.line), (0, 1, 1, 3));
+        assert_eq!(cell.content, "a very long cell");
+        assert!(!view.text().lines[3].to_string().contains("a very long cell"));
+
+        view.widget().render(area, &mut buf);
+        let selected = Theme::default().table_cell_selected.bg.unwrap();
+        assert_eq!(buf[(cell.columns.start as u16, 3)].bg, selected);
+        assert_ne!(buf[(2, 3)].bg, selected);
+
+        view.select_table_cell(CellMove::Next);
+        assert_eq!(view.selected_table_cell().unwrap().content, "c");
+        view.select_table_cell(CellMove::Prev);
+        assert_eq!(view.selected_table_cell().unwrap().content, "a very long cell");
+        view.select_table_cell(CellMove::To { row: 0, col: 9 });
+        assert_eq!(view.selected_table_cell().unwrap().content, "b");
+        view.clear_table_cell_selection();
+        assert!(view.selected_table_cell().is_none());
+    }
+
     #[test]
     fn test_markdown_view_search() {
         let mut view = MarkdownView::new("Hello world\n\nHello again\n\nGoodbye");
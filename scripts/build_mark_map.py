@@ -0,0 +1,74 @@
+#!/usr/bin/env python3
+
+# Generates md4c-rs/src/mark_map.rs, the combining marks used by heading
+# slugs:
+#
+#   scripts/build_mark_map.py > md4c-rs/src/mark_map.rs
+
+import os
+import sys
+
+
+self_path = os.path.dirname(os.path.realpath(__file__));
+f = open(self_path + "/unicode/DerivedGeneralCategory.txt", "r")
+
+version = f.readline().strip("# \n")
+codepoint_list = []
+
+# Filter codepoints falling in the right category:
+for line in f:
+    comment_off = line.find("#")
+    if comment_off >= 0:
+        line = line[:comment_off]
+    line = line.strip()
+    if not line:
+        continue
+
+    char_range, category = line.split(";")
+    char_range = char_range.strip()
+    category = category.strip()
+
+    if category[0] != 'M':
+        continue
+
+    delim_off = char_range.find("..")
+    if delim_off >= 0:
+        codepoint0 = int(char_range[:delim_off], 16)
+        codepoint1 = int(char_range[delim_off+2:], 16)
+        for codepoint in range(codepoint0, codepoint1 + 1):
+            codepoint_list.append(codepoint)
+    else:
+        codepoint = int(char_range, 16)
+        codepoint_list.append(codepoint)
+f.close()
+
+
+codepoint_list.sort()
+
+
+index0 = 0
+count = len(codepoint_list)
+
+records = list()
+while index0 < count:
+    index1 = index0 + 1
+    while index1 < count and codepoint_list[index1] == codepoint_list[index1-1] + 1:
+        index1 += 1
+
+    records.append("(0x{:04x}, 0x{:04x})".format(codepoint_list[index0], codepoint_list[index1-1]))
+
+    index0 = index1
+
+sys.stdout.write("//! Combining marks, Unicode general categories Mn, Mc and Me.\n")
+sys.stdout.write("//!\n")
+sys.stdout.write("//! Generated by `scripts/build_mark_map.py` from {}.\n\n".format(version))
+sys.stdout.write("/// Sorted, disjoint ranges of marks, inclusive at both ends.\n")
+sys.stdout.write("pub(crate) static MARK_MAP: &[(u32, u32)] = &[\n")
+# Whole records per line, since textwrap would split them at the space:
+lines = [""]
+for record in records:
+    if len(lines[-1]) + len(record) + 2 > 96:
+        lines.append("")
+    lines[-1] += record + ", "
+sys.stdout.write("\n".join("    " + line.rstrip() for line in lines))
+sys.stdout.write("\n];\n")